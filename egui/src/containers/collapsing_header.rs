@@ -338,10 +338,12 @@ impl CollapsingHeader {
             if ui.visuals().collapsing_header_frame || self.show_background {
                 ui.painter().add(epaint::RectShape {
                     rect: header_response.rect.expand(visuals.expansion),
-                    corner_radius: visuals.corner_radius,
+                    rounding: visuals.rounding,
                     fill: visuals.bg_fill,
+                    fill_gradient: None,
                     stroke: visuals.bg_stroke,
                     // stroke: Default::default(),
+                    side_strokes: None,
                 });
             }
 
@@ -350,12 +352,8 @@ impl CollapsingHeader {
             {
                 let rect = rect.expand(visuals.expansion);
 
-                ui.painter().rect(
-                    rect,
-                    visuals.corner_radius,
-                    visuals.bg_fill,
-                    visuals.bg_stroke,
-                );
+                ui.painter()
+                    .rect(rect, visuals.rounding, visuals.bg_fill, visuals.bg_stroke);
             }
 
             {
@@ -9,10 +9,12 @@ use epaint::*;
 pub struct Frame {
     /// On each side
     pub margin: Vec2,
-    pub corner_radius: f32,
+    pub rounding: Rounding,
     pub shadow: Shadow,
     pub fill: Color32,
     pub stroke: Stroke,
+    /// If set, each side of the frame gets its own stroke instead of [`Self::stroke`].
+    pub side_strokes: Option<SideStrokes>,
 }
 
 impl Frame {
@@ -24,7 +26,7 @@ impl Frame {
     pub fn group(style: &Style) -> Self {
         Self {
             margin: Vec2::splat(6.0), // symmetric looks best in corners when nesting
-            corner_radius: style.visuals.widgets.noninteractive.corner_radius,
+            rounding: style.visuals.widgets.noninteractive.rounding,
             stroke: style.visuals.widgets.noninteractive.bg_stroke,
             ..Default::default()
         }
@@ -33,7 +35,7 @@ impl Frame {
     pub(crate) fn side_top_panel(style: &Style) -> Self {
         Self {
             margin: Vec2::new(8.0, 2.0),
-            corner_radius: 0.0,
+            rounding: Rounding::none(),
             fill: style.visuals.window_fill(),
             stroke: style.visuals.window_stroke(),
             ..Default::default()
@@ -43,7 +45,7 @@ impl Frame {
     pub(crate) fn central_panel(style: &Style) -> Self {
         Self {
             margin: Vec2::new(8.0, 8.0),
-            corner_radius: 0.0,
+            rounding: Rounding::none(),
             fill: style.visuals.window_fill(),
             stroke: Default::default(),
             ..Default::default()
@@ -53,30 +55,33 @@ impl Frame {
     pub fn window(style: &Style) -> Self {
         Self {
             margin: style.spacing.window_padding,
-            corner_radius: style.visuals.window_corner_radius,
+            rounding: style.visuals.window_rounding,
             shadow: style.visuals.window_shadow,
             fill: style.visuals.window_fill(),
             stroke: style.visuals.window_stroke(),
+            side_strokes: None,
         }
     }
 
     pub fn menu(style: &Style) -> Self {
         Self {
             margin: Vec2::splat(1.0),
-            corner_radius: style.visuals.widgets.noninteractive.corner_radius,
+            rounding: style.visuals.widgets.noninteractive.rounding,
             shadow: style.visuals.popup_shadow,
             fill: style.visuals.window_fill(),
             stroke: style.visuals.window_stroke(),
+            side_strokes: None,
         }
     }
 
     pub fn popup(style: &Style) -> Self {
         Self {
             margin: style.spacing.window_padding,
-            corner_radius: style.visuals.widgets.noninteractive.corner_radius,
+            rounding: style.visuals.widgets.noninteractive.rounding,
             shadow: style.visuals.popup_shadow,
             fill: style.visuals.window_fill(),
             stroke: style.visuals.window_stroke(),
+            side_strokes: None,
         }
     }
 
@@ -84,7 +89,7 @@ impl Frame {
     pub fn dark_canvas(style: &Style) -> Self {
         Self {
             margin: Vec2::new(10.0, 10.0),
-            corner_radius: style.visuals.widgets.noninteractive.corner_radius,
+            rounding: style.visuals.widgets.noninteractive.rounding,
             fill: Color32::from_black_alpha(250),
             stroke: style.visuals.window_stroke(),
             ..Default::default()
@@ -103,8 +108,16 @@ impl Frame {
         self
     }
 
-    pub fn corner_radius(mut self, corner_radius: f32) -> Self {
-        self.corner_radius = corner_radius;
+    /// A separate stroke for each side, e.g. to leave out the bottom border of a tab.
+    ///
+    /// Overrides [`Self::stroke`].
+    pub fn side_strokes(mut self, side_strokes: impl Into<SideStrokes>) -> Self {
+        self.side_strokes = Some(side_strokes.into());
+        self
+    }
+
+    pub fn rounding(mut self, rounding: impl Into<Rounding>) -> Self {
+        self.rounding = rounding.into();
         self
     }
 
@@ -122,6 +135,16 @@ impl Frame {
     pub fn multiply_with_opacity(mut self, opacity: f32) -> Self {
         self.fill = self.fill.linear_multiply(opacity);
         self.stroke.color = self.stroke.color.linear_multiply(opacity);
+        if let Some(side_strokes) = &mut self.side_strokes {
+            for stroke in [
+                &mut side_strokes.left,
+                &mut side_strokes.top,
+                &mut side_strokes.right,
+                &mut side_strokes.bottom,
+            ] {
+                stroke.color = stroke.color.linear_multiply(opacity);
+            }
+        }
         self.shadow.color = self.shadow.color.linear_multiply(opacity);
         self
     }
@@ -172,23 +195,35 @@ impl Frame {
     pub fn paint(&self, outer_rect: Rect) -> Shape {
         let Self {
             margin: _,
-            corner_radius,
+            rounding,
             shadow,
             fill,
             stroke,
+            side_strokes,
         } = *self;
 
         let frame_shape = Shape::Rect(epaint::RectShape {
             rect: outer_rect,
-            corner_radius,
+            rounding,
             fill,
+            fill_gradient: None,
             stroke,
+            side_strokes,
         });
 
         if shadow == Default::default() {
             frame_shape
+        } else if shadow.inner {
+            // The inner shadow goes between the fill and the stroke:
+            let fill_shape = Shape::Rect(epaint::RectShape::filled(outer_rect, rounding, fill));
+            let stroke_shape = Shape::Rect(epaint::RectShape {
+                side_strokes,
+                ..epaint::RectShape::stroke(outer_rect, rounding, stroke)
+            });
+            let shadow = Shape::Mesh(shadow.tessellate(outer_rect, rounding));
+            Shape::Vec(vec![fill_shape, shadow, stroke_shape])
         } else {
-            let shadow = shadow.tessellate(outer_rect, corner_radius);
+            let shadow = shadow.tessellate(outer_rect, rounding);
             let shadow = Shape::Mesh(shadow);
             Shape::Vec(vec![shadow, frame_shape])
         }
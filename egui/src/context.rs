@@ -34,6 +34,9 @@ struct ContextImpl {
     memory: Memory,
     animation_manager: AnimationManager,
     tex_manager: WrappedTextureManager,
+    /// The textures of the pages of the font atlas after the first one,
+    /// which is [`TextureId::default`].
+    font_page_textures: Vec<TextureId>,
 
     input: InputState,
 
@@ -403,7 +406,8 @@ impl Context {
         }
 
         if response.is_pointer_button_down_on {
-            response.interact_pointer_pos = input.pointer.interact_pos();
+            let to_layer = memory.areas.layer_transform(layer_id).inverse();
+            response.interact_pointer_pos = input.pointer.interact_pos().map(|pos| to_layer * pos);
         }
 
         if input.pointer.any_down() {
@@ -733,13 +737,19 @@ impl Context {
                 .memory
                 .end_frame(&ctx_impl.input, &ctx_impl.frame_state.used_ids);
 
-            let font_image_delta = ctx_impl.fonts.as_ref().unwrap().font_image_delta();
-            if let Some(font_image_delta) = font_image_delta {
-                ctx_impl
-                    .tex_manager
-                    .0
-                    .write()
-                    .set(TextureId::default(), font_image_delta);
+            let font_image_deltas = ctx_impl.fonts.as_ref().unwrap().font_image_deltas();
+            for (page, font_image_delta) in font_image_deltas {
+                let mut tex_manager = ctx_impl.tex_manager.0.write();
+                if page == 0 {
+                    tex_manager.set(TextureId::default(), font_image_delta);
+                } else if let Some(&texture_id) = ctx_impl.font_page_textures.get(page - 1) {
+                    tex_manager.set(texture_id, font_image_delta);
+                } else {
+                    // New pages are always sent whole:
+                    let name = format!("egui_font_texture_{}", page);
+                    let texture_id = tex_manager.alloc(name, font_image_delta.image);
+                    ctx_impl.font_page_textures.push(texture_id);
+                }
             }
 
             ctx_impl
@@ -760,10 +770,15 @@ impl Context {
 
     fn drain_paint_lists(&self) -> Vec<ClippedShape> {
         let ctx_impl = &mut *self.write();
-        ctx_impl
-            .graphics
-            .drain(ctx_impl.memory.areas.order())
-            .collect()
+        ctx_impl.graphics.drain(&ctx_impl.memory.areas).collect()
+    }
+
+    /// The widgets that reported their [`WidgetInfo`] during the last frame,
+    /// in the order they were added.
+    ///
+    /// This is always empty unless [`Options::record_widget_info`] is set.
+    pub fn recorded_widgets(&self) -> Vec<crate::testing::WidgetRecord> {
+        self.frame_state().widgets.clone()
     }
 
     /// Tessellate the given shapes into triangle meshes.
@@ -776,15 +791,25 @@ impl Context {
         tessellation_options.pixels_per_point = self.pixels_per_point();
         tessellation_options.aa_size = 1.0 / self.pixels_per_point();
         let paint_stats = PaintStats::from_shapes(&shapes);
-        let clipped_meshes = tessellator::tessellate_shapes(
-            shapes,
-            tessellation_options,
-            self.fonts().font_image_size(),
-        );
-        self.write().paint_stats = paint_stats.with_clipped_meshes(&clipped_meshes);
+        let font_textures = self.font_textures();
+        let clipped_meshes =
+            tessellator::tessellate_shapes(shapes, tessellation_options, &font_textures);
+        let font_atlas_stats = self.fonts().font_atlas_stats();
+        self.write().paint_stats = paint_stats
+            .with_clipped_meshes(&clipped_meshes)
+            .with_font_atlas(font_atlas_stats);
         clipped_meshes
     }
 
+    /// The texture and size of each page of the font atlas.
+    fn font_textures(&self) -> Vec<(TextureId, [usize; 2])> {
+        let font_image_sizes = self.fonts().font_image_sizes();
+        let font_page_textures = &self.read().font_page_textures;
+        let texture_ids =
+            std::iter::once(TextureId::default()).chain(font_page_textures.iter().copied());
+        texture_ids.zip(font_image_sizes).collect()
+    }
+
     // ---------------------------------------------------------------------
 
     /// How much space is used by panels and windows.
@@ -883,6 +908,35 @@ impl Context {
         }
     }
 
+    /// Scale, rotate and move everything painted on the given layer.
+    ///
+    /// The transform maps the coordinates used when painting and laying out the layer
+    /// to screen coordinates. It is taken into account by hit-testing,
+    /// and the pointer positions and deltas in a [`Response`] are mapped back into the layer.
+    ///
+    /// The transform stays until changed. Use [`TSTransform::IDENTITY`] to remove it.
+    ///
+    /// Clip rectangles of a rotated layer are replaced with their bounding boxes.
+    ///
+    /// ```
+    /// # let ctx = egui::Context::default();
+    /// # let _ = ctx.run(Default::default(), |ctx| {
+    /// let layer_id = egui::LayerId::new(egui::Order::Middle, egui::Id::new("canvas"));
+    /// let zoom = egui::TSTransform::new(egui::vec2(100.0, 50.0), 2.0);
+    /// ctx.set_transform_layer(layer_id, zoom);
+    /// # });
+    /// ```
+    pub fn set_transform_layer(&self, layer_id: LayerId, transform: TSTransform) {
+        self.memory().areas.set_layer_transform(layer_id, transform);
+    }
+
+    /// The transform of the given layer, set with [`Self::set_transform_layer`].
+    ///
+    /// [`TSTransform::IDENTITY`] if none was set.
+    pub fn layer_transform(&self, layer_id: LayerId) -> TSTransform {
+        self.memory().areas.layer_transform(layer_id)
+    }
+
     /// Top-most layer at the given position.
     pub fn layer_id_at(&self, pos: Pos2) -> Option<LayerId> {
         let resize_grab_radius_side = self.style().interaction.resize_grab_radius_side;
@@ -892,7 +946,8 @@ impl Context {
     pub(crate) fn rect_contains_pointer(&self, layer_id: LayerId, rect: Rect) -> bool {
         let pointer_pos = self.input().pointer.interact_pos();
         if let Some(pointer_pos) = pointer_pos {
-            rect.contains(pointer_pos) && self.layer_id_at(pointer_pos) == Some(layer_id)
+            let to_layer = self.layer_transform(layer_id).inverse();
+            rect.contains(to_layer * pointer_pos) && self.layer_id_at(pointer_pos) == Some(layer_id)
         } else {
             false
         }
@@ -1058,8 +1113,9 @@ impl Context {
         CollapsingHeader::new("🔠 Font texture")
             .default_open(false)
             .show(ui, |ui| {
-                let font_image_size = self.fonts().font_image_size();
-                crate::introspection::font_texture_ui(ui, font_image_size);
+                for (texture_id, size) in self.font_textures() {
+                    crate::introspection::font_texture_ui(ui, texture_id, size);
+                }
             });
     }
 
@@ -29,6 +29,10 @@ pub(crate) struct FrameState {
     pub(crate) scroll_delta: Vec2, // TODO: move to a Mutex inside of `InputState` ?
     /// horizontal, vertical
     pub(crate) scroll_target: [Option<(f32, Align)>; 2],
+
+    /// Widgets that reported their [`WidgetInfo`] this frame.
+    /// Only filled in if [`crate::memory::Options::record_widget_info`] is set.
+    pub(crate) widgets: Vec<crate::testing::WidgetRecord>,
 }
 
 impl Default for FrameState {
@@ -41,6 +45,7 @@ impl Default for FrameState {
             tooltip_rect: None,
             scroll_delta: Vec2::ZERO,
             scroll_target: [None; 2],
+            widgets: Vec::new(),
         }
     }
 }
@@ -55,6 +60,7 @@ impl FrameState {
             tooltip_rect,
             scroll_delta,
             scroll_target,
+            widgets,
         } = self;
 
         used_ids.clear();
@@ -64,6 +70,7 @@ impl FrameState {
         *tooltip_rect = None;
         *scroll_delta = input.scroll_delta;
         *scroll_target = [None; 2];
+        widgets.clear();
     }
 
     /// How much space is still available after panels has been added.
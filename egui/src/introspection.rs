@@ -23,7 +23,11 @@ pub fn font_id_ui(ui: &mut Ui, font_id: &mut FontId) {
 }
 
 // Show font texture in demo Ui
-pub(crate) fn font_texture_ui(ui: &mut Ui, [width, height]: [usize; 2]) -> Response {
+pub(crate) fn font_texture_ui(
+    ui: &mut Ui,
+    texture_id: TextureId,
+    [width, height]: [usize; 2],
+) -> Response {
     use epaint::Mesh;
 
     ui.vertical(|ui| {
@@ -45,7 +49,7 @@ pub(crate) fn font_texture_ui(ui: &mut Ui, [width, height]: [usize; 2]) -> Respo
             size *= ui.available_width() / size.x;
         }
         let (rect, response) = ui.allocate_at_least(size, Sense::hover());
-        let mut mesh = Mesh::default();
+        let mut mesh = Mesh::with_texture(texture_id);
         mesh.add_rect_with_uv(rect, [pos2(0.0, 0.0), pos2(1.0, 1.0)].into(), color);
         ui.painter().add(Shape::mesh(mesh));
 
@@ -67,7 +71,7 @@ pub(crate) fn font_texture_ui(ui: &mut Ui, [width, height]: [usize; 2]) -> Respo
                         pos2((u - texel_radius) / tex_w, (v - texel_radius) / tex_h),
                         pos2((u + texel_radius) / tex_w, (v + texel_radius) / tex_h),
                     );
-                    let mut mesh = Mesh::default();
+                    let mut mesh = Mesh::with_texture(texture_id);
                     mesh.add_rect_with_uv(zoom_rect, uv_rect, color);
                     ui.painter().add(Shape::mesh(mesh));
                 }
@@ -98,6 +102,7 @@ impl Widget for &epaint::stats::PaintStats {
                 clipped_meshes,
                 vertices,
                 indices,
+                font_atlas,
             } = self;
 
             ui.label("Intermediate:");
@@ -121,6 +126,10 @@ impl Widget for &epaint::stats::PaintStats {
             label(ui, indices, "indices").on_hover_text("Three 32-bit indices per triangles");
             ui.add_space(10.0);
 
+            ui.label("Font atlas:");
+            ui.add(Label::new(font_atlas.format()).wrap(false));
+            ui.add_space(10.0);
+
             // ui.label("Total:");
             // ui.label(self.total().format(""));
         })
@@ -144,9 +153,18 @@ impl Widget for &mut epaint::TessellationOptions {
                 debug_paint_clip_rects,
                 debug_paint_text_rects,
                 debug_ignore_clip_rects,
+                bezier_tolerance,
             } = self;
             ui.checkbox(anti_alias, "Antialias")
                 .on_hover_text("Turn off for small performance gain.");
+            ui.add(
+                Slider::new(bezier_tolerance, 0.01..=10.0)
+                    .logarithmic(true)
+                    .text("Bézier tolerance"),
+            )
+            .on_hover_text(
+                "Maximum distance between a flattened curve and the true curve, in pixels.",
+            );
             ui.collapsing("debug", |ui| {
                 ui.checkbox(
                     coarse_tessellation_culling,
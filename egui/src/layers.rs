@@ -148,6 +148,16 @@ impl PaintList {
             shape.translate(delta);
         }
     }
+
+    /// Transform each [`Shape`] and clip rectangle, in-place.
+    ///
+    /// When rotating, the clip rectangles become the bounding boxes of the rotated clip rectangles.
+    pub fn transform(&mut self, transform: TSTransform) {
+        for ClippedShape(clip_rect, shape) in &mut self.0 {
+            *clip_rect = transform.mul_rect(*clip_rect);
+            shape.transform(transform);
+        }
+    }
 }
 
 #[derive(Clone, Default)]
@@ -160,7 +170,11 @@ impl GraphicLayers {
             .or_default()
     }
 
-    pub fn drain(&mut self, area_order: &[LayerId]) -> impl ExactSizeIterator<Item = ClippedShape> {
+    pub fn drain(
+        &mut self,
+        areas: &crate::memory::Areas,
+    ) -> impl ExactSizeIterator<Item = ClippedShape> {
+        let area_order = areas.order();
         let mut all_shapes: Vec<_> = Default::default();
 
         for &order in &Order::ALL {
@@ -171,6 +185,13 @@ impl GraphicLayers {
             // Free it to save memory:
             order_map.retain(|_, list| !list.is_empty());
 
+            for (id, list) in order_map.iter_mut() {
+                let transform = areas.layer_transform(LayerId::new(order, *id));
+                if !transform.is_identity() {
+                    list.transform(transform);
+                }
+            }
+
             // First do the layers part of area_order:
             for layer_id in area_order {
                 if layer_id.order == order {
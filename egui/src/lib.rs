@@ -374,6 +374,7 @@ pub(crate) mod placer;
 mod response;
 mod sense;
 pub mod style;
+pub mod testing;
 mod ui;
 pub mod util;
 mod widget_text;
@@ -382,20 +383,25 @@ pub mod widgets;
 pub use epaint;
 pub use epaint::emath;
 
-pub use emath::{lerp, pos2, remap, remap_clamp, vec2, Align, Align2, NumExt, Pos2, Rect, Vec2};
+pub use emath::{
+    lerp, pos2, remap, remap_clamp, vec2, Align, Align2, NumExt, Pos2, Rect, TSTransform, Vec2,
+};
 pub use epaint::{
     color, mutex,
-    text::{FontData, FontDefinitions, FontFamily, FontId},
+    text::{FontData, FontDefinitions, FontFamily, FontId, FontTweak, FontVariant, FontWeight},
     textures::TexturesDelta,
-    AlphaImage, ClippedMesh, Color32, ColorImage, ImageData, Rgba, Shape, Stroke, TextureHandle,
-    TextureId,
+    AlphaImage, ClippedMesh, Color32, ColorImage, Gradient, ImageData, Rgba, Rounding, Shape,
+    Stroke, TextureHandle, TextureId,
 };
 
 pub mod text {
     pub use epaint::text::{
-        FontData, FontDefinitions, FontFamily, Fonts, Galley, LayoutJob, LayoutSection, TextFormat,
-        TAB_SIZE,
+        FontData, FontDefinitions, FontFamily, Fonts, Galley, InlineBox, LayoutJob, LayoutSection,
+        TextFormat, TextOverflow, OBJECT_REPLACEMENT_CHAR, TAB_SIZE,
     };
+
+    #[cfg(feature = "system_fonts")]
+    pub use epaint::text::SystemFonts;
 }
 
 pub use {
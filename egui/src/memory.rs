@@ -1,6 +1,6 @@
-use epaint::ahash::AHashSet;
+use epaint::ahash::{AHashMap, AHashSet};
 
-use crate::{area, window, Id, IdMap, InputState, LayerId, Pos2, Rect, Style};
+use crate::{area, window, Id, IdMap, InputState, LayerId, Pos2, Rect, Style, TSTransform};
 
 // ----------------------------------------------------------------------------
 
@@ -116,6 +116,13 @@ pub struct Options {
     /// This can lead to fewer texture operations, but may use up the texture atlas quicker
     /// if you are changing [`Style::text_styles`], of have a lot of text styles.
     pub preload_font_glyphs: bool,
+
+    /// If true, every widget that reports a [`crate::WidgetInfo`] is recorded each frame,
+    /// together with its [`Id`] and [`Rect`].
+    ///
+    /// Read them with [`crate::Context::recorded_widgets`].
+    /// This is used by [`crate::testing::Driver`] to find widgets.
+    pub record_widget_info: bool,
 }
 
 impl Default for Options {
@@ -125,6 +132,7 @@ impl Default for Options {
             tessellation_options: Default::default(),
             screen_reader: false,
             preload_font_glyphs: true,
+            record_widget_info: false,
         }
     }
 }
@@ -483,6 +491,10 @@ pub struct Areas {
     /// So if you close three windows and then reopen them all in one frame,
     /// they will all be sent to the top, but keep their previous internal order.
     wants_to_be_on_top: AHashSet<LayerId>,
+
+    /// Set with [`crate::Context::set_transform_layer`]. Missing means identity.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    transforms: AHashMap<LayerId, TSTransform>,
 }
 
 impl Areas {
@@ -507,6 +519,22 @@ impl Areas {
         }
     }
 
+    pub(crate) fn set_layer_transform(&mut self, layer_id: LayerId, transform: TSTransform) {
+        if transform.is_identity() {
+            self.transforms.remove(&layer_id);
+        } else {
+            self.transforms.insert(layer_id, transform);
+        }
+    }
+
+    /// See [`crate::Context::set_transform_layer`].
+    pub fn layer_transform(&self, layer_id: LayerId) -> TSTransform {
+        self.transforms
+            .get(&layer_id)
+            .copied()
+            .unwrap_or(TSTransform::IDENTITY)
+    }
+
     /// Top-most layer at the given position.
     pub fn layer_id_at(&self, pos: Pos2, resize_interact_radius_side: f32) -> Option<LayerId> {
         for layer in self.order.iter().rev() {
@@ -517,6 +545,10 @@ impl Areas {
                         // Allow us to resize by dragging just outside the window:
                         rect = rect.expand(resize_interact_radius_side);
                     }
+                    let pos = match self.transforms.get(layer) {
+                        Some(transform) => transform.inverse() * pos,
+                        None => pos,
+                    };
                     if rect.contains(pos) {
                         return Some(*layer);
                     }
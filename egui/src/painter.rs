@@ -1,12 +1,12 @@
 use crate::{
-    emath::{Align2, Pos2, Rect, Vec2},
+    emath::{Align2, Pos2, Rect, TSTransform, Vec2},
     layers::{LayerId, PaintList, ShapeIdx},
     Color32, Context, FontId,
 };
 use epaint::{
     mutex::{Arc, RwLockReadGuard, RwLockWriteGuard},
     text::{Fonts, Galley},
-    CircleShape, RectShape, Shape, Stroke, TextShape,
+    CircleShape, Gradient, RectShape, Rounding, Shape, Stroke, TextShape,
 };
 
 /// Helper to paint shapes and text to a specific region on a specific layer.
@@ -27,6 +27,9 @@ pub struct Painter {
     /// If set, all shapes will have their colors modified to be closer to this.
     /// This is used to implement grayed out interfaces.
     fade_to_color: Option<Color32>,
+
+    /// Applied to all shapes added with this `Painter`.
+    transform: TSTransform,
 }
 
 impl Painter {
@@ -37,6 +40,7 @@ impl Painter {
             layer_id,
             clip_rect,
             fade_to_color: None,
+            transform: TSTransform::IDENTITY,
         }
     }
 
@@ -48,6 +52,7 @@ impl Painter {
             layer_id,
             clip_rect: self.clip_rect,
             fade_to_color: None,
+            transform: self.transform,
         }
     }
 
@@ -80,8 +85,23 @@ impl Painter {
             layer_id: self.layer_id,
             clip_rect: rect.intersect(self.clip_rect),
             fade_to_color: self.fade_to_color,
+            transform: self.transform,
         }
     }
+
+    /// Scale, rotate and move all shapes added with this `Painter` from now on.
+    ///
+    /// The clip rectangle is not affected, and this is not known to hit-testing.
+    /// To transform the widgets of a whole layer, use [`Context::set_transform_layer`] instead.
+    pub fn set_transform(&mut self, transform: TSTransform) {
+        self.transform = transform;
+    }
+
+    /// See [`Self::set_transform`].
+    #[inline(always)]
+    pub fn transform(&self) -> TSTransform {
+        self.transform
+    }
 }
 
 /// ## Accessors etc
@@ -144,6 +164,9 @@ impl Painter {
     }
 
     fn transform_shape(&self, shape: &mut Shape) {
+        if !self.transform.is_identity() {
+            shape.transform(self.transform);
+        }
         if let Some(fade_to_color) = self.fade_to_color {
             tint_shape_towards(shape, fade_to_color);
         }
@@ -170,7 +193,7 @@ impl Painter {
             return;
         }
         if !shapes.is_empty() {
-            if self.fade_to_color.is_some() {
+            if self.fade_to_color.is_some() || !self.transform.is_identity() {
                 for shape in &mut shapes {
                     self.transform_shape(shape);
                 }
@@ -253,6 +276,7 @@ impl Painter {
             center,
             radius,
             fill: fill_color.into(),
+            fill_gradient: None,
             stroke: stroke.into(),
         });
     }
@@ -262,15 +286,24 @@ impl Painter {
             center,
             radius,
             fill: fill_color.into(),
+            fill_gradient: None,
             stroke: Default::default(),
         });
     }
 
+    /// Paint a circle filled with a [`Gradient`].
+    ///
+    /// The gradient is in screen coordinates, just like `center`.
+    pub fn circle_filled_gradient(&self, center: Pos2, radius: f32, gradient: Gradient) {
+        self.add(CircleShape::filled_gradient(center, radius, gradient));
+    }
+
     pub fn circle_stroke(&self, center: Pos2, radius: f32, stroke: impl Into<Stroke>) {
         self.add(CircleShape {
             center,
             radius,
             fill: Default::default(),
+            fill_gradient: None,
             stroke: stroke.into(),
         });
     }
@@ -278,33 +311,61 @@ impl Painter {
     pub fn rect(
         &self,
         rect: Rect,
-        corner_radius: f32,
+        rounding: impl Into<Rounding>,
         fill_color: impl Into<Color32>,
         stroke: impl Into<Stroke>,
     ) {
         self.add(RectShape {
             rect,
-            corner_radius,
+            rounding: rounding.into(),
             fill: fill_color.into(),
+            fill_gradient: None,
             stroke: stroke.into(),
+            side_strokes: None,
         });
     }
 
-    pub fn rect_filled(&self, rect: Rect, corner_radius: f32, fill_color: impl Into<Color32>) {
+    pub fn rect_filled(
+        &self,
+        rect: Rect,
+        rounding: impl Into<Rounding>,
+        fill_color: impl Into<Color32>,
+    ) {
         self.add(RectShape {
             rect,
-            corner_radius,
+            rounding: rounding.into(),
             fill: fill_color.into(),
+            fill_gradient: None,
             stroke: Default::default(),
+            side_strokes: None,
         });
     }
 
-    pub fn rect_stroke(&self, rect: Rect, corner_radius: f32, stroke: impl Into<Stroke>) {
+    /// Paint a rectangle filled with a [`Gradient`].
+    ///
+    /// The gradient is in screen coordinates, just like `rect`.
+    pub fn rect_filled_gradient(
+        &self,
+        rect: Rect,
+        rounding: impl Into<Rounding>,
+        gradient: Gradient,
+    ) {
+        self.add(RectShape::filled_gradient(rect, rounding, gradient));
+    }
+
+    pub fn rect_stroke(
+        &self,
+        rect: Rect,
+        rounding: impl Into<Rounding>,
+        stroke: impl Into<Stroke>,
+    ) {
         self.add(RectShape {
             rect,
-            corner_radius,
+            rounding: rounding.into(),
             fill: Default::default(),
+            fill_gradient: None,
             stroke: stroke.into(),
+            side_strokes: None,
         });
     }
 
@@ -378,9 +439,14 @@ impl Painter {
     /// You can create the `Galley` with [`Self::layout`].
     ///
     /// If you want to change the color of the text, use [`Self::galley_with_color`].
+    ///
+    /// If the font atlas has been cleared since the galley was laid out, it is laid out again
+    /// (see [`Fonts::is_galley_stale`]), unless it is [`Galley::derived`],
+    /// in which case it is up to you to make it again.
     #[inline(always)]
     pub fn galley(&self, pos: Pos2, galley: Arc<Galley>) {
         if !galley.is_empty() {
+            let galley = self.relayout_if_stale(galley);
             self.add(Shape::galley(pos, galley));
         }
     }
@@ -393,12 +459,22 @@ impl Painter {
     #[inline(always)]
     pub fn galley_with_color(&self, pos: Pos2, galley: Arc<Galley>, text_color: Color32) {
         if !galley.is_empty() {
+            let galley = self.relayout_if_stale(galley);
             self.add(TextShape {
                 override_text_color: Some(text_color),
                 ..TextShape::new(pos, galley)
             });
         }
     }
+
+    fn relayout_if_stale(&self, galley: Arc<Galley>) -> Arc<Galley> {
+        let fonts = self.fonts();
+        if !galley.derived && fonts.is_galley_stale(&galley) {
+            fonts.layout_job((*galley.job).clone())
+        } else {
+            galley
+        }
+    }
 }
 
 fn tint_shape_towards(shape: &mut Shape, target: Color32) {
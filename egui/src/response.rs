@@ -59,6 +59,8 @@ pub struct Response {
 
     /// Where the pointer (mouse/touch) were when when this widget was clicked or dragged.
     /// `None` if the widget is not being interacted with.
+    ///
+    /// This is in the coordinates of the layer, see [`Context::set_transform_layer`].
     pub(crate) interact_pointer_pos: Option<Pos2>,
 
     /// What the underlying data changed?
@@ -148,6 +150,7 @@ impl Response {
         // We do not use self.clicked(), because we want to catch all clicks within our frame,
         // even if we aren't clickable (or even enabled).
         // This is important for windows and such that should close then the user clicks elsewhere.
+        let to_layer = self.ctx.layer_transform(self.layer_id).inverse();
         let pointer = &self.ctx.input().pointer;
 
         if pointer.any_click() {
@@ -158,7 +161,7 @@ impl Response {
             if self.hovered() {
                 false
             } else if let Some(pos) = pointer.interact_pos() {
-                !self.rect.contains(pos)
+                !self.rect.contains(to_layer * pos)
             } else {
                 false // clicked without a pointer, weird
             }
@@ -253,9 +256,15 @@ impl Response {
     }
 
     /// If dragged, how many points were we dragged and in what direction?
+    ///
+    /// This is in the coordinates of the layer, see [`Context::set_transform_layer`].
     pub fn drag_delta(&self) -> Vec2 {
         if self.dragged() {
-            self.ctx.input().pointer.delta()
+            let delta = self.ctx.input().pointer.delta();
+            self.ctx
+                .layer_transform(self.layer_id)
+                .inverse()
+                .mul_vec(delta)
         } else {
             Vec2::ZERO
         }
@@ -269,9 +278,13 @@ impl Response {
 
     /// If it is a good idea to show a tooltip, where is pointer?
     /// None if the pointer is outside the response area.
+    ///
+    /// This is in the coordinates of the layer, see [`Context::set_transform_layer`].
     pub fn hover_pos(&self) -> Option<Pos2> {
         if self.hovered() {
-            self.ctx.input().pointer.hover_pos()
+            let to_layer = self.ctx.layer_transform(self.layer_id).inverse();
+            let pos = self.ctx.input().pointer.hover_pos();
+            pos.map(|pos| to_layer * pos)
         } else {
             None
         }
@@ -476,6 +489,21 @@ impl Response {
         if let Some(event) = event {
             self.ctx.output().events.push(event);
         }
+        self.record_widget_info(make_info);
+    }
+
+    /// Remember this widget for [`Context::recorded_widgets`],
+    /// if [`crate::memory::Options::record_widget_info`] is on.
+    pub(crate) fn record_widget_info(&self, make_info: impl FnOnce() -> crate::WidgetInfo) {
+        if self.ctx.options().record_widget_info {
+            let record = crate::testing::WidgetRecord {
+                id: self.id,
+                layer_id: self.layer_id,
+                rect: self.ctx.layer_transform(self.layer_id).mul_rect(self.rect),
+                info: make_info(),
+            };
+            self.ctx.frame_state().widgets.push(record);
+        }
     }
 
     /// Response to secondary clicks (right-clicks) by showing the given menu.
@@ -0,0 +1,461 @@
+//! Tools for testing egui code without a window.
+//!
+//! The [`Driver`] runs your ui code frame by frame, finds widgets by their label or [`Id`]
+//! (using the [`WidgetInfo`] that widgets report with [`crate::Response::widget_info`]),
+//! and synthesizes clicks, drags, key presses and text input at the right coordinates.
+//!
+//! ```
+//! use egui::testing::Driver;
+//!
+//! let mut driver = Driver::new(false, |ctx, checked| {
+//!     egui::CentralPanel::default().show(ctx, |ui| {
+//!         ui.checkbox(checked, "Check me");
+//!     });
+//! });
+//! driver.run();
+//!
+//! driver.click("Check me");
+//! assert!(*driver.state());
+//! assert_eq!(driver.get_by_label("Check me").info.selected, Some(true));
+//! ```
+
+use crate::*;
+
+/// A widget that reported its [`WidgetInfo`] during a frame.
+///
+/// See [`Context::recorded_widgets`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct WidgetRecord {
+    /// The id of the widget.
+    pub id: Id,
+
+    /// The layer the widget is on.
+    pub layer_id: LayerId,
+
+    /// Where the widget is on screen, in points.
+    ///
+    /// If the layer is transformed (see [`Context::set_transform_layer`]),
+    /// this is the bounding box of the transformed widget.
+    pub rect: Rect,
+
+    /// What the widget reported about itself.
+    pub info: WidgetInfo,
+}
+
+// ----------------------------------------------------------------------------
+
+type AppFn<'a, State> = Box<dyn FnMut(&Context, &mut State) + 'a>;
+
+/// Runs ui code with scripted input, for use in tests.
+///
+/// The driver owns some `State` which is passed to the ui code each frame,
+/// and which you can inspect with [`Self::state`] between frames.
+///
+/// Methods that interact with widgets (like [`Self::click`]) look for the widget in
+/// the last frame, synthesize the input events, and then run as many frames as needed
+/// for the widget to respond. Call [`Self::run`] at least once before trying to find any widgets.
+pub struct Driver<'a, State> {
+    ctx: Context,
+    app: AppFn<'a, State>,
+    state: State,
+
+    screen_rect: Rect,
+    pixels_per_point: f32,
+    /// Time between frames, in seconds.
+    frame_time: f32,
+    time: f64,
+
+    modifiers: Modifiers,
+    /// Events to send in the next frame.
+    events: Vec<Event>,
+
+    output: Output,
+    shapes: Vec<epaint::ClippedShape>,
+    widgets: Vec<WidgetRecord>,
+}
+
+impl<'a, State> Driver<'a, State> {
+    /// Create a driver that will call `app` once each frame.
+    ///
+    /// No frame is run until you call [`Self::run`] (or any of the interaction methods).
+    pub fn new(state: State, app: impl FnMut(&Context, &mut State) + 'a) -> Self {
+        let ctx = Context::default();
+        ctx.options().record_widget_info = true;
+        Self {
+            ctx,
+            app: Box::new(app),
+            state,
+            screen_rect: Rect::from_min_size(Pos2::ZERO, vec2(800.0, 600.0)),
+            pixels_per_point: 1.0,
+            frame_time: 1.0 / 60.0,
+            time: 0.0,
+            modifiers: Default::default(),
+            events: Default::default(),
+            output: Default::default(),
+            shapes: Default::default(),
+            widgets: Default::default(),
+        }
+    }
+
+    /// Size of the simulated screen, in points. Default: 800x600.
+    pub fn screen_size(mut self, screen_size: Vec2) -> Self {
+        self.screen_rect = Rect::from_min_size(Pos2::ZERO, screen_size);
+        self
+    }
+
+    /// Default: `1.0`.
+    pub fn pixels_per_point(mut self, pixels_per_point: f32) -> Self {
+        self.pixels_per_point = pixels_per_point;
+        self
+    }
+
+    /// The simulated time between frames, in seconds. Default: 1/60.
+    pub fn frame_time(mut self, frame_time: f32) -> Self {
+        self.frame_time = frame_time;
+        self
+    }
+
+    /// The [`Context`] used by the driver.
+    pub fn ctx(&self) -> &Context {
+        &self.ctx
+    }
+
+    /// The state passed to the ui code.
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// The state passed to the ui code.
+    pub fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+
+    /// Give up the driver and get back the state.
+    pub fn into_state(self) -> State {
+        self.state
+    }
+
+    /// The [`Output`] of the last frame.
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+
+    /// The shapes painted in the last frame.
+    ///
+    /// Pass them to [`Context::tessellate`] (on [`Self::ctx`]) to get something to paint.
+    pub fn shapes(&self) -> &[epaint::ClippedShape] {
+        &self.shapes
+    }
+
+    /// Current simulated time, in seconds.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    // ------------------------------------------------------------------------
+    // Running frames:
+
+    /// Run one frame, sending all queued events.
+    pub fn run(&mut self) -> &Output {
+        let raw_input = RawInput {
+            screen_rect: Some(self.screen_rect),
+            pixels_per_point: Some(self.pixels_per_point),
+            time: Some(self.time),
+            predicted_dt: self.frame_time,
+            modifiers: self.modifiers,
+            events: std::mem::take(&mut self.events),
+            ..Default::default()
+        };
+
+        let Self {
+            ctx, app, state, ..
+        } = self;
+        let (output, shapes) = ctx.run(raw_input, |ctx| app(ctx, state));
+
+        self.widgets = self.ctx.recorded_widgets();
+        self.output = output;
+        self.shapes = shapes;
+        self.time += self.frame_time as f64;
+        &self.output
+    }
+
+    /// Run the given number of frames.
+    pub fn run_frames(&mut self, num_frames: usize) {
+        for _ in 0..num_frames {
+            self.run();
+        }
+    }
+
+    /// Run frames until egui no longer asks for a repaint (e.g. when all animations are done),
+    /// but at most `max_frames` frames.
+    ///
+    /// Returns the number of frames run.
+    pub fn run_until_idle(&mut self, max_frames: usize) -> usize {
+        for i in 0..max_frames {
+            if !self.run().needs_repaint {
+                return i + 1;
+            }
+        }
+        max_frames
+    }
+
+    // ------------------------------------------------------------------------
+    // Finding widgets:
+
+    /// All widgets that reported their [`WidgetInfo`] in the last frame,
+    /// in the order they were added.
+    pub fn widgets(&self) -> &[WidgetRecord] {
+        &self.widgets
+    }
+
+    /// Find the first widget in the last frame that fulfills the predicate.
+    pub fn find(&self, predicate: impl Fn(&WidgetRecord) -> bool) -> Option<&WidgetRecord> {
+        self.widgets.iter().find(|widget| predicate(widget))
+    }
+
+    /// Find the widget with the given label in the last frame.
+    ///
+    /// Plain [`Label`]:s are ignored if there is another widget with the same text
+    /// (e.g. the label next to a [`Slider`]).
+    ///
+    /// Panics if there is no such widget, or if there are several of them.
+    pub fn get_by_label(&self, label: &str) -> &WidgetRecord {
+        let mut matches: Vec<&WidgetRecord> = self
+            .widgets
+            .iter()
+            .filter(|widget| widget.info.label.as_deref() == Some(label))
+            .collect();
+        if matches.len() > 1 {
+            matches.retain(|widget| widget.info.typ != WidgetType::Label);
+        }
+        match matches.as_slice() {
+            [widget] => widget,
+            [] => panic!(
+                "No widget labeled {:?}. Widgets in the last frame:\n{}",
+                label,
+                self.describe_widgets()
+            ),
+            _ => panic!(
+                "Several widgets are labeled {:?}. Use `find` or `get_by_id` instead.",
+                label
+            ),
+        }
+    }
+
+    /// Find the widget with the given [`Id`] in the last frame.
+    ///
+    /// Panics if there is no such widget.
+    pub fn get_by_id(&self, id: Id) -> &WidgetRecord {
+        self.find(|widget| widget.id == id).unwrap_or_else(|| {
+            panic!(
+                "No widget with id {:?}. Widgets in the last frame:\n{}",
+                id,
+                self.describe_widgets()
+            )
+        })
+    }
+
+    fn describe_widgets(&self) -> String {
+        self.widgets
+            .iter()
+            .map(|widget| format!("  {:?} at {:?}: {:?}", widget.id, widget.rect, widget.info))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // ------------------------------------------------------------------------
+    // Low-level input:
+
+    /// Queue an event to be sent in the next frame.
+    pub fn push_event(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    /// Set the modifier keys that are held down from now on.
+    pub fn set_modifiers(&mut self, modifiers: Modifiers) {
+        self.modifiers = modifiers;
+    }
+
+    /// Move the pointer, and run a frame.
+    pub fn hover_at(&mut self, pos: Pos2) {
+        self.events.push(Event::PointerMoved(pos));
+        self.run();
+    }
+
+    /// Click the primary mouse button at the given position.
+    ///
+    /// Runs one frame with the pointer pressed, and one frame where it is released.
+    pub fn click_at(&mut self, pos: Pos2) {
+        self.click_button_at(pos, PointerButton::Primary);
+    }
+
+    /// Click the given mouse button at the given position.
+    pub fn click_button_at(&mut self, pos: Pos2, button: PointerButton) {
+        self.press_pointer(pos, button);
+        self.release_pointer(pos, button);
+    }
+
+    /// Press the primary mouse button at `from`, move to `to` and release,
+    /// running a few frames along the way.
+    pub fn drag_at(&mut self, from: Pos2, to: Pos2) {
+        const NUM_STEPS: usize = 4;
+
+        let button = PointerButton::Primary;
+        self.press_pointer(from, button);
+        for i in 1..=NUM_STEPS {
+            let pos = from + (to - from) * (i as f32 / NUM_STEPS as f32);
+            self.hover_at(pos);
+        }
+        self.release_pointer(to, button);
+    }
+
+    /// Press and release a key (with the current modifiers), and run a frame.
+    pub fn press_key(&mut self, key: Key) {
+        for pressed in [true, false] {
+            self.events.push(Event::Key {
+                key,
+                pressed,
+                modifiers: self.modifiers,
+            });
+        }
+        self.run();
+    }
+
+    /// Press and release a key while holding the given modifiers, and run a frame.
+    pub fn press_key_with_modifiers(&mut self, key: Key, modifiers: Modifiers) {
+        let old_modifiers = std::mem::replace(&mut self.modifiers, modifiers);
+        self.press_key(key);
+        self.modifiers = old_modifiers;
+    }
+
+    /// Type some text into whatever has keyboard focus, and run a frame.
+    pub fn type_text_into_focused(&mut self, text: &str) {
+        self.events.push(Event::Text(text.to_owned()));
+        self.run();
+    }
+
+    fn press_pointer(&mut self, pos: Pos2, button: PointerButton) {
+        self.events.push(Event::PointerMoved(pos));
+        self.events.push(Event::PointerButton {
+            pos,
+            button,
+            pressed: true,
+            modifiers: self.modifiers,
+        });
+        self.run();
+    }
+
+    fn release_pointer(&mut self, pos: Pos2, button: PointerButton) {
+        self.events.push(Event::PointerButton {
+            pos,
+            button,
+            pressed: false,
+            modifiers: self.modifiers,
+        });
+        self.run();
+    }
+
+    // ------------------------------------------------------------------------
+    // Interacting with widgets:
+
+    /// Move the pointer to the center of the widget with the given label.
+    pub fn hover(&mut self, label: &str) {
+        let pos = self.get_by_label(label).rect.center();
+        self.hover_at(pos);
+    }
+
+    /// Click the center of the widget with the given label.
+    pub fn click(&mut self, label: &str) {
+        let pos = self.get_by_label(label).rect.center();
+        self.click_at(pos);
+    }
+
+    /// Click the center of the widget with the given [`Id`].
+    pub fn click_id(&mut self, id: Id) {
+        let pos = self.get_by_id(id).rect.center();
+        self.click_at(pos);
+    }
+
+    /// Drag from the center of the widget with the given label by `delta` points.
+    pub fn drag(&mut self, label: &str, delta: Vec2) {
+        let from = self.get_by_label(label).rect.center();
+        self.drag_at(from, from + delta);
+    }
+
+    /// Click the widget with the given label to give it focus, then type some text.
+    pub fn type_text(&mut self, label: &str, text: &str) {
+        self.click(label);
+        self.type_text_into_focused(text);
+    }
+
+    /// Click the widget with the given [`Id`] to give it focus, then type some text.
+    pub fn type_text_id(&mut self, id: Id, text: &str) {
+        self.click_id(id);
+        self.type_text_into_focused(text);
+    }
+}
+
+#[test]
+fn test_driver_slider_and_text_edit() {
+    #[derive(Default)]
+    struct State {
+        value: f32,
+        text: String,
+        text_edit_id: Option<Id>,
+    }
+
+    let mut driver = Driver::new(State::default(), |ctx, state| {
+        CentralPanel::default().show(ctx, |ui| {
+            ui.add(Slider::new(&mut state.value, 0.0..=100.0).text("Value"));
+            let response = ui.text_edit_singleline(&mut state.text);
+            state.text_edit_id = Some(response.id);
+        });
+    });
+    driver.run();
+
+    let slider = driver.get_by_label("Value");
+    assert_eq!(slider.info.typ, WidgetType::Slider);
+    assert_eq!(slider.info.value, Some(0.0));
+
+    driver.drag("Value", vec2(1000.0, 0.0));
+    assert_eq!(driver.state().value, 100.0);
+    assert_eq!(driver.get_by_label("Value").info.value, Some(100.0));
+
+    let text_edit_id = driver.state().text_edit_id.unwrap();
+    driver.type_text_id(text_edit_id, "Hello");
+    driver.press_key(Key::Backspace);
+    assert_eq!(driver.state().text, "Hell");
+    assert_eq!(
+        driver
+            .get_by_id(text_edit_id)
+            .info
+            .current_text_value
+            .as_deref(),
+        Some("Hell")
+    );
+}
+
+#[test]
+fn test_driver_transformed_layer() {
+    let mut driver = Driver::new(0, |ctx, clicks| {
+        let layer_id = LayerId::new(Order::Middle, Id::new("canvas"));
+        let zoom = TSTransform::new(vec2(100.0, 50.0), 2.0) * TSTransform::from_rotation(0.5);
+        ctx.set_transform_layer(layer_id, zoom);
+        Area::new("canvas")
+            .fixed_pos(pos2(0.0, 0.0))
+            .show(ctx, |ui| {
+                if ui.button("Click me").clicked() {
+                    *clicks += 1;
+                }
+            });
+    });
+    driver.run_frames(2);
+
+    // The recorded rect is on screen, so clicking where the button would be untransformed misses:
+    assert!(driver.get_by_label("Click me").rect.min.x > 50.0);
+    driver.click_at(pos2(10.0, 10.0));
+    assert_eq!(*driver.state(), 0);
+
+    driver.click("Click me");
+    assert_eq!(*driver.state(), 1);
+}
@@ -47,6 +47,10 @@ impl Default for Settings {
 ///
 /// Rule 1) will make sure an undo point is not created until you _stop_ dragging that slider.
 /// Rule 2) will make sure that you will get some undo points even if you are constantly changing the state.
+///
+/// You can also call [`Self::add_undo`] yourself to group changes, e.g. one undo point per word when typing.
+///
+/// Undone states can be redone until the state changes again.
 #[derive(Clone, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Undoer<State> {
@@ -57,15 +61,20 @@ pub struct Undoer<State> {
     /// The latest undo point may (often) be the current state.
     undos: VecDeque<State>,
 
+    /// Undone states, with the latest undone at the back.
+    /// Cleared when the state changes.
+    redos: Vec<State>,
+
     #[cfg_attr(feature = "serde", serde(skip))]
     flux: Option<Flux<State>>,
 }
 
 impl<State> std::fmt::Debug for Undoer<State> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let Self { undos, .. } = self;
+        let Self { undos, redos, .. } = self;
         f.debug_struct("Undoer")
             .field("undo count", &undos.len())
+            .field("redo count", &redos.len())
             .finish()
     }
 }
@@ -82,6 +91,24 @@ impl<State> Undoer<State>
 where
     State: Clone + PartialEq,
 {
+    pub fn with_settings(settings: Settings) -> Self {
+        Self {
+            settings,
+            undos: VecDeque::new(),
+            redos: Vec::new(),
+            flux: None,
+        }
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// The latest undo point, which is often the current state.
+    pub fn latest_undo(&self) -> Option<&State> {
+        self.undos.back()
+    }
+
     /// Do we have an undo point different from the given state?
     pub fn has_undo(&self, current_state: &State) -> bool {
         match self.undos.len() {
@@ -96,12 +123,27 @@ where
         self.flux.is_some()
     }
 
+    /// Is there something to redo, i.e. has the state not changed since the latest undo?
+    pub fn has_redo(&self, current_state: &State) -> bool {
+        !self.redos.is_empty() && self.undos.back() == Some(current_state)
+    }
+
+    /// Forget all undo and redo points.
+    pub fn clear(&mut self) {
+        self.undos.clear();
+        self.redos.clear();
+        self.flux = None;
+    }
+
+    /// Returns the state to go back to, if any.
     pub fn undo(&mut self, current_state: &State) -> Option<&State> {
         if self.has_undo(current_state) {
             self.flux = None;
 
             if self.undos.back() == Some(current_state) {
-                self.undos.pop_back();
+                self.redos.push(self.undos.pop_back().unwrap());
+            } else {
+                self.redos.push(current_state.clone());
             }
 
             // Note: we keep the undo point intact.
@@ -111,12 +153,25 @@ where
         }
     }
 
+    /// Returns the undone state to go forward to again, if any.
+    pub fn redo(&mut self, current_state: &State) -> Option<&State> {
+        if self.has_redo(current_state) {
+            self.flux = None;
+            let state = self.redos.pop().unwrap();
+            self.undos.push_back(state);
+            self.undos.back()
+        } else {
+            None
+        }
+    }
+
     /// Add an undo point if, and only if, there has been a change since the latest undo point.
     ///
     /// * `time`: current time in seconds.
     pub fn add_undo(&mut self, current_state: &State) {
         if self.undos.back() != Some(current_state) {
             self.undos.push_back(current_state.clone());
+            self.redos.clear();
         }
         while self.undos.len() > self.settings.max_undos {
             self.undos.pop_front();
@@ -124,6 +179,21 @@ where
         self.flux = None;
     }
 
+    /// Like [`Self::feed_state`], for when the state is the same as when it was last fed.
+    ///
+    /// Use this when the state is expensive to build, but you know it hasn't changed.
+    ///
+    /// * `current_time`: current time in seconds.
+    pub fn feed_time(&mut self, current_time: f64) {
+        if let Some(flux) = &self.flux {
+            let time_since_latest_change = (current_time - flux.latest_change_time) as f32;
+            if time_since_latest_change >= self.settings.stable_time {
+                let latest_state = flux.latest_state.clone();
+                self.add_undo(&latest_state);
+            }
+        }
+    }
+
     /// Call this as often as you want (e.g. every frame)
     /// and `Undoer` will determine if a new undo point should be created.
     ///
@@ -139,6 +209,7 @@ where
                 if latest_undo == current_state {
                     self.flux = None;
                 } else {
+                    self.redos.clear();
                     match self.flux.as_mut() {
                         None => {
                             self.flux = Some(Flux {
@@ -170,3 +241,39 @@ where
         }
     }
 }
+
+#[test]
+fn test_undo_redo() {
+    let mut undoer = Undoer::default();
+    undoer.feed_state(0.0, &"a");
+    undoer.add_undo(&"ab");
+    undoer.add_undo(&"abc");
+
+    assert_eq!(undoer.undo(&"abc"), Some(&"ab"));
+    assert_eq!(undoer.undo(&"ab"), Some(&"a"));
+    assert!(undoer.has_redo(&"a"));
+    assert_eq!(undoer.redo(&"a"), Some(&"ab"));
+    assert_eq!(undoer.redo(&"ab"), Some(&"abc"));
+    assert_eq!(undoer.redo(&"abc"), None);
+
+    // A new change after undoing means there is nothing to redo:
+    undoer.undo(&"abc");
+    undoer.feed_state(1.0, &"abX");
+    assert!(!undoer.has_redo(&"abX"));
+    assert_eq!(undoer.redo(&"ab"), None);
+
+    // Undoing unsaved changes can be redone:
+    assert_eq!(undoer.undo(&"abX"), Some(&"ab"));
+    assert_eq!(undoer.redo(&"ab"), Some(&"abX"));
+
+    // An unchanged state becomes an undo point once it has been stable for long enough:
+    undoer.feed_state(2.0, &"abXY");
+    undoer.feed_time(2.5);
+    assert!(undoer.is_in_flux());
+    undoer.feed_time(3.0);
+    assert!(!undoer.is_in_flux());
+    assert_eq!(undoer.latest_undo(), Some(&"abXY"));
+
+    undoer.clear();
+    assert!(!undoer.has_undo(&"abX"));
+}
@@ -1,8 +1,10 @@
 use epaint::mutex::Arc;
 
 use crate::{
-    style::WidgetVisuals, text::LayoutJob, Align, Color32, FontFamily, FontSelection, Galley, Pos2,
-    Style, TextStyle, Ui, Visuals,
+    style::WidgetVisuals,
+    text::{LayoutJob, TextOverflow},
+    Align, Color32, FontFamily, FontSelection, FontWeight, Galley, Pos2, Response, Style,
+    TextStyle, Ui, Visuals,
 };
 
 /// Text and optional style choices for it.
@@ -33,8 +35,11 @@ pub struct RichText {
     weak: bool,
     strikethrough: bool,
     underline: bool,
+    weight: FontWeight,
     italics: bool,
     raised: bool,
+    line_height: Option<f32>,
+    extra_letter_spacing: f32,
 }
 
 impl From<&str> for RichText {
@@ -58,6 +63,13 @@ impl From<String> for RichText {
     }
 }
 
+impl From<std::borrow::Cow<'_, str>> for RichText {
+    #[inline]
+    fn from(text: std::borrow::Cow<'_, str>) -> Self {
+        RichText::new(text)
+    }
+}
+
 impl RichText {
     #[inline]
     pub fn new(text: impl Into<String>) -> Self {
@@ -171,7 +183,19 @@ impl RichText {
         self
     }
 
+    /// Use a bolder (or lighter) variant of the font.
+    ///
+    /// This only has an effect if you have added such a variant with [`crate::FontDefinitions::variants`].
+    /// See also [`Self::strong`].
+    #[inline]
+    pub fn weight(mut self, weight: FontWeight) -> Self {
+        self.weight = weight;
+        self
+    }
+
     /// Tilt the characters to the right.
+    ///
+    /// Uses the italic variant of the font if there is one in [`crate::FontDefinitions::variants`].
     #[inline]
     pub fn italics(mut self) -> Self {
         self.italics = true;
@@ -197,6 +221,20 @@ impl RichText {
         self
     }
 
+    /// Height of each row of text (in points), instead of the height of the font.
+    #[inline]
+    pub fn line_height(mut self, line_height: Option<f32>) -> Self {
+        self.line_height = line_height;
+        self
+    }
+
+    /// Extra space after each character (in points).
+    #[inline]
+    pub fn extra_letter_spacing(mut self, extra_letter_spacing: f32) -> Self {
+        self.extra_letter_spacing = extra_letter_spacing;
+        self
+    }
+
     /// Fill-color behind the text.
     #[inline]
     pub fn background_color(mut self, background_color: impl Into<Color32>) -> Self {
@@ -247,8 +285,11 @@ impl RichText {
             weak: _,   // already used by `get_text_color`
             strikethrough,
             underline,
+            weight,
             italics,
             raised,
+            line_height,
+            extra_letter_spacing,
         } = self;
 
         let job_has_color = text_color.is_some();
@@ -296,10 +337,13 @@ impl RichText {
             font_id,
             color: text_color,
             background: background_color,
+            weight,
             italics,
             underline,
             strikethrough,
             valign,
+            line_height,
+            extra_letter_spacing,
         };
 
         let job = LayoutJob::single_section(text, text_format);
@@ -549,7 +593,33 @@ impl WidgetText {
         available_width: f32,
         fallback_font: impl Into<FontSelection>,
     ) -> WidgetTextGalley {
-        let wrap = wrap.unwrap_or_else(|| ui.wrap_text());
+        self.into_galley_elided(
+            ui,
+            wrap,
+            available_width,
+            fallback_font,
+            usize::MAX,
+            TextOverflow::default(),
+        )
+    }
+
+    /// Like [`Self::into_galley`], but showing at most `max_rows` rows,
+    /// eliding the rest of the text as decided by `overflow`.
+    ///
+    /// Unless `wrap` says otherwise, the text will wrap if `max_rows` is set,
+    /// so that it is also elided to fit the available width.
+    ///
+    /// See [`crate::text::LayoutJob::max_rows`].
+    pub fn into_galley_elided(
+        self,
+        ui: &Ui,
+        wrap: Option<bool>,
+        available_width: f32,
+        fallback_font: impl Into<FontSelection>,
+        max_rows: usize,
+        overflow: TextOverflow,
+    ) -> WidgetTextGalley {
+        let wrap = wrap.unwrap_or_else(|| max_rows < usize::MAX || ui.wrap_text());
         let wrap_width = if wrap { available_width } else { f32::INFINITY };
 
         match self {
@@ -557,6 +627,8 @@ impl WidgetText {
                 let valign = ui.layout().vertical_align();
                 let mut text_job = text.into_text_job(ui.style(), fallback_font.into(), valign);
                 text_job.job.wrap_width = wrap_width;
+                text_job.job.max_rows = max_rows;
+                text_job.job.overflow = overflow;
                 WidgetTextGalley {
                     galley: ui.fonts().layout_job(text_job.job),
                     galley_has_color: text_job.job_has_color,
@@ -564,6 +636,8 @@ impl WidgetText {
             }
             Self::LayoutJob(mut job) => {
                 job.wrap_width = wrap_width;
+                job.max_rows = max_rows;
+                job.overflow = overflow;
                 WidgetTextGalley {
                     galley: ui.fonts().layout_job(job),
                     galley_has_color: true,
@@ -598,6 +672,13 @@ impl From<String> for WidgetText {
     }
 }
 
+impl From<std::borrow::Cow<'_, str>> for WidgetText {
+    #[inline]
+    fn from(text: std::borrow::Cow<'_, str>) -> Self {
+        Self::RichText(RichText::new(text))
+    }
+}
+
 impl From<RichText> for WidgetText {
     #[inline]
     fn from(rich_text: RichText) -> Self {
@@ -665,6 +746,16 @@ impl WidgetTextGalley {
         &self.galley
     }
 
+    /// If the text was elided (see [`crate::text::LayoutJob::max_rows`]),
+    /// show all of it when hovering the response.
+    pub fn on_hover_elided(&self, response: Response) -> Response {
+        if self.galley.elided {
+            response.on_hover_text(self.text())
+        } else {
+            response
+        }
+    }
+
     /// Use the colors in the original [`WidgetText`] if any,
     /// else fall back to the one specified by the [`WidgetVisuals`].
     pub fn paint_with_visuals(
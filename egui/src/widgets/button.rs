@@ -1,4 +1,4 @@
-use crate::*;
+use crate::{text::TextOverflow, *};
 
 /// Clickable button with text.
 ///
@@ -22,6 +22,8 @@ use crate::*;
 pub struct Button {
     text: WidgetText,
     wrap: Option<bool>,
+    max_rows: usize,
+    overflow: TextOverflow,
     /// None means default for interact
     fill: Option<Color32>,
     stroke: Option<Stroke>,
@@ -37,6 +39,8 @@ impl Button {
         Self {
             text: text.into(),
             wrap: None,
+            max_rows: usize::MAX,
+            overflow: TextOverflow::default(),
             fill: None,
             stroke: None,
             sense: Sense::click(),
@@ -62,6 +66,8 @@ impl Button {
             small: false,
             frame: None,
             wrap: None,
+            max_rows: usize::MAX,
+            overflow: TextOverflow::default(),
             min_size: Vec2::ZERO,
             image: Some(widgets::Image::new(texture_id, size)),
         }
@@ -80,6 +86,24 @@ impl Button {
         self
     }
 
+    /// Show at most this many rows of text, eliding the rest as decided by [`Self::overflow`].
+    ///
+    /// The full text is shown when hovering the button if it was elided.
+    /// Unless [`Self::wrap`] is set, this also turns on wrapping,
+    /// so that the text is elided to fit the available width too.
+    #[inline]
+    pub fn max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+
+    /// How to elide the text that does not fit in [`Self::max_rows`].
+    #[inline]
+    pub fn overflow(mut self, overflow: TextOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
     /// Override background fill color. Note that this will override any on-hover effects.
     /// Calling this will also turn on the frame.
     pub fn fill(mut self, fill: impl Into<Color32>) -> Self {
@@ -127,6 +151,8 @@ impl Widget for Button {
         let Button {
             text,
             wrap,
+            max_rows,
+            overflow,
             fill,
             stroke,
             sense,
@@ -145,7 +171,8 @@ impl Widget for Button {
         let total_extra = button_padding + button_padding;
 
         let wrap_width = ui.available_width() - total_extra.x;
-        let text = text.into_galley(ui, wrap, wrap_width, TextStyle::Button);
+        let text =
+            text.into_galley_elided(ui, wrap, wrap_width, TextStyle::Button, max_rows, overflow);
 
         let mut desired_size = text.size() + 2.0 * button_padding;
         if !small {
@@ -159,6 +186,7 @@ impl Widget for Button {
         }
 
         let (rect, response) = ui.allocate_at_least(desired_size, sense);
+        let response = text.on_hover_elided(response);
         response.widget_info(|| WidgetInfo::labeled(WidgetType::Button, text.text()));
 
         if ui.is_rect_visible(rect) {
@@ -180,7 +208,7 @@ impl Widget for Button {
                 let stroke = stroke.unwrap_or(visuals.bg_stroke);
                 ui.painter().rect(
                     rect.expand(visuals.expansion),
-                    visuals.corner_radius,
+                    visuals.rounding,
                     fill,
                     stroke,
                 );
@@ -265,9 +293,11 @@ impl<'a> Widget for Checkbox<'a> {
             let (small_icon_rect, big_icon_rect) = ui.spacing().icon_rectangles(rect);
             ui.painter().add(epaint::RectShape {
                 rect: big_icon_rect.expand(visuals.expansion),
-                corner_radius: visuals.corner_radius,
+                rounding: visuals.rounding,
                 fill: visuals.bg_fill,
+                fill_gradient: None,
                 stroke: visuals.bg_stroke,
+                side_strokes: None,
             });
 
             if *checked {
@@ -361,6 +391,7 @@ impl Widget for RadioButton {
                 center: big_icon_rect.center(),
                 radius: big_icon_rect.width() / 2.0 + visuals.expansion,
                 fill: visuals.bg_fill,
+                fill_gradient: None,
                 stroke: visuals.bg_stroke,
             });
 
@@ -369,6 +400,7 @@ impl Widget for RadioButton {
                     center: small_icon_rect.center(),
                     radius: small_icon_rect.width() / 3.0,
                     fill: visuals.fg_stroke.color, // Intentional to use stroke and not fill
+                    fill_gradient: None,
                     // fill: ui.visuals().selection.stroke.color, // too much color
                     stroke: Default::default(),
                 });
@@ -455,9 +487,14 @@ impl Widget for ImageButton {
         response.widget_info(|| WidgetInfo::new(WidgetType::ImageButton));
 
         if ui.is_rect_visible(rect) {
-            let (expansion, corner_radius, fill, stroke) = if selected {
+            let (expansion, rounding, fill, stroke) = if selected {
                 let selection = ui.visuals().selection;
-                (-padding, 0.0, selection.bg_fill, selection.stroke)
+                (
+                    -padding,
+                    Rounding::none(),
+                    selection.bg_fill,
+                    selection.stroke,
+                )
             } else if frame {
                 let visuals = ui.style().interact(&response);
                 let expansion = if response.hovered {
@@ -467,7 +504,7 @@ impl Widget for ImageButton {
                 };
                 (
                     expansion,
-                    visuals.corner_radius,
+                    visuals.rounding,
                     visuals.bg_fill,
                     visuals.bg_stroke,
                 )
@@ -477,7 +514,7 @@ impl Widget for ImageButton {
 
             // Draw frame background (for transparent images):
             ui.painter()
-                .rect_filled(rect.expand2(expansion), corner_radius, fill);
+                .rect_filled(rect.expand2(expansion), rounding, fill);
 
             let image_rect = ui
                 .layout()
@@ -487,7 +524,7 @@ impl Widget for ImageButton {
 
             // Draw frame outline:
             ui.painter()
-                .rect_stroke(rect.expand2(expansion), corner_radius, stroke);
+                .rect_stroke(rect.expand2(expansion), rounding, stroke);
         }
 
         response
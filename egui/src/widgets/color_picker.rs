@@ -61,9 +61,11 @@ fn show_hsva(ui: &mut Ui, color: Hsva, desired_size: Vec2) -> Response {
         } else {
             ui.painter().add(RectShape {
                 rect,
-                corner_radius: 2.0,
+                rounding: Rounding::same(2.0),
                 fill: color.into(),
+                fill_gradient: None,
                 stroke: Stroke::new(3.0, color.to_opaque()),
+                side_strokes: None,
             });
         }
     }
@@ -90,9 +92,9 @@ fn color_button(ui: &mut Ui, color: Color32, open: bool) -> Response {
         ui.painter().rect_filled(left_half, 0.0, color);
         ui.painter().rect_filled(right_half, 0.0, color.to_opaque());
 
-        let corner_radius = visuals.corner_radius.at_most(2.0);
+        let rounding = visuals.rounding.at_most(2.0);
         ui.painter()
-            .rect_stroke(rect, corner_radius, (2.0, visuals.bg_fill)); // fill is intentional, because default style has no border
+            .rect_stroke(rect, rounding, (2.0, visuals.bg_fill)); // fill is intentional, because default style has no border
     }
 
     response
@@ -200,6 +202,7 @@ fn color_slider_2d(
             center: pos2(x, y),
             radius: rect.width() / 12.0,
             fill: picked_color,
+            fill_gradient: None,
             stroke: Stroke::new(visuals.fg_stroke.width, contrast_color(picked_color)),
         });
     }
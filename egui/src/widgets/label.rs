@@ -1,4 +1,4 @@
-use crate::{widget_text::WidgetTextGalley, *};
+use crate::{text::TextOverflow, widget_text::WidgetTextGalley, *};
 
 /// Static text.
 ///
@@ -16,6 +16,8 @@ use crate::{widget_text::WidgetTextGalley, *};
 pub struct Label {
     text: WidgetText,
     wrap: Option<bool>,
+    max_rows: usize,
+    overflow: TextOverflow,
     sense: Sense,
 }
 
@@ -24,6 +26,8 @@ impl Label {
         Self {
             text: text.into(),
             wrap: None,
+            max_rows: usize::MAX,
+            overflow: TextOverflow::default(),
             sense: Sense::focusable_noninteractive(),
         }
     }
@@ -45,6 +49,24 @@ impl Label {
         self
     }
 
+    /// Show at most this many rows of text, eliding the rest as decided by [`Self::overflow`].
+    ///
+    /// The full text is shown when hovering the label if it was elided.
+    /// Unless [`Self::wrap`] is set, this also turns on wrapping,
+    /// so that the text is elided to fit the available width too.
+    #[inline]
+    pub fn max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+
+    /// How to elide the text that does not fit in [`Self::max_rows`].
+    #[inline]
+    pub fn overflow(mut self, overflow: TextOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
     /// Make the label respond to clicks and/or drags.
     ///
     /// By default, a label is inert and does not respond to click or drags.
@@ -87,8 +109,12 @@ impl Label {
         let mut text_job = self
             .text
             .into_text_job(ui.style(), FontSelection::Default, valign);
+        text_job.job.max_rows = self.max_rows;
+        text_job.job.overflow = self.overflow;
 
-        let should_wrap = self.wrap.unwrap_or_else(|| ui.wrap_text());
+        let should_wrap = self
+            .wrap
+            .unwrap_or_else(|| self.max_rows < usize::MAX || ui.wrap_text());
         let available_width = ui.available_width();
 
         if should_wrap
@@ -155,35 +181,74 @@ impl Label {
     }
 }
 
-impl Widget for Label {
-    fn ui(self, ui: &mut Ui) -> Response {
+impl Label {
+    /// Add the label, then call `add_contents` for each [`epaint::text::InlineBox`] in its text
+    /// with the section index and screen rectangle of the box.
+    ///
+    /// Use this to paint images or put widgets inside the text,
+    /// see [`epaint::text::LayoutJob::append_inline_box`].
+    ///
+    /// ```
+    /// # egui::__run_test_ui(|ui| {
+    /// use egui::text::{InlineBox, LayoutJob, TextFormat};
+    /// let mut job = LayoutJob::default();
+    /// job.append("Press ", 0.0, TextFormat::default());
+    /// job.append_inline_box(InlineBox::new(egui::vec2(12.0, 12.0)), TextFormat::default());
+    /// job.append(" to continue", 0.0, TextFormat::default());
+    /// egui::Label::new(job).show_with_inline_boxes(ui, |ui, _section_index, rect| {
+    ///     ui.painter().circle_filled(rect.center(), 6.0, egui::Color32::RED);
+    /// });
+    /// # });
+    /// ```
+    pub fn show_with_inline_boxes(
+        self,
+        ui: &mut Ui,
+        mut add_contents: impl FnMut(&mut Ui, usize, Rect),
+    ) -> Response {
         let (pos, text_galley, response) = self.layout_in_ui(ui);
-        response.widget_info(|| WidgetInfo::labeled(WidgetType::Label, text_galley.text()));
+        let response = text_galley.on_hover_elided(response);
+        let inline_boxes = text_galley.galley.inline_boxes.clone();
+        paint_label(ui, pos, text_galley, &response);
+        for (section_index, rect) in inline_boxes {
+            add_contents(ui, section_index, rect.translate(pos.to_vec2()));
+        }
+        response
+    }
+}
 
-        if ui.is_rect_visible(response.rect) {
-            let response_color = ui.style().interact(&response).text_color();
+fn paint_label(ui: &Ui, pos: Pos2, text_galley: WidgetTextGalley, response: &Response) {
+    response.widget_info(|| WidgetInfo::labeled(WidgetType::Label, text_galley.text()));
 
-            let underline = if response.has_focus() {
-                Stroke::new(1.0, response_color)
-            } else {
-                Stroke::none()
-            };
+    if ui.is_rect_visible(response.rect) {
+        let response_color = ui.style().interact(response).text_color();
 
-            let override_text_color = if text_galley.galley_has_color {
-                None
-            } else {
-                Some(response_color)
-            };
+        let underline = if response.has_focus() {
+            Stroke::new(1.0, response_color)
+        } else {
+            Stroke::none()
+        };
 
-            ui.painter().add(epaint::TextShape {
-                pos,
-                galley: text_galley.galley,
-                override_text_color,
-                underline,
-                angle: 0.0,
-            });
-        }
+        let override_text_color = if text_galley.galley_has_color {
+            None
+        } else {
+            Some(response_color)
+        };
+
+        ui.painter().add(epaint::TextShape {
+            pos,
+            galley: text_galley.galley,
+            override_text_color,
+            underline,
+            angle: 0.0,
+        });
+    }
+}
 
+impl Widget for Label {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let (pos, text_galley, response) = self.layout_in_ui(ui);
+        let response = text_galley.on_hover_elided(response);
+        paint_label(ui, pos, text_galley, &response);
         response
     }
 }
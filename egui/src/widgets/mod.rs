@@ -108,7 +108,7 @@ pub fn reset_button_with<T: PartialEq>(ui: &mut Ui, value: &mut T, reset_value:
 // ----------------------------------------------------------------------------
 
 pub fn stroke_ui(ui: &mut crate::Ui, stroke: &mut epaint::Stroke, text: &str) {
-    let epaint::Stroke { width, color } = stroke;
+    let epaint::Stroke { width, color, .. } = stroke;
     ui.horizontal(|ui| {
         ui.add(DragValue::new(width).speed(0.1).clamp_range(0.0..=5.0))
             .on_hover_text("Width");
@@ -124,16 +124,41 @@ pub fn stroke_ui(ui: &mut crate::Ui, stroke: &mut epaint::Stroke, text: &str) {
 }
 
 pub(crate) fn shadow_ui(ui: &mut Ui, shadow: &mut epaint::Shadow, text: &str) {
-    let epaint::Shadow { extrusion, color } = shadow;
+    let epaint::Shadow {
+        offset,
+        blur,
+        spread,
+        color,
+        inner,
+    } = shadow;
     ui.horizontal(|ui| {
         ui.label(text);
-        ui.add(
-            DragValue::new(extrusion)
-                .speed(1.0)
-                .clamp_range(0.0..=100.0),
-        )
-        .on_hover_text("Extrusion");
+        ui.add(DragValue::new(&mut offset.x).speed(0.5))
+            .on_hover_text("Offset x");
+        ui.add(DragValue::new(&mut offset.y).speed(0.5))
+            .on_hover_text("Offset y");
+        ui.add(DragValue::new(blur).speed(0.5).clamp_range(0.0..=100.0))
+            .on_hover_text("Blur");
+        ui.add(DragValue::new(spread).speed(0.5).clamp_range(0.0..=100.0))
+            .on_hover_text("Spread");
         ui.color_edit_button_srgba(color);
+        ui.checkbox(inner, "inner");
+    });
+}
+
+pub(crate) fn rounding_ui(ui: &mut Ui, rounding: &mut epaint::Rounding, text: &str) {
+    let epaint::Rounding { nw, ne, sw, se } = rounding;
+    ui.horizontal(|ui| {
+        for (radius, corner) in [
+            (nw, "North-West (left top)"),
+            (ne, "North-East (right top)"),
+            (sw, "South-West (left bottom)"),
+            (se, "South-East (right bottom)"),
+        ] {
+            ui.add(DragValue::new(radius).speed(0.1).clamp_range(0.0..=50.0))
+                .on_hover_text(corner);
+        }
+        ui.label(text);
     });
 }
 
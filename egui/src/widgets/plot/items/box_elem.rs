@@ -1,5 +1,5 @@
 use crate::emath::NumExt;
-use crate::epaint::{Color32, RectShape, Shape, Stroke};
+use crate::epaint::{Color32, RectShape, Rounding, Shape, Stroke};
 
 use super::{add_rulers_and_text, highlighted_color, Orientation, PlotConfig, RectElement};
 use crate::plot::{BoxPlot, ScreenTransform, Value};
@@ -152,9 +152,11 @@ impl BoxElem {
         );
         let rect = Shape::Rect(RectShape {
             rect,
-            corner_radius: 0.0,
+            rounding: Rounding::none(),
             fill,
+            fill_gradient: None,
             stroke,
+            side_strokes: None,
         });
         shapes.push(rect);
 
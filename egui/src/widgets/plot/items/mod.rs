@@ -857,6 +857,7 @@ impl PlotItem for Points {
                             center,
                             radius,
                             fill,
+                            fill_gradient: None,
                             stroke,
                         }));
                     }
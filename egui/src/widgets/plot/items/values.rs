@@ -93,18 +93,17 @@ impl LineStyle {
                         if highlight {
                             radius *= 2f32.sqrt();
                         }
-                        shapes.extend(Shape::dotted_line(&line, stroke.color, *spacing, radius));
+                        stroke.width = 2.0 * radius;
+                        shapes.push(Shape::line(line, stroke.dotted(*spacing)));
                     }
                     LineStyle::Dashed { length } => {
                         if highlight {
                             stroke.width *= 2.0;
                         }
                         let golden_ratio = (5.0_f32.sqrt() - 1.0) / 2.0; // 0.61803398875
-                        shapes.extend(Shape::dashed_line(
-                            &line,
-                            stroke,
-                            *length,
-                            length * golden_ratio,
+                        shapes.push(Shape::line(
+                            line,
+                            stroke.dashed(*length, length * golden_ratio),
                         ));
                     }
                 }
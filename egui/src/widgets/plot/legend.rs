@@ -122,6 +122,7 @@ impl LegendEntry {
             center: icon_rect.center(),
             radius: icon_size * 0.5,
             fill: visuals.bg_fill,
+            fill_gradient: None,
             stroke: visuals.bg_stroke,
         });
 
@@ -240,10 +241,11 @@ impl Widget for &mut LegendWidget {
             .scope(|ui| {
                 let background_frame = Frame {
                     margin: vec2(8.0, 4.0),
-                    corner_radius: ui.style().visuals.window_corner_radius,
+                    rounding: ui.style().visuals.window_rounding,
                     shadow: epaint::Shadow::default(),
                     fill: ui.style().visuals.extreme_bg_color,
                     stroke: ui.style().visuals.window_stroke(),
+                    side_strokes: None,
                 }
                 .multiply_with_opacity(config.background_alpha);
                 background_frame
@@ -386,9 +386,11 @@ impl Plot {
         if show_background {
             ui.painter().sub_region(rect).add(epaint::RectShape {
                 rect,
-                corner_radius: 2.0,
+                rounding: Rounding::same(2.0),
                 fill: ui.visuals().extreme_bg_color,
+                fill_gradient: None,
                 stroke: ui.visuals().widgets.noninteractive.bg_stroke,
+                side_strokes: None,
             });
         }
 
@@ -1,4 +1,4 @@
-use crate::*;
+use crate::{text::TextOverflow, *};
 
 /// One out of several alternatives, either selected or not.
 /// Will mark selected items with a different background color.
@@ -25,6 +25,8 @@ use crate::*;
 pub struct SelectableLabel {
     selected: bool,
     text: WidgetText,
+    max_rows: usize,
+    overflow: TextOverflow,
 }
 
 impl SelectableLabel {
@@ -32,23 +34,49 @@ impl SelectableLabel {
         Self {
             selected,
             text: text.into(),
+            max_rows: usize::MAX,
+            overflow: TextOverflow::default(),
         }
     }
+
+    /// Show at most this many rows of text, eliding the rest as decided by [`Self::overflow`].
+    ///
+    /// The full text is shown when hovering the label if it was elided.
+    /// This also turns on wrapping, so that the text is elided to fit the available width too.
+    #[inline]
+    pub fn max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+
+    /// How to elide the text that does not fit in [`Self::max_rows`].
+    #[inline]
+    pub fn overflow(mut self, overflow: TextOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
 }
 
 impl Widget for SelectableLabel {
     fn ui(self, ui: &mut Ui) -> Response {
-        let Self { selected, text } = self;
+        let Self {
+            selected,
+            text,
+            max_rows,
+            overflow,
+        } = self;
 
         let button_padding = ui.spacing().button_padding;
         let total_extra = button_padding + button_padding;
 
         let wrap_width = ui.available_width() - total_extra.x;
-        let text = text.into_galley(ui, None, wrap_width, TextStyle::Button);
+        let text =
+            text.into_galley_elided(ui, None, wrap_width, TextStyle::Button, max_rows, overflow);
 
         let mut desired_size = total_extra + text.size();
         desired_size.y = desired_size.y.at_least(ui.spacing().interact_size.y);
         let (rect, response) = ui.allocate_at_least(desired_size, Sense::click());
+        let response = text.on_hover_elided(response);
         response.widget_info(|| {
             WidgetInfo::selected(WidgetType::SelectableLabel, selected, text.text())
         });
@@ -64,12 +92,8 @@ impl Widget for SelectableLabel {
             if selected || response.hovered() || response.has_focus() {
                 let rect = rect.expand(visuals.expansion);
 
-                ui.painter().rect(
-                    rect,
-                    visuals.corner_radius,
-                    visuals.bg_fill,
-                    visuals.bg_stroke,
-                );
+                ui.painter()
+                    .rect(rect, visuals.rounding, visuals.bg_fill, visuals.bg_stroke);
             }
 
             text.paint_with_visuals(ui.painter(), text_pos, &visuals);
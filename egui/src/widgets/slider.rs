@@ -355,13 +355,15 @@ impl<'a> Slider<'a> {
             let visuals = ui.style().interact(response);
             ui.painter().add(epaint::RectShape {
                 rect: rail_rect,
-                corner_radius: ui.visuals().widgets.inactive.corner_radius,
+                rounding: ui.visuals().widgets.inactive.rounding,
                 fill: ui.visuals().widgets.inactive.bg_fill,
+                fill_gradient: None,
                 // fill: visuals.bg_fill,
                 // fill: ui.visuals().extreme_bg_color,
                 stroke: Default::default(),
                 // stroke: visuals.bg_stroke,
                 // stroke: ui.visuals().widgets.inactive.bg_stroke,
+                side_strokes: None,
             });
 
             let center = self.marker_center(position_1d, &rail_rect);
@@ -370,6 +372,7 @@ impl<'a> Slider<'a> {
                 center,
                 radius: self.handle_radius(rect) + visuals.expansion,
                 fill: visuals.bg_fill,
+                fill_gradient: None,
                 stroke: visuals.fg_stroke,
             });
         }
@@ -1,10 +1,27 @@
-use epaint::mutex::Arc;
+use std::{
+    borrow::Cow,
+    ops::{Range, RangeInclusive},
+};
 
-use epaint::text::{cursor::*, Galley, LayoutJob};
+use epaint::mutex::{Arc, Mutex};
 
-use crate::{output::OutputEvent, *};
+use epaint::text::{cursor::*, Galley, LayoutJob};
 
-use super::{CCursorRange, CursorRange, TextEditOutput, TextEditState};
+use crate::{output::OutputEvent, widget_text::WidgetTextGalley, *};
+
+use super::{
+    code_editor::{
+        foldable_paragraphs, gutter_ui, gutter_width, matching_brackets, move_folds,
+        newline_with_indentation, row_paragraphs, unfold_paragraphs, CodeEditorCache,
+    },
+    find_replace::{
+        find_query_id, find_replace_bar, find_replace_bar_has_focus, highlight_matches, FindAction,
+    },
+    state::current_undo_state,
+    text_window::{layout_paragraphs, ParagraphHeights, TextWindow},
+    CCursorRange, CursorRange, FindReplaceState, TextEditOutput, TextEditState, TextEditUndoer,
+    TextSnapshot,
+};
 
 /// A text region that the user can edit the contents of.
 ///
@@ -64,6 +81,13 @@ pub struct TextEdit<'t> {
     desired_height_rows: usize,
     lock_focus: bool,
     cursor_at_end: bool,
+    virtualized: bool,
+    find_replace: bool,
+    line_numbers: bool,
+    highlight_current_line: bool,
+    match_brackets: bool,
+    auto_indent: bool,
+    folding: bool,
 }
 
 impl<'t> WidgetWithState for TextEdit<'t> {
@@ -109,6 +133,13 @@ impl<'t> TextEdit<'t> {
             desired_height_rows: 4,
             lock_focus: false,
             cursor_at_end: true,
+            virtualized: false,
+            find_replace: false,
+            line_numbers: false,
+            highlight_current_line: false,
+            match_brackets: false,
+            auto_indent: false,
+            folding: false,
         }
     }
 
@@ -116,8 +147,19 @@ impl<'t> TextEdit<'t> {
     /// By default it comes with:
     /// - monospaced font
     /// - focus lock
+    /// - line numbers, see [`Self::line_numbers`]
+    /// - current line highlighting, see [`Self::highlight_current_line`]
+    /// - bracket matching, see [`Self::match_brackets`]
+    /// - auto-indentation, see [`Self::auto_indent`]
+    /// - code folding, see [`Self::folding`]
     pub fn code_editor(self) -> Self {
-        self.font(TextStyle::Monospace).lock_focus(true)
+        self.font(TextStyle::Monospace)
+            .lock_focus(true)
+            .line_numbers(true)
+            .highlight_current_line(true)
+            .match_brackets(true)
+            .auto_indent(true)
+            .folding(true)
     }
 
     /// Use if you want to set an explicit `Id` for this widget.
@@ -246,6 +288,96 @@ impl<'t> TextEdit<'t> {
         self.cursor_at_end = b;
         self
     }
+
+    /// For large documents: only lay out and paint the paragraphs that are visible,
+    /// e.g. inside of a [`ScrollArea`], and the ones around the text cursor.
+    ///
+    /// Each paragraph is laid out on its own, so unchanged paragraphs are found in the
+    /// [`crate::Fonts`] cache. A custom [`Self::layouter`] is therefore called once for each
+    /// visible paragraph (without the `\n`) instead of for the whole text.
+    /// Paragraphs that have not been laid out yet are assumed to be a single row high.
+    ///
+    /// This is best combined with a [`TextBuffer`] that can be indexed and edited cheaply,
+    /// like `ropey::Rope` (with the `rope` feature).
+    ///
+    /// While something is [folded](Self::folding), the whole text is looked at each frame,
+    /// and [`Self::match_brackets`] only finds brackets in the visible paragraphs.
+    ///
+    /// Only for multiline text. Default is `false`.
+    ///
+    /// ```
+    /// # egui::__run_test_ui(|ui| {
+    /// # let mut my_document = String::new();
+    /// egui::ScrollArea::vertical().show(ui, |ui| {
+    ///     ui.add(egui::TextEdit::multiline(&mut my_document).virtualized(true));
+    /// });
+    /// # });
+    /// ```
+    pub fn virtualized(mut self, virtualized: bool) -> Self {
+        self.virtualized = virtualized;
+        self
+    }
+
+    /// Let the user search the text in a bar opened with Cmd/Ctrl+F,
+    /// and replace matches in it with Cmd/Ctrl+H.
+    ///
+    /// Enter and Shift+Enter select the next and previous match, and Escape closes the bar.
+    /// Matches are highlighted by laying out the text again with a background color for them.
+    /// Regular expressions need the `regex` feature.
+    ///
+    /// The state of the bar is in [`TextEditState::find_replace`].
+    ///
+    /// Only for multiline text. Default is `false`.
+    pub fn find_replace(mut self, find_replace: bool) -> Self {
+        self.find_replace = find_replace;
+        self
+    }
+
+    /// Show the number of each line (paragraph) in a gutter to the left of the text,
+    /// next to the first row of the line.
+    ///
+    /// Only for multiline text. Default is `false`.
+    pub fn line_numbers(mut self, line_numbers: bool) -> Self {
+        self.line_numbers = line_numbers;
+        self
+    }
+
+    /// Give the line (paragraph) with the cursor a background while the `TextEdit` has focus.
+    ///
+    /// Only for multiline text. Default is `false`.
+    pub fn highlight_current_line(mut self, highlight_current_line: bool) -> Self {
+        self.highlight_current_line = highlight_current_line;
+        self
+    }
+
+    /// Outline the bracket next to the cursor and the bracket it pairs with,
+    /// for `()`, `[]` and `{}`.
+    ///
+    /// Default is `false`.
+    pub fn match_brackets(mut self, match_brackets: bool) -> Self {
+        self.match_brackets = match_brackets;
+        self
+    }
+
+    /// When pressing Enter, start the new line with the indentation of the previous one,
+    /// and indent one more level after an opening bracket.
+    ///
+    /// Default is `false`.
+    pub fn auto_indent(mut self, auto_indent: bool) -> Self {
+        self.auto_indent = auto_indent;
+        self
+    }
+
+    /// Let the user fold away the lines after a line that are indented more than it,
+    /// by clicking the markers in the gutter.
+    ///
+    /// Moving the cursor into a folded region unfolds it.
+    ///
+    /// Only for multiline text. Default is `false`.
+    pub fn folding(mut self, folding: bool) -> Self {
+        self.folding = folding;
+        self
+    }
 }
 
 // ----------------------------------------------------------------------------
@@ -279,11 +411,30 @@ impl<'t> TextEdit<'t> {
         let where_to_put_background = ui.painter().add(Shape::Noop);
 
         let margin = self.margin;
-        let max_rect = ui.available_rect_before_wrap().shrink2(margin);
+        let gutter_width = if self.multiline && (self.line_numbers || self.folding) {
+            let font_id = self.font_selection.clone().resolve(ui.style());
+            let paragraph_count = self.text.paragraph_count();
+            gutter_width(
+                ui,
+                &font_id,
+                paragraph_count,
+                self.line_numbers,
+                self.folding,
+            )
+        } else {
+            0.0
+        };
+        let mut max_rect = ui.available_rect_before_wrap().shrink2(margin);
+        max_rect.min.x += gutter_width; // the gutter is part of the margin
         let mut content_ui = ui.child_ui(max_rect, *ui.layout());
-        let mut output = self.show_content(&mut content_ui);
+        let mut output = if self.virtualized && self.multiline {
+            self.show_virtualized_content(&mut content_ui, gutter_width)
+        } else {
+            self.show_content(&mut content_ui, gutter_width)
+        };
         let id = output.response.id;
-        let frame_rect = output.response.rect.expand2(margin);
+        let mut frame_rect = output.response.rect.expand2(margin);
+        frame_rect.min.x -= gutter_width;
         ui.allocate_space(frame_rect.size());
         if interactive {
             output.response |= ui.interact(frame_rect, id, Sense::click());
@@ -299,28 +450,34 @@ impl<'t> TextEdit<'t> {
                 if output.response.has_focus() {
                     epaint::RectShape {
                         rect: frame_rect,
-                        corner_radius: visuals.corner_radius,
+                        rounding: visuals.rounding,
                         // fill: ui.visuals().selection.bg_fill,
                         fill: ui.visuals().extreme_bg_color,
+                        fill_gradient: None,
                         stroke: ui.visuals().selection.stroke,
+                        side_strokes: None,
                     }
                 } else {
                     epaint::RectShape {
                         rect: frame_rect,
-                        corner_radius: visuals.corner_radius,
+                        rounding: visuals.rounding,
                         fill: ui.visuals().extreme_bg_color,
+                        fill_gradient: None,
                         stroke: visuals.bg_stroke, // TODO: we want to show something here, or a text-edit field doesn't "pop".
+                        side_strokes: None,
                     }
                 }
             } else {
                 let visuals = &ui.style().visuals.widgets.inactive;
                 epaint::RectShape {
                     rect: frame_rect,
-                    corner_radius: visuals.corner_radius,
+                    rounding: visuals.rounding,
                     // fill: ui.visuals().extreme_bg_color,
                     // fill: visuals.bg_fill,
                     fill: Color32::TRANSPARENT,
+                    fill_gradient: None,
                     stroke: visuals.bg_stroke, // TODO: we want to show something here, or a text-edit field doesn't "pop".
+                    side_strokes: None,
                 }
             };
 
@@ -330,7 +487,9 @@ impl<'t> TextEdit<'t> {
         output
     }
 
-    fn show_content(self, ui: &mut Ui) -> TextEditOutput {
+    /// `gutter_width` is how much room there is for [`Self::line_numbers`] and [`Self::folding`]
+    /// to the left of the margin.
+    fn show_content(self, ui: &mut Ui, gutter_width: f32) -> TextEditOutput {
         let TextEdit {
             text,
             hint_text,
@@ -341,13 +500,20 @@ impl<'t> TextEdit<'t> {
             layouter,
             password,
             frame: _,
-            margin: _,
+            margin,
             multiline,
             interactive,
             desired_width,
             desired_height_rows,
             lock_focus,
             cursor_at_end,
+            virtualized: _,
+            find_replace,
+            line_numbers,
+            highlight_current_line,
+            match_brackets,
+            auto_indent,
+            folding,
         } = self;
 
         let text_color = text_color
@@ -355,32 +521,37 @@ impl<'t> TextEdit<'t> {
             // .unwrap_or_else(|| ui.style().interact(&response).text_color()); // too bright
             .unwrap_or_else(|| ui.visuals().widgets.inactive.text_color());
 
-        let prev_text = text.as_ref().to_owned();
+        let prev_text = text.as_str().into_owned();
 
         let font_id = font_selection.resolve(ui.style());
         let row_height = ui.fonts().row_height(&font_id);
-        const MIN_WIDTH: f32 = 24.0; // Never make a `TextEdit` more narrow than this.
-        let available_width = ui.available_width().at_least(MIN_WIDTH);
-        let desired_width = desired_width.unwrap_or_else(|| ui.spacing().text_edit_width);
-        let wrap_width = if ui.layout().horizontal_justify() {
-            available_width
-        } else {
-            desired_width.min(available_width)
-        };
-
-        let font_id_clone = font_id.clone();
-        let mut default_layouter = move |ui: &Ui, text: &str, wrap_width: f32| {
-            let text = mask_if_password(password, text);
-            ui.fonts().layout_job(if multiline {
-                LayoutJob::simple(text, font_id_clone.clone(), text_color, wrap_width)
-            } else {
-                LayoutJob::simple_singleline(text, font_id_clone.clone(), text_color)
-            })
-        };
+        let wrap_width = wrap_width(ui, desired_width);
 
+        let mut default_layouter =
+            default_layouter(font_id.clone(), text_color, password, multiline);
         let layouter = layouter.unwrap_or(&mut default_layouter);
 
-        let mut galley = layouter(ui, text.as_ref(), wrap_width);
+        let id = text_edit_id(ui, id, id_source);
+        let mut state = TextEditState::load(ui.ctx(), id).unwrap_or_default();
+
+        let folding = folding && multiline;
+        let code_editor_cache = state.code_editor_cache.clone();
+        let mut text_hash = None; // only if needed, and `None` again when the text changes
+        let mut hidden = if folding {
+            code_editor_cache
+                .lock()
+                .hidden_paragraphs(&mut text_hash, text, &state.folded)
+        } else {
+            vec![]
+        };
+        let mut text_layouter = TextLayouter {
+            layouter,
+            wrap_width,
+            per_paragraph: false,
+            find: None,
+            code_editor_cache: code_editor_cache.clone(),
+        };
+        let mut galley = text_layouter.layout(ui, &text.as_str(), &hidden);
 
         let desired_width = if multiline {
             galley.size().x.max(wrap_width) // always show everything in multiline
@@ -390,95 +561,55 @@ impl<'t> TextEdit<'t> {
         let desired_height = (desired_height_rows.at_least(1) as f32) * row_height;
         let desired_size = vec2(desired_width, galley.size().y.max(desired_height));
 
-        let (auto_id, rect) = ui.allocate_space(desired_size);
-
-        let id = id.unwrap_or_else(|| {
-            if let Some(id_source) = id_source {
-                ui.make_persistent_id(id_source)
-            } else {
-                auto_id // Since we are only storing the cursor a persistent Id is not super important
-            }
-        });
-        let mut state = TextEditState::load(ui.ctx(), id).unwrap_or_default();
-
-        // On touch screens (e.g. mobile in egui_web), should
-        // dragging select text, or scroll the enclosing `ScrollArea` (if any)?
-        // Since currently copying selected text in not supported on `egui_web`,
-        // we prioritize touch-scrolling:
-        let any_touches = ui.input().any_touches(); // separate line to avoid double-locking the same mutex
-        let allow_drag_to_select = !any_touches || ui.memory().has_focus(id);
-
-        let sense = if interactive {
-            if allow_drag_to_select {
-                Sense::click_and_drag()
-            } else {
-                Sense::click()
-            }
-        } else {
-            Sense::hover()
-        };
-        let mut response = ui.interact(rect, id, sense);
+        let (_, rect) = ui.allocate_space(desired_size);
+        let (mut response, allow_drag_to_select) = interact(ui, id, rect, interactive);
         let text_clip_rect = rect;
         let painter = ui.painter_at(text_clip_rect);
 
-        if interactive {
-            if let Some(pointer_pos) = ui.ctx().pointer_interact_pos() {
-                if response.hovered() && text.is_mutable() {
-                    ui.output().mutable_text_under_cursor = true;
-                }
-
-                // TODO: triple-click to select whole paragraph
-                // TODO: drag selected text to either move or clone (ctrl on windows, alt on mac)
-                let singleline_offset = vec2(state.singleline_offset, 0.0);
-                let cursor_at_pointer =
-                    galley.cursor_from_pos(pointer_pos - response.rect.min + singleline_offset);
-
-                if ui.visuals().text_cursor_preview
-                    && response.hovered()
-                    && ui.input().pointer.is_moving()
-                {
-                    // preview:
-                    paint_cursor_end(
-                        ui,
-                        row_height,
-                        &painter,
-                        response.rect.min,
-                        &galley,
-                        &cursor_at_pointer,
+        let find_replace = find_replace && multiline && !password && interactive;
+        let (find_action, find) = if find_replace {
+            find_replace_ui(ui, &mut state, text, id, rect)
+        } else {
+            (None, None)
+        };
+        let mut scroll_to_selection = false;
+        match find_action {
+            Some(FindAction::Next | FindAction::Previous) => scroll_to_selection = true,
+            Some(FindAction::Replace | FindAction::ReplaceAll) => {
+                response.mark_changed();
+                scroll_to_selection = find_action == Some(FindAction::Replace);
+                text_hash = None;
+                if folding {
+                    update_folds(&mut state.folded, &prev_text, text, true, &[]);
+                    hidden = code_editor_cache.lock().hidden_paragraphs(
+                        &mut text_hash,
+                        text,
+                        &state.folded,
                     );
                 }
-
-                if response.double_clicked() {
-                    // Select word:
-                    let center = cursor_at_pointer;
-                    let ccursor_range = select_word_at(text.as_ref(), center.ccursor);
-                    state.set_cursor_range(Some(CursorRange {
-                        primary: galley.from_ccursor(ccursor_range.primary),
-                        secondary: galley.from_ccursor(ccursor_range.secondary),
-                    }));
-                } else if allow_drag_to_select {
-                    if response.hovered() && ui.input().pointer.any_pressed() {
-                        ui.memory().request_focus(id);
-                        if ui.input().modifiers.shift {
-                            if let Some(mut cursor_range) = state.cursor_range(&*galley) {
-                                cursor_range.primary = cursor_at_pointer;
-                                state.set_cursor_range(Some(cursor_range));
-                            } else {
-                                state.set_cursor_range(Some(CursorRange::one(cursor_at_pointer)));
-                            }
-                        } else {
-                            state.set_cursor_range(Some(CursorRange::one(cursor_at_pointer)));
-                        }
-                    } else if ui.input().pointer.any_down() && response.is_pointer_button_down_on()
-                    {
-                        // drag to select text:
-                        if let Some(mut cursor_range) = state.cursor_range(&*galley) {
-                            cursor_range.primary = cursor_at_pointer;
-                            state.set_cursor_range(Some(cursor_range));
-                        }
-                    }
-                }
             }
+            _ => {}
+        }
+        let find_bar_has_focus = find.is_some() && find_replace_bar_has_focus(ui, id);
+        text_layouter.find = find;
+        if text_layouter.find.is_some() || response.changed {
+            galley = text_layouter.layout(ui, &text.as_str(), &hidden);
+        }
+
+        if interactive {
+            let galley_pos = response.rect.min - vec2(state.singleline_offset, 0.0);
+            pointer_interaction(
+                ui,
+                &mut state,
+                &response,
+                &painter,
+                &TextWindow::whole(text),
+                &galley,
+                galley_pos,
+                row_height,
+                allow_drag_to_select,
+                multiline,
+            );
         }
 
         if response.hovered() && interactive {
@@ -495,24 +626,79 @@ impl<'t> TextEdit<'t> {
             } else {
                 CursorRange::default()
             };
+            let mut cursor_ranges = vec![prev_cursor_range.unwrap_or(default_cursor_range)];
+            if multiline {
+                cursor_ranges.extend(state.extra_cursor_ranges(&galley));
+            }
 
-            let (changed, new_cursor_range) = events(
+            let input_events = ui.input().events.clone(); // avoid dead-lock by cloning. TODO: optimize
+            let (changed, new_cursor_ranges) = events(
                 ui,
                 &mut state,
-                text,
+                &mut TextWindow::whole(text),
                 &mut galley,
-                layouter,
+                &mut |ui: &Ui, text: &str| text_layouter.layout(ui, text, &hidden),
                 id,
-                wrap_width,
                 multiline,
                 password,
-                default_cursor_range,
+                auto_indent,
+                cursor_ranges,
+                &input_events,
             );
+            state.set_cursor_ranges(&new_cursor_ranges);
 
             if changed {
                 response.mark_changed();
+                text_hash = None;
+            }
+            cursor_range = Some(new_cursor_ranges[0]);
+
+            if folding {
+                let cursor_paragraphs: Vec<usize> = new_cursor_ranges
+                    .iter()
+                    .flat_map(|cursor_range| {
+                        [cursor_range.primary, cursor_range.secondary]
+                            .map(|cursor| cursor.pcursor.paragraph)
+                    })
+                    .collect();
+                let folds_changed = update_folds(
+                    &mut state.folded,
+                    &prev_text,
+                    text,
+                    changed,
+                    &cursor_paragraphs,
+                );
+                if folds_changed || (changed && !hidden.is_empty()) {
+                    hidden = code_editor_cache.lock().hidden_paragraphs(
+                        &mut text_hash,
+                        text,
+                        &state.folded,
+                    );
+                    galley = text_layouter.layout(ui, &text.as_str(), &hidden);
+                }
+            }
+        }
+
+        if scroll_to_selection {
+            if let Some(cursor_range) = state.cursor_range(&galley) {
+                // Like `Response::scroll_to_me`, but for the selection:
+                let selection_rect = galley
+                    .pos_from_cursor(&cursor_range.primary)
+                    .union(galley.pos_from_cursor(&cursor_range.secondary))
+                    .translate(response.rect.min.to_vec2());
+                let clip_rect = ui.clip_rect();
+                for d in 0..2 {
+                    if selection_rect.min[d] < clip_rect.min[d]
+                        || clip_rect.max[d] < selection_rect.max[d]
+                    {
+                        let target = lerp(
+                            selection_rect.min[d]..=selection_rect.max[d],
+                            Align::Center.to_factor(),
+                        );
+                        ui.ctx().frame_state().scroll_target[d] = Some((target, Align::Center));
+                    }
+                }
             }
-            cursor_range = Some(new_cursor_range);
         }
 
         let mut text_draw_pos = response.rect.min;
@@ -543,85 +729,74 @@ impl<'t> TextEdit<'t> {
             text_draw_pos -= vec2(offset_x, 0.0);
         }
 
-        if ui.is_rect_visible(rect) {
-            painter.galley(text_draw_pos, galley.clone());
+        let has_focus = ui.memory().has_focus(id);
+        let ccursor_ranges = state.ccursor_ranges();
 
-            if text.as_ref().is_empty() && !hint_text.is_empty() {
-                let hint_text_color = ui.visuals().weak_text_color();
-                let galley = if multiline {
-                    hint_text.into_galley(ui, Some(true), desired_size.x, font_id)
+        if ui.is_rect_visible(rect) {
+            let brackets = match (match_brackets && has_focus, ccursor_ranges.first()) {
+                (true, Some(ccursor_range)) => code_editor_cache.lock().matching_brackets(
+                    &mut text_hash,
+                    text,
+                    ccursor_range.primary,
+                ),
+                _ => None,
+            };
+            let hint_galley = (text.is_empty() && !hint_text.is_empty()).then(|| {
+                if multiline {
+                    hint_text.into_galley(ui, Some(true), desired_size.x, font_id.clone())
                 } else {
-                    hint_text.into_galley(ui, Some(false), f32::INFINITY, font_id)
-                };
-                galley.paint_with_fallback_color(&painter, response.rect.min, hint_text_color);
-            }
-
-            if ui.memory().has_focus(id) {
-                if let Some(cursor_range) = state.cursor_range(&*galley) {
-                    // We paint the cursor on top of the text, in case
-                    // the text galley has backgrounds (as e.g. `code` snippets in markup do).
-                    paint_cursor_selection(ui, &painter, text_draw_pos, &galley, &cursor_range);
-                    paint_cursor_end(
-                        ui,
-                        row_height,
-                        &painter,
-                        text_draw_pos,
-                        &galley,
-                        &cursor_range.primary,
-                    );
-
-                    if interactive && text.is_mutable() {
-                        // egui_web uses `text_cursor_pos` when showing IME,
-                        // so only set it when text is editable and visible!
-                        ui.ctx().output().text_cursor_pos = Some(
-                            galley
-                                .pos_from_cursor(&cursor_range.primary)
-                                .translate(response.rect.min.to_vec2())
-                                .left_top(),
-                        );
-                    }
+                    hint_text.into_galley(ui, Some(false), f32::INFINITY, font_id.clone())
                 }
-            }
+            });
+            paint_text(
+                ui,
+                &painter,
+                rect,
+                &TextWindow::whole(text),
+                &galley,
+                text_draw_pos,
+                row_height,
+                &font_id,
+                &ccursor_ranges,
+                highlight_current_line && multiline && has_focus,
+                brackets,
+                hint_galley,
+                has_focus || find_bar_has_focus,
+                interactive,
+            );
+        }
+
+        if gutter_width > 0.0 {
+            show_gutter(
+                ui,
+                id,
+                &mut state,
+                &TextWindow::whole(text),
+                &galley,
+                text_draw_pos,
+                rect,
+                margin,
+                gutter_width,
+                &font_id,
+                line_numbers,
+                folding,
+                &hidden,
+                &mut text_hash,
+                has_focus,
+            );
         }
 
         state.clone().store(ui.ctx(), id);
 
-        let selection_changed = if let (Some(cursor_range), Some(prev_cursor_range)) =
-            (cursor_range, prev_cursor_range)
-        {
-            prev_cursor_range.as_ccursor_range() != cursor_range.as_ccursor_range()
-        } else {
-            false
+        let selection = match (cursor_range, prev_cursor_range) {
+            (Some(cursor_range), Some(prev_cursor_range))
+                if prev_cursor_range.as_ccursor_range() != cursor_range.as_ccursor_range() =>
+            {
+                Some(cursor_range.primary.ccursor.index..=cursor_range.secondary.ccursor.index)
+            }
+            _ => None,
         };
-
-        if response.changed {
-            response.widget_info(|| {
-                WidgetInfo::text_edit(
-                    mask_if_password(password, prev_text.as_str()),
-                    mask_if_password(password, text.as_str()),
-                )
-            });
-        } else if selection_changed {
-            let cursor_range = cursor_range.unwrap();
-            let char_range =
-                cursor_range.primary.ccursor.index..=cursor_range.secondary.ccursor.index;
-            let info = WidgetInfo::text_selection_changed(
-                char_range,
-                mask_if_password(password, text.as_str()),
-            );
-            response
-                .ctx
-                .output()
-                .events
-                .push(OutputEvent::TextSelectionChanged(info));
-        } else {
-            response.widget_info(|| {
-                WidgetInfo::text_edit(
-                    mask_if_password(password, prev_text.as_str()),
-                    mask_if_password(password, text.as_str()),
-                )
-            });
-        }
+        widget_info(&response, password, &prev_text, &text.as_str(), selection);
 
         TextEditOutput {
             response,
@@ -632,208 +807,1690 @@ impl<'t> TextEdit<'t> {
             cursor_range,
         }
     }
-}
 
-fn mask_if_password(is_password: bool, text: &str) -> String {
-    fn mask_password(text: &str) -> String {
-        std::iter::repeat(epaint::text::PASSWORD_REPLACEMENT_CHAR)
-            .take(text.chars().count())
-            .collect::<String>()
-    }
+    /// Like [`Self::show_content`], but only lays out the paragraphs that are visible
+    /// or around the cursors. See [`Self::virtualized`].
+    fn show_virtualized_content(self, ui: &mut Ui, gutter_width: f32) -> TextEditOutput {
+        let TextEdit {
+            text,
+            hint_text,
+            id,
+            id_source,
+            font_selection,
+            text_color,
+            layouter,
+            password,
+            frame: _,
+            margin,
+            multiline: _,
+            interactive,
+            desired_width,
+            desired_height_rows,
+            lock_focus,
+            cursor_at_end,
+            virtualized: _,
+            find_replace,
+            line_numbers,
+            highlight_current_line,
+            match_brackets,
+            auto_indent,
+            folding,
+        } = self;
 
-    if is_password {
-        mask_password(text)
-    } else {
-        text.to_owned()
-    }
-}
+        let text_color = text_color
+            .or(ui.visuals().override_text_color)
+            .unwrap_or_else(|| ui.visuals().widgets.inactive.text_color());
 
-// ----------------------------------------------------------------------------
+        let font_id = font_selection.resolve(ui.style());
+        let row_height = ui.fonts().row_height(&font_id);
+        let wrap_width = wrap_width(ui, desired_width);
 
-/// Check for (keyboard) events to edit the cursor and/or text.
-#[allow(clippy::too_many_arguments)]
-fn events(
-    ui: &mut crate::Ui,
-    state: &mut TextEditState,
-    text: &mut dyn TextBuffer,
-    galley: &mut Arc<Galley>,
-    layouter: &mut dyn FnMut(&Ui, &str, f32) -> Arc<Galley>,
-    id: Id,
-    wrap_width: f32,
-    multiline: bool,
-    password: bool,
-    default_cursor_range: CursorRange,
-) -> (bool, CursorRange) {
-    let mut cursor_range = state.cursor_range(&*galley).unwrap_or(default_cursor_range);
+        let mut default_layouter = default_layouter(font_id.clone(), text_color, password, true);
+        let layouter = layouter.unwrap_or(&mut default_layouter);
 
-    // We feed state to the undoer both before and after handling input
-    // so that the undoer creates automatic saves even when there are no events for a while.
-    state.undoer.lock().feed_state(
-        ui.input().time,
-        &(cursor_range.as_ccursor_range(), text.as_ref().to_owned()),
-    );
+        let id = text_edit_id(ui, id, id_source);
+        let mut state = TextEditState::load(ui.ctx(), id).unwrap_or_default();
 
-    let copy_if_not_password = |ui: &Ui, text: String| {
-        if !password {
-            ui.ctx().output().copied_text = text;
-        }
-    };
+        // Folding needs the whole text, but only while something is folded:
+        let code_editor_cache = state.code_editor_cache.clone();
+        let mut text_hash = None; // only if needed, and `None` again when the text changes
+        let prev_text = if folding && !state.folded.is_empty() {
+            text.as_str().into_owned()
+        } else {
+            String::new()
+        };
+        let mut hidden = if folding {
+            code_editor_cache
+                .lock()
+                .hidden_paragraphs(&mut text_hash, text, &state.folded)
+        } else {
+            vec![]
+        };
 
-    let mut any_change = false;
+        let paragraph_heights = state.paragraph_heights.clone();
+        let mut heights = paragraph_heights.lock();
+        heights.sync(text.paragraph_count(), wrap_width);
+        heights.set_hidden(&hidden);
 
-    let events = ui.input().events.clone(); // avoid dead-lock by cloning. TODO: optimize
-    for event in &events {
-        let did_mutate_text = match event {
-            Event::Copy => {
-                if cursor_range.is_empty() {
-                    copy_if_not_password(ui, text.as_ref().to_owned());
-                } else {
-                    copy_if_not_password(ui, selected_str(text, &cursor_range).to_owned());
+        // We use the heights measured so far, so the size may change a bit as we scroll.
+        let desired_height = (desired_height_rows.at_least(1) as f32) * row_height;
+        let desired_size = vec2(
+            wrap_width,
+            heights.total_height(row_height).max(desired_height),
+        );
+        let (_, rect) = ui.allocate_space(desired_size);
+
+        let visible_paragraphs = |ui: &Ui, heights: &ParagraphHeights| {
+            let clip_rect = ui.clip_rect();
+            heights.paragraphs_between(
+                clip_rect.top() - rect.top(),
+                clip_rect.bottom() - rect.top(),
+                row_height,
+            )
+        };
+
+        let (mut response, allow_drag_to_select) = interact(ui, id, rect, interactive);
+        let text_clip_rect = rect;
+        let painter = ui.painter_at(text_clip_rect);
+
+        let mut text_layouter = TextLayouter {
+            layouter,
+            wrap_width,
+            per_paragraph: true,
+            find: None,
+            code_editor_cache: code_editor_cache.clone(),
+        };
+
+        let find_replace = find_replace && !password && interactive;
+        let (find_action, find) = if find_replace {
+            find_replace_ui(ui, &mut state, text, id, rect)
+        } else {
+            (None, None)
+        };
+        let mut scroll_to_cursor = false;
+        match find_action {
+            Some(FindAction::Next | FindAction::Previous) => scroll_to_cursor = true,
+            Some(FindAction::Replace | FindAction::ReplaceAll) => {
+                response.mark_changed();
+                scroll_to_cursor = find_action == Some(FindAction::Replace);
+                text_hash = None;
+                if folding {
+                    update_folds(&mut state.folded, &prev_text, text, true, &[]);
+                    hidden = code_editor_cache.lock().hidden_paragraphs(
+                        &mut text_hash,
+                        text,
+                        &state.folded,
+                    );
                 }
-                None
+                heights.sync(text.paragraph_count(), wrap_width);
+                heights.set_hidden(&hidden);
             }
-            Event::Cut => {
-                if cursor_range.is_empty() {
-                    copy_if_not_password(ui, text.take());
-                    Some(CCursorRange::default())
+            _ => {}
+        }
+        let find_bar_has_focus = find.is_some() && find_replace_bar_has_focus(ui, id);
+        text_layouter.find = find;
+
+        if interactive {
+            // Where is the pointer in the visible text?
+            let window = TextWindow::new(text, visible_paragraphs(ui, &heights));
+            let galley = text_layouter.layout_window(ui, &window, &hidden, &mut heights);
+            let text_draw_pos =
+                rect.min + vec2(0.0, heights.top_of(window.paragraphs.start, row_height));
+            pointer_interaction(
+                ui,
+                &mut state,
+                &response,
+                &painter,
+                &window,
+                &galley,
+                text_draw_pos,
+                row_height,
+                allow_drag_to_select,
+                true,
+            );
+        }
+
+        if response.hovered() && interactive {
+            ui.output().cursor_icon = CursorIcon::Text;
+        }
+
+        // All cursors are into the whole text, not into the window we lay out.
+        let prev_ccursor_ranges = state.ccursor_ranges();
+        let has_focus = ui.memory().has_focus(id) && interactive;
+        let mut cursor_target = None;
+        if has_focus {
+            ui.memory().lock_focus(id, lock_focus);
+
+            let mut input_events = ui.input().events.clone(); // avoid dead-lock by cloning. TODO: optimize
+            let mut ccursor_ranges = prev_ccursor_ranges.clone();
+            if ccursor_ranges.is_empty() {
+                ccursor_ranges.push(if cursor_at_end {
+                    CCursorRange::one(CCursor::new(text.char_count()))
                 } else {
-                    copy_if_not_password(ui, selected_str(text, &cursor_range).to_owned());
-                    Some(CCursorRange::one(delete_selected(text, &cursor_range)))
-                }
+                    CCursorRange::default()
+                });
             }
-            Event::Paste(text_to_insert) => {
-                if !text_to_insert.is_empty() {
-                    let mut ccursor = delete_selected(text, &cursor_range);
-                    insert_text(&mut ccursor, text, text_to_insert);
-                    Some(CCursorRange::one(ccursor))
-                } else {
-                    None
+            let mut changed = whole_text_events(
+                ui,
+                &state,
+                text,
+                &mut ccursor_ranges,
+                &mut input_events,
+                password,
+            );
+
+            // Edit the paragraphs around the cursors, so we can move the cursors into them:
+            let primary_paragraph = text.paragraph_from_char_index(ccursor_ranges[0].primary.index);
+            let visible = visible_paragraphs(ui, &heights);
+            let mut paragraphs = if visible.contains(&primary_paragraph) {
+                visible.start.saturating_sub(1)..visible.end + 1
+            } else {
+                primary_paragraph.saturating_sub(1)..primary_paragraph + 2
+            };
+            if ccursor_ranges.len() > 1 {
+                // Each cursor edits its own selection, so they all need to be in the window:
+                for ccursor_range in &ccursor_ranges {
+                    for ccursor in [ccursor_range.primary, ccursor_range.secondary] {
+                        let paragraph = text.paragraph_from_char_index(ccursor.index);
+                        paragraphs.start = paragraphs.start.min(paragraph.saturating_sub(1));
+                        paragraphs.end = paragraphs.end.max(paragraph + 2);
+                    }
                 }
             }
-            Event::Text(text_to_insert) => {
-                // Newlines are handled by `Key::Enter`.
-                if !text_to_insert.is_empty() && text_to_insert != "\n" && text_to_insert != "\r" {
-                    let mut ccursor = delete_selected(text, &cursor_range);
-                    insert_text(&mut ccursor, text, text_to_insert);
-                    Some(CCursorRange::one(ccursor))
-                } else {
-                    None
-                }
+
+            let secondary_paragraph =
+                text.paragraph_from_char_index(ccursor_ranges[0].secondary.index);
+            let has_lock_focus = ui.memory().has_lock_focus(id);
+            if !paragraphs.contains(&secondary_paragraph)
+                && input_events
+                    .iter()
+                    .any(|event| edits_text(event, has_lock_focus))
+            {
+                // The edit replaces a selection that goes outside of what we lay out:
+                delete_selection(&state, text, &mut ccursor_ranges[0], &mut input_events);
+                changed = true;
             }
-            Event::Key {
-                key: Key::Tab,
-                pressed: true,
-                modifiers,
-            } => {
-                if multiline && ui.memory().has_lock_focus(id) {
-                    let mut ccursor = delete_selected(text, &cursor_range);
-                    if modifiers.shift {
-                        // TODO: support removing indentation over a selection?
-                        decrease_identation(&mut ccursor, text);
-                    } else {
-                        insert_text(&mut ccursor, text, "\t");
-                    }
-                    Some(CCursorRange::one(ccursor))
+
+            if input_events.iter().any(is_text_event) {
+                let mut events_window = TextWindow::new(text, paragraphs);
+                let events_hidden = events_window.to_local_paragraphs(&hidden);
+                let mut events_galley =
+                    text_layouter.layout(ui, &events_window.as_str(), &events_hidden);
+                let cursor_ranges = ccursor_ranges
+                    .iter()
+                    .map(|ccursor_range| CursorRange {
+                        primary: events_galley
+                            .from_ccursor(events_window.to_local(ccursor_range.primary)),
+                        secondary: events_galley
+                            .from_ccursor(events_window.to_local(ccursor_range.secondary)),
+                    })
+                    .collect();
+                let paragraph_count = events_window.full_text().paragraph_count();
+
+                let (events_changed, new_cursor_ranges) = events(
+                    ui,
+                    &mut state,
+                    &mut events_window,
+                    &mut events_galley,
+                    &mut |ui: &Ui, text: &str| text_layouter.layout(ui, text, &events_hidden),
+                    id,
+                    true,
+                    password,
+                    auto_indent,
+                    cursor_ranges,
+                    &input_events,
+                );
+
+                let new_ccursor_ranges: Vec<CCursorRange> = if events_changed
+                    || new_cursor_ranges.len() != ccursor_ranges.len()
+                {
+                    new_cursor_ranges
+                        .iter()
+                        .map(|cursor_range| {
+                            events_window.to_global_range(cursor_range.as_ccursor_range())
+                        })
+                        .collect()
                 } else {
-                    None
+                    // Keep the ends that were outside of the window, and so did not move:
+                    new_cursor_ranges
+                        .iter()
+                        .zip(&ccursor_ranges)
+                        .map(|(new_cursor_range, ccursor_range)| {
+                            let new_ccursor_range = new_cursor_range.as_ccursor_range();
+                            CCursorRange {
+                                primary: events_window
+                                    .to_global_or(new_ccursor_range.primary, ccursor_range.primary),
+                                secondary: events_window.to_global_or(
+                                    new_ccursor_range.secondary,
+                                    ccursor_range.secondary,
+                                ),
+                            }
+                        })
+                        .collect()
+                };
+
+                if events_changed {
+                    let new_paragraph_count = events_window.full_text().paragraph_count();
+                    let edited = events_window.paragraphs.clone();
+                    heights.splice(
+                        edited.clone(),
+                        (edited.len() + new_paragraph_count).saturating_sub(paragraph_count),
+                    );
+                    changed = true;
+                }
+                if new_ccursor_ranges[0] != ccursor_ranges[0] {
+                    let window_top = heights.top_of(events_window.paragraphs.start, row_height);
+                    cursor_target = Some(
+                        events_galley
+                            .pos_from_cursor(&new_cursor_ranges[0].primary)
+                            .translate(rect.min.to_vec2() + vec2(0.0, window_top)),
+                    );
                 }
+                ccursor_ranges = new_ccursor_ranges;
             }
-            Event::Key {
-                key: Key::Enter,
-                pressed: true,
+
+            if changed {
+                response.mark_changed();
+                text_hash = None;
+                heights.sync(text.paragraph_count(), wrap_width);
+            }
+            if folding && !state.folded.is_empty() {
+                let cursor_paragraphs: Vec<usize> = ccursor_ranges
+                    .iter()
+                    .flat_map(|ccursor_range| [ccursor_range.primary, ccursor_range.secondary])
+                    .map(|ccursor| text.paragraph_from_char_index(ccursor.index))
+                    .collect();
+                let folds_changed = update_folds(
+                    &mut state.folded,
+                    &prev_text,
+                    text,
+                    changed,
+                    &cursor_paragraphs,
+                );
+                if folds_changed || changed {
+                    hidden = code_editor_cache.lock().hidden_paragraphs(
+                        &mut text_hash,
+                        text,
+                        &state.folded,
+                    );
+                }
+            }
+            heights.set_hidden(&hidden);
+
+            if cursor_target.is_none() && ccursor_ranges.first() != prev_ccursor_ranges.first() {
+                // Moved by a whole text command, so the cursor could be anywhere:
+                scroll_to_cursor = true;
+            }
+            if ccursor_ranges != prev_ccursor_ranges {
+                state.set_ccursor_ranges(ccursor_ranges);
+            }
+        }
+
+        if let (true, None, Some(ccursor_range)) =
+            (scroll_to_cursor, cursor_target, state.ccursor_range())
+        {
+            let paragraph = text.paragraph_from_char_index(ccursor_range.primary.index);
+            let top = rect.top() + heights.top_of(paragraph, row_height);
+            cursor_target = Some(Rect::from_min_size(
+                pos2(rect.left(), top),
+                vec2(0.0, heights.height(paragraph, row_height)),
+            ));
+        }
+
+        // Lay out what is visible after the edits (unchanged paragraphs are cached):
+        let window = TextWindow::new(text, visible_paragraphs(ui, &heights));
+        let galley = text_layouter.layout_window(ui, &window, &hidden, &mut heights);
+        let text_draw_pos =
+            rect.min + vec2(0.0, heights.top_of(window.paragraphs.start, row_height));
+
+        if let Some(cursor_target) = cursor_target {
+            // Scroll the enclosing `ScrollArea` (if any) just enough to show the cursor:
+            let clip_rect = ui.clip_rect();
+            if cursor_target.top() < clip_rect.top() {
+                ui.ctx().frame_state().scroll_target[1] = Some((cursor_target.top(), Align::TOP));
+            } else if cursor_target.bottom() > clip_rect.bottom() {
+                ui.ctx().frame_state().scroll_target[1] =
+                    Some((cursor_target.bottom(), Align::BOTTOM));
+            }
+        }
+
+        let ccursor_ranges = state.ccursor_ranges();
+        let cursor_range = ccursor_ranges.first().map(|ccursor_range| CursorRange {
+            primary: window.global_cursor(&galley, ccursor_range.primary),
+            secondary: window.global_cursor(&galley, ccursor_range.secondary),
+        });
+
+        if ui.is_rect_visible(rect) {
+            // Only the brackets in the window, so we don't look through all of the text:
+            let brackets = match (match_brackets && has_focus, ccursor_ranges.first()) {
+                (true, Some(ccursor_range)) if window.contains(ccursor_range.primary) => {
+                    matching_brackets(&window.as_str(), window.to_local(ccursor_range.primary)).map(
+                        |brackets| {
+                            brackets.map(|index| window.to_global(CCursor::new(index)).index)
+                        },
+                    )
+                }
+                _ => None,
+            };
+            let hint_galley = (window.full_text().is_empty() && !hint_text.is_empty())
+                .then(|| hint_text.into_galley(ui, Some(true), desired_size.x, font_id.clone()));
+            paint_text(
+                ui,
+                &painter,
+                rect,
+                &window,
+                &galley,
+                text_draw_pos,
+                row_height,
+                &font_id,
+                &ccursor_ranges,
+                highlight_current_line && has_focus,
+                brackets,
+                hint_galley,
+                has_focus || find_bar_has_focus,
+                interactive,
+            );
+        }
+
+        if gutter_width > 0.0 {
+            show_gutter(
+                ui,
+                id,
+                &mut state,
+                &window,
+                &galley,
+                text_draw_pos,
+                rect,
+                margin,
+                gutter_width,
+                &font_id,
+                line_numbers,
+                folding,
+                &hidden,
+                &mut text_hash,
+                has_focus,
+            );
+        }
+
+        drop(heights);
+        state.clone().store(ui.ctx(), id);
+
+        // Only the text we laid out, so we don't copy all of it every frame:
+        let window_text = window.as_str();
+        let selection = match (prev_ccursor_ranges.first(), ccursor_ranges.first()) {
+            (Some(prev_ccursor_range), Some(ccursor_range))
+                if prev_ccursor_range != ccursor_range =>
+            {
+                let local = |ccursor: CCursor| window.to_local(ccursor).index;
+                Some(local(ccursor_range.primary)..=local(ccursor_range.secondary))
+            }
+            _ => None,
+        };
+        widget_info(&response, password, &window_text, &window_text, selection);
+
+        TextEditOutput {
+            response,
+            galley,
+            text_draw_pos,
+            text_clip_rect,
+            state,
+            cursor_range: if has_focus { cursor_range } else { None },
+        }
+    }
+}
+
+fn mask_if_password(is_password: bool, text: &str) -> String {
+    fn mask_password(text: &str) -> String {
+        std::iter::repeat(epaint::text::PASSWORD_REPLACEMENT_CHAR)
+            .take(text.chars().count())
+            .collect::<String>()
+    }
+
+    if is_password {
+        mask_password(text)
+    } else {
+        text.to_owned()
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Shared by `TextEdit::show_content` and `TextEdit::show_virtualized_content`, which
+// work on a `TextWindow` of the text: all of it, or the paragraphs that are laid out.
+
+/// The [`Id`] the [`TextEditState`] is stored under.
+fn text_edit_id(ui: &Ui, id: Option<Id>, id_source: Option<Id>) -> Id {
+    id.unwrap_or_else(|| {
+        if let Some(id_source) = id_source {
+            ui.make_persistent_id(id_source)
+        } else {
+            // The same `Id` that `allocate_space` will return.
+            // Since we are only storing the cursor a persistent Id is not super important.
+            ui.next_auto_id()
+        }
+    })
+}
+
+fn wrap_width(ui: &Ui, desired_width: Option<f32>) -> f32 {
+    const MIN_WIDTH: f32 = 24.0; // Never make a `TextEdit` more narrow than this.
+    let available_width = ui.available_width().at_least(MIN_WIDTH);
+    let desired_width = desired_width.unwrap_or_else(|| ui.spacing().text_edit_width);
+    if ui.layout().horizontal_justify() {
+        available_width
+    } else {
+        desired_width.min(available_width)
+    }
+}
+
+/// The layouter to use when there is no [`TextEdit::layouter`].
+fn default_layouter(
+    font_id: FontId,
+    text_color: Color32,
+    password: bool,
+    multiline: bool,
+) -> impl FnMut(&Ui, &str, f32) -> Arc<Galley> {
+    move |ui: &Ui, text: &str, wrap_width: f32| {
+        let text = mask_if_password(password, text);
+        ui.fonts().layout_job(if multiline {
+            LayoutJob::simple(text, font_id.clone(), text_color, wrap_width)
+        } else {
+            LayoutJob::simple_singleline(text, font_id.clone(), text_color)
+        })
+    }
+}
+
+/// Also returns if dragging should select text.
+fn interact(ui: &Ui, id: Id, rect: Rect, interactive: bool) -> (Response, bool) {
+    // On touch screens (e.g. mobile in egui_web), should
+    // dragging select text, or scroll the enclosing `ScrollArea` (if any)?
+    // Since currently copying selected text in not supported on `egui_web`,
+    // we prioritize touch-scrolling:
+    let any_touches = ui.input().any_touches(); // separate line to avoid double-locking the same mutex
+    let allow_drag_to_select = !any_touches || ui.memory().has_focus(id);
+
+    let sense = if interactive {
+        if allow_drag_to_select {
+            Sense::click_and_drag()
+        } else {
+            Sense::click()
+        }
+    } else {
+        Sense::hover()
+    };
+    (ui.interact(rect, id, sense), allow_drag_to_select)
+}
+
+/// Lays out the text as it is shown: with the find matches highlighted,
+/// and without the folded paragraphs.
+struct TextLayouter<'l> {
+    layouter: &'l mut dyn FnMut(&Ui, &str, f32) -> Arc<Galley>,
+    wrap_width: f32,
+
+    /// Lay out each paragraph on its own, see [`TextEdit::virtualized`].
+    per_paragraph: bool,
+
+    /// The open find/replace bar, whose matches we highlight.
+    find: Option<FindReplaceState>,
+
+    code_editor_cache: Arc<Mutex<CodeEditorCache>>,
+}
+
+impl<'l> TextLayouter<'l> {
+    /// `hidden` are the paragraphs of `text` to fold away.
+    fn layout(&mut self, ui: &Ui, text: &str, hidden: &[Range<usize>]) -> Arc<Galley> {
+        let galley = if self.per_paragraph {
+            Arc::new(Galley::concat(&layout_paragraphs(
+                ui,
+                self.layouter,
+                text,
+                self.wrap_width,
+            )))
+        } else {
+            (self.layouter)(ui, text, self.wrap_width)
+        };
+        self.highlight_and_fold(ui, text, galley, hidden)
+    }
+
+    /// Lay out the paragraphs of `window`, and remember how high they are.
+    ///
+    /// `hidden` are the paragraphs of the full text to fold away.
+    fn layout_window(
+        &mut self,
+        ui: &Ui,
+        window: &TextWindow<'_>,
+        hidden: &[Range<usize>],
+        heights: &mut ParagraphHeights,
+    ) -> Arc<Galley> {
+        let text = window.as_str();
+        let parts = layout_paragraphs(ui, self.layouter, &text, self.wrap_width);
+        for (paragraph, part) in window.paragraphs.clone().zip(&parts) {
+            heights.set(paragraph, part.size().y + part.job.paragraph_spacing);
+        }
+        let galley = Arc::new(Galley::concat(&parts));
+        self.highlight_and_fold(ui, &text, galley, &window.to_local_paragraphs(hidden))
+    }
+
+    fn highlight_and_fold(
+        &mut self,
+        ui: &Ui,
+        text: &str,
+        mut galley: Arc<Galley>,
+        hidden: &[Range<usize>],
+    ) -> Arc<Galley> {
+        if let Some(find) = &self.find {
+            let matches = find.cached_matches(ui.ctx(), text);
+            galley = highlight_matches(ui, &matches, galley);
+        }
+        self.code_editor_cache.lock().fold(ui, galley, hidden)
+    }
+}
+
+/// Open the [`TextEdit::find_replace`] bar on Cmd/Ctrl+F or Cmd/Ctrl+H, and show it if it is open.
+///
+/// Returns what the user did in the bar, and the state of the bar if it is still open.
+fn find_replace_ui(
+    ui: &Ui,
+    state: &mut TextEditState,
+    text: &mut dyn TextBuffer,
+    id: Id,
+    rect: Rect,
+) -> (Option<FindAction>, Option<FindReplaceState>) {
+    let shortcut = {
+        let input = ui.input();
+        if input.modifiers.command && input.key_pressed(Key::F) {
+            Some(false)
+        } else if input.modifiers.command && input.key_pressed(Key::H) {
+            Some(true)
+        } else {
+            None
+        }
+    };
+    if let Some(show_replace) = shortcut {
+        let has_focus = ui.memory().has_focus(id);
+        if has_focus || find_replace_bar_has_focus(ui, id) {
+            let selected = state
+                .ccursor_range()
+                .map(|ccursor_range| {
+                    let [min, max] = ccursor_range.sorted();
+                    text.char_range(min.index..max.index).into_owned()
+                })
+                .filter(|selected| has_focus && !selected.is_empty());
+            let find = state.find_replace_mut();
+            find.open = true;
+            find.show_replace |= show_replace && text.is_mutable();
+            if let Some(selected) = selected.filter(|selected| !selected.contains('\n')) {
+                find.query = selected;
+            }
+            ui.memory().request_focus(find_query_id(id));
+        }
+    }
+
+    if !state.find_replace().open {
+        return (None, None);
+    }
+    let action = show_find_replace_bar(ui, state, text, id, rect.intersect(ui.clip_rect()));
+    if action == Some(FindAction::Close) {
+        state.find_replace_mut().open = false;
+        ui.memory().request_focus(id);
+        return (action, None);
+    }
+    (action, Some(state.find_replace().clone()))
+}
+
+/// Hover, click and drag on `galley`, the layout of `window` at `galley_pos`, to move the cursors.
+///
+/// The cursors in `state` are into the full text.
+#[allow(clippy::too_many_arguments)]
+fn pointer_interaction(
+    ui: &mut Ui,
+    state: &mut TextEditState,
+    response: &Response,
+    painter: &Painter,
+    window: &TextWindow<'_>,
+    galley: &Galley,
+    galley_pos: Pos2,
+    row_height: f32,
+    allow_drag_to_select: bool,
+    multiline: bool,
+) {
+    let pointer_pos = ui.ctx().pointer_interact_pos();
+    let to_layer = ui.ctx().layer_transform(ui.layer_id()).inverse();
+    let pointer_pos = match pointer_pos.map(|pos| to_layer * pos) {
+        Some(pointer_pos) => pointer_pos,
+        None => return,
+    };
+    if response.hovered() && window.is_mutable() {
+        ui.output().mutable_text_under_cursor = true;
+    }
+
+    // TODO: triple-click to select whole paragraph
+    // TODO: drag selected text to either move or clone (ctrl on windows, alt on mac)
+    let cursor_at_pointer = galley.cursor_from_pos(pointer_pos - galley_pos);
+
+    if ui.visuals().text_cursor_preview && response.hovered() && ui.input().pointer.is_moving() {
+        // preview:
+        paint_cursor_end(
+            ui,
+            row_height,
+            painter,
+            galley_pos,
+            galley,
+            &cursor_at_pointer,
+        );
+    }
+
+    let ccursor_at_pointer = window.to_global(cursor_at_pointer.ccursor);
+    if response.double_clicked() {
+        // Select word:
+        let ccursor_range = select_word_at(&window.as_str(), cursor_at_pointer.ccursor);
+        state.set_ccursor_range(Some(window.to_global_range(ccursor_range)));
+    } else if allow_drag_to_select {
+        let alt = multiline && ui.input().modifiers.alt;
+        if response.hovered() && ui.input().pointer.any_pressed() {
+            ui.memory().request_focus(response.id);
+            if alt {
+                // Add a cursor:
+                let new_ccursor_range = CCursorRange::one(ccursor_at_pointer);
+                let mut ccursor_ranges = state.ccursor_ranges();
+                ccursor_ranges.retain(|ccursor_range| *ccursor_range != new_ccursor_range);
+                ccursor_ranges.insert(0, new_ccursor_range);
+                state.set_ccursor_ranges(ccursor_ranges);
+            } else {
+                let ccursor_range = match state.ccursor_range() {
+                    Some(ccursor_range) if ui.input().modifiers.shift => {
+                        CCursorRange::two(ccursor_range.secondary, ccursor_at_pointer)
+                    }
+                    _ => CCursorRange::one(ccursor_at_pointer),
+                };
+                state.set_ccursor_range(Some(ccursor_range));
+            }
+        } else if ui.input().pointer.any_down() && response.is_pointer_button_down_on() {
+            let press_origin = ui.input().pointer.press_origin();
+            let is_click = ui.input().pointer.could_any_button_be_click();
+            if let (true, false, Some(press_origin)) = (alt, is_click, press_origin) {
+                // drag to select a column of text:
+                let origin = to_layer * press_origin - galley_pos;
+                let pointer = pointer_pos - galley_pos;
+                let ccursor_ranges = column_cursor_ranges(galley, origin, pointer)
+                    .iter()
+                    .map(|cursor_range| window.to_global_range(cursor_range.as_ccursor_range()))
+                    .collect();
+                state.set_ccursor_ranges(ccursor_ranges);
+            } else if let Some(mut ccursor_range) = state.ccursor_range() {
+                // drag to select text:
+                ccursor_range.primary = ccursor_at_pointer;
+                state.set_ccursor_range(Some(ccursor_range));
+            }
+        }
+    }
+}
+
+/// After `prev_text` was edited into `text` (if `changed`), keep the `folded` headers
+/// where they were, and unfold what the cursors moved into.
+///
+/// Returns `true` if the folds changed.
+fn update_folds(
+    folded: &mut Vec<usize>,
+    prev_text: &str,
+    text: &dyn TextBuffer,
+    changed: bool,
+    cursor_paragraphs: &[usize],
+) -> bool {
+    if folded.is_empty() {
+        return false;
+    }
+    let folded_before = folded.clone();
+    let text = text.as_str();
+    if changed {
+        move_folds(prev_text, &text, folded);
+    }
+    unfold_paragraphs(&text, folded, cursor_paragraphs);
+    *folded != folded_before
+}
+
+/// Paint `galley`, the layout of `window`, at `text_draw_pos`:
+/// the current line and the `brackets` around the text, and the cursors on top of it.
+///
+/// The cursors and `brackets` are char indices into the full text.
+#[allow(clippy::too_many_arguments)]
+fn paint_text(
+    ui: &mut Ui,
+    painter: &Painter,
+    rect: Rect,
+    window: &TextWindow<'_>,
+    galley: &Arc<Galley>,
+    text_draw_pos: Pos2,
+    row_height: f32,
+    font_id: &FontId,
+    ccursor_ranges: &[CCursorRange],
+    highlight_current_line: bool,
+    brackets: Option<[usize; 2]>,
+    hint_galley: Option<WidgetTextGalley>,
+    show_cursors: bool,
+    interactive: bool,
+) {
+    let primary_cursor = ccursor_ranges
+        .first()
+        .map(|ccursor_range| ccursor_range.primary)
+        .filter(|&ccursor| window.contains(ccursor))
+        .map(|ccursor| galley.from_ccursor(window.to_local(ccursor)));
+
+    if let (true, Some(primary_cursor)) = (highlight_current_line, primary_cursor) {
+        let current_paragraph = primary_cursor.pcursor.paragraph;
+        for (row, paragraph) in galley.rows.iter().zip(row_paragraphs(galley)) {
+            if paragraph == current_paragraph && row.rect.height() > 0.0 {
+                let row_rect = row.rect.translate(text_draw_pos.to_vec2());
+                let line_rect = Rect::from_x_y_ranges(rect.x_range(), row_rect.y_range());
+                painter.rect_filled(line_rect, 0.0, ui.visuals().faint_bg_color);
+            }
+        }
+    }
+
+    painter.galley(text_draw_pos, galley.clone());
+
+    for index in brackets.into_iter().flatten() {
+        let ccursor = CCursor::new(index);
+        if !window.contains(ccursor) {
+            continue;
+        }
+        let local = window.to_local(ccursor);
+        let pos = galley.pos_from_cursor(&galley.from_ccursor(local));
+        if pos.height() > 0.0 {
+            let c = window
+                .char_range(local.index..local.index + 1)
+                .chars()
+                .next()
+                .unwrap_or(' ');
+            let width = ui.fonts().glyph_width(font_id, c);
+            let bracket_rect = Rect::from_min_size(pos.min, vec2(width, pos.height()))
+                .translate(text_draw_pos.to_vec2());
+            painter.rect_stroke(
+                bracket_rect,
+                0.0,
+                ui.visuals().widgets.noninteractive.fg_stroke,
+            );
+        }
+    }
+
+    if let Some(hint_galley) = hint_galley {
+        let hint_text_color = ui.visuals().weak_text_color();
+        hint_galley.paint_with_fallback_color(painter, rect.min, hint_text_color);
+    }
+
+    if show_cursors {
+        // We paint the cursor on top of the text, in case
+        // the text galley has backgrounds (as e.g. `code` snippets in markup do).
+        for ccursor_range in ccursor_ranges {
+            let cursor_range = CursorRange {
+                primary: galley.from_ccursor(window.to_local(ccursor_range.primary)),
+                secondary: galley.from_ccursor(window.to_local(ccursor_range.secondary)),
+            };
+            paint_cursor_selection(ui, painter, text_draw_pos, galley, &cursor_range);
+            if window.contains(ccursor_range.primary) {
+                paint_cursor_end(
+                    ui,
+                    row_height,
+                    painter,
+                    text_draw_pos,
+                    galley,
+                    &cursor_range.primary,
+                );
+            }
+        }
+
+        if let (true, Some(primary_cursor)) = (interactive && window.is_mutable(), primary_cursor) {
+            // egui_web uses `text_cursor_pos` when showing IME,
+            // so only set it when text is editable and visible!
+            ui.ctx().output().text_cursor_pos = Some(
+                galley
+                    .pos_from_cursor(&primary_cursor)
+                    .translate(text_draw_pos.to_vec2())
+                    .left_top(),
+            );
+        }
+    }
+}
+
+/// Show the [`TextEdit::line_numbers`] and the [`TextEdit::folding`] markers of `galley`,
+/// the layout of `window`, in the gutter to the left of the margin around `rect`.
+///
+/// Clicking a marker folds or unfolds the lines after it.
+#[allow(clippy::too_many_arguments)]
+fn show_gutter(
+    ui: &Ui,
+    id: Id,
+    state: &mut TextEditState,
+    window: &TextWindow<'_>,
+    galley: &Galley,
+    text_draw_pos: Pos2,
+    rect: Rect,
+    margin: Vec2,
+    gutter_width: f32,
+    font_id: &FontId,
+    line_numbers: bool,
+    folding: bool,
+    hidden: &[Range<usize>],
+    text_hash: &mut Option<u64>,
+    has_focus: bool,
+) {
+    let gutter_rect = Rect::from_min_max(
+        pos2(rect.left() - margin.x - gutter_width, rect.top()),
+        pos2(rect.left() - margin.x, rect.bottom()),
+    );
+    let primary_ccursor = state
+        .ccursor_range()
+        .map(|ccursor_range| ccursor_range.primary);
+    let foldable = if folding {
+        foldable_paragraphs(window)
+    } else {
+        vec![]
+    };
+    let clicked = gutter_ui(
+        ui,
+        id,
+        gutter_rect,
+        text_draw_pos,
+        galley,
+        window.paragraphs.start,
+        font_id,
+        line_numbers,
+        folding.then(|| (state.folded.as_slice(), hidden, foldable.as_slice())),
+        primary_ccursor
+            .filter(|_| has_focus)
+            .map(|ccursor| window.global_cursor(galley, ccursor).pcursor.paragraph),
+    );
+
+    if let Some(header) = clicked {
+        if let Some(i) = state.folded.iter().position(|&folded| folded == header) {
+            state.folded.remove(i);
+        } else {
+            state.folded.push(header);
+            // Don't leave the cursor hidden in the fold:
+            let text = window.full_text();
+            let hidden =
+                state
+                    .code_editor_cache
+                    .lock()
+                    .hidden_paragraphs(text_hash, text, &state.folded);
+            if let Some(primary_ccursor) = primary_ccursor {
+                let paragraph = text.paragraph_from_char_index(primary_ccursor.index);
+                if hidden.iter().any(|range| range.contains(&paragraph)) {
+                    let header_end = text.char_index_from_paragraph(header + 1) - 1;
+                    state.set_ccursor_range(Some(CCursorRange::one(CCursor::new(header_end))));
+                }
+            }
+        }
+        ui.ctx().request_repaint();
+    }
+}
+
+/// Tell screen readers about the text, or that the `selection` (char indices into `text`) changed.
+fn widget_info(
+    response: &Response,
+    password: bool,
+    prev_text: &str,
+    text: &str,
+    selection: Option<RangeInclusive<usize>>,
+) {
+    let info = || {
+        WidgetInfo::text_edit(
+            mask_if_password(password, prev_text),
+            mask_if_password(password, text),
+        )
+    };
+    match selection {
+        Some(char_range) if !response.changed => {
+            let selection_info =
+                WidgetInfo::text_selection_changed(char_range, mask_if_password(password, text));
+            response
+                .ctx
+                .output()
+                .events
+                .push(OutputEvent::TextSelectionChanged(selection_info));
+            response.record_widget_info(info);
+        }
+        _ => response.widget_info(info),
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Check for (keyboard) events to edit the cursor and/or text.
+///
+/// `galley` is the layout of `text`, and `layouter` lays it out again after each change.
+/// The first of the `cursor_ranges` is the primary cursor.
+#[allow(clippy::too_many_arguments)]
+fn events(
+    ui: &mut crate::Ui,
+    state: &mut TextEditState,
+    text: &mut TextWindow<'_>,
+    galley: &mut Arc<Galley>,
+    layouter: &mut dyn FnMut(&Ui, &str) -> Arc<Galley>,
+    id: Id,
+    multiline: bool,
+    password: bool,
+    auto_indent: bool,
+    mut cursor_ranges: Vec<CursorRange>,
+    events: &[Event],
+) -> (bool, Vec<CursorRange>) {
+    // We feed state to the undoer both before and after handling input
+    // so that the undoer creates automatic saves even when there are no events for a while.
+    feed_undo_state(ui, state, text, &cursor_ranges[0], false);
+
+    let mut any_change = false;
+
+    for event in events {
+        // First the events that are about all of the cursors at once:
+        match event {
+            Event::Key {
+                key: Key::Enter,
+                pressed: true,
                 ..
-            } => {
-                if multiline {
-                    let mut ccursor = delete_selected(text, &cursor_range);
-                    insert_text(&mut ccursor, text, "\n");
-                    // TODO: if code editor, auto-indent by same leading tabs, + one if the lines end on an opening bracket
+            } if !multiline => {
+                ui.memory().surrender_focus(id); // End input with enter
+                break;
+            }
+            Event::Copy | Event::Cut if !password => {
+                ui.ctx().output().copied_text = copied_text(text, &cursor_ranges);
+            }
+            Event::Key {
+                key,
+                pressed: true,
+                modifiers,
+            } if modifiers.command && (*key == Key::Z || (*key == Key::Y && !modifiers.shift)) => {
+                let restored = {
+                    let mut undoer = state.undoer.lock();
+                    let current_state = undo_state(&undoer, text, &cursor_ranges[0]);
+                    if *key == Key::Z && !modifiers.shift {
+                        undoer.undo(&current_state).cloned()
+                    } else {
+                        undoer.redo(&current_state).cloned()
+                    }
+                };
+                if let Some((ccursor_range, restored_text)) = restored {
+                    text.restore_snapshot(&restored_text);
+                    any_change = true;
+                    *galley = layouter(ui, &text.as_str());
+                    cursor_ranges = vec![cursor_range_from_ccursor(galley, ccursor_range)];
+                }
+                continue;
+            }
+            Event::Key {
+                key: Key::D,
+                pressed: true,
+                modifiers,
+            } if modifiers.command && multiline && text.is_whole() => {
+                let mut ccursor_ranges: Vec<CCursorRange> = cursor_ranges
+                    .iter()
+                    .map(|cursor_range| cursor_range.as_ccursor_range())
+                    .collect();
+                add_next_occurrence(text, &mut ccursor_ranges);
+                cursor_ranges = ccursor_ranges
+                    .into_iter()
+                    .map(|ccursor_range| cursor_range_from_ccursor(galley, ccursor_range))
+                    .collect();
+                continue;
+            }
+            _ => {}
+        }
+
+        let undo_point = match event {
+            Event::Cut
+            | Event::Key {
+                key: Key::Enter | Key::Tab,
+                pressed: true,
+                ..
+            } => true,
+            Event::Paste(text_to_insert) => !text_to_insert.is_empty(),
+            Event::Text(text_to_insert) => {
+                text_to_insert != "\n"
+                    && text_to_insert != "\r"
+                    && ends_word(text, &cursor_ranges[0], text_to_insert)
+            }
+            _ => false,
+        };
+        if undo_point {
+            add_undo_point(state, text, &cursor_ranges[0]);
+        }
+
+        // Pasting as many lines as there are cursors puts one line at each cursor:
+        let pasted_lines: Option<Vec<&str>> = match event {
+            Event::Paste(text_to_insert) if cursor_ranges.len() > 1 => {
+                Some(text_to_insert.split('\n').collect::<Vec<_>>())
+                    .filter(|lines| lines.len() == cursor_ranges.len())
+            }
+            _ => None,
+        };
+
+        // Apply the event at each cursor, starting with the last one,
+        // so that the edits don't move the cursors we have yet to visit.
+        let mut order: Vec<usize> = (0..cursor_ranges.len()).collect();
+        order.sort_by_key(|&i| {
+            std::cmp::Reverse(cursor_ranges[i].sorted_cursors()[0].ccursor.index)
+        });
+        let mut new_ccursor_ranges: Vec<CCursorRange> = cursor_ranges
+            .iter()
+            .map(|cursor_range| cursor_range.as_ccursor_range())
+            .collect();
+        let mut did_mutate_text = false;
+        for (visited, &i) in order.iter().enumerate() {
+            if *event == Event::Cut && cursor_ranges.len() > 1 && cursor_ranges[i].is_empty() {
+                // Only the selections were copied, so only they are cut.
+                // Cutting all of the text is just for a single cursor.
+                continue;
+            }
+
+            let line_event;
+            let event = if let Some(lines) = &pasted_lines {
+                line_event = Event::Paste(lines[order.len() - 1 - visited].to_owned());
+                &line_event
+            } else {
+                event
+            };
+
+            let chars_before = text.char_count();
+            let new_ccursor_range = event_at_cursor(
+                ui,
+                state,
+                text,
+                galley,
+                id,
+                auto_indent,
+                &mut cursor_ranges[i],
+                event,
+            );
+            let added_chars = text.char_count() as isize - chars_before as isize;
+
+            if added_chars != 0 {
+                // Move the cursors after this one:
+                for &j in &order[..visited] {
+                    let moved = &mut new_ccursor_ranges[j];
+                    for ccursor in [&mut moved.primary, &mut moved.secondary] {
+                        ccursor.index = (ccursor.index as isize + added_chars).max(0) as usize;
+                    }
+                }
+            }
+            if let Some(new_ccursor_range) = new_ccursor_range {
+                did_mutate_text = true;
+                new_ccursor_ranges[i] = new_ccursor_range;
+            } else {
+                new_ccursor_ranges[i] = cursor_ranges[i].as_ccursor_range();
+            }
+        }
+
+        if did_mutate_text {
+            any_change = true;
+            if matches!(event, Event::CompositionEnd(_)) {
+                state.has_ime = false;
+            }
+
+            // Layout again to avoid frame delay, and to keep `text` and `galley` in sync.
+            *galley = layouter(ui, &text.as_str());
+
+            // Set cursor_ranges using new galley:
+            cursor_ranges = new_ccursor_ranges
+                .into_iter()
+                .map(|ccursor_range| cursor_range_from_ccursor(galley, ccursor_range))
+                .collect();
+        }
+
+        cursor_ranges = merge_cursor_ranges(cursor_ranges);
+    }
+
+    feed_undo_state(ui, state, text, &cursor_ranges[0], any_change);
+
+    (any_change, cursor_ranges)
+}
+
+/// Apply an event at one of the cursors, which may move it.
+///
+/// Returns `Some(new_cursor)` if we did mutate `text`.
+#[allow(clippy::too_many_arguments)]
+fn event_at_cursor(
+    ui: &Ui,
+    state: &mut TextEditState,
+    text: &mut dyn TextBuffer,
+    galley: &Galley,
+    id: Id,
+    auto_indent: bool,
+    cursor_range: &mut CursorRange,
+    event: &Event,
+) -> Option<CCursorRange> {
+    match event {
+        Event::Cut => {
+            if cursor_range.is_empty() {
+                text.clear();
+                Some(CCursorRange::default())
+            } else {
+                Some(CCursorRange::one(delete_selected(text, cursor_range)))
+            }
+        }
+        Event::Paste(text_to_insert) => {
+            if !text_to_insert.is_empty() {
+                let mut ccursor = delete_selected(text, cursor_range);
+                insert_text(&mut ccursor, text, text_to_insert);
+                Some(CCursorRange::one(ccursor))
+            } else {
+                None
+            }
+        }
+        Event::Text(text_to_insert) => {
+            // Newlines are handled by `Key::Enter`.
+            if !text_to_insert.is_empty() && text_to_insert != "\n" && text_to_insert != "\r" {
+                let mut ccursor = delete_selected(text, cursor_range);
+                insert_text(&mut ccursor, text, text_to_insert);
+                Some(CCursorRange::one(ccursor))
+            } else {
+                None
+            }
+        }
+        Event::Key {
+            key: Key::Tab,
+            pressed: true,
+            modifiers,
+        } => {
+            if ui.memory().has_lock_focus(id) {
+                let [min, max] = cursor_range.sorted_cursors();
+                if modifiers.shift || min.pcursor.paragraph != max.pcursor.paragraph {
+                    change_indentation(text, cursor_range, !modifiers.shift)
+                } else {
+                    let mut ccursor = delete_selected(text, cursor_range);
+                    insert_text(&mut ccursor, text, "\t");
                     Some(CCursorRange::one(ccursor))
+                }
+            } else {
+                None
+            }
+        }
+        Event::Key {
+            key: Key::Enter,
+            pressed: true,
+            ..
+        } => {
+            // Only multiline, as singleline text edits lose focus on enter.
+            let mut ccursor = delete_selected(text, cursor_range);
+            if auto_indent {
+                let (before, after) = newline_with_indentation(text, ccursor);
+                insert_text(&mut ccursor, text, &before);
+                text.insert_text(&after, ccursor.index);
+            } else {
+                insert_text(&mut ccursor, text, "\n");
+            }
+            Some(CCursorRange::one(ccursor))
+        }
+
+        Event::Key {
+            key,
+            pressed: true,
+            modifiers,
+        } => on_key_press(cursor_range, text, galley, *key, modifiers),
+
+        Event::CompositionStart => {
+            state.has_ime = true;
+            None
+        }
+
+        Event::CompositionUpdate(text_mark) => {
+            if !text_mark.is_empty() && text_mark != "\n" && text_mark != "\r" && state.has_ime {
+                let mut ccursor = delete_selected(text, cursor_range);
+                let start_cursor = ccursor;
+                insert_text(&mut ccursor, text, text_mark);
+                Some(CCursorRange::two(start_cursor, ccursor))
+            } else {
+                None
+            }
+        }
+
+        Event::CompositionEnd(prediction) => {
+            if !prediction.is_empty() && prediction != "\n" && prediction != "\r" && state.has_ime {
+                let mut ccursor = delete_selected(text, cursor_range);
+                insert_text(&mut ccursor, text, prediction);
+                Some(CCursorRange::one(ccursor))
+            } else {
+                None
+            }
+        }
+
+        _ => None,
+    }
+}
+
+fn cursor_range_from_ccursor(galley: &Galley, ccursor_range: CCursorRange) -> CursorRange {
+    CursorRange {
+        primary: galley.from_ccursor(ccursor_range.primary),
+        secondary: galley.from_ccursor(ccursor_range.secondary),
+    }
+}
+
+/// Merge the cursors that overlap, keeping the primary cursor first.
+///
+/// A cursor without a selection is also merged with a selection it touches,
+/// but two selections next to each other are kept apart.
+fn merge_cursor_ranges(cursor_ranges: Vec<CursorRange>) -> Vec<CursorRange> {
+    let mut merged: Vec<CursorRange> = Vec::with_capacity(cursor_ranges.len());
+    for cursor_range in cursor_ranges {
+        let [min, max] = cursor_range.sorted_cursors();
+        let overlapping = merged.iter_mut().find(|existing| {
+            let [existing_min, existing_max] = existing.sorted_cursors();
+            if cursor_range.is_empty() || existing.is_empty() {
+                min.ccursor.index <= existing_max.ccursor.index
+                    && existing_min.ccursor.index <= max.ccursor.index
+            } else {
+                min.ccursor.index < existing_max.ccursor.index
+                    && existing_min.ccursor.index < max.ccursor.index
+            }
+        });
+        if let Some(existing) = overlapping {
+            let [existing_min, existing_max] = existing.sorted_cursors();
+            let min = if min.ccursor.index < existing_min.ccursor.index {
+                min
+            } else {
+                existing_min
+            };
+            let max = if max.ccursor.index > existing_max.ccursor.index {
+                max
+            } else {
+                existing_max
+            };
+            *existing = if existing.primary.ccursor.index < existing.secondary.ccursor.index {
+                CursorRange::two(max, min)
+            } else {
+                CursorRange::two(min, max)
+            };
+        } else {
+            merged.push(cursor_range);
+        }
+    }
+    merged
+}
+
+/// The selected text of all the cursors, one per line, or all of the text if nothing is selected.
+fn copied_text(text: &dyn TextBuffer, cursor_ranges: &[CursorRange]) -> String {
+    let mut selections: Vec<&CursorRange> = cursor_ranges
+        .iter()
+        .filter(|cursor_range| !cursor_range.is_empty())
+        .collect();
+    if selections.is_empty() {
+        return text.as_str().into_owned();
+    }
+    selections.sort_by_key(|cursor_range| cursor_range.sorted_cursors()[0].ccursor.index);
+    selections
+        .into_iter()
+        .map(|cursor_range| selected_str(text, cursor_range))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Select the word at the primary cursor, or if something is selected,
+/// add a cursor that selects the next place where that text occurs.
+fn add_next_occurrence(text: &dyn TextBuffer, ccursor_ranges: &mut Vec<CCursorRange>) {
+    let primary = ccursor_ranges[0];
+    let [min, max] = primary.sorted();
+    if min == max {
+        ccursor_ranges[0] = select_word_at(&text.as_str(), primary.primary);
+        return;
+    }
+
+    let needle = text.char_range(min.index..max.index);
+    let haystack = text.as_str();
+    let start_byte = text.byte_index_from_char_index(max.index);
+    let found = haystack[start_byte..]
+        .find(&*needle)
+        .map(|byte_index| start_byte + byte_index)
+        .or_else(|| haystack.find(&*needle)); // wrap around
+    if let Some(byte_index) = found {
+        let min = CCursor::new(haystack[..byte_index].chars().count());
+        let max = min + needle.chars().count();
+        let already_selected = ccursor_ranges.iter().any(|ccursor_range| {
+            let [existing_min, existing_max] = ccursor_range.sorted();
+            existing_min.index == min.index && existing_max.index == max.index
+        });
+        if !already_selected {
+            ccursor_ranges.insert(0, CCursorRange::two(min, max));
+        }
+    }
+}
+
+/// One selection on each row between `origin` and `pointer` (relative to the galley),
+/// for a column (rectangular) selection. The one on the row of the `pointer` comes first.
+fn column_cursor_ranges(galley: &Galley, origin: Vec2, pointer: Vec2) -> Vec<CursorRange> {
+    let origin_row = galley.cursor_from_pos(origin).rcursor.row;
+    let pointer_row = galley.cursor_from_pos(pointer).rcursor.row;
+    let rows: Vec<usize> = if origin_row <= pointer_row {
+        (origin_row..=pointer_row).rev().collect()
+    } else {
+        (pointer_row..=origin_row).collect()
+    };
+    rows.into_iter()
+        .filter_map(|row| galley.rows.get(row))
+        .map(|row| {
+            let y = row.rect.center().y;
+            CursorRange {
+                primary: galley.cursor_from_pos(vec2(pointer.x, y)),
+                secondary: galley.cursor_from_pos(vec2(origin.x, y)),
+            }
+        })
+        .collect()
+}
+
+/// The undo history is of the whole text, even if we only edit a window of it.
+fn undo_state(
+    undoer: &TextEditUndoer,
+    text: &TextWindow<'_>,
+    cursor_range: &CursorRange,
+) -> (CCursorRange, TextSnapshot) {
+    current_undo_state(
+        undoer,
+        text.to_global_range(cursor_range.as_ccursor_range()),
+        text.full_text(),
+    )
+}
+
+/// Building the undo state compares the whole text with the latest undo point
+/// (and copies it, unless it is a rope), so we only do that if the text has `changed`
+/// (or there is nothing to undo to yet).
+fn feed_undo_state(
+    ui: &Ui,
+    state: &TextEditState,
+    text: &TextWindow<'_>,
+    cursor_range: &CursorRange,
+    changed: bool,
+) {
+    let time = ui.input().time;
+    let mut undoer = state.undoer.lock();
+    if changed || undoer.latest_undo().is_none() {
+        let current_state = undo_state(&undoer, text, cursor_range);
+        undoer.feed_state(time, &current_state);
+    } else {
+        undoer.feed_time(time);
+    }
+}
+
+/// Save the text before a change that should be undone on its own.
+fn add_undo_point(state: &TextEditState, text: &TextWindow<'_>, cursor_range: &CursorRange) {
+    let mut undoer = state.undoer.lock();
+    let current_state = undo_state(&undoer, text, cursor_range);
+    undoer.add_undo(&current_state);
+}
+
+/// Is this the whitespace after a word? Then we want an undo point for each word typed.
+fn ends_word(text: &dyn TextBuffer, cursor_range: &CursorRange, text_to_insert: &str) -> bool {
+    let [min, _] = cursor_range.sorted_cursors();
+    let previous = text
+        .char_range(min.ccursor.index.saturating_sub(1)..min.ccursor.index)
+        .chars()
+        .next();
+    text_to_insert.starts_with(char::is_whitespace)
+        && previous.map_or(false, |chr| !chr.is_whitespace())
+}
+
+// ----------------------------------------------------------------------------
+
+/// With [`TextEdit::virtualized`], handle (and remove) the events that concern the whole text
+/// rather than the paragraphs around the cursors.
+///
+/// The first of the `ccursor_ranges` is the primary cursor.
+/// Returns `true` if the text was changed.
+fn whole_text_events(
+    ui: &Ui,
+    state: &TextEditState,
+    text: &mut dyn TextBuffer,
+    ccursor_ranges: &mut Vec<CCursorRange>,
+    events: &mut Vec<Event>,
+    password: bool,
+) -> bool {
+    let mut changed = false;
+    events.retain(|event| {
+        match event {
+            // All of the text, or a selection that may go outside of what we lay out.
+            // The selections of several cursors are copied by `events`.
+            Event::Copy | Event::Cut if ccursor_ranges.len() == 1 => {
+                let ccursor_range = &mut ccursor_ranges[0];
+                let [min, max] = ccursor_range.sorted();
+                let copied = if min == max {
+                    text.as_str().into_owned()
                 } else {
-                    ui.memory().surrender_focus(id); // End input with enter
-                    break;
+                    text.char_range(min.index..max.index).into_owned()
+                };
+                if !password {
+                    ui.ctx().output().copied_text = copied;
+                }
+                if *event == Event::Cut {
+                    add_whole_text_undo_point(state, text, ccursor_range);
+                    if min == max {
+                        text.clear();
+                        *ccursor_range = CCursorRange::default();
+                    } else {
+                        text.delete_char_range(min.index..max.index);
+                        *ccursor_range = CCursorRange::one(min);
+                    }
+                    changed = true;
                 }
             }
             Event::Key {
-                key: Key::Z,
+                key,
                 pressed: true,
                 modifiers,
-            } if modifiers.command && !modifiers.shift => {
-                // TODO: redo
-                if let Some((undo_ccursor_range, undo_txt)) = state
-                    .undoer
-                    .lock()
-                    .undo(&(cursor_range.as_ccursor_range(), text.as_ref().to_owned()))
-                {
-                    text.replace(undo_txt);
-                    Some(*undo_ccursor_range)
+            } if modifiers.command && (*key == Key::Z || (*key == Key::Y && !modifiers.shift)) => {
+                let mut undoer = state.undoer.lock();
+                let current = current_undo_state(&undoer, ccursor_ranges[0], text);
+                let restored = if *key == Key::Z && !modifiers.shift {
+                    undoer.undo(&current)
                 } else {
-                    None
+                    undoer.redo(&current)
+                };
+                if let Some((restored_ccursor_range, restored_text)) = restored {
+                    text.restore_snapshot(restored_text);
+                    *ccursor_ranges = vec![*restored_ccursor_range];
+                    changed = true;
                 }
             }
-
             Event::Key {
-                key,
+                key: Key::A,
                 pressed: true,
                 modifiers,
-            } => on_key_press(&mut cursor_range, text, galley, *key, modifiers),
-
-            Event::CompositionStart => {
-                state.has_ime = true;
-                None
+            } if modifiers.command => {
+                // select all
+                *ccursor_ranges = vec![CCursorRange::two(
+                    CCursor::default(),
+                    CCursor::new(text.char_count()),
+                )];
             }
-
-            Event::CompositionUpdate(text_mark) => {
-                if !text_mark.is_empty() && text_mark != "\n" && text_mark != "\r" && state.has_ime
-                {
-                    let mut ccursor = delete_selected(text, &cursor_range);
-                    let start_cursor = ccursor;
-                    insert_text(&mut ccursor, text, text_mark);
-                    Some(CCursorRange::two(start_cursor, ccursor))
+            Event::Key {
+                key,
+                pressed: true,
+                modifiers,
+            } if (modifiers.command && matches!(key, Key::ArrowUp | Key::ArrowDown))
+                || (modifiers.ctrl && matches!(key, Key::Home | Key::End)) =>
+            {
+                let mut ccursor_range = ccursor_ranges[0];
+                ccursor_range.primary = if matches!(key, Key::ArrowUp | Key::Home) {
+                    CCursor::default()
                 } else {
-                    None
+                    CCursor::new(text.char_count())
+                };
+                if !modifiers.shift {
+                    ccursor_range.secondary = ccursor_range.primary;
                 }
+                *ccursor_ranges = vec![ccursor_range];
             }
-
-            Event::CompositionEnd(prediction) => {
-                if !prediction.is_empty()
-                    && prediction != "\n"
-                    && prediction != "\r"
-                    && state.has_ime
-                {
-                    state.has_ime = false;
-                    let mut ccursor = delete_selected(text, &cursor_range);
-                    insert_text(&mut ccursor, text, prediction);
-                    Some(CCursorRange::one(ccursor))
-                } else {
-                    None
-                }
+            Event::Key {
+                key: Key::D,
+                pressed: true,
+                modifiers,
+            } if modifiers.command => {
+                // The next occurrence may be anywhere in the text:
+                add_next_occurrence(text, ccursor_ranges);
             }
+            _ => return true,
+        }
+        false
+    });
+    changed
+}
 
-            _ => None,
-        };
+/// Would this event replace the selection?
+fn edits_text(event: &Event, has_lock_focus: bool) -> bool {
+    match event {
+        Event::Text(text) | Event::Paste(text) => !text.is_empty(),
+        Event::CompositionUpdate(_)
+        | Event::CompositionEnd(_)
+        | Event::Key {
+            key: Key::Backspace | Key::Delete | Key::Enter,
+            pressed: true,
+            ..
+        } => true,
+        Event::Key {
+            key: Key::Tab,
+            pressed: true,
+            ..
+        } => has_lock_focus,
+        _ => false,
+    }
+}
 
-        if let Some(new_ccursor_range) = did_mutate_text {
-            any_change = true;
+/// Is this an event that [`events`] handles, i.e. one that may edit the text or move the cursor?
+fn is_text_event(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::Copy
+            | Event::Cut
+            | Event::Paste(_)
+            | Event::Text(_)
+            | Event::Key { pressed: true, .. }
+            | Event::CompositionStart
+            | Event::CompositionUpdate(_)
+            | Event::CompositionEnd(_)
+    )
+}
 
-            // Layout again to avoid frame delay, and to keep `text` and `galley` in sync.
-            *galley = layouter(ui, text.as_ref(), wrap_width);
+/// Delete the selection before the first event that edits the text,
+/// which is all that event does if it is a deletion.
+fn delete_selection(
+    state: &TextEditState,
+    text: &mut dyn TextBuffer,
+    ccursor_range: &mut CCursorRange,
+    events: &mut Vec<Event>,
+) {
+    add_whole_text_undo_point(state, text, ccursor_range);
+    let [min, max] = ccursor_range.sorted();
+    text.delete_char_range(min.index..max.index);
+    *ccursor_range = CCursorRange::one(min);
 
-            // Set cursor_range using new galley:
-            cursor_range = CursorRange {
-                primary: galley.from_ccursor(new_ccursor_range.primary),
-                secondary: galley.from_ccursor(new_ccursor_range.secondary),
-            };
+    if let Some(index) = events.iter().position(|event| edits_text(event, true)) {
+        if matches!(
+            events[index],
+            Event::Key {
+                key: Key::Backspace | Key::Delete,
+                ..
+            }
+        ) {
+            events.remove(index);
         }
     }
+}
 
-    state.set_cursor_range(Some(cursor_range));
+fn add_whole_text_undo_point(
+    state: &TextEditState,
+    text: &dyn TextBuffer,
+    ccursor_range: &CCursorRange,
+) {
+    let mut undoer = state.undoer.lock();
+    let current_state = current_undo_state(&undoer, *ccursor_range, text);
+    undoer.add_undo(&current_state);
+}
 
-    state.undoer.lock().feed_state(
-        ui.input().time,
-        &(cursor_range.as_ccursor_range(), text.as_ref().to_owned()),
-    );
+/// Show the find/replace bar of [`TextEdit::find_replace`] in the top right of `rect`,
+/// and do what the user asks of it, e.g. select the next match.
+fn show_find_replace_bar(
+    ui: &Ui,
+    state: &mut TextEditState,
+    text: &mut dyn TextBuffer,
+    id: Id,
+    rect: Rect,
+) -> Option<FindAction> {
+    let find = state.find_replace().clone();
+    let matches = find.cached_matches(ui.ctx(), &text.as_str());
+    let ccursor_range = state.ccursor_range().unwrap_or_default();
+    let status = match &*matches {
+        Ok(matches) => {
+            let [min, max] = ccursor_range.sorted();
+            let selected = (
+                text.byte_index_from_char_index(min.index),
+                text.byte_index_from_char_index(max.index),
+            );
+            let current = matches.iter().position(|m| (m.start, m.end) == selected);
+            Ok(if let Some(current) = current {
+                format!("{} of {}", current + 1, matches.len())
+            } else {
+                format!("{} found", matches.len())
+            })
+        }
+        Err(error) => Err(error.clone()),
+    };
+
+    let right_top = ui.ctx().layer_transform(ui.layer_id()) * rect.right_top();
+    let action = find_replace_bar(ui, id, right_top, state.find_replace_mut(), status);
 
-    (any_change, cursor_range)
+    let new_find = state.find_replace().clone();
+    let matches = if (&new_find.query, new_find.case_sensitive, new_find.regex)
+        == (&find.query, find.case_sensitive, find.regex)
+    {
+        matches
+    } else {
+        new_find.cached_matches(ui.ctx(), &text.as_str())
+    };
+    let find = new_find;
+    match action {
+        Some(FindAction::Next | FindAction::Previous) => {
+            let forward = action == Some(FindAction::Next);
+            if let Ok(found_matches) = &*matches {
+                let found = FindReplaceState::select_match(
+                    &text.as_str(),
+                    found_matches,
+                    ccursor_range,
+                    forward,
+                );
+                if found.is_some() {
+                    state.set_ccursor_range(found);
+                    return action;
+                }
+            }
+        }
+        Some(FindAction::Replace) if text.is_mutable() => {
+            let replaced = find.replace(text, ccursor_range, |text| {
+                add_whole_text_undo_point(state, text, &ccursor_range);
+            });
+            if let Ok(Some(ccursor)) = replaced {
+                let matches = find.cached_matches(ui.ctx(), &text.as_str());
+                let next = match &*matches {
+                    Ok(matches) => FindReplaceState::select_match(
+                        &text.as_str(),
+                        matches,
+                        CCursorRange::one(ccursor),
+                        true,
+                    ),
+                    Err(_) => None,
+                };
+                state.set_ccursor_range(next.or_else(|| Some(CCursorRange::one(ccursor))));
+                return action;
+            }
+        }
+        Some(FindAction::ReplaceAll) if text.is_mutable() => {
+            let replaced = find.replace_all(text, |text| {
+                add_whole_text_undo_point(state, text, &ccursor_range);
+            });
+            if let Ok(count) = replaced {
+                if count > 0 {
+                    let ccursor = CCursor::new(ccursor_range.primary.index.min(text.char_count()));
+                    state.set_ccursor_range(Some(CCursorRange::one(ccursor)));
+                    return action;
+                }
+            }
+        }
+        Some(FindAction::Close) => return action,
+        _ => {}
+    }
+    None
 }
 
 // ----------------------------------------------------------------------------
@@ -857,6 +2514,37 @@ fn paint_cursor_selection(
 
     for ri in min.row..=max.row {
         let row = &galley.rows[ri];
+        let newline_size = if ri != max.row && row.ends_with_newline {
+            row.height() / 2.0 // visualize that we select the newline
+        } else {
+            0.0
+        };
+
+        if row.has_rtl() {
+            // Mixed-direction text: the selected chars may be spread out over the row.
+            let first_column = if ri == min.row { min.column } else { 0 };
+            let last_column = if ri == max.row {
+                max.column
+            } else {
+                row.char_count_excluding_newline()
+            };
+            let ranges = row.x_ranges(first_column..last_column);
+            let num_ranges = ranges.len();
+            for (i, range) in ranges.into_iter().enumerate() {
+                let right = if i + 1 == num_ranges {
+                    *range.end() + newline_size
+                } else {
+                    *range.end()
+                };
+                let rect = Rect::from_min_max(
+                    pos + vec2(*range.start(), row.min_y()),
+                    pos + vec2(right, row.max_y()),
+                );
+                painter.rect_filled(rect, 0.0, color);
+            }
+            continue;
+        }
+
         let left = if ri == min.row {
             row.x_offset(min.column)
         } else {
@@ -865,11 +2553,6 @@ fn paint_cursor_selection(
         let right = if ri == max.row {
             row.x_offset(max.column)
         } else {
-            let newline_size = if row.ends_with_newline {
-                row.height() / 2.0 // visualize that we select the newline
-            } else {
-                0.0
-            };
             row.rect.right() + newline_size
         };
         let rect = Rect::from_min_max(
@@ -919,7 +2602,7 @@ fn paint_cursor_end(
 
 // ----------------------------------------------------------------------------
 
-fn selected_str<'s>(text: &'s dyn TextBuffer, cursor_range: &CursorRange) -> &'s str {
+fn selected_str<'s>(text: &'s dyn TextBuffer, cursor_range: &CursorRange) -> Cow<'s, str> {
     let [min, max] = cursor_range.sorted_cursors();
     text.char_range(min.ccursor.index..max.ccursor.index)
 }
@@ -958,12 +2641,12 @@ fn delete_next_char(text: &mut dyn TextBuffer, ccursor: CCursor) -> CCursor {
 }
 
 fn delete_previous_word(text: &mut dyn TextBuffer, max_ccursor: CCursor) -> CCursor {
-    let min_ccursor = ccursor_previous_word(text.as_ref(), max_ccursor);
+    let min_ccursor = ccursor_previous_word(&text.as_str(), max_ccursor);
     delete_selected_ccursor_range(text, [min_ccursor, max_ccursor])
 }
 
 fn delete_next_word(text: &mut dyn TextBuffer, min_ccursor: CCursor) -> CCursor {
-    let max_ccursor = ccursor_next_word(text.as_ref(), min_ccursor);
+    let max_ccursor = ccursor_next_word(&text.as_str(), min_ccursor);
     delete_selected_ccursor_range(text, [min_ccursor, max_ccursor])
 }
 
@@ -1230,46 +2913,462 @@ fn is_word_char(c: char) -> bool {
     c.is_ascii_alphanumeric() || c == '_'
 }
 
-/// Accepts and returns character offset (NOT byte offset!).
-fn find_line_start(text: &str, current_index: CCursor) -> CCursor {
-    // We know that new lines, '\n', are a single byte char, but we have to
-    // work with char offsets because before the new line there may be any
-    // number of multi byte chars.
-    // We need to know the char index to be able to correctly set the cursor
-    // later.
-    let chars_count = text.chars().count();
+/// Indent (or outdent) all of the lines that the cursor range is on.
+///
+/// Returns `None` if there was nothing to outdent.
+fn change_indentation(
+    text: &mut dyn TextBuffer,
+    cursor_range: &CursorRange,
+    indent: bool,
+) -> Option<CCursorRange> {
+    let [min, max] = cursor_range.as_ccursor_range().sorted();
+    let first = text.paragraph_from_char_index(min.index);
+    let mut last = text.paragraph_from_char_index(max.index);
+    if last > first && text.char_index_from_paragraph(last) == max.index {
+        last -= 1; // the selection ends at the start of a line, so leave that line alone
+    }
 
-    let position = text
-        .chars()
-        .rev()
-        .skip(chars_count - current_index.index)
-        .position(|x| x == '\n');
+    let mut new_range = cursor_range.as_ccursor_range();
+    let mut changed = false;
+    for paragraph in (first..=last).rev() {
+        let line_start = text.char_index_from_paragraph(paragraph);
+        if indent {
+            text.insert_text("\t", line_start);
+            for ccursor in [&mut new_range.primary, &mut new_range.secondary] {
+                if ccursor.index >= line_start {
+                    ccursor.index += 1;
+                }
+            }
+            changed = true;
+        } else {
+            let line =
+                text.char_range(line_start..(line_start + text::TAB_SIZE).min(text.char_count()));
+            let remove_len = if line.starts_with('\t') {
+                1
+            } else {
+                line.chars().take_while(|&c| c == ' ').count()
+            };
+            if remove_len > 0 {
+                text.delete_char_range(line_start..line_start + remove_len);
+                for ccursor in [&mut new_range.primary, &mut new_range.secondary] {
+                    if ccursor.index > line_start {
+                        ccursor.index = ccursor.index.saturating_sub(remove_len).max(line_start);
+                    }
+                }
+                changed = true;
+            }
+        }
+    }
+    changed.then(|| new_range)
+}
 
-    match position {
-        Some(pos) => CCursor::new(current_index.index - pos),
-        None => CCursor::new(0),
+#[test]
+fn test_multiple_cursors() {
+    fn select(ctx: &Context, id: Id, selections: &[(usize, usize)]) {
+        let mut state = TextEditState::load(ctx, id).unwrap_or_default();
+        state.set_ccursor_ranges(
+            selections
+                .iter()
+                .map(|&(min, max)| CCursorRange::two(CCursor::new(min), CCursor::new(max)))
+                .collect(),
+        );
+        state.store(ctx, id);
+        ctx.memory().request_focus(id);
     }
+    fn set_cursors(ctx: &Context, id: Id, indices: &[usize]) {
+        let selections: Vec<_> = indices.iter().map(|&index| (index, index)).collect();
+        select(ctx, id, &selections);
+    }
+
+    let mut driver =
+        crate::testing::Driver::new((String::from("ab\ncd"), Id::new("")), |ctx, (text, id)| {
+            CentralPanel::default().show(ctx, |ui| {
+                *id = ui.text_edit_multiline(text).id;
+            });
+        });
+    driver.run();
+    let id = driver.state().1;
+
+    set_cursors(driver.ctx(), id, &[0, 3]);
+    driver.type_text_into_focused("X");
+    assert_eq!(driver.state().0, "Xab\nXcd");
+
+    driver.press_key(Key::Backspace);
+    assert_eq!(driver.state().0, "ab\ncd");
+
+    // Cursors that meet are merged:
+    set_cursors(driver.ctx(), id, &[1, 2]);
+    driver.press_key(Key::Backspace);
+    assert_eq!(driver.state().0, "\ncd");
+    let state = TextEditState::load(driver.ctx(), id).unwrap();
+    assert_eq!(state.ccursor_ranges().len(), 1);
+
+    // Copy and cut all the selections, one per line:
+    driver.state_mut().0 = String::from("ab cd ef");
+    select(driver.ctx(), id, &[(6, 8), (0, 2)]);
+    driver.push_event(Event::Copy);
+    driver.run();
+    assert_eq!(driver.output().copied_text, "ab\nef");
+
+    // A cursor without a selection cuts nothing when others have one:
+    select(driver.ctx(), id, &[(0, 2), (4, 4)]);
+    driver.push_event(Event::Cut);
+    driver.run();
+    assert_eq!(driver.output().copied_text, "ab");
+    assert_eq!(driver.state().0, " cd ef");
+
+    // As many lines as there are cursors are pasted one at each cursor:
+    driver.state_mut().0 = String::from("ab\ncd");
+    set_cursors(driver.ctx(), id, &[3, 0]);
+    driver.push_event(Event::Paste(String::from("1\n2")));
+    driver.run();
+    assert_eq!(driver.state().0, "1ab\n2cd");
+
+    // Cmd/Ctrl+D selects the word, then adds a cursor at its next occurrence:
+    driver.state_mut().0 = String::from("ab cd ab");
+    set_cursors(driver.ctx(), id, &[1]);
+    let command = Modifiers {
+        command: true,
+        ..Default::default()
+    };
+    driver.press_key_with_modifiers(Key::D, command);
+    driver.press_key_with_modifiers(Key::D, command);
+    driver.type_text_into_focused("X");
+    assert_eq!(driver.state().0, "X cd X");
+
+    // Selections that only touch are not merged:
+    driver.state_mut().0 = String::from("abab");
+    select(driver.ctx(), id, &[(0, 2)]);
+    driver.press_key_with_modifiers(Key::D, command);
+    let state = TextEditState::load(driver.ctx(), id).unwrap();
+    assert_eq!(state.ccursor_ranges().len(), 2);
+    driver.type_text_into_focused("X");
+    assert_eq!(driver.state().0, "XX");
 }
 
-fn decrease_identation(ccursor: &mut CCursor, text: &mut dyn TextBuffer) {
-    let line_start = find_line_start(text.as_ref(), *ccursor);
+#[test]
+fn test_column_selection() {
+    let mut driver = crate::testing::Driver::new(
+        (String::from("abc\ndef\nghi"), Id::new("")),
+        |ctx, (text, id)| {
+            CentralPanel::default().show(ctx, |ui| {
+                *id = ui.text_edit_multiline(text).id;
+            });
+        },
+    );
+    driver.run();
+    let id = driver.state().1;
+    let rect = driver.get_by_id(id).rect;
+    let font_id = FontSelection::default().resolve(&driver.ctx().style());
+    let row_height = driver.ctx().fonts().row_height(&font_id);
+
+    // Alt+drag from the start of the first row to past the end of the second:
+    driver.set_modifiers(Modifiers {
+        alt: true,
+        ..Default::default()
+    });
+    driver.drag_at(
+        rect.left_top() + vec2(1.0, 0.5 * row_height),
+        rect.right_top() + vec2(-1.0, 1.5 * row_height),
+    );
+    driver.set_modifiers(Modifiers::default());
+    let state = TextEditState::load(driver.ctx(), id).unwrap();
+    assert_eq!(state.ccursor_ranges().len(), 2);
+
+    driver.push_event(Event::Cut);
+    driver.run();
+    assert_eq!(driver.output().copied_text, "abc\ndef");
+    assert_eq!(driver.state().0, "\n\nghi");
+}
 
-    let remove_len = if text.as_ref()[line_start.index..].starts_with('\t') {
-        Some(1)
-    } else if text.as_ref()[line_start.index..]
-        .chars()
-        .take(text::TAB_SIZE)
-        .all(|c| c == ' ')
-    {
-        Some(text::TAB_SIZE)
-    } else {
-        None
+#[test]
+fn test_redo_after_moving_cursor() {
+    let mut driver =
+        crate::testing::Driver::new((String::new(), Id::new("")), |ctx, (text, id)| {
+            CentralPanel::default().show(ctx, |ui| {
+                *id = ui.text_edit_singleline(text).id;
+            });
+        });
+    driver.run();
+    let id = driver.state().1;
+    driver.click_id(id);
+    driver.type_text_into_focused("ab");
+    driver.type_text_into_focused(" cd");
+
+    let command = Modifiers {
+        command: true,
+        ..Default::default()
     };
+    driver.press_key_with_modifiers(Key::Z, command);
+    assert_eq!(driver.state().0, "ab");
+
+    // Just moving the cursor doesn't forget what can be redone:
+    driver.press_key(Key::ArrowLeft);
+    let text = driver.state().0.clone();
+    assert!(TextEditState::load(driver.ctx(), id)
+        .unwrap()
+        .has_redo(&text));
+    driver.press_key_with_modifiers(
+        Key::Z,
+        Modifiers {
+            shift: true,
+            ..command
+        },
+    );
+    assert_eq!(driver.state().0, "ab cd");
+}
 
-    if let Some(len) = remove_len {
-        text.delete_char_range(line_start.index..(line_start.index + len));
-        if *ccursor != line_start {
-            *ccursor -= len;
-        }
-    }
+#[test]
+fn test_virtualized() {
+    let mut driver = crate::testing::Driver::new(
+        (String::from("ab\ncd\nef"), Id::new("")),
+        |ctx, (text, id)| {
+            CentralPanel::default().show(ctx, |ui| {
+                ScrollArea::vertical().show(ui, |ui| {
+                    *id = TextEdit::multiline(text)
+                        .virtualized(true)
+                        .show(ui)
+                        .response
+                        .id;
+                });
+            });
+        },
+    );
+    driver.run();
+    let id = driver.state().1;
+    driver.click_id(id);
+    driver.type_text_into_focused("X");
+    assert_eq!(str::replace(&driver.state().0, 'X', ""), "ab\ncd\nef");
+
+    // Moving the pointer over the focused editor changes nothing:
+    let rect = driver.get_by_id(id).rect;
+    driver.hover_at(rect.left_top());
+    driver.hover_at(rect.center());
+    assert!(driver.state().0.contains('X'));
+
+    let command = Modifiers {
+        command: true,
+        ..Default::default()
+    };
+    driver.press_key_with_modifiers(Key::Z, command);
+    assert_eq!(driver.state().0, "ab\ncd\nef");
+}
+
+#[test]
+fn test_virtualized_code_editor() {
+    // Far more lines than fit on the screen, each block 17 chars long:
+    let text: String = (0..1000)
+        .map(|i| format!("fn f{:03}() {{\n\tx\n}}\n", i))
+        .collect();
+    let mut driver = crate::testing::Driver::new(
+        (text.clone(), Id::new(""), None),
+        |ctx, (text, id, cursor_range): &mut (String, Id, Option<CursorRange>)| {
+            CentralPanel::default().show(ctx, |ui| {
+                ScrollArea::vertical().show(ui, |ui| {
+                    let output = TextEdit::multiline(text)
+                        .code_editor()
+                        .find_replace(true)
+                        .virtualized(true)
+                        .show(ui);
+                    *id = output.response.id;
+                    *cursor_range = output.cursor_range;
+                });
+            });
+        },
+    );
+    driver.run();
+    let id = driver.state().1;
+    let set_cursors = |driver: &mut crate::testing::Driver<'_, _>, indices: &[usize]| {
+        let mut state = TextEditState::load(driver.ctx(), id).unwrap_or_default();
+        state.set_ccursor_ranges(
+            indices
+                .iter()
+                .map(|&index| CCursorRange::one(CCursor::new(index)))
+                .collect(),
+        );
+        state.store(driver.ctx(), id);
+        driver.ctx().memory().request_focus(id);
+    };
+    let command = Modifiers {
+        command: true,
+        ..Default::default()
+    };
+
+    // Each cursor edits the text, even if it is far outside of what is visible:
+    set_cursors(&mut driver, &[0, 999 * 17]);
+    driver.type_text_into_focused("X");
+    assert!(driver.state().0.starts_with("Xfn f000"));
+    assert!(driver.state().0.ends_with("Xfn f999() {\n\tx\n}\n"));
+    driver.press_key_with_modifiers(Key::Z, command);
+    assert_eq!(driver.state().0, text);
+
+    // The output cursor is into the whole text, not into what is laid out:
+    driver.press_key_with_modifiers(Key::ArrowDown, command);
+    driver.run();
+    let cursor_range = driver.state().2.unwrap();
+    assert_eq!(cursor_range.primary.ccursor.index, text.chars().count());
+    assert_eq!(cursor_range.primary.pcursor.paragraph, 3000);
+
+    // Find the last block, far from the top, and select it:
+    driver.press_key_with_modifiers(Key::F, command);
+    driver.type_text_into_focused("f999");
+    driver.press_key(Key::Enter);
+    driver.press_key(Key::Escape);
+    driver.run();
+    let cursor_range = driver.state().2.unwrap();
+    let selected = driver
+        .state()
+        .0
+        .char_range(cursor_range.as_sorted_char_range())
+        .into_owned();
+    assert_eq!(selected, "f999");
+
+    driver.press_key_with_modifiers(Key::H, command);
+    driver.click_id(id.with("find_replacement"));
+    driver.type_text_into_focused("g");
+    driver.click("Replace all");
+    assert!(driver.state().0.ends_with("fn g() {\n\tx\n}\n"));
+    driver.ctx().memory().request_focus(id);
+    driver.press_key_with_modifiers(Key::Z, command);
+    assert_eq!(driver.state().0, text);
+
+    // Fold the first block, which is visible again, in the gutter.
+    // Hover first, or the `ScrollArea` sees the pointer jump as a drag:
+    driver.press_key_with_modifiers(Key::ArrowUp, command);
+    driver.run();
+    let marker = driver.get_by_id(id.with(("fold", 0_usize))).rect.center();
+    let height = driver.get_by_id(id).rect.height();
+    driver.hover_at(marker);
+    driver.click_at(marker);
+    let state = TextEditState::load(driver.ctx(), id).unwrap();
+    assert_eq!(state.folded, vec![0]);
+    driver.run();
+    assert!(driver.get_by_id(id).rect.height() < height);
+
+    // The fold moves with the lines above it, and unfolds when the cursor moves into it:
+    set_cursors(&mut driver, &[0]);
+    driver.press_key(Key::Enter);
+    let state = TextEditState::load(driver.ctx(), id).unwrap();
+    assert_eq!(state.folded, vec![1]);
+    set_cursors(&mut driver, &[14]);
+    driver.run();
+    let state = TextEditState::load(driver.ctx(), id).unwrap();
+    assert!(state.folded.is_empty());
+}
+
+#[test]
+fn test_find_replace() {
+    let mut driver = crate::testing::Driver::new(
+        (String::from("ab cd ab"), Id::new("")),
+        |ctx, (text, id)| {
+            CentralPanel::default().show(ctx, |ui| {
+                *id = TextEdit::multiline(text)
+                    .find_replace(true)
+                    .show(ui)
+                    .response
+                    .id;
+            });
+        },
+    );
+    driver.run();
+    let id = driver.state().1;
+    let command = Modifiers {
+        command: true,
+        ..Default::default()
+    };
+    driver.click_id(id);
+
+    driver.press_key_with_modifiers(Key::F, command);
+    driver.type_text_into_focused("AB");
+    driver.press_key(Key::Enter);
+    let state = TextEditState::load(driver.ctx(), id).unwrap();
+    assert_eq!(state.find_replace().query, "AB");
+    let selection = state.ccursor_range().unwrap().sorted();
+    assert!(selection[0].index == 0 || selection[0].index == 6);
+    assert_eq!(selection[1].index - selection[0].index, 2);
+
+    driver.press_key_with_modifiers(Key::H, command);
+    driver.click_id(id.with("find_replacement"));
+    driver.type_text_into_focused("xy");
+    driver.click("Replace all");
+    assert_eq!(driver.state().0, "xy cd xy");
+
+    driver.click_id(id);
+    driver.press_key_with_modifiers(Key::Z, command);
+    assert_eq!(driver.state().0, "ab cd ab");
+}
+
+#[test]
+fn test_code_editor() {
+    let mut driver = crate::testing::Driver::new(
+        (String::from("fn f() {}"), Id::new("")),
+        |ctx, (text, id)| {
+            CentralPanel::default().show(ctx, |ui| {
+                *id = TextEdit::multiline(text).code_editor().show(ui).response.id;
+            });
+        },
+    );
+    driver.run();
+    let id = driver.state().1;
+    let set_ccursor_range = |driver: &mut crate::testing::Driver<'_, _>, range: CCursorRange| {
+        let mut state = TextEditState::load(driver.ctx(), id).unwrap_or_default();
+        state.set_ccursor_range(Some(range));
+        state.store(driver.ctx(), id);
+    };
+    driver.click_id(id);
+
+    // Enter between brackets indents the new line, and moves the closing bracket down:
+    set_ccursor_range(&mut driver, CCursorRange::one(CCursor::new(8)));
+    driver.press_key(Key::Enter);
+    driver.type_text_into_focused("x");
+    assert_eq!(driver.state().0, "fn f() {\n\tx\n}");
+
+    // Tab and Shift+Tab indent and outdent all the selected lines:
+    set_ccursor_range(
+        &mut driver,
+        CCursorRange::two(CCursor::new(0), CCursor::new(13)),
+    );
+    driver.press_key(Key::Tab);
+    assert_eq!(driver.state().0, "\tfn f() {\n\t\tx\n\t}");
+    let shift = Modifiers {
+        shift: true,
+        ..Default::default()
+    };
+    driver.press_key_with_modifiers(Key::Tab, shift);
+    driver.press_key_with_modifiers(Key::Tab, shift);
+    assert_eq!(driver.state().0, "fn f() {\nx\n}");
+    let command = Modifiers {
+        command: true,
+        ..Default::default()
+    };
+    driver.press_key_with_modifiers(Key::Z, command);
+    assert_eq!(driver.state().0, "fn f() {\n\tx\n}");
+
+    // Folding the block moves the cursor out of it:
+    set_ccursor_range(&mut driver, CCursorRange::one(CCursor::new(11)));
+    driver.click_id(id.with(("fold", 0_usize)));
+    let state = TextEditState::load(driver.ctx(), id).unwrap();
+    assert_eq!(state.folded, vec![0]);
+    assert_eq!(
+        state.ccursor_range(),
+        Some(CCursorRange::one(CCursor::new(8)))
+    );
+    driver.click_id(id.with(("fold", 0_usize)));
+    assert!(TextEditState::load(driver.ctx(), id)
+        .unwrap()
+        .folded
+        .is_empty());
+
+    // A fold moves with the lines above it, even when they are not edited at the cursor:
+    driver.click_id(id);
+    set_ccursor_range(&mut driver, CCursorRange::one(CCursor::new(0)));
+    driver.press_key(Key::Enter);
+    assert_eq!(driver.state().0, "\nfn f() {\n\tx\n}");
+    driver.click_id(id.with(("fold", 1_usize)));
+    set_ccursor_range(&mut driver, CCursorRange::one(CCursor::new(14)));
+    driver.ctx().memory().request_focus(id);
+    driver.press_key_with_modifiers(Key::Z, command);
+    assert_eq!(driver.state().0, "fn f() {\n\tx\n}");
+    let state = TextEditState::load(driver.ctx(), id).unwrap();
+    assert_eq!(state.folded, vec![0]);
 }
@@ -0,0 +1,554 @@
+//! The parts of [`TextEdit::code_editor`] that are about code:
+//! the gutter with line numbers, folding, bracket matching and indentation.
+
+use std::{borrow::Cow, ops::Range, sync::Arc};
+
+use epaint::text::{cursor::CCursor, Galley, TAB_SIZE};
+
+use crate::*;
+
+use super::{text_window::TextWindow, TextBuffer};
+
+const BRACKETS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+/// The paragraph of each row of the galley.
+pub(crate) fn row_paragraphs(galley: &Galley) -> Vec<usize> {
+    let mut paragraph = 0;
+    galley
+        .rows
+        .iter()
+        .map(|row| {
+            let row_paragraph = paragraph;
+            if row.ends_with_newline {
+                paragraph += 1;
+            }
+            row_paragraph
+        })
+        .collect()
+}
+
+/// How much room the line numbers and fold markers need to the left of the text.
+pub(crate) fn gutter_width(
+    ui: &Ui,
+    font_id: &FontId,
+    paragraph_count: usize,
+    line_numbers: bool,
+    folding: bool,
+) -> f32 {
+    let fonts = ui.fonts();
+    let mut width = ui.spacing().item_spacing.x;
+    if line_numbers {
+        let digits = paragraph_count.max(1).to_string().len();
+        width += digits as f32 * fonts.glyph_width(font_id, '0');
+    }
+    if folding {
+        width += fonts.row_height(font_id);
+    }
+    width
+}
+
+// ----------------------------------------------------------------------------
+// Folding:
+
+/// How far the line is indented, or `None` for blank lines.
+fn indentation(line: &str) -> Option<usize> {
+    if line.trim().is_empty() {
+        return None;
+    }
+    Some(
+        line.chars()
+            .take_while(|c| c.is_whitespace())
+            .map(|c| if c == '\t' { TAB_SIZE } else { 1 })
+            .sum(),
+    )
+}
+
+/// Is the next line that isn't blank indented more than this one?
+fn is_foldable(lines: &[&str], header: usize) -> bool {
+    fold_region(lines, header).is_some()
+}
+
+/// Which of the paragraphs in `window` can be folded.
+pub(crate) fn foldable_paragraphs(window: &TextWindow<'_>) -> Vec<bool> {
+    let window_text = window.as_str();
+    let mut lines: Vec<Cow<'_, str>> = window_text.split('\n').map(Cow::Borrowed).collect();
+    // The last paragraphs may be followed by what they fold, up to the first line that isn't blank:
+    let full_text = window.full_text();
+    for paragraph in window.paragraphs.end..full_text.paragraph_count() {
+        let line = full_text.paragraph(paragraph);
+        let is_blank = indentation(&line).is_none();
+        lines.push(line);
+        if !is_blank {
+            break;
+        }
+    }
+    let lines: Vec<&str> = lines.iter().map(|line| &**line).collect();
+    (0..window.paragraphs.len())
+        .map(|paragraph| is_foldable(&lines, paragraph))
+        .collect()
+}
+
+/// The paragraphs that folding `header` hides: the ones after it that are indented more.
+fn fold_region(lines: &[&str], header: usize) -> Option<Range<usize>> {
+    let header_indentation = indentation(lines.get(header)?)?;
+    let mut end = header + 1;
+    for (i, line) in lines.iter().enumerate().skip(header + 1) {
+        match indentation(line) {
+            None => {} // blank lines are hidden if there is more to hide after them
+            Some(indentation) if indentation > header_indentation => end = i + 1,
+            Some(_) => break,
+        }
+    }
+    if end > header + 1 {
+        Some(header + 1..end)
+    } else {
+        None
+    }
+}
+
+/// The paragraphs hidden by folding the `folded` paragraphs, sorted and without overlaps.
+pub(crate) fn hidden_paragraphs(text: &str, folded: &[usize]) -> Vec<Range<usize>> {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let mut folded = folded.to_vec();
+    folded.sort_unstable();
+    let mut hidden: Vec<Range<usize>> = vec![];
+    for header in folded {
+        if hidden.last().map_or(false, |range| range.contains(&header)) {
+            continue; // inside of an outer fold
+        }
+        if let Some(region) = fold_region(&lines, header) {
+            hidden.push(region);
+        }
+    }
+    hidden
+}
+
+/// The hash of `text`, unless we already have it.
+fn hash_text(text_hash: &mut Option<u64>, text: &dyn TextBuffer) -> u64 {
+    *text_hash.get_or_insert_with(|| crate::util::hash(text.as_str()))
+}
+
+/// A galley, the paragraphs hidden in it, and the galley folded.
+type FoldedGalley = (Arc<Galley>, Vec<Range<usize>>, Arc<Galley>);
+
+/// What [`TextEdit::code_editor`] would otherwise work out from the whole text every frame.
+#[derive(Clone, Default)]
+pub(crate) struct CodeEditorCache {
+    /// Text hash and folded headers, and the paragraphs they hide.
+    hidden: Option<(u64, Vec<usize>, Vec<Range<usize>>)>,
+
+    /// Two of them, because the galley is folded both with and without find highlights.
+    folded: Vec<FoldedGalley>,
+
+    /// Text hash and cursor, and the brackets around it.
+    brackets: Option<(u64, CCursor, Option<[usize; 2]>)>,
+}
+
+impl CodeEditorCache {
+    /// Like [`hidden_paragraphs`], but only when the text or folds changed.
+    ///
+    /// `text_hash` is filled in if it is `None`, and should be reset when the text changes.
+    pub(crate) fn hidden_paragraphs(
+        &mut self,
+        text_hash: &mut Option<u64>,
+        text: &dyn TextBuffer,
+        folded: &[usize],
+    ) -> Vec<Range<usize>> {
+        if folded.is_empty() {
+            return vec![]; // without looking at the text
+        }
+        let text_hash = hash_text(text_hash, text);
+        match &self.hidden {
+            Some((hash, cached_folded, hidden))
+                if *hash == text_hash && cached_folded == folded =>
+            {
+                hidden.clone()
+            }
+            _ => {
+                let hidden = hidden_paragraphs(&text.as_str(), folded);
+                self.hidden = Some((text_hash, folded.to_vec(), hidden.clone()));
+                hidden
+            }
+        }
+    }
+
+    /// Like [`Galley::fold`], but only when the galley or the hidden paragraphs changed.
+    pub(crate) fn fold(
+        &mut self,
+        ui: &Ui,
+        galley: Arc<Galley>,
+        hidden: &[Range<usize>],
+    ) -> Arc<Galley> {
+        if hidden.is_empty() {
+            return galley;
+        }
+        let cached = self.folded.iter().find(|(unfolded, cached_hidden, _)| {
+            Arc::ptr_eq(unfolded, &galley) && cached_hidden == hidden
+        });
+        if let Some((_, _, folded)) = cached {
+            // A stale galley would be laid out again when painted, unfolding it:
+            if !ui.fonts().is_galley_stale(folded) {
+                return folded.clone();
+            }
+        }
+        let folded = Arc::new(galley.fold(hidden));
+        self.folded
+            .retain(|(unfolded, _, _)| !Arc::ptr_eq(unfolded, &galley));
+        if self.folded.len() >= 2 {
+            self.folded.remove(0);
+        }
+        self.folded.push((galley, hidden.to_vec(), folded.clone()));
+        folded
+    }
+
+    /// Like [`matching_brackets`], but only when the text or cursor changed.
+    pub(crate) fn matching_brackets(
+        &mut self,
+        text_hash: &mut Option<u64>,
+        text: &dyn TextBuffer,
+        ccursor: CCursor,
+    ) -> Option<[usize; 2]> {
+        let text_hash = hash_text(text_hash, text);
+        match self.brackets {
+            Some((hash, cached_ccursor, brackets))
+                if hash == text_hash && cached_ccursor == ccursor =>
+            {
+                brackets
+            }
+            _ => {
+                let brackets = matching_brackets(&text.as_str(), ccursor);
+                self.brackets = Some((text_hash, ccursor, brackets));
+                brackets
+            }
+        }
+    }
+}
+
+/// The part of `old_text` that was changed to get `new_text`, as a byte range.
+///
+/// Everything before and after it is unchanged, so an edit anywhere is found
+/// (e.g. at another cursor, or an undo), not just at the cursor.
+fn edited_range(old_text: &str, new_text: &str) -> Range<usize> {
+    let prefix: usize = old_text
+        .chars()
+        .zip(new_text.chars())
+        .take_while(|(old, new)| old == new)
+        .map(|(c, _)| c.len_utf8())
+        .sum();
+    let max_suffix = old_text.len().min(new_text.len()) - prefix;
+    let mut suffix = 0;
+    for (old, new) in old_text[prefix..]
+        .chars()
+        .rev()
+        .zip(new_text[prefix..].chars().rev())
+    {
+        if old != new || suffix + old.len_utf8() > max_suffix {
+            break;
+        }
+        suffix += old.len_utf8();
+    }
+    prefix..old_text.len() - suffix
+}
+
+/// Keep the folds where they were after `old_text` was edited into `new_text`,
+/// and forget the folds that no longer fold anything, or whose header was edited away.
+pub(crate) fn move_folds(old_text: &str, new_text: &str, folded: &mut Vec<usize>) {
+    let edited = edited_range(old_text, new_text);
+    let added_paragraphs =
+        new_text.matches('\n').count() as isize - old_text.matches('\n').count() as isize;
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(old_text.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let lines: Vec<&str> = new_text.split('\n').collect();
+    *folded = folded
+        .iter()
+        .filter_map(|&header| {
+            let line_start = *line_starts.get(header)?;
+            if edited.end <= line_start {
+                // After the edit:
+                Some((header as isize + added_paragraphs) as usize)
+            } else if line_start < edited.start || added_paragraphs == 0 {
+                // Before the edit, or at least nothing moved:
+                Some(header)
+            } else {
+                None // the header was edited away
+            }
+        })
+        .filter(|&header| is_foldable(&lines, header))
+        .collect();
+}
+
+/// Unfold the folds that hide any of the given paragraphs, e.g. because a cursor is there.
+pub(crate) fn unfold_paragraphs(text: &str, folded: &mut Vec<usize>, paragraphs: &[usize]) {
+    let lines: Vec<&str> = text.split('\n').collect();
+    folded.retain(|&header| {
+        fold_region(&lines, header).map_or(false, |region| {
+            !paragraphs
+                .iter()
+                .any(|paragraph| region.contains(paragraph))
+        })
+    });
+}
+
+// ----------------------------------------------------------------------------
+// Brackets and indentation:
+
+/// If there is a bracket just before (or else after) the cursor, find the one it pairs with.
+///
+/// Returns the char indices of both brackets.
+pub(crate) fn matching_brackets(text: &str, ccursor: CCursor) -> Option<[usize; 2]> {
+    let chars: Vec<char> = text.chars().collect();
+    for index in [ccursor.index.checked_sub(1), Some(ccursor.index)]
+        .into_iter()
+        .flatten()
+    {
+        let c = match chars.get(index) {
+            Some(&c) => c,
+            None => continue,
+        };
+        for (open, close) in BRACKETS {
+            let partner = if c == open {
+                find_partner(chars[index + 1..].iter().enumerate(), open, close)
+                    .map(|i| index + 1 + i)
+            } else if c == close {
+                find_partner(chars[..index].iter().enumerate().rev(), close, open)
+            } else {
+                continue;
+            };
+            return partner.map(|partner| [index, partner]);
+        }
+    }
+    None
+}
+
+/// Find the `to` bracket that closes the nesting we are in, counting `from` brackets as nesting deeper.
+fn find_partner<'a>(
+    chars: impl Iterator<Item = (usize, &'a char)>,
+    from: char,
+    to: char,
+) -> Option<usize> {
+    let mut depth = 0;
+    for (i, &c) in chars {
+        if c == from {
+            depth += 1;
+        } else if c == to {
+            if depth == 0 {
+                return Some(i);
+            }
+            depth -= 1;
+        }
+    }
+    None
+}
+
+/// What to insert when pressing Enter at `ccursor`: a newline and the indentation of the line,
+/// with one more level of indentation after an opening bracket.
+///
+/// Returns what goes before the new cursor position, and what goes after it
+/// (the closing bracket moves down to a line of its own).
+pub(crate) fn newline_with_indentation(
+    text: &dyn TextBuffer,
+    ccursor: CCursor,
+) -> (String, String) {
+    let line_start = text.char_index_from_paragraph(text.paragraph_from_char_index(ccursor.index));
+    let line_before_cursor = text.char_range(line_start..ccursor.index);
+    let indentation: String = line_before_cursor
+        .chars()
+        .take_while(|&c| c == ' ' || c == '\t')
+        .collect();
+
+    let last_char = line_before_cursor.trim_end().chars().last();
+    let close = BRACKETS
+        .iter()
+        .find(|(open, _)| Some(*open) == last_char)
+        .map(|(_, close)| *close);
+    if let Some(close) = close {
+        let unit = if indentation.starts_with(' ') {
+            " ".repeat(TAB_SIZE)
+        } else {
+            "\t".to_owned()
+        };
+        let next_char = text
+            .char_range(ccursor.index..(ccursor.index + 1).min(text.char_count()))
+            .chars()
+            .next();
+        let after = if next_char == Some(close) {
+            format!("\n{}", indentation)
+        } else {
+            String::new()
+        };
+        (format!("\n{}{}", indentation, unit), after)
+    } else {
+        (format!("\n{}", indentation), String::new())
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// The folded headers, the paragraphs they hide, and which paragraphs of the galley can be folded.
+type Folding<'a> = (&'a [usize], &'a [Range<usize>], &'a [bool]);
+
+/// Paint the line numbers and fold markers in `gutter_rect`, next to the text painted at `text_pos`.
+///
+/// The `galley` starts with paragraph `first_paragraph` of the text.
+///
+/// Returns the paragraph whose fold marker was clicked, if any.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn gutter_ui(
+    ui: &Ui,
+    id: Id,
+    gutter_rect: Rect,
+    text_pos: Pos2,
+    galley: &Galley,
+    first_paragraph: usize,
+    font_id: &FontId,
+    line_numbers: bool,
+    folding: Option<Folding<'_>>,
+    current_paragraph: Option<usize>,
+) -> Option<usize> {
+    let painter = ui.painter();
+    let clip_rect = ui.clip_rect();
+    let marker_width = ui.fonts().row_height(font_id);
+    let spacing = ui.spacing().item_spacing.x;
+
+    let mut clicked = None;
+    let mut paragraph = first_paragraph;
+    let mut is_first_row = true;
+    for row in &galley.rows {
+        let row_paragraph = paragraph;
+        let row_is_first = is_first_row;
+        if row.ends_with_newline {
+            paragraph += 1;
+        }
+        is_first_row = row.ends_with_newline;
+
+        let row_rect = row.rect.translate(text_pos.to_vec2());
+        if row_rect.bottom() < clip_rect.top() || clip_rect.bottom() < row_rect.top() {
+            continue;
+        }
+        if let Some((_, hidden, _)) = folding {
+            if hidden.iter().any(|range| range.contains(&row_paragraph)) {
+                continue;
+            }
+        }
+
+        let is_current = current_paragraph == Some(row_paragraph);
+        if line_numbers && row_is_first {
+            let color = if is_current {
+                ui.visuals().text_color()
+            } else {
+                ui.visuals().weak_text_color()
+            };
+            painter.text(
+                pos2(gutter_rect.right() - spacing, row_rect.top()),
+                Align2::RIGHT_TOP,
+                row_paragraph + 1,
+                font_id.clone(),
+                color,
+            );
+        }
+
+        if let Some((folded, _, foldable)) = folding {
+            let is_folded = folded.contains(&row_paragraph);
+            let is_foldable = foldable
+                .get(row_paragraph - first_paragraph)
+                .copied()
+                .unwrap_or(false);
+            if row_is_first && is_foldable {
+                let marker_rect = Rect::from_min_size(
+                    pos2(gutter_rect.left(), row_rect.top()),
+                    vec2(marker_width, row_rect.height()),
+                );
+                let response = ui.interact(
+                    marker_rect,
+                    id.with(("fold", row_paragraph)),
+                    Sense::click(),
+                );
+                response.widget_info(|| {
+                    let label = if is_folded { "Unfold" } else { "Fold" };
+                    WidgetInfo::labeled(WidgetType::Button, label)
+                });
+                let color = if response.hovered() {
+                    ui.visuals().strong_text_color()
+                } else {
+                    ui.visuals().weak_text_color()
+                };
+                let marker = if is_folded { "⏵" } else { "⏷" };
+                painter.text(
+                    marker_rect.center(),
+                    Align2::CENTER_CENTER,
+                    marker,
+                    font_id.clone(),
+                    color,
+                );
+                if response.clicked() {
+                    clicked = Some(row_paragraph);
+                }
+            }
+            if is_folded && (row.ends_with_newline || paragraph == row_paragraph) {
+                // Show that there is more after the last row of the fold header:
+                painter.text(
+                    pos2(row_rect.right() + spacing, row_rect.center().y),
+                    Align2::LEFT_CENTER,
+                    "⋯",
+                    font_id.clone(),
+                    ui.visuals().weak_text_color(),
+                );
+            }
+        }
+    }
+    clicked
+}
+
+#[test]
+fn test_folding() {
+    let text = "fn main() {\n    if x {\n        y();\n\n    }\n}\nfn other() {}";
+    let lines: Vec<&str> = text.split('\n').collect();
+    assert_eq!(fold_region(&lines, 0), Some(1..5));
+    assert_eq!(fold_region(&lines, 1), Some(2..3));
+    assert_eq!(fold_region(&lines, 2), None);
+    assert_eq!(fold_region(&lines, 6), None);
+    assert_eq!(hidden_paragraphs(text, &[1, 0]), vec![1..5]);
+
+    // A line was added in the first fold, and the second fold is now empty:
+    let mut folded = vec![1, 5];
+    let old_text = "fn main() {\n    if x {\n        y();\n    }\n}\nfn other() {\n    w();\n}";
+    let text = old_text.replace("y();\n", "y();\n        z();\n");
+    move_folds(old_text, &text, &mut folded);
+    assert_eq!(folded, vec![1, 6]);
+    let old_text = text;
+    let text = str::replace(&old_text, "    w();\n", "");
+    move_folds(&old_text, &text, &mut folded);
+    assert_eq!(folded, vec![1]);
+
+    // Lines inserted before a fold, e.g. by an undo, move it down:
+    let mut folded = vec![1];
+    let text = format!("// a\n// b\n{}", old_text);
+    move_folds(&old_text, &text, &mut folded);
+    assert_eq!(folded, vec![3]);
+
+    // The fold header was deleted:
+    let mut folded = vec![1];
+    let text = str::replace(&old_text, "    if x {\n", "");
+    move_folds(&old_text, &text, &mut folded);
+    assert!(folded.is_empty());
+}
+
+#[test]
+fn test_brackets_and_indentation() {
+    let text = "f(a[0], {b})";
+    assert_eq!(matching_brackets(text, CCursor::new(2)), Some([1, 11]));
+    assert_eq!(matching_brackets(text, CCursor::new(12)), Some([11, 1]));
+    assert_eq!(matching_brackets(text, CCursor::new(5)), Some([5, 3]));
+    assert_eq!(matching_brackets("f(", CCursor::new(2)), None);
+
+    let text = "    if x {}";
+    assert_eq!(
+        newline_with_indentation(&text, CCursor::new(10)),
+        ("\n        ".to_owned(), "\n    ".to_owned())
+    );
+    assert_eq!(
+        newline_with_indentation(&text, CCursor::new(4)),
+        ("\n    ".to_owned(), String::new())
+    );
+}
@@ -0,0 +1,546 @@
+use std::ops::Range;
+
+use epaint::mutex::Arc;
+use epaint::text::{cursor::CCursor, Galley, LayoutSection};
+
+use crate::*;
+
+use super::{CCursorRange, TextBuffer};
+
+/// The find/replace bar of a [`TextEdit`], see [`TextEdit::find_replace`].
+///
+/// Part of the [`super::TextEditState`].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct FindReplaceState {
+    /// Is the bar shown? Opened with Cmd/Ctrl+F or Cmd/Ctrl+H.
+    pub open: bool,
+
+    /// Show the replace field too (opened with Cmd/Ctrl+H).
+    pub show_replace: bool,
+
+    /// What to look for.
+    pub query: String,
+
+    /// What to replace it with.
+    /// With [`Self::regex`] this can refer to capture groups, e.g. `$1`.
+    pub replacement: String,
+
+    /// Tell upper and lower case apart.
+    pub case_sensitive: bool,
+
+    /// Treat the [`Self::query`] as a regular expression.
+    /// Only has an effect with the `regex` feature.
+    pub regex: bool,
+}
+
+/// What the user asked the find/replace bar to do.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum FindAction {
+    Next,
+    Previous,
+    Replace,
+    ReplaceAll,
+    Close,
+}
+
+impl FindReplaceState {
+    /// Where the [`Self::query`] occurs in `text`, as byte ranges.
+    ///
+    /// # Errors
+    /// If [`Self::regex`] is set and the query is not a valid regular expression.
+    pub fn find_in(&self, text: &str) -> Result<Vec<Range<usize>>, String> {
+        Ok(Matcher::new(self)?.find(text))
+    }
+
+    /// Like [`Self::find_in`], but only searches again when `text` or the query have changed.
+    pub(crate) fn cached_matches(&self, ctx: &Context, text: &str) -> MatchesResult {
+        type MatchCache = crate::util::cache::FrameCache<MatchesResult, MatchFinder>;
+
+        let mut memory = ctx.memory();
+        let cache = memory.caches.cache::<MatchCache>();
+        cache.get((text, self.query.as_str(), self.case_sensitive, self.regex))
+    }
+
+    /// Select the next (or previous) match after (or before) the current selection,
+    /// wrapping around at the end of the text.
+    pub(crate) fn select_match(
+        text: &str,
+        matches: &[Range<usize>],
+        ccursor_range: CCursorRange,
+        forward: bool,
+    ) -> Option<CCursorRange> {
+        let [min, max] = ccursor_range.sorted();
+        let matches = char_ranges(text, matches);
+        let found = if forward {
+            matches
+                .iter()
+                .find(|m| max.index <= m.start)
+                .or_else(|| matches.first())
+        } else {
+            matches
+                .iter()
+                .rev()
+                .find(|m| m.end <= min.index)
+                .or_else(|| matches.last())
+        };
+        found.map(|m| CCursorRange::two(CCursor::new(m.start), CCursor::new(m.end)))
+    }
+
+    /// If the selection is a match, replace it, and return the cursor after the replacement.
+    ///
+    /// `before_edit` is called just before `text` is changed, e.g. to add an undo point.
+    pub(crate) fn replace(
+        &self,
+        text: &mut dyn TextBuffer,
+        ccursor_range: CCursorRange,
+        before_edit: impl FnOnce(&dyn TextBuffer),
+    ) -> Result<Option<CCursor>, String> {
+        let matcher = Matcher::new(self)?;
+        let full_text = text.as_str().into_owned();
+        let [min, max] = ccursor_range.sorted();
+        let selected = (
+            text.byte_index_from_char_index(min.index),
+            text.byte_index_from_char_index(max.index),
+        );
+        let matches = matcher.find(&full_text);
+        if let Some(m) = matches.iter().find(|m| (m.start, m.end) == selected) {
+            let replacement = matcher.replacement(&full_text, m.clone(), &self.replacement);
+            before_edit(text);
+            text.delete_char_range(min.index..max.index);
+            let inserted = text.insert_text(&replacement, min.index);
+            Ok(Some(CCursor::new(min.index + inserted)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Replace all matches in `text`, returning how many there were.
+    ///
+    /// `before_edit` is called just before `text` is changed, e.g. to add an undo point.
+    pub(crate) fn replace_all(
+        &self,
+        text: &mut dyn TextBuffer,
+        before_edit: impl FnOnce(&dyn TextBuffer),
+    ) -> Result<usize, String> {
+        let matcher = Matcher::new(self)?;
+        let full_text = text.as_str().into_owned();
+        let matches = matcher.find_with_replacements(&full_text, &self.replacement);
+        if matches.is_empty() {
+            return Ok(0);
+        }
+        let mut new_text = String::with_capacity(full_text.len());
+        let mut last_end = 0;
+        for (m, replacement) in &matches {
+            new_text += &full_text[last_end..m.start];
+            new_text += replacement;
+            last_end = m.end;
+        }
+        new_text += &full_text[last_end..];
+        before_edit(text);
+        text.replace(&new_text);
+        Ok(matches.len())
+    }
+}
+
+pub(crate) type MatchesResult = Arc<Result<Vec<Range<usize>>, String>>;
+
+#[derive(Default)]
+struct MatchFinder {}
+
+impl crate::util::cache::ComputerMut<(&str, &str, bool, bool), MatchesResult> for MatchFinder {
+    fn compute(
+        &mut self,
+        (text, query, case_sensitive, regex): (&str, &str, bool, bool),
+    ) -> MatchesResult {
+        let state = FindReplaceState {
+            query: query.to_owned(),
+            case_sensitive,
+            regex,
+            ..Default::default()
+        };
+        Arc::new(state.find_in(text))
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+enum Matcher {
+    Plain {
+        query: String,
+        case_sensitive: bool,
+    },
+
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+impl Matcher {
+    fn new(state: &FindReplaceState) -> Result<Self, String> {
+        #[cfg(feature = "regex")]
+        if state.regex {
+            return regex::RegexBuilder::new(&state.query)
+                .case_insensitive(!state.case_sensitive)
+                .multi_line(true)
+                .build()
+                .map(Self::Regex)
+                .map_err(|err| err.to_string());
+        }
+
+        Ok(Self::Plain {
+            query: state.query.clone(),
+            case_sensitive: state.case_sensitive,
+        })
+    }
+
+    /// Byte ranges of the (non-overlapping, non-empty) matches.
+    fn find(&self, text: &str) -> Vec<Range<usize>> {
+        match self {
+            Self::Plain { query, .. } if query.is_empty() => vec![],
+            Self::Plain {
+                query,
+                case_sensitive: true,
+            } => text
+                .match_indices(query.as_str())
+                .map(|(start, m)| start..start + m.len())
+                .collect(),
+            Self::Plain {
+                query,
+                case_sensitive: false,
+            } => {
+                let mut matches = vec![];
+                for (start, _) in text.char_indices() {
+                    if matches
+                        .last()
+                        .map_or(false, |m: &Range<usize>| start < m.end)
+                    {
+                        continue;
+                    }
+                    if let Some(len) = match_len_ignoring_case(&text[start..], query) {
+                        matches.push(start..start + len);
+                    }
+                }
+                matches
+            }
+
+            #[cfg(feature = "regex")]
+            Self::Regex(regex) => regex
+                .find_iter(text)
+                .filter(|m| !m.as_str().is_empty())
+                .map(|m| m.start()..m.end())
+                .collect(),
+        }
+    }
+
+    /// Like [`Self::find`], together with what to replace each match with.
+    fn find_with_replacements(&self, text: &str, replacement: &str) -> Vec<(Range<usize>, String)> {
+        match self {
+            Self::Plain { .. } => self
+                .find(text)
+                .into_iter()
+                .map(|m| (m, replacement.to_owned()))
+                .collect(),
+
+            // Expand each match as we find it, instead of searching for it again:
+            #[cfg(feature = "regex")]
+            Self::Regex(regex) => regex
+                .captures_iter(text)
+                .filter_map(|captures| {
+                    let m = captures.get(0).filter(|m| !m.as_str().is_empty())?;
+                    let mut expanded = String::new();
+                    captures.expand(replacement, &mut expanded);
+                    Some((m.start()..m.end(), expanded))
+                })
+                .collect(),
+        }
+    }
+
+    /// What to replace the match at `byte_range` with.
+    ///
+    /// This searches `text` from the start, so use [`Self::find_with_replacements`] for many matches.
+    #[cfg_attr(not(feature = "regex"), allow(unused_variables))]
+    fn replacement(&self, text: &str, byte_range: Range<usize>, replacement: &str) -> String {
+        match self {
+            Self::Plain { .. } => replacement.to_owned(),
+
+            #[cfg(feature = "regex")]
+            Self::Regex(regex) => {
+                let mut expanded = String::new();
+                let captures = regex
+                    .captures_iter(text)
+                    .find(|captures| captures.get(0).map(|m| m.start()) == Some(byte_range.start));
+                if let Some(captures) = captures {
+                    captures.expand(replacement, &mut expanded);
+                }
+                expanded
+            }
+        }
+    }
+}
+
+/// If `haystack` starts with `needle` (ignoring case), how many bytes of `haystack` is that?
+fn match_len_ignoring_case(haystack: &str, needle: &str) -> Option<usize> {
+    let mut haystack_chars = haystack.char_indices();
+    for needle_char in needle.chars() {
+        let (_, haystack_char) = haystack_chars.next()?;
+        if !haystack_char.to_lowercase().eq(needle_char.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(haystack_chars.next().map_or(haystack.len(), |(i, _)| i))
+}
+
+/// From sorted byte ranges into `text` to character ranges.
+fn char_ranges(text: &str, byte_ranges: &[Range<usize>]) -> Vec<Range<usize>> {
+    let mut char_index_from_byte_index = {
+        let mut chars = text.char_indices().enumerate().peekable();
+        move |byte_index: usize| {
+            while let Some(&(char_index, (i, _))) = chars.peek() {
+                if byte_index <= i {
+                    return char_index;
+                }
+                chars.next();
+            }
+            text.chars().count()
+        }
+    };
+    byte_ranges
+        .iter()
+        .map(|m| char_index_from_byte_index(m.start)..char_index_from_byte_index(m.end))
+        .collect()
+}
+
+// ----------------------------------------------------------------------------
+
+/// Give the `matches` (found in the text of the galley) a background color,
+/// by laying out the [`epaint::text::LayoutJob`] of the galley again with more sections.
+pub(crate) fn highlight_matches(
+    ui: &Ui,
+    matches: &MatchesResult,
+    galley: Arc<Galley>,
+) -> Arc<Galley> {
+    let matches = match &**matches {
+        Ok(matches) if !matches.is_empty() => matches,
+        _ => return galley,
+    };
+    let background = ui.visuals().selection.bg_fill.linear_multiply(0.3);
+
+    let mut job = (*galley.job).clone();
+    let mut sections = Vec::with_capacity(job.sections.len() + 2 * matches.len());
+    for section in job.sections.drain(..) {
+        if section.inline_box.is_some() {
+            sections.push(section);
+            continue;
+        }
+        let mut start = section.byte_range.start;
+        let end = section.byte_range.end;
+        for m in matches {
+            if m.end <= start || end <= m.start {
+                continue;
+            }
+            let m = m.start.max(start)..m.end.min(end);
+            if start < m.start {
+                sections.push(LayoutSection {
+                    byte_range: start..m.start,
+                    ..section.clone()
+                });
+            }
+            let mut format = section.format.clone();
+            format.background = background;
+            sections.push(LayoutSection {
+                leading_space: if m.start == section.byte_range.start {
+                    section.leading_space
+                } else {
+                    0.0
+                },
+                byte_range: m.clone(),
+                format,
+                inline_box: None,
+            });
+            start = m.end;
+        }
+        if start < end || start == section.byte_range.start {
+            sections.push(LayoutSection {
+                leading_space: if start == section.byte_range.start {
+                    section.leading_space
+                } else {
+                    0.0
+                },
+                byte_range: start..end,
+                ..section
+            });
+        }
+    }
+    job.sections = sections;
+    ui.fonts().layout_job(job)
+}
+
+/// Show the find/replace bar with its top right corner at `pos` (in screen coordinates).
+///
+/// `status` is e.g. the number of matches.
+pub(crate) fn find_replace_bar(
+    ui: &Ui,
+    id: Id,
+    right_top: Pos2,
+    state: &mut FindReplaceState,
+    status: Result<String, String>,
+) -> Option<FindAction> {
+    let area_id = id.with("find_replace");
+    let width = ui
+        .memory()
+        .areas
+        .get(area_id)
+        .map_or(0.0, |area| area.size.x);
+    if width == 0.0 {
+        ui.ctx().request_repaint(); // so we can put it in the right place
+    }
+
+    let mut action = None;
+    Area::new(area_id)
+        .order(Order::Foreground)
+        .fixed_pos(right_top - vec2(width, 0.0))
+        .show(ui.ctx(), |ui| {
+            Frame::popup(ui.style()).show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    let response = ui.add(
+                        TextEdit::singleline(&mut state.query)
+                            .id(find_query_id(id))
+                            .hint_text("Find")
+                            .desired_width(120.0),
+                    );
+                    if response.lost_focus() {
+                        let input = ui.input();
+                        if input.key_pressed(Key::Enter) {
+                            action = Some(if input.modifiers.shift {
+                                FindAction::Previous
+                            } else {
+                                FindAction::Next
+                            });
+                        } else if input.key_pressed(Key::Escape) {
+                            action = Some(FindAction::Close);
+                        }
+                    }
+                    if action == Some(FindAction::Next) || action == Some(FindAction::Previous) {
+                        response.request_focus();
+                    }
+
+                    if ui
+                        .selectable_label(state.case_sensitive, "Aa")
+                        .on_hover_text("Match case")
+                        .clicked()
+                    {
+                        state.case_sensitive = !state.case_sensitive;
+                    }
+                    #[cfg(feature = "regex")]
+                    if ui
+                        .selectable_label(state.regex, ".*")
+                        .on_hover_text("Regular expression")
+                        .clicked()
+                    {
+                        state.regex = !state.regex;
+                    }
+
+                    match status {
+                        Ok(status) => {
+                            ui.label(status);
+                        }
+                        Err(error) => {
+                            ui.colored_label(Color32::RED, "Invalid")
+                                .on_hover_text(error);
+                        }
+                    }
+
+                    if ui.small_button("⏶").on_hover_text("Previous").clicked() {
+                        action = Some(FindAction::Previous);
+                    }
+                    if ui.small_button("⏷").on_hover_text("Next").clicked() {
+                        action = Some(FindAction::Next);
+                    }
+                    if ui.small_button("🗙").on_hover_text("Close").clicked() {
+                        action = Some(FindAction::Close);
+                    }
+                });
+
+                if state.show_replace {
+                    ui.horizontal(|ui| {
+                        let response = ui.add(
+                            TextEdit::singleline(&mut state.replacement)
+                                .id(id.with("find_replacement"))
+                                .hint_text("Replace")
+                                .desired_width(120.0),
+                        );
+                        if response.lost_focus() {
+                            let input = ui.input();
+                            if input.key_pressed(Key::Enter) {
+                                action = Some(FindAction::Replace);
+                            } else if input.key_pressed(Key::Escape) {
+                                action = Some(FindAction::Close);
+                            }
+                        }
+                        if action == Some(FindAction::Replace) {
+                            response.request_focus();
+                        }
+
+                        if ui.button("Replace").clicked() {
+                            action = Some(FindAction::Replace);
+                        }
+                        if ui.button("Replace all").clicked() {
+                            action = Some(FindAction::ReplaceAll);
+                        }
+                    });
+                }
+            });
+        });
+    action
+}
+
+/// The [`Id`] of the query field of the find/replace bar of the [`TextEdit`] with the given id.
+pub(crate) fn find_query_id(text_edit_id: Id) -> Id {
+    text_edit_id.with("find_query")
+}
+
+/// Does any field of the find/replace bar have keyboard focus?
+pub(crate) fn find_replace_bar_has_focus(ui: &Ui, text_edit_id: Id) -> bool {
+    let memory = ui.memory();
+    memory.has_focus(find_query_id(text_edit_id))
+        || memory.has_focus(text_edit_id.with("find_replacement"))
+}
+
+#[test]
+fn test_find() {
+    let mut state = FindReplaceState {
+        query: "ab".to_owned(),
+        ..Default::default()
+    };
+    assert_eq!(state.find_in("xAbab\u{e9}ab"), Ok(vec![1..3, 3..5, 7..9]));
+    state.case_sensitive = true;
+    assert_eq!(state.find_in("xAbab\u{e9}ab"), Ok(vec![3..5, 7..9]));
+
+    // Byte ranges to char ranges:
+    assert_eq!(
+        char_ranges("xAbab\u{e9}ab", &[3..5, 7..9]),
+        vec![3..5, 6..8]
+    );
+
+    let mut text = String::from("one ab, two ab");
+    state.replacement = "cd".to_owned();
+    assert_eq!(state.replace_all(&mut text, |_| {}), Ok(2));
+    assert_eq!(text, "one cd, two cd");
+    assert_eq!(
+        state.replace_all(&mut text, |_| panic!("nothing to replace")),
+        Ok(0)
+    );
+
+    #[cfg(feature = "regex")]
+    {
+        state.regex = true;
+        state.query = r"(\w+) cd".to_owned();
+        state.replacement = "$1!".to_owned();
+        assert_eq!(state.replace_all(&mut text, |_| {}), Ok(2));
+        assert_eq!(text, "one!, two!");
+
+        state.query = "(".to_owned();
+        assert!(state.find_in(&text).is_err());
+    }
+}
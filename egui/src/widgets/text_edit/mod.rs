@@ -1,10 +1,17 @@
 mod builder;
+mod code_editor;
 mod cursor_range;
+mod find_replace;
 mod output;
 mod state;
 mod text_buffer;
+mod text_window;
 
 pub use {
-    builder::TextEdit, cursor_range::*, output::TextEditOutput, state::TextEditState,
-    text_buffer::TextBuffer,
+    builder::TextEdit,
+    cursor_range::*,
+    find_replace::FindReplaceState,
+    output::TextEditOutput,
+    state::{TextEditState, TextEditUndoer},
+    text_buffer::{TextBuffer, TextSnapshot},
 };
@@ -6,6 +6,8 @@ pub struct TextEditOutput {
     pub response: crate::Response,
 
     /// How the text was displayed.
+    ///
+    /// With [`crate::TextEdit::virtualized`] this is only the paragraphs that were laid out.
     pub galley: Arc<crate::Galley>,
 
     /// Where the text in [`Self::galley`] ended up on the screen.
@@ -18,7 +20,25 @@ pub struct TextEditOutput {
     pub state: super::TextEditState,
 
     /// Where the text cursor is.
+    ///
+    /// With [`crate::TextEdit::virtualized`] this is still into the whole text,
+    /// except for the [`epaint::text::cursor::RCursor`]s, which are into [`Self::galley`]
+    /// (and the default if the cursor is outside of it).
     pub cursor_range: Option<super::CursorRange>,
 }
 
+impl TextEditOutput {
+    /// The section index and screen rectangle of each [`epaint::text::InlineBox`] in the text,
+    /// so you can paint into them.
+    pub fn inline_boxes(&self) -> Vec<(usize, crate::Rect)> {
+        self.galley
+            .inline_boxes
+            .iter()
+            .map(|&(section_index, rect)| {
+                (section_index, rect.translate(self.text_draw_pos.to_vec2()))
+            })
+            .collect()
+    }
+}
+
 // TODO: add `output.paint` and `output.store` and split out that code from `TextEdit::show`.
@@ -4,9 +4,29 @@ use crate::mutex::Mutex;
 
 use crate::*;
 
-use super::{CCursorRange, CursorRange};
+use super::{
+    code_editor::CodeEditorCache, text_window::ParagraphHeights, CCursorRange, CursorRange,
+    FindReplaceState, TextBuffer, TextSnapshot,
+};
 
-type Undoer = crate::util::undoer::Undoer<(CCursorRange, String)>;
+/// The undo history of a [`TextEdit`]: the selection and the text.
+pub type TextEditUndoer = crate::util::undoer::Undoer<(CCursorRange, TextSnapshot)>;
+
+/// The state to give the [`TextEditUndoer`].
+///
+/// Only changes to the text count: if the text is that of the latest undo point,
+/// that undo point is returned as is, so that just moving the cursor
+/// neither creates an undo point nor forgets what can be redone.
+pub(crate) fn current_undo_state(
+    undoer: &TextEditUndoer,
+    ccursor_range: CCursorRange,
+    text: &dyn TextBuffer,
+) -> (CCursorRange, TextSnapshot) {
+    match undoer.latest_undo() {
+        Some(latest_undo) if text.equals_snapshot(&latest_undo.1) => latest_undo.clone(),
+        _ => (ccursor_range, text.snapshot()),
+    }
+}
 
 /// The text edit state stored between frames.
 #[derive(Clone, Default)]
@@ -19,9 +39,18 @@ pub struct TextEditState {
     /// so users are more likely to read/write this.
     ccursor_range: Option<CCursorRange>,
 
+    /// More cursors, besides the primary one above, for editing in several places at once.
+    extra_ccursor_ranges: Vec<CCursorRange>,
+
+    /// See [`TextEdit::find_replace`].
+    find_replace: FindReplaceState,
+
+    /// The lines (paragraphs) whose [`TextEdit::folding`] region is folded away.
+    pub(crate) folded: Vec<usize>,
+
     /// Wrapped in Arc for cheaper clones.
     #[cfg_attr(feature = "serde", serde(skip))]
-    pub(crate) undoer: Arc<Mutex<Undoer>>,
+    pub(crate) undoer: Arc<Mutex<TextEditUndoer>>,
 
     // If IME candidate window is shown on this text edit.
     #[cfg_attr(feature = "serde", serde(skip))]
@@ -30,6 +59,14 @@ pub struct TextEditState {
     // Visual offset when editing singleline text bigger than the width.
     #[cfg_attr(feature = "serde", serde(skip))]
     pub(crate) singleline_offset: f32,
+
+    // Used by `TextEdit::virtualized` to know where each paragraph is.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) paragraph_heights: Arc<Mutex<ParagraphHeights>>,
+
+    // Used by `TextEdit::code_editor` to not fold and match brackets from scratch every frame.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) code_editor_cache: Arc<Mutex<CodeEditorCache>>,
 }
 
 impl TextEditState {
@@ -50,14 +87,142 @@ impl TextEditState {
     }
 
     /// Sets the currently selected range of characters.
+    ///
+    /// This removes any extra cursors.
     pub fn set_ccursor_range(&mut self, ccursor_range: Option<CCursorRange>) {
         self.cursor_range = None;
         self.ccursor_range = ccursor_range;
+        self.extra_ccursor_ranges.clear();
     }
 
+    /// This removes any extra cursors.
     pub fn set_cursor_range(&mut self, cursor_range: Option<CursorRange>) {
         self.cursor_range = cursor_range;
         self.ccursor_range = None;
+        self.extra_ccursor_ranges.clear();
+    }
+
+    /// All the cursors, starting with the primary one (the same as [`Self::ccursor_range`]).
+    ///
+    /// There can be more than one in a multiline [`TextEdit`], e.g. after alt-clicking.
+    pub fn ccursor_ranges(&self) -> Vec<CCursorRange> {
+        self.ccursor_range()
+            .into_iter()
+            .chain(self.extra_ccursor_ranges.iter().copied())
+            .collect()
+    }
+
+    /// Sets all the cursors. The first one is the primary cursor.
+    pub fn set_ccursor_ranges(&mut self, ccursor_ranges: Vec<CCursorRange>) {
+        let mut ccursor_ranges = ccursor_ranges.into_iter();
+        self.set_ccursor_range(ccursor_ranges.next());
+        self.extra_ccursor_ranges = ccursor_ranges.collect();
+    }
+
+    /// Sets all the cursors, without forgetting the column the primary cursor wants to be at.
+    pub(crate) fn set_cursor_ranges(&mut self, cursor_ranges: &[CursorRange]) {
+        self.set_cursor_range(cursor_ranges.first().copied());
+        self.extra_ccursor_ranges = cursor_ranges
+            .iter()
+            .skip(1)
+            .map(|cursor_range| cursor_range.as_ccursor_range())
+            .collect();
+    }
+
+    /// The cursors besides the primary one (see [`Self::cursor_range`]).
+    pub fn extra_cursor_ranges(&self, galley: &Galley) -> Vec<CursorRange> {
+        self.extra_ccursor_ranges
+            .iter()
+            .map(|ccursor_range| CursorRange {
+                primary: galley.from_ccursor(ccursor_range.primary),
+                secondary: galley.from_ccursor(ccursor_range.secondary),
+            })
+            .collect()
+    }
+
+    /// The find/replace bar, see [`TextEdit::find_replace`].
+    pub fn find_replace(&self) -> &FindReplaceState {
+        &self.find_replace
+    }
+
+    /// E.g. to open the find/replace bar from a menu.
+    pub fn find_replace_mut(&mut self) -> &mut FindReplaceState {
+        &mut self.find_replace
+    }
+
+    /// A copy of the undo history.
+    pub fn undoer(&self) -> TextEditUndoer {
+        self.undoer.lock().clone()
+    }
+
+    /// Replace the undo history, e.g. to use other [`crate::util::undoer::Settings`].
+    pub fn set_undoer(&mut self, undoer: TextEditUndoer) {
+        *self.undoer.lock() = undoer;
+    }
+
+    /// Forget the undo history.
+    pub fn clear_undo_history(&mut self) {
+        self.undoer.lock().clear();
+    }
+
+    /// Is there anything to undo, given the current `text`?
+    pub fn has_undo(&self, text: &dyn TextBuffer) -> bool {
+        let undoer = self.undoer.lock();
+        undoer.has_undo(&self.undo_state(&undoer, text))
+    }
+
+    /// Is there anything to redo, given the current `text`?
+    pub fn has_redo(&self, text: &dyn TextBuffer) -> bool {
+        let undoer = self.undoer.lock();
+        undoer.has_redo(&self.undo_state(&undoer, text))
+    }
+
+    /// Undo the latest change to `text`, e.g. from an "Edit" menu.
+    ///
+    /// Returns `false` if there was nothing to undo.
+    /// Remember to [`Self::store`] the state afterwards.
+    pub fn undo(&mut self, text: &mut dyn TextBuffer) -> bool {
+        let undone = {
+            let mut undoer = self.undoer.lock();
+            let current_state = self.undo_state(&undoer, text);
+            undoer.undo(&current_state).cloned()
+        };
+        self.restore_undo_state(text, undone)
+    }
+
+    /// Redo the latest undone change to `text`.
+    ///
+    /// Returns `false` if there was nothing to redo.
+    /// Remember to [`Self::store`] the state afterwards.
+    pub fn redo(&mut self, text: &mut dyn TextBuffer) -> bool {
+        let redone = {
+            let mut undoer = self.undoer.lock();
+            let current_state = self.undo_state(&undoer, text);
+            undoer.redo(&current_state).cloned()
+        };
+        self.restore_undo_state(text, redone)
+    }
+
+    fn undo_state(
+        &self,
+        undoer: &TextEditUndoer,
+        text: &dyn TextBuffer,
+    ) -> (CCursorRange, TextSnapshot) {
+        current_undo_state(undoer, self.ccursor_range().unwrap_or_default(), text)
+    }
+
+    fn restore_undo_state(
+        &mut self,
+        text: &mut dyn TextBuffer,
+        state: Option<(CCursorRange, TextSnapshot)>,
+    ) -> bool {
+        if let Some((ccursor_range, new_text)) = state {
+            text.restore_snapshot(&new_text);
+            self.set_ccursor_range(Some(ccursor_range));
+            true
+        } else {
+            false
+        }
     }
 
     pub fn cursor_range(&mut self, galley: &Galley) -> Option<CursorRange> {
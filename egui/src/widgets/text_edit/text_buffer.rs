@@ -1,31 +1,86 @@
-use std::ops::Range;
+use std::{borrow::Cow, ops::Range};
 
 /// Trait constraining what types [`crate::TextEdit`] may use as
 /// an underlying buffer.
 ///
 /// Most likely you will use a `String` which implements `TextBuffer`.
-pub trait TextBuffer: AsRef<str> {
+///
+/// The text does not need to be stored in one piece.
+/// With the `rope` feature, `ropey::Rope` implements `TextBuffer` too,
+/// which together with [`crate::TextEdit::virtualized`] is a good fit for large documents.
+pub trait TextBuffer {
     /// Can this text be edited?
     fn is_mutable(&self) -> bool;
 
-    /// Returns this buffer as a `str`.
+    /// Returns the whole text.
     ///
-    /// This is an utility method, as it simply relies on the `AsRef<str>`
-    /// implementation.
-    fn as_str(&self) -> &str {
-        self.as_ref()
+    /// This is borrowed if the text is stored in one piece, but may have to be copied otherwise.
+    fn as_str(&self) -> Cow<'_, str>;
+
+    /// The number of characters (not bytes) in the text.
+    fn char_count(&self) -> usize {
+        self.as_str().chars().count()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.as_str().is_empty()
     }
 
     /// Reads the given character range.
-    fn char_range(&self, char_range: Range<usize>) -> &str {
+    fn char_range(&self, char_range: Range<usize>) -> Cow<'_, str> {
         assert!(char_range.start <= char_range.end);
         let start_byte = self.byte_index_from_char_index(char_range.start);
         let end_byte = self.byte_index_from_char_index(char_range.end);
-        &self.as_str()[start_byte..end_byte]
+        match self.as_str() {
+            Cow::Borrowed(s) => Cow::Borrowed(&s[start_byte..end_byte]),
+            Cow::Owned(s) => Cow::Owned(s[start_byte..end_byte].to_owned()),
+        }
     }
 
     fn byte_index_from_char_index(&self, char_index: usize) -> usize {
-        byte_index_from_char_index(self.as_str(), char_index)
+        byte_index_from_char_index(&self.as_str(), char_index)
+    }
+
+    /// The number of paragraphs, i.e. one more than the number of `\n`.
+    fn paragraph_count(&self) -> usize {
+        self.as_str().bytes().filter(|&b| b == b'\n').count() + 1
+    }
+
+    /// The character index where the given paragraph starts.
+    fn char_index_from_paragraph(&self, paragraph: usize) -> usize {
+        if paragraph == 0 {
+            return 0;
+        }
+        let mut newlines = 0;
+        for (char_index, chr) in self.as_str().chars().enumerate() {
+            if chr == '\n' {
+                newlines += 1;
+                if newlines == paragraph {
+                    return char_index + 1;
+                }
+            }
+        }
+        self.char_count()
+    }
+
+    /// The paragraph the given character index is in.
+    fn paragraph_from_char_index(&self, char_index: usize) -> usize {
+        self.as_str()
+            .chars()
+            .take(char_index)
+            .filter(|&chr| chr == '\n')
+            .count()
+    }
+
+    /// The text of the given paragraph, without the trailing `\n`.
+    fn paragraph(&self, paragraph: usize) -> Cow<'_, str> {
+        let start = self.char_index_from_paragraph(paragraph);
+        let end = if paragraph + 1 < self.paragraph_count() {
+            self.char_index_from_paragraph(paragraph + 1) - 1
+        } else {
+            self.char_count()
+        };
+        self.char_range(start..end.max(start))
     }
 
     /// Inserts text `text` into this buffer at character index `char_index`.
@@ -45,7 +100,7 @@ pub trait TextBuffer: AsRef<str> {
 
     /// Clears all characters in this buffer
     fn clear(&mut self) {
-        self.delete_char_range(0..self.as_ref().len());
+        self.delete_char_range(0..self.char_count());
     }
 
     /// Replaces all contents of this string with `text`
@@ -56,10 +111,50 @@ pub trait TextBuffer: AsRef<str> {
 
     /// Clears all characters in this buffer and returns a string of the contents.
     fn take(&mut self) -> String {
-        let s = self.as_ref().to_owned();
+        let s = self.as_str().into_owned();
         self.clear();
         s
     }
+
+    /// A copy of the text, as kept in the undo history of a [`crate::TextEdit`].
+    fn snapshot(&self) -> TextSnapshot {
+        TextSnapshot::String(self.as_str().into_owned())
+    }
+
+    /// Is the text the same as in the `snapshot`? This does not copy the text.
+    fn equals_snapshot(&self, snapshot: &TextSnapshot) -> bool {
+        match snapshot {
+            TextSnapshot::String(string) => self.as_str() == string.as_str(),
+            #[cfg(feature = "rope")]
+            TextSnapshot::Rope(rope) => *rope == &*self.as_str(),
+        }
+    }
+
+    /// Replaces all contents with the text of the `snapshot`, e.g. when undoing.
+    fn restore_snapshot(&mut self, snapshot: &TextSnapshot) {
+        match snapshot {
+            TextSnapshot::String(string) => self.replace(string),
+            #[cfg(feature = "rope")]
+            TextSnapshot::Rope(rope) => self.replace(&rope.to_string()),
+        }
+    }
+}
+
+/// A copy of the text of a [`TextBuffer`], see [`TextBuffer::snapshot`].
+///
+/// A `ropey::Rope` (with the `rope` feature) shares its memory with its copies,
+/// so an undo point of a large document is cheap to make and to keep.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TextSnapshot {
+    String(String),
+    #[cfg(feature = "rope")]
+    Rope(ropey::Rope),
+}
+
+impl Default for TextSnapshot {
+    fn default() -> Self {
+        Self::String(String::new())
+    }
 }
 
 impl TextBuffer for String {
@@ -67,6 +162,10 @@ impl TextBuffer for String {
         true
     }
 
+    fn as_str(&self) -> Cow<'_, str> {
+        Cow::Borrowed(String::as_str(self))
+    }
+
     fn insert_text(&mut self, text: &str, char_index: usize) -> usize {
         // Get the byte index from the character index
         let byte_idx = self.byte_index_from_char_index(char_index);
@@ -107,6 +206,10 @@ impl<'a> TextBuffer for &'a str {
         false
     }
 
+    fn as_str(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+
     fn insert_text(&mut self, _text: &str, _ch_idx: usize) -> usize {
         0
     }
@@ -114,6 +217,96 @@ impl<'a> TextBuffer for &'a str {
     fn delete_char_range(&mut self, _ch_range: Range<usize>) {}
 }
 
+/// A rope is cheap to edit and index anywhere, no matter how large the text is.
+///
+/// egui turns off the default `unicode_lines` feature of `ropey`, so that only `\n` starts a new
+/// paragraph, as in a `String`. If another crate you depend on turns that feature on,
+/// cargo enables it for egui too, and then `\r`, `U+2028` and the other unicode line breaks
+/// also count as paragraphs. These no longer match the paragraphs `TextEdit` lays out,
+/// so make sure `unicode_lines` stays off (`cargo tree -e features -i ropey` tells you who enables it).
+#[cfg(feature = "rope")]
+impl TextBuffer for ropey::Rope {
+    fn is_mutable(&self) -> bool {
+        true
+    }
+
+    fn as_str(&self) -> Cow<'_, str> {
+        self.char_range(0..self.len_chars())
+    }
+
+    fn char_count(&self) -> usize {
+        self.len_chars()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len_chars() == 0
+    }
+
+    fn char_range(&self, char_range: Range<usize>) -> Cow<'_, str> {
+        let slice = self.slice(char_range);
+        slice
+            .as_str()
+            .map_or_else(|| Cow::Owned(slice.to_string()), Cow::Borrowed)
+    }
+
+    fn byte_index_from_char_index(&self, char_index: usize) -> usize {
+        self.char_to_byte(char_index.min(self.len_chars()))
+    }
+
+    fn paragraph_count(&self) -> usize {
+        self.len_lines()
+    }
+
+    fn char_index_from_paragraph(&self, paragraph: usize) -> usize {
+        if paragraph < self.len_lines() {
+            self.line_to_char(paragraph)
+        } else {
+            self.len_chars()
+        }
+    }
+
+    fn paragraph_from_char_index(&self, char_index: usize) -> usize {
+        self.char_to_line(char_index.min(self.len_chars()))
+    }
+
+    fn insert_text(&mut self, text: &str, char_index: usize) -> usize {
+        self.insert(char_index.min(self.len_chars()), text);
+        text.chars().count()
+    }
+
+    fn delete_char_range(&mut self, char_range: Range<usize>) {
+        assert!(char_range.start <= char_range.end);
+        let end = char_range.end.min(self.len_chars());
+        self.remove(char_range.start.min(end)..end);
+    }
+
+    fn clear(&mut self) {
+        *self = ropey::Rope::new();
+    }
+
+    fn replace(&mut self, text: &str) {
+        *self = ropey::Rope::from_str(text);
+    }
+
+    fn snapshot(&self) -> TextSnapshot {
+        TextSnapshot::Rope(self.clone())
+    }
+
+    fn equals_snapshot(&self, snapshot: &TextSnapshot) -> bool {
+        match snapshot {
+            TextSnapshot::String(string) => self == string,
+            TextSnapshot::Rope(rope) => self == rope,
+        }
+    }
+
+    fn restore_snapshot(&mut self, snapshot: &TextSnapshot) {
+        match snapshot {
+            TextSnapshot::String(string) => self.replace(string),
+            TextSnapshot::Rope(rope) => *self = rope.clone(),
+        }
+    }
+}
+
 fn byte_index_from_char_index(s: &str, char_index: usize) -> usize {
     for (ci, (bi, _)) in s.char_indices().enumerate() {
         if ci == char_index {
@@ -122,3 +315,51 @@ fn byte_index_from_char_index(s: &str, char_index: usize) -> usize {
     }
     s.len()
 }
+
+#[test]
+fn test_paragraphs() {
+    fn check(text: &dyn TextBuffer) {
+        assert_eq!(text.paragraph_count(), 3);
+        assert_eq!(text.paragraph(0), "första");
+        assert_eq!(text.paragraph(1), "");
+        assert_eq!(text.paragraph(2), "tredje");
+        assert_eq!(text.char_index_from_paragraph(2), 8);
+        assert_eq!(text.paragraph_from_char_index(6), 0);
+        assert_eq!(text.paragraph_from_char_index(7), 1);
+        assert_eq!(text.paragraph_from_char_index(8), 2);
+        assert_eq!(text.char_range(1..3), "ör");
+    }
+
+    let text = "första\n\ntredje";
+    check(&text);
+    #[cfg(feature = "rope")]
+    check(&ropey::Rope::from_str(text));
+
+    // Only `\n` starts a paragraph (fails if `ropey/unicode_lines` is on):
+    let text = "a\rb\u{2028}c";
+    assert_eq!(text.paragraph_count(), 1);
+    #[cfg(feature = "rope")]
+    assert_eq!(ropey::Rope::from_str(text).paragraph_count(), 1);
+}
+
+#[test]
+fn test_snapshots() {
+    fn check(text: &mut dyn TextBuffer) {
+        let snapshot = text.snapshot();
+        text.insert_text("X", 1);
+        assert!(!text.equals_snapshot(&snapshot));
+        text.restore_snapshot(&snapshot);
+        assert!(text.equals_snapshot(&snapshot));
+        assert_eq!(text.as_str(), "ab\ncd");
+    }
+
+    check(&mut String::from("ab\ncd"));
+    #[cfg(feature = "rope")]
+    {
+        let mut rope = ropey::Rope::from_str("ab\ncd");
+        check(&mut rope);
+        // Undo points of a rope are not copied into a `String`:
+        assert!(matches!(rope.snapshot(), TextSnapshot::Rope(_)));
+        assert!(rope.equals_snapshot(&"ab\ncd".to_owned().snapshot()));
+    }
+}
@@ -0,0 +1,504 @@
+use std::{borrow::Cow, ops::Range};
+
+use epaint::text::{
+    cursor::{CCursor, Cursor, PCursor, RCursor},
+    Galley,
+};
+
+use super::{CCursorRange, TextBuffer, TextSnapshot};
+
+/// Some whole paragraphs of a [`TextBuffer`], which is all that a [`crate::TextEdit`] lays out.
+///
+/// Without [`crate::TextEdit::virtualized`] the window covers the whole text.
+/// Character indices into the window are relative to its first character.
+pub(crate) struct TextWindow<'t> {
+    text: &'t mut dyn TextBuffer,
+
+    /// The paragraphs of `text` in the window, as they were when the window was created.
+    pub paragraphs: Range<usize>,
+
+    /// Index of the first character of the window in `text`.
+    char_start: usize,
+
+    /// Number of characters in the window, kept up to date through edits.
+    char_count: usize,
+
+    is_whole: bool,
+}
+
+impl<'t> TextWindow<'t> {
+    /// All of `text`.
+    pub fn whole(text: &'t mut dyn TextBuffer) -> Self {
+        let char_count = text.char_count();
+        let paragraph_count = text.paragraph_count();
+        Self {
+            text,
+            paragraphs: 0..paragraph_count,
+            char_start: 0,
+            char_count,
+            is_whole: true,
+        }
+    }
+
+    /// The given (non-empty) range of paragraphs.
+    pub fn new(text: &'t mut dyn TextBuffer, paragraphs: Range<usize>) -> Self {
+        let paragraph_count = text.paragraph_count();
+        let start = paragraphs.start.min(paragraph_count - 1);
+        let paragraphs = start..paragraphs.end.min(paragraph_count).max(start + 1);
+        let char_start = text.char_index_from_paragraph(paragraphs.start);
+        let char_end = if paragraphs.end < paragraph_count {
+            text.char_index_from_paragraph(paragraphs.end) - 1 // exclude the `\n`
+        } else {
+            text.char_count()
+        };
+        let is_whole = paragraphs == (0..paragraph_count);
+        Self {
+            text,
+            paragraphs,
+            char_start,
+            char_count: char_end - char_start,
+            is_whole,
+        }
+    }
+
+    /// Does the window cover all of the text?
+    pub fn is_whole(&self) -> bool {
+        self.is_whole
+    }
+
+    /// The text we are a window into.
+    pub fn full_text(&self) -> &dyn TextBuffer {
+        self.text
+    }
+
+    /// From an index into the full text to one into the window, clamped to the window.
+    pub fn to_local(&self, ccursor: CCursor) -> CCursor {
+        CCursor {
+            index: ccursor
+                .index
+                .saturating_sub(self.char_start)
+                .min(self.char_count),
+            ..ccursor
+        }
+    }
+
+    /// Is this index into the full text inside of the window?
+    pub fn contains(&self, ccursor: CCursor) -> bool {
+        (self.char_start..=self.char_start + self.char_count).contains(&ccursor.index)
+    }
+
+    pub fn to_global(&self, ccursor: CCursor) -> CCursor {
+        CCursor {
+            index: self.char_start + ccursor.index,
+            ..ccursor
+        }
+    }
+
+    pub fn to_global_range(&self, ccursor_range: CCursorRange) -> CCursorRange {
+        CCursorRange {
+            primary: self.to_global(ccursor_range.primary),
+            secondary: self.to_global(ccursor_range.secondary),
+        }
+    }
+
+    /// The cursor at `ccursor` in the full text, where `galley` is the layout of the window.
+    ///
+    /// Only the [`RCursor`] is into `galley`, since the rest of the text is not laid out,
+    /// and it is the default if `ccursor` is outside of the window.
+    pub fn global_cursor(&self, galley: &Galley, ccursor: CCursor) -> Cursor {
+        if self.contains(ccursor) {
+            let cursor = galley.from_ccursor(self.to_local(ccursor));
+            Cursor {
+                ccursor,
+                pcursor: PCursor {
+                    paragraph: self.paragraphs.start + cursor.pcursor.paragraph,
+                    ..cursor.pcursor
+                },
+                ..cursor
+            }
+        } else {
+            let paragraph = self.text.paragraph_from_char_index(ccursor.index);
+            Cursor {
+                ccursor,
+                rcursor: RCursor::default(),
+                pcursor: PCursor {
+                    paragraph,
+                    offset: ccursor.index - self.text.char_index_from_paragraph(paragraph),
+                    prefer_next_row: ccursor.prefer_next_row,
+                },
+            }
+        }
+    }
+
+    /// The parts of the `paragraphs` of the full text that are in the window,
+    /// counted from the start of the window.
+    pub fn to_local_paragraphs(&self, paragraphs: &[Range<usize>]) -> Vec<Range<usize>> {
+        let window = &self.paragraphs;
+        paragraphs
+            .iter()
+            .filter_map(|range| {
+                let start = range.start.max(window.start);
+                let end = range.end.min(window.end);
+                (start < end).then(|| start - window.start..end - window.start)
+            })
+            .collect()
+    }
+
+    /// Like [`Self::to_global`], but if `local` is just `previous` clamped to the window,
+    /// then it was outside of the window and unchanged, and we return `previous`.
+    pub fn to_global_or(&self, local: CCursor, previous: CCursor) -> CCursor {
+        if self.to_local(previous) == local {
+            previous
+        } else {
+            self.to_global(local)
+        }
+    }
+}
+
+impl<'t> TextBuffer for TextWindow<'t> {
+    fn is_mutable(&self) -> bool {
+        self.text.is_mutable()
+    }
+
+    fn as_str(&self) -> Cow<'_, str> {
+        if self.is_whole {
+            self.text.as_str()
+        } else {
+            self.text
+                .char_range(self.char_start..self.char_start + self.char_count)
+        }
+    }
+
+    fn char_count(&self) -> usize {
+        self.char_count
+    }
+
+    fn is_empty(&self) -> bool {
+        self.char_count == 0
+    }
+
+    fn char_range(&self, char_range: Range<usize>) -> Cow<'_, str> {
+        self.text
+            .char_range(self.char_start + char_range.start..self.char_start + char_range.end)
+    }
+
+    fn insert_text(&mut self, text: &str, char_index: usize) -> usize {
+        let char_index = self.char_start + char_index.min(self.char_count);
+        let inserted = self.text.insert_text(text, char_index);
+        self.char_count += inserted;
+        inserted
+    }
+
+    fn delete_char_range(&mut self, char_range: Range<usize>) {
+        assert!(char_range.start <= char_range.end);
+        let end = char_range.end.min(self.char_count);
+        let start = char_range.start.min(end);
+        self.text
+            .delete_char_range(self.char_start + start..self.char_start + end);
+        self.char_count -= end - start;
+    }
+
+    /// Snapshots are of the full text, so they can only be restored into a whole window.
+    fn restore_snapshot(&mut self, snapshot: &TextSnapshot) {
+        assert!(self.is_whole, "can only restore the whole text");
+        self.text.restore_snapshot(snapshot);
+        self.char_count = self.text.char_count();
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// The height of each paragraph of a [`crate::TextEdit::virtualized`] text,
+/// as measured the last time it was laid out.
+///
+/// This is what lets us know where each paragraph is without laying out all of them.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ParagraphHeights {
+    wrap_width: f32,
+
+    /// `None` for paragraphs we have not laid out yet.
+    heights: Vec<Option<f32>>,
+
+    /// Sums of `heights`, so we don't need to add up all the paragraphs above the one we want.
+    sums: PrefixSums,
+
+    /// The paragraphs folded away by [`crate::TextEdit::folding`], which take up no room.
+    hidden: Vec<Range<usize>>,
+}
+
+impl ParagraphHeights {
+    /// Forget all measurements if the number of paragraphs or the wrap width has changed,
+    /// e.g. because the text was changed by someone else than the `TextEdit`.
+    pub fn sync(&mut self, paragraph_count: usize, wrap_width: f32) {
+        if self.heights.len() != paragraph_count || self.wrap_width != wrap_width {
+            self.wrap_width = wrap_width;
+            self.heights.clear();
+            self.heights.resize(paragraph_count, None);
+            self.sums = PrefixSums::new(&self.heights);
+            self.hidden.clear();
+        }
+    }
+
+    /// Hide these paragraphs (sorted and without overlaps), and show the ones hidden before.
+    pub fn set_hidden(&mut self, hidden: &[Range<usize>]) {
+        if self.hidden != hidden {
+            let shown = std::mem::replace(&mut self.hidden, hidden.to_vec());
+            for paragraph in shown.into_iter().flatten() {
+                self.replace(paragraph, None); // measure it again when shown
+            }
+            for paragraph in hidden.iter().cloned().flatten() {
+                self.replace(paragraph, None);
+            }
+        }
+    }
+
+    pub fn set(&mut self, paragraph: usize, height: f32) {
+        self.replace(paragraph, Some(height));
+    }
+
+    fn replace(&mut self, paragraph: usize, height: Option<f32>) {
+        let height = if self.is_hidden(paragraph) {
+            Some(0.0)
+        } else {
+            height
+        };
+        if let Some(slot) = self.heights.get_mut(paragraph) {
+            if *slot != height {
+                self.sums.add(paragraph, PrefixSums::value(height));
+                self.sums.add(paragraph, PrefixSums::negated(*slot));
+                *slot = height;
+            }
+        }
+    }
+
+    /// The `paragraphs` were edited into `new_count` paragraphs,
+    /// which we need to measure again.
+    pub fn splice(&mut self, paragraphs: Range<usize>, new_count: usize) {
+        let paragraphs =
+            paragraphs.start.min(self.heights.len())..paragraphs.end.min(self.heights.len());
+        if paragraphs.len() == new_count {
+            for paragraph in paragraphs {
+                self.replace(paragraph, None);
+            }
+        } else {
+            // The hidden paragraphs have moved, so forget them until we are told again:
+            for paragraph in std::mem::take(&mut self.hidden).into_iter().flatten() {
+                if let Some(slot) = self.heights.get_mut(paragraph) {
+                    *slot = None;
+                }
+            }
+            self.heights
+                .splice(paragraphs, std::iter::repeat(None).take(new_count));
+            self.sums = PrefixSums::new(&self.heights);
+        }
+    }
+
+    fn is_hidden(&self, paragraph: usize) -> bool {
+        self.hidden.iter().any(|range| range.contains(&paragraph))
+    }
+
+    /// The height of a paragraph, or `estimate` if we have not measured it yet.
+    pub fn height(&self, paragraph: usize, estimate: f32) -> f32 {
+        self.heights
+            .get(paragraph)
+            .copied()
+            .flatten()
+            .unwrap_or(estimate)
+    }
+
+    /// Where the given paragraph starts.
+    pub fn top_of(&self, paragraph: usize, estimate: f32) -> f32 {
+        let sum = self.sums.sum(paragraph.min(self.heights.len()));
+        sum.height(estimate) as f32
+    }
+
+    pub fn total_height(&self, estimate: f32) -> f32 {
+        self.top_of(self.heights.len(), estimate)
+    }
+
+    /// The paragraphs that overlap `min_y..max_y`.
+    ///
+    /// Always returns at least one paragraph, the one closest to the range.
+    pub fn paragraphs_between(&self, min_y: f32, max_y: f32, estimate: f32) -> Range<usize> {
+        let last = self.heights.len().saturating_sub(1);
+        let (min_y, max_y) = (min_y as f64, max_y as f64);
+
+        // The paragraphs above `min_y`, and those that start above `max_y`:
+        let above = self.sums.count_while(estimate, |top| top <= min_y);
+        let end = if max_y <= 0.0 {
+            0
+        } else {
+            (self.sums.count_while(estimate, |top| top < max_y) + 1).min(self.heights.len())
+        };
+
+        let start = above.min(end).min(last);
+        start..end.max(start + 1)
+    }
+}
+
+/// A Fenwick tree of the measured heights and the number of paragraphs not yet measured.
+///
+/// Node `i` (counting from 1) holds the sum of the `i & i.wrapping_neg()` paragraphs
+/// that end with paragraph `i - 1`, so both a sum and an update visit `O(log n)` nodes.
+#[derive(Clone, Debug, Default)]
+struct PrefixSums {
+    nodes: Vec<Sum>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Sum {
+    measured: f64,
+    unmeasured: isize,
+}
+
+impl Sum {
+    fn height(self, estimate: f32) -> f64 {
+        self.measured + self.unmeasured as f64 * estimate as f64
+    }
+
+    fn plus(self, other: Self) -> Self {
+        Self {
+            measured: self.measured + other.measured,
+            unmeasured: self.unmeasured + other.unmeasured,
+        }
+    }
+}
+
+impl PrefixSums {
+    fn new(heights: &[Option<f32>]) -> Self {
+        let mut nodes = vec![Sum::default(); heights.len() + 1];
+        for (paragraph, height) in heights.iter().enumerate() {
+            let i = paragraph + 1;
+            nodes[i] = nodes[i].plus(Self::value(*height));
+            let parent = i + (i & i.wrapping_neg());
+            if parent < nodes.len() {
+                nodes[parent] = nodes[parent].plus(nodes[i]);
+            }
+        }
+        Self { nodes }
+    }
+
+    fn value(height: Option<f32>) -> Sum {
+        match height {
+            Some(height) => Sum {
+                measured: height as f64,
+                unmeasured: 0,
+            },
+            None => Sum {
+                measured: 0.0,
+                unmeasured: 1,
+            },
+        }
+    }
+
+    fn negated(height: Option<f32>) -> Sum {
+        let value = Self::value(height);
+        Sum {
+            measured: -value.measured,
+            unmeasured: -value.unmeasured,
+        }
+    }
+
+    fn add(&mut self, paragraph: usize, value: Sum) {
+        let mut i = paragraph + 1;
+        while i < self.nodes.len() {
+            self.nodes[i] = self.nodes[i].plus(value);
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// The sum of the first `count` paragraphs.
+    fn sum(&self, count: usize) -> Sum {
+        let mut sum = Sum::default();
+        let mut i = count.min(self.nodes.len().saturating_sub(1));
+        while i > 0 {
+            sum = sum.plus(self.nodes[i]);
+            i &= i - 1;
+        }
+        sum
+    }
+
+    /// The largest number of paragraphs whose total height is still `inside`,
+    /// which must be true for small heights and false for larger ones.
+    fn count_while(&self, estimate: f32, inside: impl Fn(f64) -> bool) -> usize {
+        let len = self.nodes.len().saturating_sub(1);
+        let mut count = 0;
+        let mut sum = Sum::default();
+        let mut step = if len == 0 {
+            0
+        } else {
+            1 << (usize::BITS - 1 - len.leading_zeros())
+        };
+        while step > 0 {
+            if count + step <= len {
+                let next = sum.plus(self.nodes[count + step]);
+                if inside(next.height(estimate)) {
+                    count += step;
+                    sum = next;
+                }
+            }
+            step /= 2;
+        }
+        count
+    }
+}
+
+/// Lay out each paragraph of `text` on its own,
+/// so that unchanged paragraphs are found in the [`epaint::Fonts`] cache.
+pub(crate) fn layout_paragraphs(
+    ui: &crate::Ui,
+    layouter: &mut dyn FnMut(&crate::Ui, &str, f32) -> std::sync::Arc<crate::Galley>,
+    text: &str,
+    wrap_width: f32,
+) -> Vec<std::sync::Arc<crate::Galley>> {
+    text.split('\n')
+        .map(|paragraph| layouter(ui, paragraph, wrap_width))
+        .collect()
+}
+
+#[test]
+fn test_paragraph_heights() {
+    let mut heights = ParagraphHeights::default();
+    heights.sync(5, 100.0);
+    assert_eq!(heights.total_height(10.0), 50.0);
+
+    heights.set(1, 30.0);
+    assert_eq!(heights.top_of(2, 10.0), 40.0);
+    assert_eq!(heights.total_height(10.0), 70.0);
+    assert_eq!(heights.paragraphs_between(0.0, 15.0, 10.0), 0..2);
+    assert_eq!(heights.paragraphs_between(15.0, 45.0, 10.0), 1..3);
+    assert_eq!(heights.paragraphs_between(100.0, 200.0, 10.0), 4..5);
+
+    // Paragraph 1 was split in three:
+    heights.splice(1..2, 3);
+    assert_eq!(heights.total_height(10.0), 70.0);
+    heights.sync(7, 100.0);
+    assert_eq!(heights.total_height(10.0), 70.0);
+
+    // The wrap width changed, so all measurements are stale:
+    heights.set(0, 20.0);
+    heights.sync(7, 50.0);
+    assert_eq!(heights.total_height(10.0), 70.0);
+
+    // Same number of paragraphs after the edit:
+    heights.set(2, 25.0);
+    heights.set(3, 5.0);
+    assert_eq!(heights.total_height(10.0), 80.0);
+    heights.splice(2..3, 1);
+    assert_eq!(heights.top_of(4, 10.0), 35.0);
+    assert_eq!(heights.total_height(10.0), 65.0);
+    assert_eq!(heights.paragraphs_between(30.0, 36.0, 10.0), 3..5);
+
+    // Folded paragraphs take up no room until they are shown again:
+    heights.set_hidden(&[1..2, 2..3]);
+    assert_eq!(heights.top_of(3, 10.0), 10.0);
+    heights.set(2, 50.0);
+    assert_eq!(heights.top_of(3, 10.0), 10.0);
+    heights.set_hidden(&[]);
+    assert_eq!(heights.top_of(3, 10.0), 30.0);
+
+    let mut empty = ParagraphHeights::default();
+    empty.sync(0, 100.0);
+    assert_eq!(empty.total_height(10.0), 0.0);
+    assert_eq!(empty.paragraphs_between(0.0, 10.0, 10.0), 0..1);
+}
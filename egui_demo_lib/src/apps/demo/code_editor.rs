@@ -90,6 +90,7 @@ impl super::View for CodeEditor {
                     .desired_rows(10)
                     .lock_focus(true)
                     .desired_width(f32::INFINITY)
+                    .find_replace(true)
                     .layouter(&mut layouter),
             );
         });
@@ -66,10 +66,12 @@ pub fn drop_target<R>(
     ui.painter().set(
         where_to_put_background,
         epaint::RectShape {
-            corner_radius: style.corner_radius,
+            rounding: style.rounding,
             fill,
+            fill_gradient: None,
             stroke,
             rect,
+            side_strokes: None,
         },
     );
 
@@ -16,6 +16,7 @@ pub mod layout_test;
 pub mod misc_demo_window;
 pub mod multi_touch;
 pub mod painting;
+pub mod pan_zoom;
 pub mod password;
 pub mod plot_demo;
 pub mod scrolling;
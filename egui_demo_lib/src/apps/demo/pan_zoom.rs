@@ -0,0 +1,118 @@
+use egui::{emath::TSTransform, vec2, Area, Frame, Order, Pos2, Rect, Sense};
+
+/// Ordinary widgets on a canvas that can be panned, zoomed and rotated.
+pub struct PanZoom {
+    /// From canvas coordinates (relative to the top left of the canvas) to screen offsets.
+    transform: TSTransform,
+    checked: bool,
+    value: f32,
+    text: String,
+}
+
+impl Default for PanZoom {
+    fn default() -> Self {
+        Self {
+            transform: TSTransform::IDENTITY,
+            checked: true,
+            value: 0.5,
+            text: "Edit me".to_owned(),
+        }
+    }
+}
+
+impl super::Demo for PanZoom {
+    fn name(&self) -> &'static str {
+        "🔍 Pan Zoom"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        egui::Window::new(self.name())
+            .open(open)
+            .default_size(vec2(512.0, 512.0))
+            .resizable(true)
+            .show(ctx, |ui| {
+                use super::View as _;
+                self.ui(ui);
+            });
+    }
+}
+
+impl super::View for PanZoom {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.vertical_centered(|ui| {
+            ui.add(crate::__egui_github_link_file!());
+        });
+        ui.label("Drag the background to pan, and pinch or ctrl-scroll to zoom.");
+        ui.horizontal(|ui| {
+            if ui.button("⟲ Rotate").clicked() {
+                self.rotate_around_origin(-std::f32::consts::TAU / 16.0);
+            }
+            if ui.button("⟳ Rotate").clicked() {
+                self.rotate_around_origin(std::f32::consts::TAU / 16.0);
+            }
+            if ui.button("Reset").clicked() {
+                self.transform = TSTransform::IDENTITY;
+            }
+        });
+
+        let (canvas_rect, response) =
+            ui.allocate_exact_size(ui.available_size_before_wrap(), Sense::drag());
+        ui.painter()
+            .rect_filled(canvas_rect, 0.0, ui.visuals().extreme_bg_color);
+
+        if response.dragged() {
+            self.transform = TSTransform::from_translation(response.drag_delta()) * self.transform;
+        }
+        if let Some(pointer) = response.hover_pos() {
+            let zoom = ui.input().zoom_delta();
+            if zoom != 1.0 {
+                // Keep the point under the pointer fixed:
+                let pointer = pointer - canvas_rect.min;
+                self.transform = TSTransform::from_translation(pointer)
+                    * TSTransform::from_scaling(zoom)
+                    * TSTransform::from_translation(-pointer)
+                    * self.transform;
+            }
+        }
+
+        let to_screen = TSTransform::from_translation(canvas_rect.min.to_vec2()) * self.transform;
+        let clip_rect = to_screen.inverse().mul_rect(canvas_rect);
+
+        let items = [
+            (Pos2::new(20.0, 20.0), "Checkbox"),
+            (Pos2::new(60.0, 120.0), "Slider"),
+            (Pos2::new(140.0, 220.0), "Text"),
+        ];
+        for (pos, title) in items {
+            let response = Area::new(("pan_zoom", title))
+                .default_pos(pos)
+                .order(Order::Foreground)
+                .drag_bounds(Rect::EVERYTHING)
+                .show(ui.ctx(), |ui| {
+                    ui.set_clip_rect(clip_rect);
+                    Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.strong(title);
+                        match title {
+                            "Checkbox" => {
+                                ui.checkbox(&mut self.checked, "Check me");
+                            }
+                            "Slider" => {
+                                ui.add(egui::Slider::new(&mut self.value, 0.0..=1.0));
+                            }
+                            _ => {
+                                ui.text_edit_singleline(&mut self.text);
+                            }
+                        }
+                    });
+                })
+                .response;
+            ui.ctx().set_transform_layer(response.layer_id, to_screen);
+        }
+    }
+}
+
+impl PanZoom {
+    fn rotate_around_origin(&mut self, angle: f32) {
+        self.transform = TSTransform::from_rotation(angle) * self.transform;
+    }
+}
@@ -11,3 +11,5 @@ pub use fractal_clock::FractalClock;
 pub use http_app::HttpApp;
 
 pub use demo::DemoWindows; // used for tests
+#[cfg(test)]
+pub use demo::{View, WidgetGallery}; // used for tests
@@ -136,7 +136,7 @@ fn format_from_style(
     egui_style: &egui::Style,
     emark_style: &easy_mark_parser::Style,
 ) -> egui::text::TextFormat {
-    use egui::{Align, Color32, Stroke, TextStyle};
+    use egui::{Align, Color32, FontWeight, Stroke, TextStyle};
 
     let color = if emark_style.strong || emark_style.heading {
         egui_style.visuals.strong_text_color()
@@ -184,9 +184,15 @@ fn format_from_style(
         font_id: text_style.resolve(egui_style),
         color,
         background,
+        weight: if emark_style.strong {
+            FontWeight::BOLD
+        } else {
+            FontWeight::NORMAL
+        },
         italics: emark_style.italics,
         underline,
         strikethrough,
         valign,
+        ..Default::default()
     }
 }
@@ -76,9 +76,11 @@ impl FrameHistory {
         let mut shapes = Vec::with_capacity(3 + 2 * history.len());
         shapes.push(Shape::Rect(epaint::RectShape {
             rect,
-            corner_radius: style.corner_radius,
+            rounding: style.rounding,
             fill: ui.visuals().extreme_bg_color,
+            fill_gradient: None,
             stroke: ui.style().noninteractive().bg_stroke,
+            side_strokes: None,
         }));
 
         let rect = rect.shrink(4.0);
@@ -88,6 +88,8 @@ mod apps;
 mod backend_panel;
 pub mod easy_mark;
 pub(crate) mod frame_history;
+#[cfg(test)]
+mod snapshot;
 pub mod syntax_highlighting;
 mod wrap_app;
 
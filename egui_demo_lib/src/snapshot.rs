@@ -0,0 +1,538 @@
+//! Golden-image ("snapshot") tests.
+//!
+//! A UI is run for a few frames with scripted [`egui::RawInput`],
+//! the last frame is painted with the software [`Rasterizer`],
+//! and the result is compared to a PNG committed in `egui_demo_lib/snapshots/`.
+//!
+//! If the images differ, `<name>.new.png` and `<name>.diff.png` are written next to the
+//! committed image and the test fails.
+//! To accept the new look (or to create a missing snapshot), run the tests with
+//! `UPDATE_SNAPSHOTS=1 cargo test -p egui_demo_lib`.
+
+use egui::{epaint::raster::Rasterizer, Color32, ColorImage, Context, RawInput, Rect, Vec2};
+use std::path::PathBuf;
+
+/// How to render and compare a snapshot.
+#[derive(Clone, Debug)]
+pub struct SnapshotOptions {
+    /// Size of the screen, in points.
+    pub screen_size: Vec2,
+
+    /// The resolution of the rendered image.
+    pub pixels_per_point: f32,
+
+    /// Background color of the image.
+    pub clear_color: egui::Rgba,
+
+    /// Two pixels are considered equal if no channel differs more than this.
+    pub threshold: u8,
+
+    /// The snapshot test fails if more than this many pixels differ.
+    pub max_differing_pixels: usize,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        Self {
+            screen_size: egui::vec2(400.0, 300.0),
+            pixels_per_point: 1.0,
+            clear_color: egui::Rgba::BLACK,
+            threshold: 8,
+            max_differing_pixels: 0,
+        }
+    }
+}
+
+/// Run `run_ui` once for each of the given `inputs`, and paint the last frame.
+///
+/// [`RawInput::screen_rect`] and [`RawInput::pixels_per_point`] are filled in from
+/// the options unless they are already set.
+pub fn render(
+    options: &SnapshotOptions,
+    inputs: impl IntoIterator<Item = RawInput>,
+    mut run_ui: impl FnMut(&Context),
+) -> ColorImage {
+    let ctx = Context::default();
+    let mut rasterizer = Rasterizer::default();
+    let size_in_pixels = [
+        (options.screen_size.x * options.pixels_per_point).round() as usize,
+        (options.screen_size.y * options.pixels_per_point).round() as usize,
+    ];
+
+    let mut image = ColorImage::new(size_in_pixels, options.clear_color.into());
+    for mut raw_input in inputs {
+        raw_input.screen_rect = raw_input
+            .screen_rect
+            .or_else(|| Some(Rect::from_min_size(Default::default(), options.screen_size)));
+        raw_input.pixels_per_point = raw_input
+            .pixels_per_point
+            .or(Some(options.pixels_per_point));
+
+        let (output, shapes) = ctx.run(raw_input, &mut run_ui);
+        let clipped_meshes = ctx.tessellate(shapes);
+        image = rasterizer.paint_and_update_textures(
+            size_in_pixels,
+            options.pixels_per_point,
+            options.clear_color,
+            &clipped_meshes,
+            &output.textures_delta,
+        );
+    }
+    image
+}
+
+/// Count the pixels that differ by more than `threshold` in any channel,
+/// and produce an image highlighting them in red.
+pub fn compare(expected: &ColorImage, actual: &ColorImage, threshold: u8) -> (usize, ColorImage) {
+    if expected.size != actual.size {
+        let num_pixels = expected.pixels.len().max(actual.pixels.len());
+        return (num_pixels, ColorImage::new(actual.size, Color32::RED));
+    }
+
+    let mut num_differing = 0;
+    let pixels = expected
+        .pixels
+        .iter()
+        .zip(&actual.pixels)
+        .map(|(e, a)| {
+            let differs = e
+                .to_array()
+                .iter()
+                .zip(a.to_array().iter())
+                .any(|(&e, &a)| (e as i16 - a as i16).abs() > threshold as i16);
+            if differs {
+                num_differing += 1;
+                Color32::RED
+            } else {
+                // Dimmed so that the differences stand out:
+                let gray = ((e.r() as u16 + e.g() as u16 + e.b() as u16) / 6) as u8;
+                Color32::from_gray(gray)
+            }
+        })
+        .collect();
+
+    (
+        num_differing,
+        ColorImage {
+            size: actual.size,
+            pixels,
+        },
+    )
+}
+
+/// Compare `image` to the committed snapshot called `name`.
+///
+/// Panics if they differ by more than the options allow.
+pub fn assert_snapshot(name: &str, image: &ColorImage, options: &SnapshotOptions) {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("snapshots");
+    let path = dir.join(format!("{}.png", name));
+    let new_path = dir.join(format!("{}.new.png", name));
+    let diff_path = dir.join(format!("{}.diff.png", name));
+
+    let _ = std::fs::remove_file(&new_path);
+    let _ = std::fs::remove_file(&diff_path);
+
+    let update = std::env::var("UPDATE_SNAPSHOTS").map_or(false, |value| value != "0");
+    if update {
+        std::fs::create_dir_all(&dir).unwrap();
+        save_png(&path, image);
+        return;
+    }
+
+    let expected = match load_png(&path) {
+        Ok(expected) => expected,
+        Err(err) => {
+            save_png(&new_path, image);
+            panic!(
+                "Failed to load snapshot {:?}: {}. Run with UPDATE_SNAPSHOTS=1 to create it.",
+                path, err
+            );
+        }
+    };
+
+    let (num_differing, diff) = compare(&expected, image, options.threshold);
+    if num_differing > options.max_differing_pixels {
+        save_png(&new_path, image);
+        save_png(&diff_path, &diff);
+        panic!(
+            "Snapshot {:?} failed: {} pixels differ (max allowed: {}). See {:?} and {:?}. \
+            Run with UPDATE_SNAPSHOTS=1 to accept the new image.",
+            name, num_differing, options.max_differing_pixels, new_path, diff_path
+        );
+    }
+}
+
+fn save_png(path: &std::path::Path, image: &ColorImage) {
+    let bytes: Vec<u8> = image.pixels.iter().flat_map(|c| c.to_array()).collect();
+    image::save_buffer(
+        path,
+        &bytes,
+        image.width() as u32,
+        image.height() as u32,
+        image::ColorType::Rgba8,
+    )
+    .unwrap_or_else(|err| panic!("Failed to write {:?}: {}", path, err));
+}
+
+fn load_png(path: &std::path::Path) -> Result<ColorImage, String> {
+    let image = image::open(path).map_err(|err| err.to_string())?;
+    let image = image.to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    // The rasterizer output is premultiplied, and so is what we saved:
+    let pixels = image
+        .as_raw()
+        .chunks_exact(4)
+        .map(|p| Color32::from_rgba_premultiplied(p[0], p[1], p[2], p[3]))
+        .collect();
+    Ok(ColorImage { size, pixels })
+}
+
+// ----------------------------------------------------------------------------
+
+fn frames(num_frames: usize) -> impl Iterator<Item = RawInput> {
+    std::iter::repeat(RawInput::default()).take(num_frames)
+}
+
+#[test]
+fn test_snapshot_widget_gallery() {
+    use crate::apps::{View as _, WidgetGallery};
+
+    // Shaping applies the kerning of the fonts, which moves the glyphs of some letter pairs:
+    let suffix = if cfg!(feature = "text_shaping") {
+        "_shaped"
+    } else {
+        ""
+    };
+    for (name, visuals) in [
+        ("widget_gallery_dark", egui::Visuals::dark()),
+        ("widget_gallery_light", egui::Visuals::light()),
+    ] {
+        let name = format!("{}{}", name, suffix);
+        let options = SnapshotOptions {
+            screen_size: egui::vec2(380.0, 520.0),
+            ..Default::default()
+        };
+        let mut gallery = WidgetGallery::default();
+        let image = render(&options, frames(3), |ctx| {
+            ctx.set_visuals(visuals.clone());
+            egui::CentralPanel::default().show(ctx, |ui| gallery.ui(ui));
+        });
+        assert_snapshot(&name, &image, &options);
+    }
+}
+
+#[test]
+fn test_snapshot_hovered_button() {
+    let options = SnapshotOptions {
+        screen_size: egui::vec2(120.0, 40.0),
+        pixels_per_point: 2.0,
+        ..Default::default()
+    };
+    let hover = RawInput {
+        events: vec![egui::Event::PointerMoved(egui::pos2(30.0, 18.0))],
+        ..Default::default()
+    };
+    let image = render(&options, frames(1).chain(Some(hover)), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let _ = ui.button("Hover me");
+        });
+    });
+    assert_snapshot("hovered_button", &image, &options);
+}
+
+#[test]
+fn test_snapshot_gradients() {
+    use egui::{pos2, vec2, Gradient, Shape, Stroke};
+
+    let options = SnapshotOptions {
+        screen_size: vec2(320.0, 120.0),
+        ..Default::default()
+    };
+    let image = render(&options, frames(1), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let painter = ui.painter();
+
+            let rect = Rect::from_min_size(pos2(10.0, 10.0), vec2(140.0, 40.0));
+            painter.rect_filled_gradient(
+                rect,
+                8.0,
+                Gradient::linear(
+                    rect.left_center(),
+                    rect.right_center(),
+                    [
+                        (0.0, Color32::RED),
+                        (0.5, Color32::YELLOW),
+                        (1.0, Color32::BLUE),
+                    ],
+                ),
+            );
+
+            let rect = Rect::from_min_size(pos2(10.0, 60.0), vec2(140.0, 50.0));
+            painter.rect_filled_gradient(
+                rect,
+                0.0,
+                Gradient::linear(
+                    rect.center_top(),
+                    rect.center_bottom(),
+                    [(0.0, Color32::WHITE), (1.0, Color32::TRANSPARENT)],
+                ),
+            );
+
+            let center = pos2(200.0, 60.0);
+            painter.circle_filled_gradient(
+                center,
+                40.0,
+                Gradient::radial(
+                    center,
+                    40.0,
+                    [(0.0, Color32::WHITE), (1.0, Color32::from_rgb(0, 92, 128))],
+                ),
+            );
+
+            let points = vec![pos2(260.0, 20.0), pos2(310.0, 60.0), pos2(260.0, 100.0)];
+            painter.add(Shape::convex_polygon_gradient(
+                points,
+                Gradient::linear(
+                    pos2(260.0, 20.0),
+                    pos2(310.0, 100.0),
+                    [(0.0, Color32::GREEN), (1.0, Color32::DARK_GREEN)],
+                ),
+                Stroke::new(1.0, Color32::WHITE),
+            ));
+        });
+    });
+    assert_snapshot("gradients", &image, &options);
+}
+
+#[test]
+fn test_snapshot_bezier() {
+    use egui::{epaint::CubicBezierShape, epaint::QuadraticBezierShape, pos2, vec2, Stroke};
+
+    let options = SnapshotOptions {
+        screen_size: vec2(240.0, 120.0),
+        ..Default::default()
+    };
+    let image = render(&options, frames(1), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let painter = ui.painter();
+            painter.add(CubicBezierShape::from_points_stroke(
+                [
+                    pos2(10.0, 100.0),
+                    pos2(60.0, -20.0),
+                    pos2(80.0, 140.0),
+                    pos2(120.0, 20.0),
+                ],
+                false,
+                Color32::TRANSPARENT,
+                Stroke::new(2.0, Color32::LIGHT_BLUE),
+            ));
+            painter.add(QuadraticBezierShape::from_points_stroke(
+                [pos2(140.0, 100.0), pos2(180.0, 0.0), pos2(230.0, 100.0)],
+                true,
+                Color32::from_rgb(80, 40, 20),
+                Stroke::new(1.0, Color32::GOLD),
+            ));
+        });
+    });
+    assert_snapshot("bezier", &image, &options);
+}
+
+#[test]
+fn test_snapshot_rounding() {
+    use egui::{pos2, vec2, Rounding, Stroke};
+
+    let options = SnapshotOptions {
+        screen_size: vec2(200.0, 80.0),
+        ..Default::default()
+    };
+    let image = render(&options, frames(1), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let painter = ui.painter();
+            let tab = Rounding {
+                nw: 8.0,
+                ne: 8.0,
+                ..Rounding::none()
+            };
+            let rect = Rect::from_min_size(pos2(10.0, 10.0), vec2(80.0, 30.0));
+            painter.rect(
+                rect,
+                tab,
+                Color32::DARK_BLUE,
+                Stroke::new(1.0, Color32::WHITE),
+            );
+
+            let rect = Rect::from_min_size(pos2(110.0, 10.0), vec2(80.0, 60.0));
+            let rounding = Rounding {
+                nw: 0.0,
+                ne: 4.0,
+                sw: 16.0,
+                se: 40.0,
+            };
+            painter.rect(
+                rect,
+                rounding,
+                Color32::DARK_RED,
+                Stroke::new(2.0, Color32::GOLD),
+            );
+        });
+    });
+    assert_snapshot("rounding", &image, &options);
+}
+
+#[test]
+fn test_snapshot_side_strokes() {
+    use egui::{epaint::SideStrokes, pos2, vec2, Frame, Rounding, Stroke};
+
+    let options = SnapshotOptions {
+        screen_size: vec2(300.0, 80.0),
+        ..Default::default()
+    };
+    let image = render(&options, frames(1), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            // A tab without a border towards its contents:
+            let border = Stroke::new(1.0, Color32::WHITE);
+            let tab = Frame::none()
+                .fill(Color32::DARK_BLUE)
+                .rounding(Rounding {
+                    nw: 8.0,
+                    ne: 8.0,
+                    ..Rounding::none()
+                })
+                .side_strokes(SideStrokes {
+                    bottom: Stroke::none(),
+                    ..SideStrokes::same(border)
+                });
+            let rect = Rect::from_min_size(pos2(10.0, 10.0), vec2(80.0, 30.0));
+            ui.painter().add(tab.paint(rect));
+
+            let sides = SideStrokes {
+                left: Stroke::new(2.0, Color32::RED),
+                top: Stroke::new(4.0, Color32::GREEN),
+                right: Stroke::new(6.0, Color32::LIGHT_BLUE),
+                bottom: Stroke::new(2.0, Color32::GOLD).dashed(6.0, 3.0),
+            };
+            let rect = Rect::from_min_size(pos2(110.0, 10.0), vec2(70.0, 60.0));
+            ui.painter()
+                .add(egui::epaint::RectShape::stroke_sides(rect, 0.0, sides));
+            let rect = Rect::from_min_size(pos2(210.0, 10.0), vec2(70.0, 60.0));
+            ui.painter()
+                .add(egui::epaint::RectShape::stroke_sides(rect, 12.0, sides));
+        });
+    });
+    assert_snapshot("side_strokes", &image, &options);
+}
+
+#[test]
+fn test_snapshot_dashed_strokes() {
+    use egui::{pos2, vec2, Shape, Stroke};
+
+    let options = SnapshotOptions {
+        screen_size: vec2(300.0, 120.0),
+        ..Default::default()
+    };
+    let image = render(&options, frames(1), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let painter = ui.painter();
+            let rect = Rect::from_min_size(pos2(10.0, 10.0), vec2(80.0, 80.0));
+            painter.rect_stroke(
+                rect,
+                10.0,
+                Stroke::new(2.0, Color32::WHITE).dashed(8.0, 4.0),
+            );
+
+            painter.circle_stroke(
+                pos2(150.0, 50.0),
+                40.0,
+                Stroke::new(3.0, Color32::LIGHT_BLUE).dotted(8.0),
+            );
+
+            let points = vec![pos2(210.0, 90.0), pos2(250.0, 10.0), pos2(290.0, 90.0)];
+            painter.add(Shape::closed_line(
+                points,
+                Stroke::new(1.0, Color32::GOLD)
+                    .dashed(12.0, 3.0)
+                    .dash_offset(6.0),
+            ));
+
+            // Without spacing the patterns fall back to solid lines:
+            painter.line_segment(
+                [pos2(10.0, 105.0), pos2(140.0, 105.0)],
+                Stroke::new(2.0, Color32::WHITE).dashed(0.0, 4.0),
+            );
+            painter.line_segment(
+                [pos2(160.0, 105.0), pos2(290.0, 105.0)],
+                Stroke::new(2.0, Color32::LIGHT_BLUE).dotted(0.0),
+            );
+        });
+    });
+    assert_snapshot("dashed_strokes", &image, &options);
+}
+
+#[test]
+fn test_snapshot_shadows() {
+    use egui::{epaint::Shadow, pos2, vec2, Frame, Stroke};
+
+    let options = SnapshotOptions {
+        screen_size: vec2(340.0, 120.0),
+        ..Default::default()
+    };
+    let image = render(&options, frames(1), |ctx| {
+        ctx.set_visuals(egui::Visuals::light());
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let window = Frame::window(ui.style()).shadow(Shadow {
+                offset: vec2(6.0, 6.0),
+                blur: 8.0,
+                spread: 0.0,
+                color: Color32::from_black_alpha(128),
+                inner: false,
+            });
+            let rect = Rect::from_min_size(pos2(20.0, 20.0), vec2(80.0, 70.0));
+            ui.painter().add(window.paint(rect));
+
+            let sunken = Frame::none()
+                .fill(Color32::from_gray(220))
+                .stroke(Stroke::new(1.0, Color32::GRAY))
+                .rounding(6.0)
+                .shadow(Shadow::inner(
+                    vec2(2.0, 3.0),
+                    4.0,
+                    Color32::from_black_alpha(160),
+                ));
+            let rect = Rect::from_min_size(pos2(130.0, 20.0), vec2(90.0, 70.0));
+            ui.painter().add(sunken.paint(rect));
+
+            // The inner shadow follows the rounded corners:
+            let well = sunken.rounding(24.0).shadow(Shadow {
+                spread: 2.0,
+                ..Shadow::inner(vec2(4.0, 6.0), 6.0, Color32::from_black_alpha(200))
+            });
+            let rect = Rect::from_min_size(pos2(240.0, 20.0), vec2(80.0, 80.0));
+            ui.painter().add(well.paint(rect));
+        });
+    });
+    assert_snapshot("shadows", &image, &options);
+}
+
+#[test]
+fn test_snapshot_transformed_layer() {
+    use egui::{pos2, vec2, Area, Frame, Id, LayerId, Order, TSTransform};
+
+    let options = SnapshotOptions {
+        screen_size: vec2(240.0, 160.0),
+        ..Default::default()
+    };
+    let image = render(&options, frames(2), |ctx| {
+        let layer_id = LayerId::new(Order::Middle, Id::new("canvas"));
+        let transform = TSTransform::new(vec2(60.0, 20.0), 1.5) * TSTransform::from_rotation(0.3);
+        ctx.set_transform_layer(layer_id, transform);
+        Area::new("canvas")
+            .fixed_pos(pos2(0.0, 0.0))
+            .show(ctx, |ui| {
+                Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.label("Zoomed and rotated");
+                    let _ = ui.button("Button");
+                });
+            });
+    });
+    assert_snapshot("transformed_layer", &image, &options);
+}
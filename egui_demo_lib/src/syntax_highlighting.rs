@@ -353,6 +353,11 @@ impl Highligher {
             for (style, range) in h.highlight(line, &self.ps) {
                 let fg = style.foreground;
                 let text_color = egui::Color32::from_rgb(fg.r, fg.g, fg.b);
+                let weight = if style.font_style.contains(FontStyle::BOLD) {
+                    egui::FontWeight::BOLD
+                } else {
+                    egui::FontWeight::NORMAL
+                };
                 let italics = style.font_style.contains(FontStyle::ITALIC);
                 let underline = style.font_style.contains(FontStyle::ITALIC);
                 let underline = if underline {
@@ -366,10 +371,12 @@ impl Highligher {
                     format: TextFormat {
                         font_id: egui::FontId::monospace(14.0),
                         color: text_color,
+                        weight,
                         italics,
                         underline,
                         ..Default::default()
                     },
+                    inline_box: None,
                 });
             }
         }
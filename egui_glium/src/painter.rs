@@ -212,6 +212,14 @@ impl Painter {
                     .map(|color| color.to_tuple())
                     .collect()
             }
+            egui::ImageData::Sdf(image) => {
+                // TODO: evaluate the distances in the shader, like `egui_glow` does.
+                let gamma = 1.0;
+                image
+                    .srgba_pixels(gamma)
+                    .map(|color| color.to_tuple())
+                    .collect()
+            }
         };
         let glium_image = glium::texture::RawImage2d {
             data: std::borrow::Cow::Owned(pixels),
@@ -91,10 +91,14 @@ pub mod painter;
 #[cfg(feature = "winit")]
 use egui_winit::winit;
 pub use glow;
+#[cfg(feature = "epi")]
+pub use offscreen::OffscreenContext;
 pub use painter::Painter;
 #[cfg(feature = "winit")]
 mod epi_backend;
 mod misc_util;
+#[cfg(feature = "epi")]
+mod offscreen;
 mod post_process;
 mod shader_version;
 mod vao_emulate;
@@ -0,0 +1,209 @@
+#![allow(unsafe_code)]
+
+use std::collections::HashMap;
+
+use glow::HasContext;
+
+use crate::Painter;
+
+/// An [`egui::Context`] that is painted into a texture instead of onto the screen.
+///
+/// Use it to show a live preview of another ui, or to cache an expensive one:
+///
+/// ```no_run
+/// # fn example(gl: &glow::Context, painter: &mut egui_glow::Painter, ui: &mut egui::Ui) {
+/// let mut preview = egui_glow::OffscreenContext::default();
+/// let raw_input = egui::RawInput {
+///     screen_rect: Some(egui::Rect::from_min_size(Default::default(), egui::vec2(320.0, 240.0))),
+///     ..Default::default()
+/// };
+/// let (texture_id, _output) = preview
+///     .run(gl, painter, raw_input, |ctx| {
+///         egui::CentralPanel::default().show(ctx, |ui| ui.label("Hello from inside a texture"));
+///     })
+///     .unwrap();
+/// ui.image(texture_id, egui::vec2(320.0, 240.0));
+/// # }
+/// ```
+///
+/// The nested context has its own font atlas and textures, which are kept here.
+/// Textures registered with the [`Painter`] (e.g. with [`epi::NativeTexture`]) can be shown too.
+///
+/// This must be destroyed with [`Self::destroy`] before dropping.
+#[derive(Default)]
+pub struct OffscreenContext {
+    pub egui_ctx: egui::Context,
+
+    /// The textures managed by `egui_ctx`.
+    textures: HashMap<egui::TextureId, glow::Texture>,
+
+    /// What we paint into, registered with the [`Painter`].
+    texture_id: Option<egui::TextureId>,
+}
+
+impl OffscreenContext {
+    /// Run a frame of the nested context and paint it into a texture.
+    ///
+    /// `raw_input.screen_rect` decides the size of the texture (together with `raw_input.pixels_per_point`).
+    /// Any pointer positions in `raw_input` should be relative to that rectangle.
+    ///
+    /// The same texture is reused between calls, so the returned id stays the same.
+    ///
+    /// # Errors
+    /// If `raw_input.screen_rect` is not set, or if the texture could not be painted.
+    pub fn run(
+        &mut self,
+        gl: &glow::Context,
+        painter: &mut Painter,
+        raw_input: egui::RawInput,
+        run_ui: impl FnOnce(&egui::Context),
+    ) -> Result<(egui::TextureId, egui::Output), String> {
+        let screen_rect = raw_input
+            .screen_rect
+            .ok_or_else(|| "OffscreenContext::run needs a screen_rect".to_owned())?;
+        let pixels_per_point = raw_input.pixels_per_point.unwrap_or(1.0);
+        let size_in_pixels = [
+            (screen_rect.width() * pixels_per_point).round().max(1.0) as u32,
+            (screen_rect.height() * pixels_per_point).round().max(1.0) as u32,
+        ];
+
+        let (mut output, shapes) = self.egui_ctx.run(raw_input, run_ui);
+        let textures_delta = std::mem::take(&mut output.textures_delta);
+
+        for (id, image_delta) in textures_delta.set {
+            let texture =
+                get_or_create_texture(&mut self.textures, id, || unsafe { gl.create_texture() })?;
+            painter.upload_image_delta(gl, texture, &image_delta);
+        }
+
+        let clipped_meshes = self.egui_ctx.tessellate(shapes);
+        let texture_id = if let Some(texture_id) = self.texture_id {
+            painter.repaint_texture_with(
+                gl,
+                texture_id,
+                size_in_pixels,
+                pixels_per_point,
+                clipped_meshes,
+                Some(&self.textures),
+            )?;
+            texture_id
+        } else {
+            let texture = unsafe { gl.create_texture()? };
+            if let Err(err) = painter.paint_into_texture(
+                gl,
+                texture,
+                size_in_pixels,
+                pixels_per_point,
+                clipped_meshes,
+                Some(&self.textures),
+            ) {
+                unsafe { gl.delete_texture(texture) };
+                return Err(err);
+            }
+            let texture_id = epi::NativeTexture::register_native_texture(painter, texture);
+            self.texture_id = Some(texture_id);
+            texture_id
+        };
+
+        free_textures(&mut self.textures, &textures_delta.free, |texture| unsafe {
+            gl.delete_texture(texture);
+        });
+
+        Ok((texture_id, output))
+    }
+
+    /// The texture we paint into, if [`Self::run`] has been called.
+    pub fn texture_id(&self) -> Option<egui::TextureId> {
+        self.texture_id
+    }
+
+    /// Free the textures of the nested context, and the texture we paint into.
+    pub fn destroy(&mut self, gl: &glow::Context, painter: &mut Painter) {
+        for (_, texture) in self.textures.drain() {
+            unsafe { gl.delete_texture(texture) };
+        }
+        if let Some(texture_id) = self.texture_id.take() {
+            painter.free_texture(gl, texture_id);
+        }
+    }
+}
+
+/// The texture of the nested context with the given id, created the first time the id is seen.
+fn get_or_create_texture<Texture: Copy, E>(
+    textures: &mut HashMap<egui::TextureId, Texture>,
+    id: egui::TextureId,
+    create: impl FnOnce() -> Result<Texture, E>,
+) -> Result<Texture, E> {
+    if let Some(texture) = textures.get(&id) {
+        Ok(*texture)
+    } else {
+        let texture = create()?;
+        textures.insert(id, texture);
+        Ok(texture)
+    }
+}
+
+/// Forget the textures that the nested context has freed, and `delete` them.
+fn free_textures<Texture>(
+    textures: &mut HashMap<egui::TextureId, Texture>,
+    freed: &[egui::TextureId],
+    mut delete: impl FnMut(Texture),
+) {
+    for id in freed {
+        if let Some(texture) = textures.remove(id) {
+            delete(texture);
+        }
+    }
+}
+
+#[test]
+fn test_texture_ids() {
+    use egui::TextureId;
+
+    let mut textures = HashMap::new();
+    let mut next_texture = 1;
+    let mut create = || -> Result<u32, String> {
+        next_texture += 1;
+        Ok(next_texture)
+    };
+
+    let font = get_or_create_texture(&mut textures, TextureId::Managed(0), &mut create).unwrap();
+    let image = get_or_create_texture(&mut textures, TextureId::Managed(1), &mut create).unwrap();
+    assert_ne!(font, image);
+
+    // Partial updates go to the texture we already have:
+    let again = get_or_create_texture(&mut textures, TextureId::Managed(0), &mut create).unwrap();
+    assert_eq!(again, font);
+    assert_eq!(textures.len(), 2);
+
+    // Nothing is remembered if the texture could not be created:
+    let failed = get_or_create_texture(&mut textures, TextureId::Managed(2), || {
+        Err::<u32, _>("out of memory".to_owned())
+    });
+    assert!(failed.is_err());
+    assert_eq!(textures.len(), 2);
+}
+
+#[test]
+fn test_texture_lifetime() {
+    use egui::TextureId;
+
+    let mut textures: HashMap<TextureId, u32> = HashMap::new();
+    textures.insert(TextureId::Managed(0), 10);
+    textures.insert(TextureId::Managed(1), 11);
+
+    let mut deleted = vec![];
+    free_textures(
+        &mut textures,
+        &[TextureId::Managed(1), TextureId::Managed(7)],
+        |texture| deleted.push(texture),
+    );
+    assert_eq!(deleted, vec![11], "only textures we have are deleted, once");
+    assert_eq!(textures.len(), 1);
+
+    // A freed id that comes back gets a new texture:
+    let texture =
+        get_or_create_texture(&mut textures, TextureId::Managed(1), || Ok::<_, String>(12))
+            .unwrap();
+    assert_eq!(texture, 12);
+}
@@ -1,6 +1,6 @@
 #![allow(unsafe_code)]
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use egui::{
     emath::Rect,
@@ -29,6 +29,8 @@ pub struct Painter {
     program: glow::Program,
     u_screen_size: glow::UniformLocation,
     u_sampler: glow::UniformLocation,
+    /// `None` if the shader can't evaluate signed distance fields.
+    u_sdf: Option<glow::UniformLocation>,
     is_webgl_1: bool,
     is_embedded: bool,
     vertex_array: crate::misc_util::VAO,
@@ -40,6 +42,8 @@ pub struct Painter {
     element_array_buffer: glow::Buffer,
 
     textures: HashMap<egui::TextureId, glow::Texture>,
+    /// The textures holding signed distance fields (see [`egui::ImageData::Sdf`]).
+    sdf_textures: HashSet<glow::Texture>,
 
     #[cfg(feature = "epi")]
     next_native_tex_id: u64, // TODO: 128-bit texture space?
@@ -132,6 +136,18 @@ impl Painter {
             _ => (None, "#define SRGB_SUPPORTED"),
         };
 
+        // Signed distance fields need `fwidth`, which is an extension in WebGL1 and OpenGL ES 2.0:
+        let sdf_support_define = if shader_version != ShaderVersion::Es100 {
+            "#define SDF_SUPPORTED"
+        } else if gl
+            .supported_extensions()
+            .contains("OES_standard_derivatives")
+        {
+            "#extension GL_OES_standard_derivatives : enable\n#define SDF_SUPPORTED"
+        } else {
+            ""
+        };
+
         unsafe {
             let vert = compile_shader(
                 gl,
@@ -148,8 +164,9 @@ impl Painter {
                 gl,
                 glow::FRAGMENT_SHADER,
                 &format!(
-                    "{}\n{}\n{}\n{}\n{}",
+                    "{}\n{}\n{}\n{}\n{}\n{}",
                     header,
+                    sdf_support_define,
                     shader_prefix,
                     srgb_support_define,
                     shader_version.is_new_shader_interface(),
@@ -163,6 +180,7 @@ impl Painter {
             gl.delete_shader(frag);
             let u_screen_size = gl.get_uniform_location(program, "u_screen_size").unwrap();
             let u_sampler = gl.get_uniform_location(program, "u_sampler").unwrap();
+            let u_sdf = gl.get_uniform_location(program, "u_sdf");
             let vertex_buffer = gl.create_buffer()?;
             let element_array_buffer = gl.create_buffer()?;
             gl.bind_buffer(glow::ARRAY_BUFFER, Some(vertex_buffer));
@@ -211,6 +229,7 @@ impl Painter {
                 program,
                 u_screen_size,
                 u_sampler,
+                u_sdf,
                 is_webgl_1,
                 is_embedded: matches!(shader_version, ShaderVersion::Es100 | ShaderVersion::Es300),
                 vertex_array,
@@ -220,6 +239,7 @@ impl Painter {
                 vertex_buffer,
                 element_array_buffer,
                 textures: Default::default(),
+                sdf_textures: Default::default(),
                 #[cfg(feature = "epi")]
                 next_native_tex_id: 1 << 32,
                 textures_to_destroy: Vec::new(),
@@ -305,7 +325,16 @@ impl Painter {
         }
         let size_in_pixels = unsafe { self.prepare_painting(inner_size, gl, pixels_per_point) };
         for egui::ClippedMesh(clip_rect, mesh) in clipped_meshes {
-            self.paint_mesh(gl, size_in_pixels, pixels_per_point, clip_rect, &mesh);
+            if let Some(texture) = self.get_texture(mesh.texture_id) {
+                self.paint_mesh(
+                    gl,
+                    size_in_pixels,
+                    pixels_per_point,
+                    clip_rect,
+                    &mesh,
+                    texture,
+                );
+            }
         }
         unsafe {
             self.vertex_array.unbind_vertex_array(gl);
@@ -322,66 +351,275 @@ impl Painter {
     }
 
     #[inline(never)] // Easier profiling
+    #[allow(clippy::needless_pass_by_value)] // false positive: `glow::Texture` is `Copy`
     fn paint_mesh(
-        &mut self,
+        &self,
         gl: &glow::Context,
         size_in_pixels: (u32, u32),
         pixels_per_point: f32,
         clip_rect: Rect,
         mesh: &Mesh,
+        texture: glow::Texture,
     ) {
         debug_assert!(mesh.is_valid());
-        if let Some(texture) = self.get_texture(mesh.texture_id) {
-            unsafe {
-                gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vertex_buffer));
-                gl.buffer_data_u8_slice(
-                    glow::ARRAY_BUFFER,
-                    bytemuck::cast_slice(&mesh.vertices),
-                    glow::STREAM_DRAW,
-                );
+        unsafe {
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vertex_buffer));
+            gl.buffer_data_u8_slice(
+                glow::ARRAY_BUFFER,
+                bytemuck::cast_slice(&mesh.vertices),
+                glow::STREAM_DRAW,
+            );
 
-                gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(self.element_array_buffer));
-                gl.buffer_data_u8_slice(
-                    glow::ELEMENT_ARRAY_BUFFER,
-                    bytemuck::cast_slice(&mesh.indices),
-                    glow::STREAM_DRAW,
-                );
+            gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(self.element_array_buffer));
+            gl.buffer_data_u8_slice(
+                glow::ELEMENT_ARRAY_BUFFER,
+                bytemuck::cast_slice(&mesh.indices),
+                glow::STREAM_DRAW,
+            );
 
-                gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            if let Some(u_sdf) = &self.u_sdf {
+                let is_sdf = self.sdf_textures.contains(&texture);
+                gl.uniform_1_i32(Some(u_sdf), is_sdf as i32);
             }
+        }
 
-            // Transform clip rect to physical pixels:
-            let clip_min_x = pixels_per_point * clip_rect.min.x;
-            let clip_min_y = pixels_per_point * clip_rect.min.y;
-            let clip_max_x = pixels_per_point * clip_rect.max.x;
-            let clip_max_y = pixels_per_point * clip_rect.max.y;
+        // Transform clip rect to physical pixels:
+        let clip_min_x = pixels_per_point * clip_rect.min.x;
+        let clip_min_y = pixels_per_point * clip_rect.min.y;
+        let clip_max_x = pixels_per_point * clip_rect.max.x;
+        let clip_max_y = pixels_per_point * clip_rect.max.y;
 
-            // Make sure clip rect can fit within a `u32`:
-            let clip_min_x = clip_min_x.clamp(0.0, size_in_pixels.0 as f32);
-            let clip_min_y = clip_min_y.clamp(0.0, size_in_pixels.1 as f32);
-            let clip_max_x = clip_max_x.clamp(clip_min_x, size_in_pixels.0 as f32);
-            let clip_max_y = clip_max_y.clamp(clip_min_y, size_in_pixels.1 as f32);
+        // Make sure clip rect can fit within a `u32`:
+        let clip_min_x = clip_min_x.clamp(0.0, size_in_pixels.0 as f32);
+        let clip_min_y = clip_min_y.clamp(0.0, size_in_pixels.1 as f32);
+        let clip_max_x = clip_max_x.clamp(clip_min_x, size_in_pixels.0 as f32);
+        let clip_max_y = clip_max_y.clamp(clip_min_y, size_in_pixels.1 as f32);
 
-            let clip_min_x = clip_min_x.round() as i32;
-            let clip_min_y = clip_min_y.round() as i32;
-            let clip_max_x = clip_max_x.round() as i32;
-            let clip_max_y = clip_max_y.round() as i32;
+        let clip_min_x = clip_min_x.round() as i32;
+        let clip_min_y = clip_min_y.round() as i32;
+        let clip_max_x = clip_max_x.round() as i32;
+        let clip_max_y = clip_max_y.round() as i32;
 
-            unsafe {
-                gl.scissor(
-                    clip_min_x,
-                    size_in_pixels.1 as i32 - clip_max_y,
-                    clip_max_x - clip_min_x,
-                    clip_max_y - clip_min_y,
-                );
-                gl.draw_elements(
-                    glow::TRIANGLES,
-                    mesh.indices.len() as i32,
-                    glow::UNSIGNED_INT,
-                    0,
+        unsafe {
+            gl.scissor(
+                clip_min_x,
+                size_in_pixels.1 as i32 - clip_max_y,
+                clip_max_x - clip_min_x,
+                clip_max_y - clip_min_y,
+            );
+            gl.draw_elements(
+                glow::TRIANGLES,
+                mesh.indices.len() as i32,
+                glow::UNSIGNED_INT,
+                0,
+            );
+        }
+    }
+
+    /// Paint meshes into a new texture instead of onto the screen.
+    ///
+    /// The texture is cleared to transparent before painting,
+    /// and is in the same format as the textures egui uploads (premultiplied `sRGBA`),
+    /// so it can be shown with e.g. `ui.image` after registering it
+    /// with [`epi::NativeTexture::register_native_texture`] (or use [`Self::paint_to_texture`]).
+    ///
+    /// Leaves the default framebuffer bound.
+    ///
+    /// # Errors
+    /// If the texture or framebuffer could not be created.
+    pub fn paint_to_native_texture(
+        &mut self,
+        gl: &glow::Context,
+        size_in_pixels: [u32; 2],
+        pixels_per_point: f32,
+        clipped_meshes: Vec<egui::ClippedMesh>,
+    ) -> Result<glow::Texture, String> {
+        self.assert_not_destroyed();
+        let texture = unsafe { gl.create_texture()? };
+        let result = self.paint_into_texture(
+            gl,
+            texture,
+            size_in_pixels,
+            pixels_per_point,
+            clipped_meshes,
+            None,
+        );
+        if result.is_err() {
+            unsafe { gl.delete_texture(texture) };
+        }
+        result.map(|()| texture)
+    }
+
+    /// Like [`Self::paint_to_native_texture`], but also registers the texture so it can be painted by egui.
+    ///
+    /// Free it with [`Self::free_texture`] when no longer needed.
+    ///
+    /// # Errors
+    /// If the texture or framebuffer could not be created.
+    #[cfg(feature = "epi")]
+    pub fn paint_to_texture(
+        &mut self,
+        gl: &glow::Context,
+        size_in_pixels: [u32; 2],
+        pixels_per_point: f32,
+        clipped_meshes: Vec<egui::ClippedMesh>,
+    ) -> Result<egui::TextureId, String> {
+        let texture =
+            self.paint_to_native_texture(gl, size_in_pixels, pixels_per_point, clipped_meshes)?;
+        Ok(epi::NativeTexture::register_native_texture(self, texture))
+    }
+
+    /// Clear and paint again into a texture created with [`Self::paint_to_texture`],
+    /// e.g. to update a cached or live preview.
+    ///
+    /// The texture is resized to `size_in_pixels`.
+    ///
+    /// # Errors
+    /// If there is no such texture, or the framebuffer could not be created.
+    pub fn repaint_texture(
+        &mut self,
+        gl: &glow::Context,
+        texture_id: egui::TextureId,
+        size_in_pixels: [u32; 2],
+        pixels_per_point: f32,
+        clipped_meshes: Vec<egui::ClippedMesh>,
+    ) -> Result<(), String> {
+        self.repaint_texture_with(
+            gl,
+            texture_id,
+            size_in_pixels,
+            pixels_per_point,
+            clipped_meshes,
+            None,
+        )
+    }
+
+    /// `extra_textures` are looked up before our own textures.
+    pub(crate) fn repaint_texture_with(
+        &mut self,
+        gl: &glow::Context,
+        texture_id: egui::TextureId,
+        size_in_pixels: [u32; 2],
+        pixels_per_point: f32,
+        clipped_meshes: Vec<egui::ClippedMesh>,
+        extra_textures: Option<&HashMap<egui::TextureId, glow::Texture>>,
+    ) -> Result<(), String> {
+        self.assert_not_destroyed();
+        let texture = self
+            .get_texture(texture_id)
+            .ok_or_else(|| format!("No texture with id {:?}", texture_id))?;
+        self.paint_into_texture(
+            gl,
+            texture,
+            size_in_pixels,
+            pixels_per_point,
+            clipped_meshes,
+            extra_textures,
+        )
+    }
+
+    /// (Re)allocate `texture` and paint the meshes into it, using a temporary framebuffer.
+    ///
+    /// `extra_textures` are looked up before our own textures.
+    #[allow(clippy::needless_pass_by_value)] // false positive: `glow::Texture` is `Copy`
+    pub(crate) fn paint_into_texture(
+        &mut self,
+        gl: &glow::Context,
+        texture: glow::Texture,
+        [width_in_pixels, height_in_pixels]: [u32; 2],
+        pixels_per_point: f32,
+        mut clipped_meshes: Vec<egui::ClippedMesh>,
+        extra_textures: Option<&HashMap<egui::TextureId, glow::Texture>>,
+    ) -> Result<(), String> {
+        let (internal_format, src_format) = self.texture_format();
+
+        unsafe {
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            for (parameter, value) in [
+                (glow::TEXTURE_MAG_FILTER, self.texture_filter.glow_code()),
+                (glow::TEXTURE_MIN_FILTER, self.texture_filter.glow_code()),
+                (glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE),
+                (glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE),
+            ] {
+                gl.tex_parameter_i32(glow::TEXTURE_2D, parameter, value as i32);
+            }
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                internal_format as _,
+                width_in_pixels as _,
+                height_in_pixels as _,
+                0,
+                src_format,
+                glow::UNSIGNED_BYTE,
+                None,
+            );
+            gl.bind_texture(glow::TEXTURE_2D, None);
+            check_for_gl_error(gl, "render target texture");
+
+            let fbo = gl.create_framebuffer()?;
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(fbo));
+            gl.framebuffer_texture_2d(
+                glow::FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                glow::TEXTURE_2D,
+                Some(texture),
+                0,
+            );
+            let status = gl.check_framebuffer_status(glow::FRAMEBUFFER);
+            if status != glow::FRAMEBUFFER_COMPLETE {
+                gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+                gl.delete_framebuffer(fbo);
+                return Err(format!("Incomplete framebuffer: 0x{:X}", status));
+            }
+
+            gl.disable(glow::SCISSOR_TEST);
+            gl.clear_color(0.0, 0.0, 0.0, 0.0);
+            gl.clear(glow::COLOR_BUFFER_BIT);
+
+            // OpenGL puts the first row of a texture at the bottom of the framebuffer,
+            // but egui expects the first row at the top, so we paint upside-down:
+            let height_in_points = height_in_pixels as f32 / pixels_per_point;
+            for egui::ClippedMesh(clip_rect, mesh) in &mut clipped_meshes {
+                for vertex in &mut mesh.vertices {
+                    vertex.pos.y = height_in_points - vertex.pos.y;
+                }
+                *clip_rect = Rect::from_x_y_ranges(
+                    clip_rect.x_range(),
+                    (height_in_points - clip_rect.max.y)..=(height_in_points - clip_rect.min.y),
                 );
             }
+
+            let size_in_pixels =
+                self.prepare_painting([width_in_pixels, height_in_pixels], gl, pixels_per_point);
+            for egui::ClippedMesh(clip_rect, mesh) in clipped_meshes {
+                let mesh_texture = extra_textures
+                    .and_then(|textures| textures.get(&mesh.texture_id).copied())
+                    .or_else(|| self.get_texture(mesh.texture_id));
+                if let Some(mesh_texture) = mesh_texture {
+                    self.paint_mesh(
+                        gl,
+                        size_in_pixels,
+                        pixels_per_point,
+                        clip_rect,
+                        &mesh,
+                        mesh_texture,
+                    );
+                }
+            }
+
+            self.vertex_array.unbind_vertex_array(gl);
+            gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, None);
+            gl.bind_texture(glow::TEXTURE_2D, None);
+            gl.use_program(None);
+            gl.disable(glow::SCISSOR_TEST);
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            gl.delete_framebuffer(fbo);
+
+            check_for_gl_error(gl, "painting to texture");
         }
+        Ok(())
     }
 
     // Set the filter to be used for any subsequent textures loaded via
@@ -404,10 +642,30 @@ impl Painter {
             .textures
             .entry(tex_id)
             .or_insert_with(|| unsafe { gl.create_texture().unwrap() });
+        self.upload_image_delta(gl, glow_texture, delta);
+    }
+
+    /// Upload an image (or a part of it) to the given texture.
+    #[allow(clippy::needless_pass_by_value)] // false positive: `glow::Texture` is `Copy`
+    pub(crate) fn upload_image_delta(
+        &mut self,
+        gl: &glow::Context,
+        glow_texture: glow::Texture,
+        delta: &egui::epaint::ImageDelta,
+    ) {
         unsafe {
             gl.bind_texture(glow::TEXTURE_2D, Some(glow_texture));
         }
 
+        if delta.is_whole() {
+            let is_sdf = matches!(delta.image, egui::ImageData::Sdf(_)) && self.u_sdf.is_some();
+            if is_sdf {
+                self.sdf_textures.insert(glow_texture);
+            } else {
+                self.sdf_textures.remove(&glow_texture);
+            }
+        }
+
         match &delta.image {
             egui::ImageData::Color(image) => {
                 assert_eq!(
@@ -437,6 +695,34 @@ impl Painter {
                     .flat_map(|a| a.to_array())
                     .collect();
 
+                self.upload_texture_srgb(gl, delta.pos, image.size, &data);
+            }
+            egui::ImageData::Sdf(image) => {
+                assert_eq!(
+                    image.width() * image.height(),
+                    image.pixels.len(),
+                    "Mismatch between texture size and texel count"
+                );
+
+                let data: Vec<u8> = if self.u_sdf.is_some() {
+                    // The shader reads the distance from the alpha channel, which is not gamma encoded:
+                    image
+                        .pixels
+                        .iter()
+                        .flat_map(|&distance| [255, 255, 255, distance])
+                        .collect()
+                } else {
+                    let gamma = if self.is_embedded && self.post_process.is_none() {
+                        1.0 / 2.2
+                    } else {
+                        1.0
+                    };
+                    image
+                        .srgba_pixels(gamma)
+                        .flat_map(|a| a.to_array())
+                        .collect()
+                };
+
                 self.upload_texture_srgb(gl, delta.pos, image.size, &data);
             }
         };
@@ -475,16 +761,7 @@ impl Painter {
             );
             check_for_gl_error(gl, "tex_parameter");
 
-            let (internal_format, src_format) = if self.is_webgl_1 {
-                let format = if self.srgb_support {
-                    glow::SRGB_ALPHA
-                } else {
-                    glow::RGBA
-                };
-                (format, format)
-            } else {
-                (glow::SRGB8_ALPHA8, glow::RGBA)
-            };
+            let (internal_format, src_format) = self.texture_format();
 
             gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
 
@@ -520,8 +797,23 @@ impl Painter {
         }
     }
 
+    /// Internal format and source format of our textures.
+    fn texture_format(&self) -> (u32, u32) {
+        if self.is_webgl_1 {
+            let format = if self.srgb_support {
+                glow::SRGB_ALPHA
+            } else {
+                glow::RGBA
+            };
+            (format, format)
+        } else {
+            (glow::SRGB8_ALPHA8, glow::RGBA)
+        }
+    }
+
     pub fn free_texture(&mut self, gl: &glow::Context, tex_id: egui::TextureId) {
         if let Some(old_tex) = self.textures.remove(&tex_id) {
+            self.sdf_textures.remove(&old_tex);
             unsafe { gl.delete_texture(old_tex) };
         }
     }
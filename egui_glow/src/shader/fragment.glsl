@@ -16,11 +16,30 @@ uniform sampler2D u_sampler;
     varying vec2 v_tc;
 #endif
 
+#ifdef SDF_SUPPORTED
+    // Is the texture a signed distance field, with the distance in alpha?
+    uniform bool u_sdf;
+
+    // Linear premultiplied white, covering as much of the pixel as is inside the edge.
+    vec4 coverage_from_sdf(vec4 texel) {
+        float distance = texel.a;
+        // How much the distance changes over one pixel, so the edge stays one pixel wide at any scale:
+        float edge_width = max(fwidth(distance), 0.0001);
+        return vec4(clamp((distance - 0.5) / edge_width + 0.5, 0.0, 1.0));
+    }
+#endif
+
 #ifdef SRGB_SUPPORTED
     void main() {
         // The texture sampler is sRGB aware, and OpenGL already expects linear rgba output
         // so no need for any sRGB conversions here:
-        gl_FragColor = v_rgba * texture2D(u_sampler, v_tc);
+        vec4 texture_rgba = texture2D(u_sampler, v_tc);
+    #ifdef SDF_SUPPORTED
+        if (u_sdf) {
+            texture_rgba = coverage_from_sdf(texture_rgba);
+        }
+    #endif
+        gl_FragColor = v_rgba * texture_rgba;
     }
 #else
     // 0-255 sRGB  from  0-1 linear
@@ -49,7 +68,13 @@ uniform sampler2D u_sampler;
 
     void main() {
         // We must decode the colors, since WebGL1 doesn't come with sRGBA textures:
-        vec4 texture_rgba = linear_from_srgba(texture2D(u_sampler, v_tc) * 255.0);
+        vec4 texel = texture2D(u_sampler, v_tc);
+        vec4 texture_rgba = linear_from_srgba(texel * 255.0);
+    #ifdef SDF_SUPPORTED
+        if (u_sdf) {
+            texture_rgba = coverage_from_sdf(texel);
+        }
+    #endif
         /// Multiply vertex color with texture color (in linear space).
         gl_FragColor = v_rgba * texture_rgba;
 
@@ -300,6 +300,16 @@ impl Painter {
             epaint::image::ImageData::Alpha(image) => {
                 let gamma = 1.0;
 
+                let data: Vec<u8> = image
+                    .srgba_pixels(gamma)
+                    .flat_map(|a| a.to_array())
+                    .collect();
+                (data, image.width() as u32, image.height() as u32)
+            }
+            epaint::image::ImageData::Sdf(image) => {
+                // todo: evaluate the distances in the shader
+                let gamma = 1.0;
+
                 let data: Vec<u8> = image
                     .srgba_pixels(gamma)
                     .flat_map(|a| a.to_array())
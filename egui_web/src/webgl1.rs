@@ -328,6 +328,19 @@ impl crate::Painter for WebGlPainter {
                     .collect();
                 self.set_texture_rgba(tex_id, delta.pos, image.size, &data);
             }
+            egui::ImageData::Sdf(image) => {
+                // The distances are converted to coverage on the CPU. Use `egui_glow` for crisp scaled text.
+                let gamma = if self.post_process.is_none() {
+                    1.0 / 2.2 // HACK due to non-linear framebuffer blending.
+                } else {
+                    1.0 // post process enables linear blending
+                };
+                let data: Vec<u8> = image
+                    .srgba_pixels(gamma)
+                    .flat_map(|a| a.to_array())
+                    .collect();
+                self.set_texture_rgba(tex_id, delta.pos, image.size, &data);
+            }
         };
     }
 
@@ -308,6 +308,15 @@ impl crate::Painter for WebGl2Painter {
                     .collect();
                 self.set_texture_rgba(tex_id, delta.pos, image.size, &data);
             }
+            egui::ImageData::Sdf(image) => {
+                // The distances are converted to coverage on the CPU. Use `egui_glow` for crisp scaled text.
+                let gamma = 1.0;
+                let data: Vec<u8> = image
+                    .srgba_pixels(gamma)
+                    .flat_map(|a| a.to_array())
+                    .collect();
+                self.set_texture_rgba(tex_id, delta.pos, image.size, &data);
+            }
         };
     }
 
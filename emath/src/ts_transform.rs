@@ -0,0 +1,173 @@
+use crate::*;
+
+/// Scales and rotates around the origin, then translates.
+///
+/// This is a similarity transform (a 2D affine transform without shearing or non-uniform scaling),
+/// so circles stay circles and text stays undistorted.
+///
+/// ```
+/// # use emath::*;
+/// let transform = TSTransform::from_translation(vec2(10.0, 0.0)) * TSTransform::from_scaling(2.0);
+/// assert_eq!(transform * pos2(1.0, 1.0), pos2(12.0, 2.0));
+/// assert_eq!(transform.inverse() * pos2(12.0, 2.0), pos2(1.0, 1.0));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct TSTransform {
+    /// Rotation, applied after scaling. Always normalized (unit length).
+    pub rotation: Rot2,
+
+    /// Uniform scale factor, applied first.
+    pub scaling: f32,
+
+    /// Translation, applied last.
+    pub translation: Vec2,
+}
+
+impl Default for TSTransform {
+    #[inline]
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl TSTransform {
+    /// Leaves everything where it is.
+    pub const IDENTITY: Self = Self {
+        rotation: Rot2::IDENTITY,
+        scaling: 1.0,
+        translation: Vec2::ZERO,
+    };
+
+    /// Scale by `scaling`, then translate by `translation`.
+    #[inline]
+    pub fn new(translation: Vec2, scaling: f32) -> Self {
+        Self {
+            rotation: Rot2::IDENTITY,
+            scaling,
+            translation,
+        }
+    }
+
+    #[inline]
+    pub fn from_translation(translation: Vec2) -> Self {
+        Self::new(translation, 1.0)
+    }
+
+    #[inline]
+    pub fn from_scaling(scaling: f32) -> Self {
+        Self::new(Vec2::ZERO, scaling)
+    }
+
+    /// Rotate clockwise around the origin by this many radians.
+    #[inline]
+    pub fn from_rotation(angle: f32) -> Self {
+        Self {
+            rotation: Rot2::from_angle(angle),
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Clockwise rotation in radians.
+    #[inline]
+    pub fn angle(&self) -> f32 {
+        self.rotation.angle()
+    }
+
+    #[inline]
+    pub fn is_identity(&self) -> bool {
+        *self == Self::IDENTITY
+    }
+
+    /// Does this transform keep axis-aligned rectangles axis-aligned?
+    ///
+    /// True when there is no rotation (or a rotation by a multiple of 360°).
+    #[inline]
+    pub fn is_axis_aligned(&self) -> bool {
+        self.rotation == Rot2::IDENTITY
+    }
+
+    /// Undoes this transform.
+    #[must_use]
+    pub fn inverse(&self) -> Self {
+        let rotation = self.rotation.inverse();
+        let scaling = 1.0 / self.scaling;
+        Self {
+            rotation,
+            scaling,
+            translation: -(scaling * (rotation * self.translation)),
+        }
+    }
+
+    /// Transform a position.
+    #[inline]
+    pub fn mul_pos(&self, pos: Pos2) -> Pos2 {
+        self.mul_vec(pos.to_vec2()).to_pos2() + self.translation
+    }
+
+    /// Transform a direction or a delta. The translation is ignored.
+    #[inline]
+    pub fn mul_vec(&self, vec: Vec2) -> Vec2 {
+        self.rotation * (self.scaling * vec)
+    }
+
+    /// The smallest axis-aligned rectangle containing the transformed rectangle.
+    ///
+    /// Without rotation this is exact.
+    pub fn mul_rect(&self, rect: Rect) -> Rect {
+        if !rect.is_finite() {
+            return rect;
+        }
+        let corners = [
+            self.mul_pos(rect.left_top()),
+            self.mul_pos(rect.right_top()),
+            self.mul_pos(rect.left_bottom()),
+            self.mul_pos(rect.right_bottom()),
+        ];
+        Rect::from_points(&corners)
+    }
+}
+
+/// Transform a position.
+impl std::ops::Mul<Pos2> for TSTransform {
+    type Output = Pos2;
+
+    #[inline]
+    fn mul(self, pos: Pos2) -> Pos2 {
+        self.mul_pos(pos)
+    }
+}
+
+/// Combine two transforms: `(a * b) * p == a * (b * p)`.
+impl std::ops::Mul<TSTransform> for TSTransform {
+    type Output = TSTransform;
+
+    fn mul(self, rhs: TSTransform) -> TSTransform {
+        TSTransform {
+            rotation: (self.rotation * rhs.rotation).normalized(),
+            scaling: self.scaling * rhs.scaling,
+            translation: self.mul_vec(rhs.translation) + self.translation,
+        }
+    }
+}
+
+#[test]
+fn test_ts_transform() {
+    let transform = TSTransform::from_translation(vec2(5.0, -3.0))
+        * TSTransform::from_rotation(0.3)
+        * TSTransform::from_scaling(2.5);
+    let inverse = transform.inverse();
+    for pos in [pos2(0.0, 0.0), pos2(1.0, 2.0), pos2(-40.0, 7.5)] {
+        let back = inverse * (transform * pos);
+        assert!(back.distance(pos) < 1e-4);
+        let back = (transform * inverse) * pos;
+        assert!(back.distance(pos) < 1e-4);
+    }
+
+    let rect = Rect::from_min_max(pos2(1.0, 1.0), pos2(2.0, 3.0));
+    let transformed = TSTransform::new(vec2(10.0, 0.0), 2.0).mul_rect(rect);
+    assert_eq!(
+        transformed,
+        Rect::from_min_max(pos2(12.0, 2.0), pos2(14.0, 6.0))
+    );
+}
@@ -0,0 +1,236 @@
+//! Quadratic and cubic Bézier curves.
+
+use crate::{Color32, PathShape, Shape, Stroke};
+use emath::*;
+
+/// Never flatten a curve into more segments than this.
+const MAX_SEGMENTS: f32 = 1024.0;
+
+// ----------------------------------------------------------------------------
+
+/// A quadratic Bézier curve with an optional fill and stroke.
+///
+/// `points[0]` is the start of the curve, `points[2]` the end and `points[1]` the control point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct QuadraticBezierShape {
+    pub points: [Pos2; 3],
+    /// If true, connect the end of the curve back to the start with a straight line.
+    /// This is required if `fill != TRANSPARENT`.
+    pub closed: bool,
+    /// Fill is only supported for convex areas.
+    pub fill: Color32,
+    pub stroke: Stroke,
+}
+
+impl QuadraticBezierShape {
+    #[inline]
+    pub fn from_points_stroke(
+        points: [Pos2; 3],
+        closed: bool,
+        fill: impl Into<Color32>,
+        stroke: impl Into<Stroke>,
+    ) -> Self {
+        Self {
+            points,
+            closed,
+            fill: fill.into(),
+            stroke: stroke.into(),
+        }
+    }
+
+    /// The point on the curve at `t` (in `0..=1`).
+    pub fn sample(&self, t: f32) -> Pos2 {
+        let [p0, p1, p2] = self.points;
+        let s = 1.0 - t;
+        pos2(
+            s * s * p0.x + 2.0 * s * t * p1.x + t * t * p2.x,
+            s * s * p0.y + 2.0 * s * t * p1.y + t * t * p2.y,
+        )
+    }
+
+    /// Screen-space bounding rectangle.
+    ///
+    /// The curve never leaves the hull of its control points, so this may be a bit too big.
+    #[inline]
+    pub fn bounding_rect(&self) -> Rect {
+        Rect::from_points(&self.points).expand(self.stroke.width)
+    }
+
+    /// Approximate the curve with straight lines,
+    /// so that no point on the curve is further than `tolerance` from the lines.
+    ///
+    /// The first and last points of the result are the end points of the curve.
+    pub fn flatten(&self, tolerance: f32) -> Vec<Pos2> {
+        let [p0, p1, p2] = self.points;
+        // The second derivative is constant, 2 * (p0 - 2 p1 + p2),
+        // and the error of a chord spanning `h` in `t` is at most |B''| h² / 8:
+        let dd = (p0.to_vec2() - 2.0 * p1.to_vec2() + p2.to_vec2()).length();
+        let num_segments = num_segments(dd / 4.0, tolerance);
+        (0..=num_segments)
+            .map(|i| self.sample(i as f32 / num_segments as f32))
+            .collect()
+    }
+
+    /// Convert to a [`PathShape`] by flattening the curve with the given `tolerance`.
+    pub fn to_path_shape(&self, tolerance: f32) -> PathShape {
+        PathShape {
+            points: self.flatten(tolerance),
+            closed: self.closed,
+            fill: self.fill,
+            fill_gradient: None,
+            stroke: self.stroke,
+        }
+    }
+}
+
+impl From<QuadraticBezierShape> for Shape {
+    #[inline(always)]
+    fn from(shape: QuadraticBezierShape) -> Self {
+        Self::QuadraticBezier(shape)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// A cubic Bézier curve with an optional fill and stroke.
+///
+/// `points[0]` is the start of the curve, `points[3]` the end,
+/// and `points[1]` and `points[2]` are the control points.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct CubicBezierShape {
+    pub points: [Pos2; 4],
+    /// If true, connect the end of the curve back to the start with a straight line.
+    /// This is required if `fill != TRANSPARENT`.
+    pub closed: bool,
+    /// Fill is only supported for convex areas.
+    pub fill: Color32,
+    pub stroke: Stroke,
+}
+
+impl CubicBezierShape {
+    #[inline]
+    pub fn from_points_stroke(
+        points: [Pos2; 4],
+        closed: bool,
+        fill: impl Into<Color32>,
+        stroke: impl Into<Stroke>,
+    ) -> Self {
+        Self {
+            points,
+            closed,
+            fill: fill.into(),
+            stroke: stroke.into(),
+        }
+    }
+
+    /// The point on the curve at `t` (in `0..=1`).
+    #[allow(clippy::many_single_char_names)]
+    pub fn sample(&self, t: f32) -> Pos2 {
+        let [p0, p1, p2, p3] = self.points;
+        let s = 1.0 - t;
+        let (a, b, c, d) = (s * s * s, 3.0 * s * s * t, 3.0 * s * t * t, t * t * t);
+        pos2(
+            a * p0.x + b * p1.x + c * p2.x + d * p3.x,
+            a * p0.y + b * p1.y + c * p2.y + d * p3.y,
+        )
+    }
+
+    /// Screen-space bounding rectangle.
+    ///
+    /// The curve never leaves the hull of its control points, so this may be a bit too big.
+    #[inline]
+    pub fn bounding_rect(&self) -> Rect {
+        Rect::from_points(&self.points).expand(self.stroke.width)
+    }
+
+    /// Approximate the curve with straight lines,
+    /// so that no point on the curve is further than `tolerance` from the lines.
+    ///
+    /// The first and last points of the result are the end points of the curve.
+    pub fn flatten(&self, tolerance: f32) -> Vec<Pos2> {
+        let [p0, p1, p2, p3] = self.points;
+        // |B''| is at most 6 * max(|p0 - 2 p1 + p2|, |p1 - 2 p2 + p3|),
+        // and the error of a chord spanning `h` in `t` is at most |B''| h² / 8:
+        let dd0 = (p0.to_vec2() - 2.0 * p1.to_vec2() + p2.to_vec2()).length();
+        let dd1 = (p1.to_vec2() - 2.0 * p2.to_vec2() + p3.to_vec2()).length();
+        let num_segments = num_segments(0.75 * dd0.max(dd1), tolerance);
+        (0..=num_segments)
+            .map(|i| self.sample(i as f32 / num_segments as f32))
+            .collect()
+    }
+
+    /// Convert to a [`PathShape`] by flattening the curve with the given `tolerance`.
+    pub fn to_path_shape(&self, tolerance: f32) -> PathShape {
+        PathShape {
+            points: self.flatten(tolerance),
+            closed: self.closed,
+            fill: self.fill,
+            fill_gradient: None,
+            stroke: self.stroke,
+        }
+    }
+}
+
+impl From<CubicBezierShape> for Shape {
+    #[inline(always)]
+    fn from(shape: CubicBezierShape) -> Self {
+        Self::CubicBezier(shape)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// How many equally long (in `t`) segments we need so that the error `error_scale / n²`
+/// is at most `tolerance`.
+fn num_segments(error_scale: f32, tolerance: f32) -> usize {
+    let n = (error_scale / tolerance.max(1e-6)).sqrt().ceil();
+    if n.is_finite() {
+        n.at_least(1.0).at_most(MAX_SEGMENTS) as usize
+    } else {
+        1
+    }
+}
+
+#[test]
+fn test_flatten_within_tolerance() {
+    let curve = CubicBezierShape::from_points_stroke(
+        [
+            pos2(0.0, 0.0),
+            pos2(100.0, 300.0),
+            pos2(200.0, -300.0),
+            pos2(300.0, 0.0),
+        ],
+        false,
+        Color32::TRANSPARENT,
+        Stroke::none(),
+    );
+    let tolerance = 0.1;
+    let points = curve.flatten(tolerance);
+    assert_eq!(points.first(), Some(&curve.points[0]));
+    assert_eq!(points.last(), Some(&curve.points[3]));
+
+    // Check the distance from densely sampled curve points to the polyline:
+    let num_segments = points.len() - 1;
+    for i in 0..num_segments {
+        let (start, end) = (points[i], points[i + 1]);
+        let segment = end - start;
+        for j in 0..=10 {
+            let t = (i as f32 + j as f32 / 10.0) / num_segments as f32;
+            let sample = curve.sample(t);
+            let along = (sample - start).x * segment.x + (sample - start).y * segment.y;
+            let closest = start + (along / segment.length_sq()).clamp(0.0, 1.0) * segment;
+            assert!(sample.distance(closest) <= tolerance + 1e-3);
+        }
+    }
+
+    // Straight lines need no subdivision:
+    let line = QuadraticBezierShape::from_points_stroke(
+        [pos2(0.0, 0.0), pos2(5.0, 5.0), pos2(10.0, 10.0)],
+        false,
+        Color32::TRANSPARENT,
+        Stroke::none(),
+    );
+    assert_eq!(line.flatten(tolerance).len(), 2);
+}
@@ -0,0 +1,157 @@
+//! Color gradients, used for filling shapes.
+
+use crate::{Color32, Rgba};
+use emath::*;
+
+/// The geometry of a [`Gradient`]: how a position maps to a gradient parameter `t`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum GradientKind {
+    /// `t` goes from `0` at `from` to `1` at `to`,
+    /// and is constant along lines perpendicular to `from → to`.
+    Linear { from: Pos2, to: Pos2 },
+
+    /// `t` goes from `0` at `center` to `1` at a distance of `radius` from it.
+    Radial { center: Pos2, radius: f32 },
+}
+
+/// A smooth blend between colors, used to fill a [`crate::Shape`].
+///
+/// The positions are in the same coordinate system as the shape (screen-space points),
+/// so a gradient that should follow a shape must be moved along with it.
+///
+/// ```
+/// # use epaint::*;
+/// let gradient = Gradient::linear(
+///     pos2(0.0, 0.0),
+///     pos2(100.0, 0.0),
+///     [(0.0, Color32::RED), (0.5, Color32::WHITE), (1.0, Color32::BLUE)],
+/// );
+/// assert_eq!(gradient.color_at(pos2(-10.0, 5.0)), Color32::RED);
+/// assert_eq!(gradient.color_at(pos2(50.0, 42.0)), Color32::WHITE);
+/// assert_eq!(gradient.color_at(pos2(100.0, 0.0)), Color32::BLUE);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Gradient {
+    pub kind: GradientKind,
+
+    /// The color stops as `(t, color)`, sorted by `t`.
+    ///
+    /// Before the first stop the color is that of the first stop,
+    /// and after the last stop it is that of the last stop.
+    /// Between stops the colors are interpolated in linear space.
+    pub stops: Vec<(f32, Color32)>,
+}
+
+impl Gradient {
+    /// A gradient going from `from` to `to`.
+    ///
+    /// `stops` are `(t, color)` pairs, where `t = 0` at `from` and `t = 1` at `to`.
+    pub fn linear(from: Pos2, to: Pos2, stops: impl IntoIterator<Item = (f32, Color32)>) -> Self {
+        Self::new(GradientKind::Linear { from, to }, stops)
+    }
+
+    /// A gradient going outwards from `center`.
+    ///
+    /// `stops` are `(t, color)` pairs, where `t = 0` at `center` and `t = 1` at `radius`.
+    pub fn radial(
+        center: Pos2,
+        radius: f32,
+        stops: impl IntoIterator<Item = (f32, Color32)>,
+    ) -> Self {
+        Self::new(GradientKind::Radial { center, radius }, stops)
+    }
+
+    /// The stops will be sorted by `t`.
+    pub fn new(kind: GradientKind, stops: impl IntoIterator<Item = (f32, Color32)>) -> Self {
+        let mut stops: Vec<(f32, Color32)> = stops.into_iter().collect();
+        stops.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        Self { kind, stops }
+    }
+
+    /// Are all the colors transparent?
+    pub fn is_transparent(&self) -> bool {
+        self.stops
+            .iter()
+            .all(|(_, color)| *color == Color32::TRANSPARENT)
+    }
+
+    /// Move the gradient by this many points, in-place.
+    pub fn translate(&mut self, delta: Vec2) {
+        match &mut self.kind {
+            GradientKind::Linear { from, to } => {
+                *from += delta;
+                *to += delta;
+            }
+            GradientKind::Radial { center, .. } => {
+                *center += delta;
+            }
+        }
+    }
+
+    /// Scale, rotate and move the gradient, in-place.
+    pub fn transform(&mut self, transform: TSTransform) {
+        match &mut self.kind {
+            GradientKind::Linear { from, to } => {
+                *from = transform * *from;
+                *to = transform * *to;
+            }
+            GradientKind::Radial { center, radius } => {
+                *center = transform * *center;
+                *radius *= transform.scaling;
+            }
+        }
+    }
+
+    /// The gradient parameter at the given position. Not clamped.
+    pub fn t_at(&self, pos: Pos2) -> f32 {
+        match self.kind {
+            GradientKind::Linear { from, to } => {
+                let dir = to - from;
+                let length_sq = dir.length_sq();
+                if length_sq > 0.0 {
+                    let d = pos - from;
+                    (d.x * dir.x + d.y * dir.y) / length_sq
+                } else {
+                    0.0
+                }
+            }
+            GradientKind::Radial { center, radius } => {
+                if radius > 0.0 {
+                    pos.distance(center) / radius
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+
+    /// The color at the gradient parameter `t`.
+    pub fn color_at_t(&self, t: f32) -> Color32 {
+        let stops = &self.stops;
+        match stops.iter().position(|(stop_t, _)| t < *stop_t) {
+            None => stops
+                .last()
+                .map_or(Color32::TRANSPARENT, |(_, color)| *color),
+            Some(0) => stops[0].1,
+            Some(i) => {
+                let (t0, c0) = stops[i - 1];
+                let (t1, c1) = stops[i];
+                let f = remap_clamp(t, t0..=t1, 0.0..=1.0);
+                let c = Rgba::from(c0) * (1.0 - f) + Rgba::from(c1) * f;
+                c.into()
+            }
+        }
+    }
+
+    /// The color at the given position.
+    pub fn color_at(&self, pos: Pos2) -> Color32 {
+        self.color_at_t(self.t_at(pos))
+    }
+
+    /// Is `t` a linear function of position for this gradient?
+    pub(crate) fn is_linear(&self) -> bool {
+        matches!(self.kind, GradientKind::Linear { .. })
+    }
+}
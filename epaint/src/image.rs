@@ -6,7 +6,7 @@ use crate::Color32;
 ///
 /// In order to paint the image on screen, you first need to convert it to
 ///
-/// See also: [`ColorImage`], [`AlphaImage`].
+/// See also: [`ColorImage`], [`AlphaImage`], [`SdfImage`].
 #[derive(Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum ImageData {
@@ -14,6 +14,8 @@ pub enum ImageData {
     Color(ColorImage),
     /// Used for the font texture.
     Alpha(AlphaImage),
+    /// Used for the font texture when the glyphs are signed distance fields.
+    Sdf(SdfImage),
 }
 
 impl ImageData {
@@ -21,6 +23,7 @@ impl ImageData {
         match self {
             Self::Color(image) => image.size,
             Self::Alpha(image) => image.size,
+            Self::Sdf(image) => image.size,
         }
     }
 
@@ -35,7 +38,7 @@ impl ImageData {
     pub fn bytes_per_pixel(&self) -> usize {
         match self {
             Self::Color(_) => 4,
-            Self::Alpha(_) => 1,
+            Self::Alpha(_) | Self::Sdf(_) => 1,
         }
     }
 }
@@ -259,6 +262,88 @@ impl From<AlphaImage> for ImageData {
 
 // ----------------------------------------------------------------------------
 
+/// A signed distance field: an 8-bit image where each texel is the distance to the closest edge of a shape.
+///
+/// Used for the font texture when [`crate::text::FontDefinitions::sdf`] is set.
+/// A value of `128` is on the edge, `255` is [`Self::spread`] texels inside the shape,
+/// and `0` is [`Self::spread`] texels outside of it.
+///
+/// Since the distance can be interpolated, a glyph in a signed distance field
+/// can be painted crisply at any scale. See [`Self::coverage`].
+#[derive(Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct SdfImage {
+    /// width, height
+    pub size: [usize; 2],
+    /// The encoded distances, one byte per pixel.
+    pub pixels: Vec<u8>,
+    /// The largest distance that can be represented, in texels.
+    pub spread: f32,
+}
+
+impl SdfImage {
+    pub fn new(size: [usize; 2], spread: f32) -> Self {
+        Self {
+            size,
+            pixels: vec![0; size[0] * size[1]],
+            spread,
+        }
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.size[0]
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.size[1]
+    }
+
+    /// How much of a pixel is covered by the shape.
+    ///
+    /// `value` is a (possibly interpolated) texel in the range `0..=1`.
+    /// `texels_per_pixel` is how many texels wide a pixel is where the image is painted.
+    #[inline]
+    pub fn coverage(value: f32, spread: f32, texels_per_pixel: f32) -> f32 {
+        let distance_in_texels = (value - 0.5) * 2.0 * spread;
+        let distance_in_pixels = distance_in_texels / texels_per_pixel.max(1e-6);
+        (distance_in_pixels + 0.5).clamp(0.0, 1.0)
+    }
+
+    /// Converts the distances to coverage, and returns it as `sRGBA` premultiplied pixels,
+    /// like [`AlphaImage::srgba_pixels`].
+    ///
+    /// This is for painters that can't evaluate the distances when painting.
+    /// The edges are smoothed over half the [`Self::spread`],
+    /// which looks right when the image is painted at around a third of its size.
+    pub fn srgba_pixels(
+        &'_ self,
+        gamma: f32,
+    ) -> impl ExactSizeIterator<Item = super::Color32> + '_ {
+        let texels_per_pixel = 0.5 * self.spread;
+        let srgba_from_distance_lut: Vec<Color32> = (0..=255)
+            .map(|value| {
+                let coverage = Self::coverage(value as f32 / 255.0, self.spread, texels_per_pixel);
+                super::Rgba::from_white_alpha(coverage.powf(gamma)).into()
+            })
+            .collect();
+
+        self.pixels
+            .iter()
+            .map(move |&value| srgba_from_distance_lut[value as usize])
+    }
+}
+
+impl From<SdfImage> for ImageData {
+    #[inline(always)]
+    fn from(image: SdfImage) -> Self {
+        Self::Sdf(image)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
 /// A change to an image.
 ///
 /// Either a whole new image,
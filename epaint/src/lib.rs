@@ -87,10 +87,13 @@
 #![allow(clippy::float_cmp)]
 #![allow(clippy::manual_range_contains)]
 
+mod bezier;
 pub mod color;
+pub mod gradient;
 pub mod image;
 mod mesh;
 pub mod mutex;
+pub mod raster;
 mod shadow;
 mod shape;
 pub mod shape_transform;
@@ -104,13 +107,15 @@ pub mod textures;
 pub mod util;
 
 pub use {
+    bezier::{CubicBezierShape, QuadraticBezierShape},
     color::{Color32, Rgba},
-    image::{AlphaImage, ColorImage, ImageData, ImageDelta},
+    gradient::{Gradient, GradientKind},
+    image::{AlphaImage, ColorImage, ImageData, ImageDelta, SdfImage},
     mesh::{Mesh, Mesh16, Vertex},
     shadow::Shadow,
-    shape::{CircleShape, PathShape, RectShape, Shape, TextShape},
+    shape::{CircleShape, PathShape, RectShape, Rounding, Shape, TextShape},
     stats::PaintStats,
-    stroke::Stroke,
+    stroke::{SideStrokes, Stroke, StrokeKind},
     tessellator::{tessellate_shapes, TessellationOptions, Tessellator},
     text::{FontFamily, FontId, Fonts, Galley},
     texture_atlas::TextureAtlas,
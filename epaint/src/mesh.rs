@@ -242,6 +242,13 @@ impl Mesh {
             v.pos += delta;
         }
     }
+
+    /// Transform the position of each vertex, in-place.
+    pub fn transform(&mut self, transform: TSTransform) {
+        for v in &mut self.vertices {
+            v.pos = transform * v.pos;
+        }
+    }
 }
 
 // ----------------------------------------------------------------------------
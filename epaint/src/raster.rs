@@ -0,0 +1,489 @@
+//! A software rasterizer that paints [`ClippedMesh`]:es into a [`ColorImage`].
+//!
+//! Useful when there is no GPU around, e.g. for screenshot tests, thumbnails
+//! or server-side rendering.
+//!
+//! ```
+//! use epaint::{raster::Rasterizer, *};
+//!
+//! let mut rasterizer = Rasterizer::default();
+//!
+//! // The font texture (or any other texture you use) must be uploaded first.
+//! // Its top left pixel must be white (see [`crate::WHITE_UV`]):
+//! let mut font_image = AlphaImage::new([16, 16]);
+//! font_image[(0, 0)] = 255;
+//! rasterizer.set_texture(TextureId::default(), &ImageDelta::full(font_image));
+//!
+//! let mut mesh = Mesh::default();
+//! mesh.add_colored_rect(Rect::from_min_max(pos2(2.0, 2.0), pos2(6.0, 6.0)), Color32::RED);
+//! let clip_rect = Rect::EVERYTHING;
+//!
+//! let image = rasterizer.paint_meshes([8, 8], 1.0, Rgba::BLACK, &[ClippedMesh(clip_rect, mesh)]);
+//! assert_eq!(image[(1, 1)], Color32::BLACK);
+//! assert_eq!(image[(3, 3)], Color32::RED);
+//! ```
+
+use crate::{
+    color::{linear_f32_from_gamma_u8, linear_f32_from_linear_u8},
+    textures::TexturesDelta,
+    ClippedMesh, Color32, ColorImage, ImageData, ImageDelta, Mesh, Rgba, SdfImage, TextureId,
+};
+use ahash::AHashMap;
+use emath::*;
+
+/// How to sample textures that are magnified or minified.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextureFilter {
+    /// Bilinear interpolation between the four closest texels.
+    Linear,
+
+    /// Pick the closest texel.
+    Nearest,
+}
+
+impl Default for TextureFilter {
+    fn default() -> Self {
+        Self::Linear
+    }
+}
+
+/// A texture in RAM, stored as premultiplied linear RGBA.
+struct Texture {
+    size: [usize; 2],
+    pixels: Vec<Rgba>,
+    /// Set for signed distance fields, where the distance is stored in all channels of [`Self::pixels`].
+    sdf_spread: Option<f32>,
+}
+
+impl Texture {
+    fn texel(&self, x: isize, y: isize) -> Rgba {
+        // Clamp to edge, like the GPU painters do:
+        let x = x.clamp(0, self.size[0] as isize - 1) as usize;
+        let y = y.clamp(0, self.size[1] as isize - 1) as usize;
+        self.pixels[y * self.size[0] + x]
+    }
+
+    fn sample(&self, filter: TextureFilter, uv: Pos2) -> Rgba {
+        let x = uv.x * self.size[0] as f32;
+        let y = uv.y * self.size[1] as f32;
+        match filter {
+            TextureFilter::Nearest => self.texel(x.floor() as isize, y.floor() as isize),
+            TextureFilter::Linear => {
+                // Texel centers are at half-integer coordinates:
+                let x = x - 0.5;
+                let y = y - 0.5;
+                let x0 = x.floor();
+                let y0 = y.floor();
+                let tx = x - x0;
+                let ty = y - y0;
+                let (x0, y0) = (x0 as isize, y0 as isize);
+                let top = lerp_rgba(self.texel(x0, y0), self.texel(x0 + 1, y0), tx);
+                let bottom = lerp_rgba(self.texel(x0, y0 + 1), self.texel(x0 + 1, y0 + 1), tx);
+                lerp_rgba(top, bottom, ty)
+            }
+        }
+    }
+}
+
+fn lerp_rgba(a: Rgba, b: Rgba, t: f32) -> Rgba {
+    a * (1.0 - t) + b * t
+}
+
+/// A mesh vertex in physical pixels, with the color decoded to linear space.
+#[derive(Clone, Copy)]
+struct RasterVertex {
+    pos: Pos2,
+    uv: Pos2,
+    color: Rgba,
+}
+
+/// Paints [`ClippedMesh`]:es into a [`ColorImage`] on the CPU.
+///
+/// This is the software equivalent of the painters in `egui_glow`, `egui_glium` etc:
+/// vertex colors and textures are decoded from `sRGB` to linear space,
+/// and blending is done with premultiplied alpha in linear space,
+/// which is what the GPU painters do when the framebuffer is `sRGB`-aware.
+/// The final image is encoded back to `sRGB`.
+///
+/// Just like with a GPU painter, you need to upload the textures
+/// using [`Self::set_texture`] before painting, or use [`Self::paint_and_update_textures`].
+pub struct Rasterizer {
+    textures: AHashMap<TextureId, Texture>,
+
+    /// The filter used when sampling textures.
+    texture_filter: TextureFilter,
+
+    /// Linear [0, 1] from gamma [0, 255].
+    linear_from_gamma_lut: [f32; 256],
+}
+
+impl Default for Rasterizer {
+    fn default() -> Self {
+        let mut linear_from_gamma_lut = [0.0; 256];
+        for (i, value) in linear_from_gamma_lut.iter_mut().enumerate() {
+            *value = linear_f32_from_gamma_u8(i as u8);
+        }
+        Self {
+            textures: Default::default(),
+            texture_filter: Default::default(),
+            linear_from_gamma_lut,
+        }
+    }
+}
+
+impl Rasterizer {
+    /// Set the filter to be used when sampling textures.
+    pub fn set_texture_filter(&mut self, texture_filter: TextureFilter) {
+        self.texture_filter = texture_filter;
+    }
+
+    /// Is there a texture uploaded with this id?
+    pub fn has_texture(&self, id: TextureId) -> bool {
+        self.textures.contains_key(&id)
+    }
+
+    /// Upload a whole texture, or update a region of an existing one.
+    ///
+    /// Works for both [`TextureId::Managed`] (from [`TexturesDelta`])
+    /// and your own [`TextureId::User`] textures.
+    pub fn set_texture(&mut self, id: TextureId, delta: &ImageDelta) {
+        let [w, h] = delta.image.size();
+        let mut sdf_spread = None;
+        let pixels: Vec<Rgba> = match &delta.image {
+            ImageData::Color(image) => {
+                assert_eq!(
+                    image.width() * image.height(),
+                    image.pixels.len(),
+                    "Mismatch between texture size and texel count"
+                );
+                image.pixels.iter().map(|&c| self.linear_rgba(c)).collect()
+            }
+            ImageData::Alpha(image) => {
+                assert_eq!(
+                    image.width() * image.height(),
+                    image.pixels.len(),
+                    "Mismatch between texture size and texel count"
+                );
+                // Go via sRGB, exactly like the GPU painters do when they upload the texture:
+                image
+                    .srgba_pixels(1.0)
+                    .map(|c| self.linear_rgba(c))
+                    .collect()
+            }
+            ImageData::Sdf(image) => {
+                assert_eq!(
+                    image.width() * image.height(),
+                    image.pixels.len(),
+                    "Mismatch between texture size and texel count"
+                );
+                // Keep the distances, so that we can interpolate them when painting:
+                sdf_spread = Some(image.spread);
+                image
+                    .pixels
+                    .iter()
+                    .map(|&value| {
+                        let value = value as f32 / 255.0;
+                        Rgba::from_rgba_premultiplied(value, value, value, value)
+                    })
+                    .collect()
+            }
+        };
+
+        if let Some([x, y]) = delta.pos {
+            if let Some(texture) = self.textures.get_mut(&id) {
+                assert!(
+                    x + w <= texture.size[0] && y + h <= texture.size[1],
+                    "Partial texture update outside of the texture"
+                );
+                for row in 0..h {
+                    let dst = (y + row) * texture.size[0] + x;
+                    texture.pixels[dst..dst + w].copy_from_slice(&pixels[row * w..(row + 1) * w]);
+                }
+            } else {
+                crate::epaint_assert!(
+                    false,
+                    "Tried updating texture {:?} which is not allocated",
+                    id
+                );
+            }
+        } else {
+            self.textures.insert(
+                id,
+                Texture {
+                    size: [w, h],
+                    pixels,
+                    sdf_spread,
+                },
+            );
+        }
+    }
+
+    /// Forget about a texture.
+    pub fn free_texture(&mut self, id: TextureId) {
+        self.textures.remove(&id);
+    }
+
+    /// Apply the texture changes, paint the meshes and then free the textures
+    /// that should be freed, in the same order a GPU painter would.
+    pub fn paint_and_update_textures(
+        &mut self,
+        size_in_pixels: [usize; 2],
+        pixels_per_point: f32,
+        clear_color: Rgba,
+        clipped_meshes: &[ClippedMesh],
+        textures_delta: &TexturesDelta,
+    ) -> ColorImage {
+        for (&id, image_delta) in &textures_delta.set {
+            self.set_texture(id, image_delta);
+        }
+
+        let image = self.paint_meshes(
+            size_in_pixels,
+            pixels_per_point,
+            clear_color,
+            clipped_meshes,
+        );
+
+        for &id in &textures_delta.free {
+            self.free_texture(id);
+        }
+
+        image
+    }
+
+    /// Paint the meshes on top of `clear_color` into a new image
+    /// of `size_in_pixels` physical pixels.
+    ///
+    /// Meshes using a texture that has not been uploaded are skipped.
+    pub fn paint_meshes(
+        &self,
+        size_in_pixels: [usize; 2],
+        pixels_per_point: f32,
+        clear_color: Rgba,
+        clipped_meshes: &[ClippedMesh],
+    ) -> ColorImage {
+        let [width, height] = size_in_pixels;
+        let mut target = vec![clear_color; width * height];
+
+        for ClippedMesh(clip_rect, mesh) in clipped_meshes {
+            crate::epaint_assert!(mesh.is_valid());
+            self.paint_mesh(
+                &mut target,
+                size_in_pixels,
+                pixels_per_point,
+                *clip_rect,
+                mesh,
+            );
+        }
+
+        ColorImage {
+            size: size_in_pixels,
+            pixels: target.into_iter().map(Color32::from).collect(),
+        }
+    }
+
+    fn paint_mesh(
+        &self,
+        target: &mut [Rgba],
+        [width, height]: [usize; 2],
+        pixels_per_point: f32,
+        clip_rect: Rect,
+        mesh: &Mesh,
+    ) {
+        let texture = if let Some(texture) = self.textures.get(&mesh.texture_id) {
+            texture
+        } else {
+            return;
+        };
+
+        // Transform clip rect to physical pixels, the same way the GPU painters do:
+        let clip_min_x = (pixels_per_point * clip_rect.min.x).clamp(0.0, width as f32);
+        let clip_min_y = (pixels_per_point * clip_rect.min.y).clamp(0.0, height as f32);
+        let clip_max_x = (pixels_per_point * clip_rect.max.x).clamp(clip_min_x, width as f32);
+        let clip_max_y = (pixels_per_point * clip_rect.max.y).clamp(clip_min_y, height as f32);
+        let clip = [
+            clip_min_x.round() as usize,
+            clip_min_y.round() as usize,
+            clip_max_x.round() as usize,
+            clip_max_y.round() as usize,
+        ];
+        if clip[0] >= clip[2] || clip[1] >= clip[3] {
+            return;
+        }
+
+        let vertices: Vec<RasterVertex> = mesh
+            .vertices
+            .iter()
+            .map(|v| RasterVertex {
+                pos: (pixels_per_point * v.pos.to_vec2()).to_pos2(),
+                uv: v.uv,
+                color: self.linear_rgba(v.color),
+            })
+            .collect();
+
+        for triangle in mesh.indices.chunks_exact(3) {
+            self.paint_triangle(
+                target,
+                width,
+                clip,
+                texture,
+                [
+                    vertices[triangle[0] as usize],
+                    vertices[triangle[1] as usize],
+                    vertices[triangle[2] as usize],
+                ],
+            );
+        }
+    }
+
+    fn paint_triangle(
+        &self,
+        target: &mut [Rgba],
+        width: usize,
+        [clip_min_x, clip_min_y, clip_max_x, clip_max_y]: [usize; 4],
+        texture: &Texture,
+        [a, b, c]: [RasterVertex; 3],
+    ) {
+        let area = edge_function(a.pos, b.pos, c.pos);
+        if !area.is_finite() || area == 0.0 {
+            return; // Degenerate
+        }
+        // egui is not consistent with winding order, so normalize it:
+        let (b, c, area) = if area < 0.0 {
+            (c, b, -area)
+        } else {
+            (b, c, area)
+        };
+
+        let bounds = Rect::from_points(&[a.pos, b.pos, c.pos]);
+        let min_x = (bounds.min.x.floor().max(0.0) as usize).max(clip_min_x);
+        let min_y = (bounds.min.y.floor().max(0.0) as usize).max(clip_min_y);
+        let max_x = (bounds.max.x.ceil().max(0.0) as usize).min(clip_max_x);
+        let max_y = (bounds.max.y.ceil().max(0.0) as usize).min(clip_max_y);
+
+        // Use the top-left fill rule so that pixels on an edge shared by
+        // two triangles are only painted once:
+        let bias_a = fill_bias(b.pos, c.pos);
+        let bias_b = fill_bias(c.pos, a.pos);
+        let bias_c = fill_bias(a.pos, b.pos);
+
+        // For signed distance fields: how many texels wide a pixel is.
+        let texels_per_pixel = {
+            let [tex_width, tex_height] = texture.size;
+            let texel = |uv: Pos2| pos2(uv.x * tex_width as f32, uv.y * tex_height as f32);
+            let area_in_texels = edge_function(texel(a.uv), texel(b.uv), texel(c.uv));
+            (area_in_texels / area).abs().sqrt()
+        };
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let p = pos2(x as f32 + 0.5, y as f32 + 0.5);
+                let wa = edge_function(b.pos, c.pos, p);
+                let wb = edge_function(c.pos, a.pos, p);
+                let wc = edge_function(a.pos, b.pos, p);
+                if wa + bias_a <= 0.0 || wb + bias_b <= 0.0 || wc + bias_c <= 0.0 {
+                    continue;
+                }
+
+                let (wa, wb, wc) = (wa / area, wb / area, wc / area);
+                let color = a.color * wa + b.color * wb + c.color * wc;
+                let uv = pos2(
+                    wa * a.uv.x + wb * b.uv.x + wc * c.uv.x,
+                    wa * a.uv.y + wb * b.uv.y + wc * c.uv.y,
+                );
+                let texel = texture.sample(self.texture_filter, uv);
+                let texel = if let Some(spread) = texture.sdf_spread {
+                    Rgba::from_white_alpha(SdfImage::coverage(texel.a(), spread, texels_per_pixel))
+                } else {
+                    texel
+                };
+                let src = color * texel;
+
+                // Premultiplied alpha blending, like `glBlendFuncSeparate(ONE, ONE_MINUS_SRC_ALPHA, ONE_MINUS_DST_ALPHA, ONE)`:
+                let dst = &mut target[y * width + x];
+                let src_a = src.a().clamp(0.0, 1.0);
+                let dst_a = dst.a();
+                *dst = Rgba::from_rgba_premultiplied(
+                    src.r() + (1.0 - src_a) * dst.r(),
+                    src.g() + (1.0 - src_a) * dst.g(),
+                    src.b() + (1.0 - src_a) * dst.b(),
+                    (1.0 - dst_a) * src_a + dst_a,
+                );
+            }
+        }
+    }
+
+    fn linear_rgba(&self, color: Color32) -> Rgba {
+        let [r, g, b, a] = color.to_array();
+        Rgba::from_rgba_premultiplied(
+            self.linear_from_gamma_lut[r as usize],
+            self.linear_from_gamma_lut[g as usize],
+            self.linear_from_gamma_lut[b as usize],
+            linear_f32_from_linear_u8(a),
+        )
+    }
+}
+
+/// Twice the signed area of the triangle `a, b, p`.
+///
+/// Positive if `p` is to the right of `a -> b` (with y pointing down).
+#[inline(always)]
+fn edge_function(a: Pos2, b: Pos2, p: Pos2) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Pixels exactly on a top or left edge are considered inside,
+/// pixels exactly on a bottom or right edge are not.
+#[inline(always)]
+fn fill_bias(a: Pos2, b: Pos2) -> f32 {
+    let d = b - a;
+    let is_top_left = d.y < 0.0 || (d.y == 0.0 && d.x > 0.0);
+    if is_top_left {
+        f32::EPSILON
+    } else {
+        0.0
+    }
+}
+
+#[test]
+fn test_rasterizer_shared_edges() {
+    use crate::textures::TextureManager;
+
+    let mut textures = TextureManager::default();
+    let mut font_image = crate::AlphaImage::new([4, 4]);
+    font_image[(0, 0)] = 255;
+    let font_id = textures.alloc("font".to_owned(), font_image.clone().into());
+    assert_eq!(font_id, TextureId::default());
+
+    // Two half-transparent triangles sharing a diagonal must not double-blend on it:
+    let mut mesh = Mesh::default();
+    let rect = Rect::from_min_max(pos2(0.0, 0.0), pos2(8.0, 8.0));
+    mesh.add_colored_rect(rect, Color32::from_white_alpha(128));
+
+    let image = Rasterizer::default().paint_and_update_textures(
+        [8, 8],
+        1.0,
+        Rgba::BLACK,
+        &[ClippedMesh(Rect::EVERYTHING, mesh.clone())],
+        &textures.take_delta(),
+    );
+    let first = image.pixels[0];
+    assert!(first != Color32::BLACK);
+    assert!(image.pixels.iter().all(|&p| p == first));
+
+    // Clipping is applied in physical pixels:
+    let mut rasterizer = Rasterizer::default();
+    rasterizer.set_texture(font_id, &ImageDelta::full(font_image));
+    let image = rasterizer.paint_meshes(
+        [16, 16],
+        2.0,
+        Rgba::BLACK,
+        &[ClippedMesh(
+            Rect::from_min_max(pos2(0.0, 0.0), pos2(2.0, 8.0)),
+            mesh,
+        )],
+    );
+    assert_eq!(image[(3, 3)], first);
+    assert_eq!(image[(3, 15)], first);
+    assert_eq!(image[(4, 4)], Color32::BLACK);
+}
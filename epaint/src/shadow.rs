@@ -1,69 +1,289 @@
 use super::*;
+use emath::*;
+use std::f32::consts::TAU;
 
 /// The color and fuzziness of a fuzzy shape.
 /// Can be used for a rectangular shadow with a soft penumbra.
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Shadow {
-    /// The shadow extends this much outside the rect.
-    /// The size of the fuzzy penumbra.
-    pub extrusion: f32,
+    /// Move the shadow by this much.
+    ///
+    /// For instance, a value of `[0.0, 4.0]` would move the shadow down 4 points,
+    /// as if lit from above.
+    pub offset: Vec2,
+
+    /// The width of the fuzzy penumbra on each side of the edge of the shadow.
+    ///
+    /// A value of `0.0` gives a sharp shadow.
+    pub blur: f32,
+
+    /// Expand the shadow in all directions by this much, before blurring.
+    /// For inner shadows this shrinks the shadow-free area instead.
+    pub spread: f32,
 
     /// Color of the opaque center of the shadow.
     pub color: Color32,
+
+    /// If `true`, the shadow is painted inside the rectangle, as if the rectangle was a hole.
+    ///
+    /// Inner shadows should be painted on top of the fill of the rectangle.
+    pub inner: bool,
 }
 
 impl Shadow {
     /// Tooltips, menus, …
     pub fn small_dark() -> Self {
         Self {
-            extrusion: 16.0,
+            offset: vec2(0.0, 2.0),
+            blur: 8.0,
+            spread: 4.0,
             color: Color32::from_black_alpha(96),
+            inner: false,
         }
     }
 
     /// Tooltips, menus, …
     pub fn small_light() -> Self {
         Self {
-            extrusion: 16.0,
+            offset: vec2(0.0, 2.0),
+            blur: 8.0,
+            spread: 4.0,
             color: Color32::from_black_alpha(32),
+            inner: false,
         }
     }
 
     /// Subtle and nice on dark backgrounds
     pub fn big_dark() -> Self {
         Self {
-            extrusion: 32.0,
+            offset: vec2(0.0, 4.0),
+            blur: 16.0,
+            spread: 8.0,
             color: Color32::from_black_alpha(96),
+            inner: false,
         }
     }
 
     /// Subtle and nice on white backgrounds
     pub fn big_light() -> Self {
         Self {
-            extrusion: 32.0,
+            offset: vec2(0.0, 4.0),
+            blur: 16.0,
+            spread: 8.0,
             color: Color32::from_black_alpha(40),
+            inner: false,
         }
     }
 
-    pub fn tessellate(&self, rect: emath::Rect, corner_radius: f32) -> Mesh {
-        // tessellator.clip_rect = clip_rect; // TODO: culling
+    /// An inner shadow, making a rectangle look sunken.
+    pub fn inner(offset: Vec2, blur: f32, color: impl Into<Color32>) -> Self {
+        Self {
+            offset,
+            blur,
+            spread: 0.0,
+            color: color.into(),
+            inner: true,
+        }
+    }
+
+    /// How far outside of the rectangle the shadow can reach.
+    pub fn margin(&self) -> f32 {
+        if self.inner {
+            0.0
+        } else {
+            self.spread + self.blur + self.offset.x.abs().max(self.offset.y.abs())
+        }
+    }
 
-        let Self { extrusion, color } = *self;
+    pub fn tessellate(&self, rect: Rect, rounding: impl Into<Rounding>) -> Mesh {
+        // tessellator.clip_rect = clip_rect; // TODO: culling
 
-        use crate::tessellator::*;
-        let rect = RectShape::filled(
-            rect.expand(0.5 * extrusion),
-            corner_radius + 0.5 * extrusion,
+        let Self {
+            offset,
+            blur,
+            spread,
             color,
-        );
-        let mut tessellator = Tessellator::from_options(TessellationOptions {
-            aa_size: extrusion,
-            anti_alias: true,
-            ..Default::default()
-        });
+            inner,
+        } = *self;
+        let rounding = rounding.into();
+
         let mut mesh = Mesh::default();
-        tessellator.tessellate_rect(&rect, &mut mesh);
+        if color == Color32::TRANSPARENT {
+            return mesh;
+        }
+
+        if inner {
+            let hole = rect.translate(offset).shrink(spread);
+            let hole_rounding = rounding + -spread;
+            let mut outer = vec![];
+            rounded_rect_outline(rect, rounding, &mut outer);
+
+            // From the edge of the rectangle and inwards:
+            let mut rings = vec![(outer.clone(), 1.0)];
+            for d in blur_offsets(blur).rev() {
+                let mut ring = vec![];
+                rounded_rect_outline(hole.expand(d), hole_rounding + d, &mut ring);
+                for p in &mut ring {
+                    *p = clamp_to_rounded_rect(*p, rect, rounding);
+                }
+                rings.push((ring, blur_coverage(-d, blur)));
+            }
+            add_rings(&mut mesh, &rings, color);
+        } else {
+            let base = rect.translate(offset).expand(spread);
+            let base_rounding = rounding + spread;
+
+            // From the inside and outwards:
+            let rings: Vec<(Vec<Pos2>, f32)> = blur_offsets(blur)
+                .map(|d| {
+                    let mut ring = vec![];
+                    rounded_rect_outline(base.expand(d), base_rounding + d, &mut ring);
+                    (ring, blur_coverage(d, blur))
+                })
+                .collect();
+
+            // Fill the center:
+            let (center, _) = &rings[0];
+            let idx = mesh.vertices.len() as u32;
+            for &pos in center {
+                mesh.colored_vertex(pos, color);
+            }
+            for i in 2..center.len() as u32 {
+                mesh.add_triangle(idx, idx + i - 1, idx + i);
+            }
+
+            add_rings(&mut mesh, &rings, color);
+        }
         mesh
     }
 }
+
+/// Number of rings used to approximate the blurred edge.
+const NUM_BLUR_RINGS: usize = 8;
+
+/// Number of line segments for each rounded corner.
+const CORNER_SEGMENTS: usize = 8;
+
+/// Signed distances from the edge of the shadow (positive = outwards) at which we place rings.
+fn blur_offsets(blur: f32) -> impl DoubleEndedIterator<Item = f32> {
+    let num_rings = if blur > 0.0 { NUM_BLUR_RINGS } else { 1 };
+    (0..num_rings).map(move |i| {
+        if num_rings == 1 {
+            0.0
+        } else {
+            lerp(-blur..=blur, i as f32 / (num_rings - 1) as f32)
+        }
+    })
+}
+
+/// How much of the shadow color to use at signed distance `d` from the edge of the shadow.
+///
+/// The edge of a blurred rectangle follows the normal distribution,
+/// here with a standard deviation of `blur / 2`, normalized to be exactly one at `-blur`
+/// and zero at `+blur`.
+fn blur_coverage(d: f32, blur: f32) -> f32 {
+    if blur <= 0.0 {
+        return 1.0;
+    }
+    let sigma = 0.5 * blur;
+    let max = normal_cdf(2.0);
+    let min = normal_cdf(-2.0);
+    remap_clamp(normal_cdf(-d / sigma), min..=max, 0.0..=1.0)
+}
+
+/// The cumulative distribution function of the standard normal distribution.
+fn normal_cdf(x: f32) -> f32 {
+    0.5 * (1.0 + erf(x / std::f32::consts::SQRT_2))
+}
+
+/// Approximation of the error function (Abramowitz and Stegun 7.1.26), max error 1.5e-7.
+fn erf(x: f32) -> f32 {
+    let sign = x.signum();
+    let x = x.abs();
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let y = 1.0
+        - (((((1.061_405_4 * t - 1.453_152_1) * t) + 1.421_413_8) * t - 0.284_496_75) * t
+            + 0.254_829_6)
+            * t
+            * (-x * x).exp();
+    sign * y
+}
+
+/// The outline of a rounded rectangle, always with the same number of points
+/// so that the outlines of different rectangles can be stitched together.
+///
+/// Negative sizes and radii are clamped to zero.
+fn rounded_rect_outline(rect: Rect, rounding: Rounding, out: &mut Vec<Pos2>) {
+    let center = rect.center();
+    let rect = Rect::from_min_max(rect.min.min(center), rect.max.max(center));
+    let r = rounding
+        .at_most(0.5 * rect.width())
+        .at_most(0.5 * rect.height())
+        .at_least(0.0);
+    let corners = [
+        (pos2(rect.max.x - r.se, rect.max.y - r.se), r.se),
+        (pos2(rect.min.x + r.sw, rect.max.y - r.sw), r.sw),
+        (pos2(rect.min.x + r.nw, rect.min.y + r.nw), r.nw),
+        (pos2(rect.max.x - r.ne, rect.min.y + r.ne), r.ne),
+    ];
+
+    out.clear();
+    out.reserve(4 * (CORNER_SEGMENTS + 1));
+    for (quadrant, (corner_center, radius)) in corners.iter().enumerate() {
+        for i in 0..=CORNER_SEGMENTS {
+            let angle = (quadrant as f32 + i as f32 / CORNER_SEGMENTS as f32) * TAU / 4.0;
+            out.push(*corner_center + *radius * Vec2::angled(angle));
+        }
+    }
+}
+
+/// The closest point to `pos` inside the rounded rectangle.
+fn clamp_to_rounded_rect(pos: Pos2, rect: Rect, rounding: Rounding) -> Pos2 {
+    let pos = pos.clamp(rect.min, rect.max);
+    let r = rounding
+        .at_most(0.5 * rect.width())
+        .at_most(0.5 * rect.height())
+        .at_least(0.0);
+    // The center of each rounded corner, and which way the corner points:
+    let corners = [
+        (pos2(rect.max.x - r.se, rect.max.y - r.se), r.se, vec2(1.0, 1.0)),
+        (pos2(rect.min.x + r.sw, rect.max.y - r.sw), r.sw, vec2(-1.0, 1.0)),
+        (pos2(rect.min.x + r.nw, rect.min.y + r.nw), r.nw, vec2(-1.0, -1.0)),
+        (pos2(rect.max.x - r.ne, rect.min.y + r.ne), r.ne, vec2(1.0, -1.0)),
+    ];
+    for (corner_center, radius, direction) in corners {
+        let delta = pos - corner_center;
+        let in_corner = delta.x * direction.x > 0.0 && delta.y * direction.y > 0.0;
+        if in_corner && delta.length() > radius {
+            return corner_center + radius * delta.normalized();
+        }
+    }
+    pos
+}
+
+/// Connect each ring to the next with triangles, with the colors fading according to each ring.
+fn add_rings(mesh: &mut Mesh, rings: &[(Vec<Pos2>, f32)], color: Color32) {
+    for pair in rings.windows(2) {
+        let (ring0, coverage0) = &pair[0];
+        let (ring1, coverage1) = &pair[1];
+        let color0 = color.linear_multiply(*coverage0);
+        let color1 = color.linear_multiply(*coverage1);
+        if color0 == Color32::TRANSPARENT && color1 == Color32::TRANSPARENT {
+            continue;
+        }
+
+        let n = ring0.len() as u32;
+        let idx = mesh.vertices.len() as u32;
+        for (&p0, &p1) in ring0.iter().zip(ring1) {
+            mesh.colored_vertex(p0, color0);
+            mesh.colored_vertex(p1, color1);
+        }
+        for i0 in 0..n {
+            let i1 = (i0 + 1) % n;
+            mesh.add_triangle(idx + 2 * i0, idx + 2 * i1, idx + 2 * i0 + 1);
+            mesh.add_triangle(idx + 2 * i1, idx + 2 * i1 + 1, idx + 2 * i0 + 1);
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     text::{FontId, Fonts, Galley},
-    Color32, Mesh, Stroke,
+    Color32, CubicBezierShape, Gradient, Mesh, QuadraticBezierShape, SideStrokes, Stroke,
 };
 use emath::*;
 
@@ -26,6 +26,8 @@ pub enum Shape {
     Rect(RectShape),
     Text(TextShape),
     Mesh(Mesh),
+    QuadraticBezier(QuadraticBezierShape),
+    CubicBezier(CubicBezierShape),
 }
 
 /// ## Constructors
@@ -55,6 +57,8 @@ impl Shape {
     }
 
     /// Turn a line into equally spaced dots.
+    ///
+    /// See also [`Stroke::dotted`], which works for any shape.
     pub fn dotted_line(
         points: &[Pos2],
         color: impl Into<Color32>,
@@ -67,6 +71,8 @@ impl Shape {
     }
 
     /// Turn a line into dashes.
+    ///
+    /// See also [`Stroke::dashed`], which works for any shape.
     pub fn dashed_line(
         points: &[Pos2],
         stroke: impl Into<Stroke>,
@@ -100,24 +106,56 @@ impl Shape {
         Self::Path(PathShape::convex_polygon(points, fill, stroke))
     }
 
+    /// A convex polygon filled with a gradient, and an optional stroke.
+    #[inline]
+    pub fn convex_polygon_gradient(
+        points: Vec<Pos2>,
+        gradient: Gradient,
+        stroke: impl Into<Stroke>,
+    ) -> Self {
+        Self::Path(PathShape::convex_polygon_gradient(points, gradient, stroke))
+    }
+
     #[inline]
     pub fn circle_filled(center: Pos2, radius: f32, fill_color: impl Into<Color32>) -> Self {
         Self::Circle(CircleShape::filled(center, radius, fill_color))
     }
 
+    #[inline]
+    pub fn circle_filled_gradient(center: Pos2, radius: f32, gradient: Gradient) -> Self {
+        Self::Circle(CircleShape::filled_gradient(center, radius, gradient))
+    }
+
     #[inline]
     pub fn circle_stroke(center: Pos2, radius: f32, stroke: impl Into<Stroke>) -> Self {
         Self::Circle(CircleShape::stroke(center, radius, stroke))
     }
 
     #[inline]
-    pub fn rect_filled(rect: Rect, corner_radius: f32, fill_color: impl Into<Color32>) -> Self {
-        Self::Rect(RectShape::filled(rect, corner_radius, fill_color))
+    pub fn rect_filled(
+        rect: Rect,
+        rounding: impl Into<Rounding>,
+        fill_color: impl Into<Color32>,
+    ) -> Self {
+        Self::Rect(RectShape::filled(rect, rounding, fill_color))
     }
 
     #[inline]
-    pub fn rect_stroke(rect: Rect, corner_radius: f32, stroke: impl Into<Stroke>) -> Self {
-        Self::Rect(RectShape::stroke(rect, corner_radius, stroke))
+    pub fn rect_filled_gradient(
+        rect: Rect,
+        rounding: impl Into<Rounding>,
+        gradient: Gradient,
+    ) -> Self {
+        Self::Rect(RectShape::filled_gradient(rect, rounding, gradient))
+    }
+
+    #[inline]
+    pub fn rect_stroke(
+        rect: Rect,
+        rounding: impl Into<Rounding>,
+        stroke: impl Into<Stroke>,
+    ) -> Self {
+        Self::Rect(RectShape::stroke(rect, rounding, stroke))
     }
 
     #[allow(clippy::needless_pass_by_value)]
@@ -167,6 +205,9 @@ impl Shape {
             }
             Shape::Circle(circle_shape) => {
                 circle_shape.center += delta;
+                if let Some(gradient) = &mut circle_shape.fill_gradient {
+                    gradient.translate(delta);
+                }
             }
             Shape::LineSegment { points, .. } => {
                 for p in points {
@@ -177,9 +218,15 @@ impl Shape {
                 for p in &mut path_shape.points {
                     *p += delta;
                 }
+                if let Some(gradient) = &mut path_shape.fill_gradient {
+                    gradient.translate(delta);
+                }
             }
             Shape::Rect(rect_shape) => {
                 rect_shape.rect = rect_shape.rect.translate(delta);
+                if let Some(gradient) = &mut rect_shape.fill_gradient {
+                    gradient.translate(delta);
+                }
             }
             Shape::Text(text_shape) => {
                 text_shape.pos += delta;
@@ -187,6 +234,128 @@ impl Shape {
             Shape::Mesh(mesh) => {
                 mesh.translate(delta);
             }
+            Shape::QuadraticBezier(bezier_shape) => {
+                for p in &mut bezier_shape.points {
+                    *p += delta;
+                }
+            }
+            Shape::CubicBezier(bezier_shape) => {
+                for p in &mut bezier_shape.points {
+                    *p += delta;
+                }
+            }
+        }
+    }
+
+    /// Scale, rotate and move the shape, in-place.
+    ///
+    /// Stroke widths, dash patterns and text are scaled too.
+    /// A rotated [`Shape::Rect`] is turned into a [`Shape::Path`].
+    pub fn transform(&mut self, transform: TSTransform) {
+        let scaling = transform.scaling;
+        match self {
+            Shape::Noop => {}
+            Shape::Vec(shapes) => {
+                for shape in shapes {
+                    shape.transform(transform);
+                }
+            }
+            Shape::Circle(circle_shape) => {
+                circle_shape.center = transform * circle_shape.center;
+                circle_shape.radius *= scaling;
+                circle_shape.stroke.scale(scaling);
+                if let Some(gradient) = &mut circle_shape.fill_gradient {
+                    gradient.transform(transform);
+                }
+            }
+            Shape::LineSegment { points, stroke } => {
+                for p in points {
+                    *p = transform * *p;
+                }
+                stroke.scale(scaling);
+            }
+            Shape::Path(path_shape) => {
+                for p in &mut path_shape.points {
+                    *p = transform * *p;
+                }
+                path_shape.stroke.scale(scaling);
+                if let Some(gradient) = &mut path_shape.fill_gradient {
+                    gradient.transform(transform);
+                }
+            }
+            Shape::Rect(rect_shape) => {
+                if transform.is_axis_aligned() {
+                    rect_shape.rect = transform.mul_rect(rect_shape.rect);
+                    rect_shape.rounding = rect_shape.rounding * scaling;
+                    rect_shape.stroke.scale(scaling);
+                    if let Some(side_strokes) = &mut rect_shape.side_strokes {
+                        side_strokes.scale(scaling);
+                    }
+                    if let Some(gradient) = &mut rect_shape.fill_gradient {
+                        gradient.transform(transform);
+                    }
+                } else {
+                    let mut points = vec![];
+                    crate::tessellator::path::rounded_rectangle(
+                        &mut points,
+                        rect_shape.rect,
+                        rect_shape.rounding,
+                    );
+                    let mut path = Shape::Path(PathShape {
+                        points,
+                        closed: true,
+                        fill: rect_shape.fill,
+                        fill_gradient: rect_shape.fill_gradient.take(),
+                        stroke: if rect_shape.side_strokes.is_some() {
+                            Stroke::none()
+                        } else {
+                            rect_shape.stroke
+                        },
+                    });
+                    if let Some(side_strokes) = rect_shape.side_strokes {
+                        // The fill, followed by each side as an open path:
+                        let sides = crate::tessellator::path::rounded_rectangle_sides(
+                            rect_shape.rect,
+                            rect_shape.rounding,
+                            &side_strokes,
+                        );
+                        let mut shapes = vec![path];
+                        shapes.extend(
+                            sides
+                                .into_iter()
+                                .zip(side_strokes.to_array())
+                                .map(|(points, stroke)| Shape::line(points, stroke)),
+                        );
+                        path = Shape::Vec(shapes);
+                    }
+                    path.transform(transform);
+                    *self = path;
+                }
+            }
+            Shape::Text(text_shape) => {
+                text_shape.pos = transform * text_shape.pos;
+                text_shape.angle += transform.angle();
+                text_shape.underline.scale(scaling);
+                if scaling != 1.0 && !text_shape.galley.is_empty() {
+                    let galley = std::sync::Arc::make_mut(&mut text_shape.galley);
+                    galley.scale(scaling);
+                }
+            }
+            Shape::Mesh(mesh) => {
+                mesh.transform(transform);
+            }
+            Shape::QuadraticBezier(bezier_shape) => {
+                for p in &mut bezier_shape.points {
+                    *p = transform * *p;
+                }
+                bezier_shape.stroke.scale(scaling);
+            }
+            Shape::CubicBezier(bezier_shape) => {
+                for p in &mut bezier_shape.points {
+                    *p = transform * *p;
+                }
+                bezier_shape.stroke.scale(scaling);
+            }
         }
     }
 }
@@ -194,12 +363,14 @@ impl Shape {
 // ----------------------------------------------------------------------------
 
 /// How to paint a circle.
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct CircleShape {
     pub center: Pos2,
     pub radius: f32,
     pub fill: Color32,
+    /// If set, the circle is filled with this instead of [`Self::fill`].
+    pub fill_gradient: Option<Gradient>,
     pub stroke: Stroke,
 }
 
@@ -210,6 +381,18 @@ impl CircleShape {
             center,
             radius,
             fill: fill_color.into(),
+            fill_gradient: None,
+            stroke: Default::default(),
+        }
+    }
+
+    #[inline]
+    pub fn filled_gradient(center: Pos2, radius: f32, gradient: Gradient) -> Self {
+        Self {
+            center,
+            radius,
+            fill: Default::default(),
+            fill_gradient: Some(gradient),
             stroke: Default::default(),
         }
     }
@@ -220,6 +403,7 @@ impl CircleShape {
             center,
             radius,
             fill: Default::default(),
+            fill_gradient: None,
             stroke: stroke.into(),
         }
     }
@@ -244,6 +428,8 @@ pub struct PathShape {
     pub closed: bool,
     /// Fill is only supported for convex polygons.
     pub fill: Color32,
+    /// If set, the polygon is filled with this instead of [`Self::fill`].
+    pub fill_gradient: Option<Gradient>,
     pub stroke: Stroke,
 }
 
@@ -257,6 +443,7 @@ impl PathShape {
             points,
             closed: false,
             fill: Default::default(),
+            fill_gradient: None,
             stroke: stroke.into(),
         }
     }
@@ -268,6 +455,7 @@ impl PathShape {
             points,
             closed: true,
             fill: Default::default(),
+            fill_gradient: None,
             stroke: stroke.into(),
         }
     }
@@ -283,6 +471,23 @@ impl PathShape {
             points,
             closed: true,
             fill: fill.into(),
+            fill_gradient: None,
+            stroke: stroke.into(),
+        }
+    }
+
+    /// A convex polygon filled with a gradient, and an optional stroke.
+    #[inline]
+    pub fn convex_polygon_gradient(
+        points: Vec<Pos2>,
+        gradient: Gradient,
+        stroke: impl Into<Stroke>,
+    ) -> Self {
+        PathShape {
+            points,
+            closed: true,
+            fill: Default::default(),
+            fill_gradient: Some(gradient),
             stroke: stroke.into(),
         }
     }
@@ -304,41 +509,89 @@ impl From<PathShape> for Shape {
 // ----------------------------------------------------------------------------
 
 /// How to paint a rectangle.
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct RectShape {
     pub rect: Rect,
-    /// How rounded the corners are. Use `0.0` for no rounding.
-    pub corner_radius: f32,
+    /// How rounded the corners are. Use `Rounding::none()` for no rounding.
+    pub rounding: Rounding,
     pub fill: Color32,
+    /// If set, the rectangle is filled with this instead of [`Self::fill`].
+    pub fill_gradient: Option<Gradient>,
     pub stroke: Stroke,
+    /// If set, each side is stroked with its own stroke instead of [`Self::stroke`].
+    pub side_strokes: Option<SideStrokes>,
 }
 
 impl RectShape {
     #[inline]
-    pub fn filled(rect: Rect, corner_radius: f32, fill_color: impl Into<Color32>) -> Self {
+    pub fn filled(
+        rect: Rect,
+        rounding: impl Into<Rounding>,
+        fill_color: impl Into<Color32>,
+    ) -> Self {
         Self {
             rect,
-            corner_radius,
+            rounding: rounding.into(),
             fill: fill_color.into(),
+            fill_gradient: None,
             stroke: Default::default(),
+            side_strokes: None,
         }
     }
 
     #[inline]
-    pub fn stroke(rect: Rect, corner_radius: f32, stroke: impl Into<Stroke>) -> Self {
+    pub fn filled_gradient(rect: Rect, rounding: impl Into<Rounding>, gradient: Gradient) -> Self {
         Self {
             rect,
-            corner_radius,
+            rounding: rounding.into(),
             fill: Default::default(),
+            fill_gradient: Some(gradient),
+            stroke: Default::default(),
+            side_strokes: None,
+        }
+    }
+
+    #[inline]
+    pub fn stroke(rect: Rect, rounding: impl Into<Rounding>, stroke: impl Into<Stroke>) -> Self {
+        Self {
+            rect,
+            rounding: rounding.into(),
+            fill: Default::default(),
+            fill_gradient: None,
             stroke: stroke.into(),
+            side_strokes: None,
         }
     }
 
+    /// A rectangle outline with a separate stroke for each side.
+    #[inline]
+    pub fn stroke_sides(
+        rect: Rect,
+        rounding: impl Into<Rounding>,
+        side_strokes: impl Into<SideStrokes>,
+    ) -> Self {
+        Self {
+            rect,
+            rounding: rounding.into(),
+            fill: Default::default(),
+            fill_gradient: None,
+            stroke: Default::default(),
+            side_strokes: Some(side_strokes.into()),
+        }
+    }
+
+    /// The width of the widest stroke.
+    #[inline]
+    pub fn stroke_width(&self) -> f32 {
+        self.side_strokes
+            .map_or(self.stroke.width, |side_strokes| side_strokes.max_width())
+    }
+
     /// Screen-space bounding rectangle.
     #[inline]
     pub fn bounding_rect(&self) -> Rect {
-        self.rect.expand(self.stroke.width)
+        self.rect.expand(self.stroke_width())
     }
 }
 
@@ -349,6 +602,104 @@ impl From<RectShape> for Shape {
     }
 }
 
+/// How rounded the corners of things should be.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Rounding {
+    /// Radius of the rounding of the North-West (left top) corner.
+    pub nw: f32,
+    /// Radius of the rounding of the North-East (right top) corner.
+    pub ne: f32,
+    /// Radius of the rounding of the South-West (left bottom) corner.
+    pub sw: f32,
+    /// Radius of the rounding of the South-East (right bottom) corner.
+    pub se: f32,
+}
+
+impl From<f32> for Rounding {
+    #[inline]
+    fn from(radius: f32) -> Self {
+        Self::same(radius)
+    }
+}
+
+impl Rounding {
+    /// The same radius for all four corners.
+    #[inline]
+    pub fn same(radius: f32) -> Self {
+        Self {
+            nw: radius,
+            ne: radius,
+            sw: radius,
+            se: radius,
+        }
+    }
+
+    /// No rounding on any corner.
+    #[inline]
+    pub fn none() -> Self {
+        Self::same(0.0)
+    }
+
+    /// Do all corners have the same rounding?
+    #[inline]
+    pub fn is_same(&self) -> bool {
+        self.nw == self.ne && self.nw == self.sw && self.nw == self.se
+    }
+
+    /// Make sure each corner has a rounding of at least this.
+    #[inline]
+    pub fn at_least(&self, min: f32) -> Self {
+        Self {
+            nw: self.nw.max(min),
+            ne: self.ne.max(min),
+            sw: self.sw.max(min),
+            se: self.se.max(min),
+        }
+    }
+
+    /// Make sure each corner has a rounding of at most this.
+    #[inline]
+    pub fn at_most(&self, max: f32) -> Self {
+        Self {
+            nw: self.nw.min(max),
+            ne: self.ne.min(max),
+            sw: self.sw.min(max),
+            se: self.se.min(max),
+        }
+    }
+}
+
+impl std::ops::Add<f32> for Rounding {
+    type Output = Self;
+
+    /// Add the same amount to the radius of each corner.
+    #[inline]
+    fn add(self, rhs: f32) -> Self {
+        Self {
+            nw: self.nw + rhs,
+            ne: self.ne + rhs,
+            sw: self.sw + rhs,
+            se: self.se + rhs,
+        }
+    }
+}
+
+impl std::ops::Mul<f32> for Rounding {
+    type Output = Self;
+
+    /// Scale the radius of each corner.
+    #[inline]
+    fn mul(self, rhs: f32) -> Self {
+        Self {
+            nw: self.nw * rhs,
+            ne: self.ne * rhs,
+            sw: self.sw * rhs,
+            se: self.se * rhs,
+        }
+    }
+}
+
 // ----------------------------------------------------------------------------
 
 /// How to paint some text on screen.
@@ -11,6 +11,11 @@ pub fn adjust_colors(shape: &mut Shape, adjust_color: &impl Fn(&mut Color32)) {
         }
         Shape::Circle(circle_shape) => {
             adjust_color(&mut circle_shape.fill);
+            if let Some(gradient) = &mut circle_shape.fill_gradient {
+                for (_, color) in &mut gradient.stops {
+                    adjust_color(color);
+                }
+            }
             adjust_color(&mut circle_shape.stroke.color);
         }
         Shape::LineSegment { stroke, .. } => {
@@ -18,11 +23,27 @@ pub fn adjust_colors(shape: &mut Shape, adjust_color: &impl Fn(&mut Color32)) {
         }
         Shape::Path(path_shape) => {
             adjust_color(&mut path_shape.fill);
+            if let Some(gradient) = &mut path_shape.fill_gradient {
+                for (_, color) in &mut gradient.stops {
+                    adjust_color(color);
+                }
+            }
             adjust_color(&mut path_shape.stroke.color);
         }
         Shape::Rect(rect_shape) => {
             adjust_color(&mut rect_shape.fill);
+            if let Some(gradient) = &mut rect_shape.fill_gradient {
+                for (_, color) in &mut gradient.stops {
+                    adjust_color(color);
+                }
+            }
             adjust_color(&mut rect_shape.stroke.color);
+            if let Some(side_strokes) = &mut rect_shape.side_strokes {
+                adjust_color(&mut side_strokes.left.color);
+                adjust_color(&mut side_strokes.top.color);
+                adjust_color(&mut side_strokes.right.color);
+                adjust_color(&mut side_strokes.bottom.color);
+            }
         }
         Shape::Text(text_shape) => {
             if let Some(override_text_color) = &mut text_shape.override_text_color {
@@ -43,5 +64,13 @@ pub fn adjust_colors(shape: &mut Shape, adjust_color: &impl Fn(&mut Color32)) {
                 adjust_color(&mut v.color);
             }
         }
+        Shape::QuadraticBezier(bezier_shape) => {
+            adjust_color(&mut bezier_shape.fill);
+            adjust_color(&mut bezier_shape.stroke.color);
+        }
+        Shape::CubicBezier(bezier_shape) => {
+            adjust_color(&mut bezier_shape.fill);
+            adjust_color(&mut bezier_shape.stroke.color);
+        }
     }
 }
@@ -170,6 +170,8 @@ pub struct PaintStats {
     pub clipped_meshes: AllocInfo,
     pub vertices: AllocInfo,
     pub indices: AllocInfo,
+
+    pub font_atlas: FontAtlasStats,
 }
 
 impl PaintStats {
@@ -195,8 +197,12 @@ impl PaintStats {
                     self.add(shape);
                 }
             }
-            Shape::Noop | Shape::Circle { .. } | Shape::LineSegment { .. } | Shape::Rect { .. } => {
-            }
+            Shape::Noop
+            | Shape::Circle { .. }
+            | Shape::LineSegment { .. }
+            | Shape::Rect { .. }
+            | Shape::QuadraticBezier(_)
+            | Shape::CubicBezier(_) => {}
             Shape::Path(path_shape) => {
                 self.shape_path += AllocInfo::from_slice(&path_shape.points);
             }
@@ -223,6 +229,11 @@ impl PaintStats {
         self
     }
 
+    pub fn with_font_atlas(mut self, font_atlas: FontAtlasStats) -> Self {
+        self.font_atlas = font_atlas;
+        self
+    }
+
     // pub fn total(&self) -> AllocInfo {
     //     self.shapes
     //         + self.shape_text
@@ -234,6 +245,38 @@ impl PaintStats {
     // }
 }
 
+/// Statistics about the [`crate::TextureAtlas`] of the fonts.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FontAtlasStats {
+    /// Number of pages (textures).
+    pub num_pages: usize,
+
+    /// When all these pages are full, the least recently used page is cleared.
+    pub max_pages: usize,
+
+    /// Size of all pages.
+    pub num_bytes: usize,
+
+    /// How much of the space of all possible pages that has been used, from 0 to 1.
+    pub fill_ratio: f32,
+
+    /// Number of pages that has been cleared to make room for new glyphs.
+    pub num_evictions: usize,
+}
+
+impl FontAtlasStats {
+    pub fn format(&self) -> String {
+        format!(
+            "{} / {} pages  {}  {:3.0}% full  {} pages evicted",
+            self.num_pages,
+            self.max_pages,
+            megabytes(self.num_bytes),
+            self.fill_ratio * 100.0,
+            self.num_evictions
+        )
+    }
+}
+
 fn megabytes(size: usize) -> String {
     format!("{:.2} MB", size as f64 / 1e6)
 }
@@ -2,7 +2,7 @@
 
 use super::*;
 
-/// Describes the width and color of a line.
+/// Describes the width and color of a line, and whether it is solid, dashed or dotted.
 ///
 /// The default stroke is the same as [`Stroke::none`].
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
@@ -10,6 +10,41 @@ use super::*;
 pub struct Stroke {
     pub width: f32,
     pub color: Color32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub kind: StrokeKind,
+}
+
+/// Is a [`Stroke`] solid, dashed or dotted?
+///
+/// The dash pattern follows the path around corners,
+/// and continues across the start of closed paths (like rectangles and circles).
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum StrokeKind {
+    Solid,
+
+    /// Dashes of `dash_length` separated by gaps of `gap_length`, all in points.
+    ///
+    /// `offset` is how far into the pattern the path starts.
+    Dashed {
+        dash_length: f32,
+        gap_length: f32,
+        offset: f32,
+    },
+
+    /// Round dots with a diameter of the stroke width, with their centers `spacing` points apart.
+    ///
+    /// `offset` is how far into the pattern the path starts.
+    Dotted {
+        spacing: f32,
+        offset: f32,
+    },
+}
+
+impl Default for StrokeKind {
+    fn default() -> Self {
+        Self::Solid
+    }
 }
 
 impl Stroke {
@@ -24,14 +59,76 @@ impl Stroke {
         Self {
             width: width.into(),
             color: color.into(),
+            kind: StrokeKind::Solid,
         }
     }
 
+    /// Make this a dashed stroke.
+    ///
+    /// ```
+    /// # use epaint::*;
+    /// let stroke = Stroke::new(1.0, Color32::WHITE).dashed(4.0, 2.0);
+    /// ```
+    #[inline]
+    pub fn dashed(mut self, dash_length: f32, gap_length: f32) -> Self {
+        self.kind = StrokeKind::Dashed {
+            dash_length,
+            gap_length,
+            offset: 0.0,
+        };
+        self
+    }
+
+    /// Make this a dotted stroke, with the dot centers `spacing` points apart.
+    #[inline]
+    pub fn dotted(mut self, spacing: f32) -> Self {
+        self.kind = StrokeKind::Dotted {
+            spacing,
+            offset: 0.0,
+        };
+        self
+    }
+
+    /// Shift the dash or dot pattern this many points along the path.
+    ///
+    /// Has no effect on solid strokes.
+    #[inline]
+    pub fn dash_offset(mut self, new_offset: f32) -> Self {
+        match &mut self.kind {
+            StrokeKind::Solid => {}
+            StrokeKind::Dashed { offset, .. } | StrokeKind::Dotted { offset, .. } => {
+                *offset = new_offset;
+            }
+        }
+        self
+    }
+
     /// True if width is zero or color is transparent
     #[inline]
     pub fn is_empty(&self) -> bool {
         self.width <= 0.0 || self.color == Color32::TRANSPARENT
     }
+
+    /// Scale the width and the dash pattern, in-place.
+    pub(crate) fn scale(&mut self, factor: f32) {
+        self.width *= factor;
+        match &mut self.kind {
+            StrokeKind::Solid => {}
+            StrokeKind::Dashed {
+                dash_length,
+                gap_length,
+                offset,
+            } => {
+                *dash_length *= factor;
+                *gap_length *= factor;
+                *offset *= factor;
+            }
+            StrokeKind::Dotted { spacing, offset } => {
+                *spacing *= factor;
+                *offset *= factor;
+            }
+        }
+    }
 }
 
 impl<Color> From<(f32, Color)> for Stroke
@@ -44,11 +141,86 @@ where
     }
 }
 
+/// A separate [`Stroke`] for each side of a rectangle,
+/// e.g. for a tab that should have no border towards its contents.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct SideStrokes {
+    pub left: Stroke,
+    pub top: Stroke,
+    pub right: Stroke,
+    pub bottom: Stroke,
+}
+
+impl SideStrokes {
+    /// The same stroke on all four sides.
+    #[inline]
+    pub fn same(stroke: Stroke) -> Self {
+        Self {
+            left: stroke,
+            top: stroke,
+            right: stroke,
+            bottom: stroke,
+        }
+    }
+
+    /// The strokes in clockwise order: `[left, top, right, bottom]`.
+    #[inline]
+    pub fn to_array(&self) -> [Stroke; 4] {
+        [self.left, self.top, self.right, self.bottom]
+    }
+
+    /// The width of the widest of the strokes.
+    #[inline]
+    pub fn max_width(&self) -> f32 {
+        self.left
+            .width
+            .max(self.top.width)
+            .max(self.right.width)
+            .max(self.bottom.width)
+    }
+
+    /// Scale the width and the dash pattern of each side, in-place.
+    pub(crate) fn scale(&mut self, factor: f32) {
+        self.left.scale(factor);
+        self.top.scale(factor);
+        self.right.scale(factor);
+        self.bottom.scale(factor);
+    }
+}
+
+impl From<Stroke> for SideStrokes {
+    #[inline]
+    fn from(stroke: Stroke) -> Self {
+        Self::same(stroke)
+    }
+}
+
 impl std::hash::Hash for Stroke {
     #[inline(always)]
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        let Self { width, color } = *self;
+        let Self { width, color, kind } = *self;
         crate::f32_hash(state, width);
         color.hash(state);
+        match kind {
+            StrokeKind::Solid => {
+                0_u8.hash(state);
+            }
+            StrokeKind::Dashed {
+                dash_length,
+                gap_length,
+                offset,
+            } => {
+                1_u8.hash(state);
+                crate::f32_hash(state, dash_length);
+                crate::f32_hash(state, gap_length);
+                crate::f32_hash(state, offset);
+            }
+            StrokeKind::Dotted { spacing, offset } => {
+                2_u8.hash(state);
+                crate::f32_hash(state, spacing);
+                crate::f32_hash(state, offset);
+            }
+        }
     }
 }
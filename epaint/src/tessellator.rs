@@ -175,6 +175,16 @@ impl Path {
     pub fn fill(&self, color: Color32, options: &TessellationOptions, out: &mut Mesh) {
         fill_closed_path(&self.0, color, options, out);
     }
+
+    /// The path is taken to be closed (i.e. returning to the start again).
+    pub fn fill_with_gradient(
+        &self,
+        gradient: &Gradient,
+        options: &TessellationOptions,
+        out: &mut Mesh,
+    ) {
+        fill_closed_path_with_gradient(&self.0, gradient, options, out);
+    }
 }
 
 pub mod path {
@@ -182,17 +192,18 @@ pub mod path {
     use super::*;
 
     /// overwrites existing points
-    pub fn rounded_rectangle(path: &mut Vec<Pos2>, rect: Rect, corner_radius: f32) {
+    pub fn rounded_rectangle(path: &mut Vec<Pos2>, rect: Rect, rounding: Rounding) {
         path.clear();
 
         let min = rect.min;
         let max = rect.max;
 
-        let cr = corner_radius
-            .min(rect.width() * 0.5)
-            .min(rect.height() * 0.5);
+        let r = rounding
+            .at_most(rect.width() * 0.5)
+            .at_most(rect.height() * 0.5)
+            .at_least(0.0);
 
-        if cr <= 0.0 {
+        if r == Rounding::none() {
             let min = rect.min;
             let max = rect.max;
             path.reserve(4);
@@ -201,11 +212,65 @@ pub mod path {
             path.push(pos2(max.x, max.y));
             path.push(pos2(min.x, max.y));
         } else {
-            add_circle_quadrant(path, pos2(max.x - cr, max.y - cr), cr, 0.0);
-            add_circle_quadrant(path, pos2(min.x + cr, max.y - cr), cr, 1.0);
-            add_circle_quadrant(path, pos2(min.x + cr, min.y + cr), cr, 2.0);
-            add_circle_quadrant(path, pos2(max.x - cr, min.y + cr), cr, 3.0);
+            add_circle_quadrant(path, pos2(max.x - r.se, max.y - r.se), r.se, 0.0);
+            add_circle_quadrant(path, pos2(min.x + r.sw, max.y - r.sw), r.sw, 1.0);
+            add_circle_quadrant(path, pos2(min.x + r.nw, min.y + r.nw), r.nw, 2.0);
+            add_circle_quadrant(path, pos2(max.x - r.ne, min.y + r.ne), r.ne, 3.0);
+        }
+    }
+
+    /// The outline of each side of a rounded rectangle, as open paths in clockwise order:
+    /// `[left, top, right, bottom]`.
+    ///
+    /// Each side ends in the middle of the rounded corners next to it.
+    /// At sharp corners the sides are extended by half the width of the neighboring side's stroke,
+    /// so that the two strokes meet without a notch.
+    pub fn rounded_rectangle_sides(
+        rect: Rect,
+        rounding: Rounding,
+        side_strokes: &SideStrokes,
+    ) -> [Vec<Pos2>; 4] {
+        let min = rect.min;
+        let max = rect.max;
+
+        let r = rounding
+            .at_most(rect.width() * 0.5)
+            .at_most(rect.height() * 0.5)
+            .at_least(0.0);
+
+        // The corner at the start of each side, going clockwise:
+        let corners = [
+            (pos2(min.x + r.sw, max.y - r.sw), r.sw, 1.0),
+            (pos2(min.x + r.nw, min.y + r.nw), r.nw, 2.0),
+            (pos2(max.x - r.ne, min.y + r.ne), r.ne, 3.0),
+            (pos2(max.x - r.se, max.y - r.se), r.se, 0.0),
+        ];
+        let arcs = corners.map(|(center, radius, quadrant)| {
+            let mut arc = vec![];
+            add_circle_quadrant(&mut arc, center, radius, quadrant);
+            arc
+        });
+        let directions = [Vec2::UP, Vec2::RIGHT, Vec2::DOWN, Vec2::LEFT];
+        let widths = side_strokes.to_array().map(|stroke| stroke.width);
+
+        let mut sides = [vec![], vec![], vec![], vec![]];
+        for (i, side) in sides.iter_mut().enumerate() {
+            let next = (i + 1) % 4;
+            let (start, end) = (&arcs[i], &arcs[next]);
+            side.extend_from_slice(&start[start.len() / 2..]);
+            side.extend_from_slice(&end[..=end.len() / 2]);
+
+            if start.len() == 1 {
+                let previous = (i + 3) % 4;
+                side[0] -= 0.5 * widths[previous] * directions[i];
+            }
+            if end.len() == 1 {
+                let last = side.len() - 1;
+                side[last] += 0.5 * widths[next] * directions[i];
+            }
+            side.dedup();
         }
+        sides
     }
 
     /// Add one quadrant of a circle
@@ -214,6 +279,8 @@ pub mod path {
     /// * quadrant 1: left bottom
     /// * quadrant 2: left top
     /// * quadrant 3: right top
+    ///
+    /// A `radius` of zero (or less) adds just the corner point.
     //
     // Derivation:
     //
@@ -229,6 +296,11 @@ pub mod path {
     pub fn add_circle_quadrant(path: &mut Vec<Pos2>, center: Pos2, radius: f32, quadrant: f32) {
         // TODO: optimize with precalculated vertices for some radii ranges
 
+        if radius <= 0.0 {
+            path.push(center);
+            return;
+        }
+
         let n = (radius * 0.75).round() as i32; // TODO: tweak a bit more
         let n = n.clamp(2, 32);
         const RIGHT_ANGLE: f32 = TAU / 4.0;
@@ -286,6 +358,10 @@ pub struct TessellationOptions {
 
     /// If true, no clipping will be done.
     pub debug_ignore_clip_rects: bool,
+
+    /// Bézier curves are flattened into lines which deviate at most this much
+    /// from the true curve, in physical pixels.
+    pub bezier_tolerance: f32,
 }
 
 impl Default for TessellationOptions {
@@ -299,6 +375,7 @@ impl Default for TessellationOptions {
             debug_paint_text_rects: false,
             debug_paint_clip_rects: false,
             debug_ignore_clip_rects: false,
+            bezier_tolerance: 0.1,
         }
     }
 }
@@ -369,6 +446,208 @@ fn fill_closed_path(
     }
 }
 
+/// Tessellate the given convex area into a polygon, filled with a gradient.
+fn fill_closed_path_with_gradient(
+    path: &[PathPoint],
+    gradient: &Gradient,
+    options: &TessellationOptions,
+    out: &mut Mesh,
+) {
+    if gradient.is_transparent() {
+        return;
+    }
+
+    // Fill with white so that the alpha of each vertex is its coverage
+    // (i.e. we keep the anti-aliasing), then color it with the gradient:
+    let first_vertex = out.vertices.len();
+    let first_index = out.indices.len();
+    fill_closed_path(path, Color32::WHITE, options, out);
+    color_with_gradient(gradient, out, first_vertex, first_index);
+}
+
+/// A [`Vertex`] that has yet to be colored by a [`Gradient`].
+#[derive(Clone, Copy)]
+struct GradientVertex {
+    pos: Pos2,
+    uv: Pos2,
+    /// The alpha of the white fill, i.e. how much of the pixel is covered.
+    coverage: f32,
+    /// The gradient parameter at `pos`.
+    t: f32,
+}
+
+/// Stop subdividing a radial gradient when `t` is this close to linear over a triangle.
+const GRADIENT_TOLERANCE: f32 = 0.01;
+
+/// Never subdivide edges shorter than this (in points).
+const GRADIENT_MIN_EDGE_LENGTH: f32 = 0.5;
+
+/// Replace the colors of the triangles added since `first_index` with the gradient.
+///
+/// The GPU interpolates colors linearly over each triangle, so we first subdivide the triangles
+/// until `t` is close to linear over each of them (only needed for radial gradients),
+/// and then cut them along each color stop.
+fn color_with_gradient(
+    gradient: &Gradient,
+    out: &mut Mesh,
+    first_vertex: usize,
+    first_index: usize,
+) {
+    let mut vertices: Vec<GradientVertex> = out.vertices[first_vertex..]
+        .iter()
+        .map(|v| GradientVertex {
+            pos: v.pos,
+            uv: v.uv,
+            coverage: v.color.a() as f32 / 255.0,
+            t: gradient.t_at(v.pos),
+        })
+        .collect();
+    let mut triangles: Vec<[u32; 3]> = out.indices[first_index..]
+        .chunks_exact(3)
+        .map(|tri| {
+            let offset = first_vertex as u32;
+            [tri[0] - offset, tri[1] - offset, tri[2] - offset]
+        })
+        .collect();
+    out.vertices.truncate(first_vertex);
+    out.indices.truncate(first_index);
+
+    if !gradient.is_linear() {
+        triangles = subdivide_for_gradient(gradient, &mut vertices, triangles);
+    }
+
+    let stops: Vec<f32> = gradient.stops.iter().map(|(t, _)| *t).collect();
+    let mut polygon = vec![];
+    let mut below = vec![];
+    let mut above = vec![];
+    for triangle in triangles {
+        polygon.clear();
+        polygon.extend_from_slice(&triangle);
+        let t_min = triangle
+            .iter()
+            .map(|&i| vertices[i as usize].t)
+            .fold(f32::INFINITY, f32::min);
+        let t_max = triangle
+            .iter()
+            .map(|&i| vertices[i as usize].t)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let mut last_stop = f32::NEG_INFINITY;
+        for &stop in &stops {
+            if stop <= t_min || t_max <= stop || stop == last_stop {
+                continue;
+            }
+            last_stop = stop;
+            split_polygon_at(&mut vertices, &polygon, stop, &mut below, &mut above);
+            add_triangle_fan(out, first_vertex as u32, &below);
+            std::mem::swap(&mut polygon, &mut above);
+        }
+        add_triangle_fan(out, first_vertex as u32, &polygon);
+    }
+
+    out.vertices.extend(vertices.iter().map(|v| {
+        let color = gradient.color_at_t(v.t);
+        Vertex {
+            pos: v.pos,
+            uv: v.uv,
+            color: if v.coverage < 1.0 {
+                color.linear_multiply(v.coverage)
+            } else {
+                color
+            },
+        }
+    }));
+}
+
+/// Bisect edges until the gradient parameter is close to linear over each triangle.
+///
+/// Whether or not an edge is split only depends on the edge itself,
+/// so neighboring triangles agree and no cracks appear.
+fn subdivide_for_gradient(
+    gradient: &Gradient,
+    vertices: &mut Vec<GradientVertex>,
+    mut triangles: Vec<[u32; 3]>,
+) -> Vec<[u32; 3]> {
+    let needs_split = |a: &GradientVertex, b: &GradientVertex| {
+        let mid = pos2(0.5 * (a.pos.x + b.pos.x), 0.5 * (a.pos.y + b.pos.y));
+        (a.pos - b.pos).length_sq() > GRADIENT_MIN_EDGE_LENGTH * GRADIENT_MIN_EDGE_LENGTH
+            && (gradient.t_at(mid) - 0.5 * (a.t + b.t)).abs() > GRADIENT_TOLERANCE
+    };
+
+    let mut result = Vec::with_capacity(triangles.len());
+    while let Some(triangle) = triangles.pop() {
+        let split = (0..3).find(|&i| {
+            let a = &vertices[triangle[i] as usize];
+            let b = &vertices[triangle[(i + 1) % 3] as usize];
+            needs_split(a, b)
+        });
+        if let Some(i) = split {
+            let [a, b, c] = [triangle[i], triangle[(i + 1) % 3], triangle[(i + 2) % 3]];
+            let (va, vb) = (vertices[a as usize], vertices[b as usize]);
+            let pos = pos2(0.5 * (va.pos.x + vb.pos.x), 0.5 * (va.pos.y + vb.pos.y));
+            let m = vertices.len() as u32;
+            vertices.push(GradientVertex {
+                pos,
+                uv: pos2(0.5 * (va.uv.x + vb.uv.x), 0.5 * (va.uv.y + vb.uv.y)),
+                coverage: 0.5 * (va.coverage + vb.coverage),
+                t: gradient.t_at(pos),
+            });
+            triangles.push([a, m, c]);
+            triangles.push([m, b, c]);
+        } else {
+            result.push(triangle);
+        }
+    }
+    result
+}
+
+/// Split a convex polygon in two along the line where `t == stop`.
+fn split_polygon_at(
+    vertices: &mut Vec<GradientVertex>,
+    polygon: &[u32],
+    stop: f32,
+    below: &mut Vec<u32>,
+    above: &mut Vec<u32>,
+) {
+    below.clear();
+    above.clear();
+    for i in 0..polygon.len() {
+        let a = polygon[i];
+        let b = polygon[(i + 1) % polygon.len()];
+        let (va, vb) = (vertices[a as usize], vertices[b as usize]);
+        if va.t <= stop {
+            below.push(a);
+        }
+        if va.t >= stop {
+            above.push(a);
+        }
+        if (va.t < stop && stop < vb.t) || (vb.t < stop && stop < va.t) {
+            // Always interpolate in the same direction, so that the triangle on the other side
+            // of the edge gets the exact same vertex:
+            let (lo, hi) = if va.t < vb.t { (va, vb) } else { (vb, va) };
+            let f = (stop - lo.t) / (hi.t - lo.t);
+            let m = vertices.len() as u32;
+            vertices.push(GradientVertex {
+                pos: lo.pos + f * (hi.pos - lo.pos),
+                uv: lo.uv + f * (hi.uv - lo.uv),
+                coverage: lo.coverage + f * (hi.coverage - lo.coverage),
+                t: stop,
+            });
+            below.push(m);
+            above.push(m);
+        }
+    }
+}
+
+fn add_triangle_fan(out: &mut Mesh, offset: u32, polygon: &[u32]) {
+    for i in 2..polygon.len() {
+        out.add_triangle(
+            offset + polygon[0],
+            offset + polygon[i - 1],
+            offset + polygon[i],
+        );
+    }
+}
+
 /// Tessellate the given path as a stroke with thickness.
 fn stroke_path(
     path: &[PathPoint],
@@ -383,6 +662,34 @@ fn stroke_path(
         return;
     }
 
+    match stroke.kind {
+        StrokeKind::Solid => {}
+        StrokeKind::Dashed {
+            dash_length,
+            gap_length,
+            offset,
+        } => {
+            if dash_length > 0.0 && gap_length > 0.0 {
+                let solid = Stroke::new(stroke.width, stroke.color);
+                for_each_dash(path, path_type, [dash_length, gap_length], offset, |dash| {
+                    stroke_path(dash, PathType::Open, solid, options, out);
+                });
+                return;
+            }
+        }
+        StrokeKind::Dotted { spacing, offset } => {
+            if spacing > 0.0 {
+                let mut dot = Path::default();
+                for_each_dash(path, path_type, [0.0, spacing], offset, |dash| {
+                    dot.clear();
+                    dot.add_circle(dash[0].pos, 0.5 * stroke.width);
+                    dot.fill(stroke.color, options, out);
+                });
+                return;
+            }
+        }
+    }
+
     let idx = out.vertices.len() as u32;
 
     if options.anti_alias {
@@ -595,6 +902,135 @@ fn stroke_path(
     }
 }
 
+/// Shorter dashes (except for dots) and gaps are made this long.
+const MIN_DASH_LENGTH: f32 = 0.1;
+
+/// Patterns that would cut a path into more dashes than this are stretched to fit.
+const MAX_DASHES: f32 = 10_000.0;
+
+/// Cut a path into dashes, following the `[dash_length, gap_length]` pattern,
+/// starting `offset` points into the pattern.
+///
+/// Each dash keeps the corners of the path it passes, so it can be stroked as an open path.
+/// A `dash_length` of zero gives dashes consisting of a single point.
+fn for_each_dash(
+    path: &[PathPoint],
+    path_type: PathType,
+    [dash_length, gap_length]: [f32; 2],
+    offset: f32,
+    mut add_dash: impl FnMut(&[PathPoint]),
+) {
+    let period = dash_length + gap_length;
+    if period <= 0.0 || !period.is_finite() {
+        return;
+    }
+
+    let mut points: Vec<&PathPoint> = path.iter().collect();
+    if path_type == PathType::Closed {
+        points.push(&path[0]);
+    }
+
+    // Otherwise a tiny dash or gap may not move us along the path at all:
+    let dash_length = if dash_length > 0.0 {
+        dash_length.max(MIN_DASH_LENGTH)
+    } else {
+        0.0
+    };
+    let gap_length = gap_length.max(MIN_DASH_LENGTH);
+    let path_length: f32 = points
+        .windows(2)
+        .map(|segment| segment[0].pos.distance(segment[1].pos))
+        .sum();
+    let stretch = (path_length / (MAX_DASHES * (dash_length + gap_length))).at_least(1.0);
+    let [dash_length, gap_length] = [stretch * dash_length, stretch * gap_length];
+    let period = dash_length + gap_length;
+
+    let phase = offset.rem_euclid(period);
+    let mut in_dash = phase < dash_length || phase == 0.0;
+    let mut remaining = if in_dash {
+        dash_length - phase
+    } else {
+        period - phase
+    };
+    let starts_in_dash = in_dash;
+
+    let mut dashes: Vec<Vec<PathPoint>> = vec![];
+    let mut dash: Vec<PathPoint> = vec![];
+    if in_dash {
+        dash.push(points[0].clone());
+    }
+
+    for segment in points.windows(2) {
+        let (a, b) = (segment[0], segment[1]);
+        let segment_length = a.pos.distance(b.pos);
+        let normal = (b.pos - a.pos).normalized().rot90();
+        let mut t = 0.0; // How far along this segment we are
+        while remaining <= segment_length - t {
+            if remaining > 0.0 && t + remaining <= t {
+                break; // too small to move along a segment this long
+            }
+            t += remaining;
+            let pos = a.pos + (t / segment_length.max(f32::EPSILON)) * (b.pos - a.pos);
+            dash.push(PathPoint { pos, normal });
+            if in_dash {
+                dashes.push(std::mem::take(&mut dash));
+                remaining = gap_length;
+            } else {
+                remaining = dash_length;
+            }
+            in_dash = !in_dash;
+        }
+        remaining = (remaining - (segment_length - t)).at_least(0.0);
+        if in_dash {
+            dash.push(b.clone());
+        }
+    }
+
+    if in_dash && !dash.is_empty() {
+        if path_type == PathType::Closed && starts_in_dash && !dashes.is_empty() {
+            // Join the last dash with the first, so there is no seam at the start of the path:
+            let first = dashes.remove(0);
+            dash.extend(first.into_iter().skip(1));
+        }
+        dashes.push(dash);
+    }
+
+    for dash in &dashes {
+        add_dash(dash);
+    }
+}
+
+#[test]
+fn test_tiny_dashes() {
+    let line = |length: f32| {
+        let normal = vec2(0.0, 1.0);
+        [
+            PathPoint {
+                pos: pos2(0.0, 0.0),
+                normal,
+            },
+            PathPoint {
+                pos: pos2(length, 0.0),
+                normal,
+            },
+        ]
+    };
+    let count_dashes = |path: &[PathPoint], pattern: [f32; 2]| {
+        let mut count = 0;
+        for_each_dash(path, PathType::Open, pattern, 0.0, |_| count += 1);
+        count
+    };
+
+    // A dash much smaller than a float step at the end of the line:
+    assert_eq!(count_dashes(&line(200.0), [1e-6, 10.0]), 20);
+    assert_eq!(count_dashes(&line(200.0), [1e-6, 1e-6]), 1000);
+    assert_eq!(count_dashes(&line(200.0), [0.0, 10.0]), 21);
+
+    // Too many dashes are stretched to fit:
+    let count = count_dashes(&line(1e7), [1.0, 1.0]);
+    assert!(count <= MAX_DASHES as usize + 1);
+}
+
 fn mul_color(color: Color32, factor: f32) -> Color32 {
     crate::epaint_assert!(0.0 <= factor && factor <= 1.0);
     // As an unfortunate side-effect of using premultiplied alpha
@@ -648,6 +1084,7 @@ impl Tessellator {
                 center,
                 radius,
                 fill,
+                fill_gradient,
                 stroke,
             }) => {
                 if radius <= 0.0 {
@@ -662,7 +1099,12 @@ impl Tessellator {
 
                 self.scratchpad_path.clear();
                 self.scratchpad_path.add_circle(center, radius);
-                self.scratchpad_path.fill(fill, options, out);
+                if let Some(gradient) = &fill_gradient {
+                    self.scratchpad_path
+                        .fill_with_gradient(gradient, options, out);
+                } else {
+                    self.scratchpad_path.fill(fill, options, out);
+                }
                 self.scratchpad_path.stroke_closed(stroke, options, out);
             }
             Shape::Mesh(mesh) => {
@@ -700,6 +1142,12 @@ impl Tessellator {
             Shape::Rect(rect_shape) => {
                 self.tessellate_rect(&rect_shape, out);
             }
+            Shape::QuadraticBezier(bezier_shape) => {
+                self.tessellate_quadratic_bezier(bezier_shape, out);
+            }
+            Shape::CubicBezier(bezier_shape) => {
+                self.tessellate_cubic_bezier(bezier_shape, out);
+            }
             Shape::Text(text_shape) => {
                 if options.debug_paint_text_rects {
                     let rect = text_shape.galley.rect.translate(text_shape.pos.to_vec2());
@@ -708,7 +1156,7 @@ impl Tessellator {
                         out,
                     );
                 }
-                self.tessellate_text(tex_size, text_shape, out);
+                self.tessellate_text(tex_size, &text_shape, out);
             }
         }
     }
@@ -728,6 +1176,7 @@ impl Tessellator {
             points,
             closed,
             fill,
+            fill_gradient,
             stroke,
         } = path_shape;
 
@@ -738,7 +1187,14 @@ impl Tessellator {
             self.scratchpad_path.add_open_points(&points);
         }
 
-        if fill != Color32::TRANSPARENT {
+        if let Some(gradient) = &fill_gradient {
+            crate::epaint_assert!(
+                closed,
+                "You asked to fill a path that is not closed. That makes no sense."
+            );
+            self.scratchpad_path
+                .fill_with_gradient(gradient, &self.options, out);
+        } else if fill != Color32::TRANSPARENT {
             crate::epaint_assert!(
                 closed,
                 "You asked to fill a path that is not closed. That makes no sense."
@@ -753,16 +1209,53 @@ impl Tessellator {
         self.scratchpad_path.stroke(typ, stroke, &self.options, out);
     }
 
+    pub(crate) fn tessellate_quadratic_bezier(
+        &mut self,
+        bezier_shape: QuadraticBezierShape,
+        out: &mut Mesh,
+    ) {
+        if self.options.coarse_tessellation_culling
+            && !bezier_shape.bounding_rect().intersects(self.clip_rect)
+        {
+            return;
+        }
+
+        let path_shape = bezier_shape.to_path_shape(self.bezier_tolerance_in_points());
+        self.tessellate_path(path_shape, out);
+    }
+
+    pub(crate) fn tessellate_cubic_bezier(
+        &mut self,
+        bezier_shape: CubicBezierShape,
+        out: &mut Mesh,
+    ) {
+        if self.options.coarse_tessellation_culling
+            && !bezier_shape.bounding_rect().intersects(self.clip_rect)
+        {
+            return;
+        }
+
+        let path_shape = bezier_shape.to_path_shape(self.bezier_tolerance_in_points());
+        self.tessellate_path(path_shape, out);
+    }
+
+    fn bezier_tolerance_in_points(&self) -> f32 {
+        self.options.bezier_tolerance / self.options.pixels_per_point
+    }
+
     pub(crate) fn tessellate_rect(&mut self, rect: &RectShape, out: &mut Mesh) {
+        let stroke_width = rect.stroke_width();
         let RectShape {
             mut rect,
-            corner_radius,
+            rounding,
             fill,
+            ref fill_gradient,
             stroke,
+            ref side_strokes,
         } = *rect;
 
         if self.options.coarse_tessellation_culling
-            && !rect.expand(stroke.width).intersects(self.clip_rect)
+            && !rect.expand(stroke_width).intersects(self.clip_rect)
         {
             return;
         }
@@ -777,13 +1270,62 @@ impl Tessellator {
 
         let path = &mut self.scratchpad_path;
         path.clear();
-        path::rounded_rectangle(&mut self.scratchpad_points, rect, corner_radius);
+        path::rounded_rectangle(&mut self.scratchpad_points, rect, rounding);
         path.add_line_loop(&self.scratchpad_points);
-        path.fill(fill, &self.options, out);
-        path.stroke_closed(stroke, &self.options, out);
+        if let Some(gradient) = fill_gradient {
+            path.fill_with_gradient(gradient, &self.options, out);
+        } else {
+            path.fill(fill, &self.options, out);
+        }
+        if let Some(side_strokes) = side_strokes {
+            // Each side is its own open path, so it can have its own stroke:
+            let sides = path::rounded_rectangle_sides(rect, rounding, side_strokes);
+            for (points, stroke) in sides.iter().zip(side_strokes.to_array()) {
+                if points.len() < 2 {
+                    continue;
+                }
+                path.clear();
+                path.add_open_points(points);
+                path.stroke_open(stroke, &self.options, out);
+            }
+        } else {
+            path.stroke_closed(stroke, &self.options, out);
+        }
     }
 
-    pub fn tessellate_text(&mut self, tex_size: [usize; 2], text_shape: TextShape, out: &mut Mesh) {
+    /// Tessellate all the text, as if all pages of the font atlas were the same texture.
+    ///
+    /// This is only correct if the galley uses one page of the font atlas.
+    /// [`tessellate_shapes`] handles any number of pages.
+    pub fn tessellate_text(
+        &mut self,
+        tex_size: [usize; 2],
+        text_shape: &TextShape,
+        out: &mut Mesh,
+    ) {
+        self.tessellate_text_in_page(None, tex_size, text_shape, out);
+    }
+
+    /// Tessellate the parts of the text that use the given page (texture) of the font atlas.
+    ///
+    /// * `tex_size`: size of that page.
+    pub fn tessellate_text_page(
+        &mut self,
+        page: usize,
+        tex_size: [usize; 2],
+        text_shape: &TextShape,
+        out: &mut Mesh,
+    ) {
+        self.tessellate_text_in_page(Some(page), tex_size, text_shape, out);
+    }
+
+    fn tessellate_text_in_page(
+        &mut self,
+        page: Option<usize>,
+        tex_size: [usize; 2],
+        text_shape: &TextShape,
+        out: &mut Mesh,
+    ) {
         let TextShape {
             pos: galley_pos,
             galley,
@@ -791,6 +1333,8 @@ impl Tessellator {
             override_text_color,
             angle,
         } = text_shape;
+        let (galley_pos, underline, override_text_color, angle) =
+            (*galley_pos, *underline, *override_text_color, *angle);
 
         if galley.is_empty() {
             return;
@@ -827,15 +1371,26 @@ impl Tessellator {
                 continue;
             }
 
+            let page_index_ranges = &row.visuals.page_index_ranges;
+            let in_page = |row_page: usize| page.map_or(true, |page| page == row_page);
+            if !page_index_ranges
+                .iter()
+                .any(|(row_page, _)| in_page(*row_page))
+            {
+                continue;
+            }
+
             let index_offset = out.vertices.len() as u32;
 
-            out.indices.extend(
-                row.visuals
-                    .mesh
-                    .indices
-                    .iter()
-                    .map(|index| index + index_offset),
-            );
+            for (row_page, range) in page_index_ranges {
+                if in_page(*row_page) {
+                    out.indices.extend(
+                        row.visuals.mesh.indices[range.clone()]
+                            .iter()
+                            .map(|index| index + index_offset),
+                    );
+                }
+            }
 
             out.vertices.extend(
                 row.visuals
@@ -866,7 +1421,10 @@ impl Tessellator {
                     }),
             );
 
-            if underline != Stroke::none() {
+            let is_first_page = page_index_ranges
+                .first()
+                .map_or(false, |(row_page, _)| in_page(*row_page));
+            if underline != Stroke::none() && is_first_page {
                 self.scratchpad_path.clear();
                 self.scratchpad_path
                     .add_line_segment([row_rect.left_bottom(), row_rect.right_bottom()]);
@@ -884,7 +1442,8 @@ impl Tessellator {
 ///
 /// * `shapes`: what to tessellate
 /// * `options`: tessellation quality
-/// * `tex_size`: size of the font texture (required to normalize glyph uv rectangles)
+/// * `font_textures`: texture id and size of each page of the font atlas, in order
+///   (required to normalize glyph uv rectangles). See [`crate::Fonts::font_image_sizes`].
 ///
 /// The implementation uses a [`Tessellator`].
 ///
@@ -893,8 +1452,11 @@ impl Tessellator {
 pub fn tessellate_shapes(
     shapes: Vec<ClippedShape>,
     options: TessellationOptions,
-    tex_size: [usize; 2],
+    font_textures: &[(TextureId, [usize; 2])],
 ) -> Vec<ClippedMesh> {
+    crate::epaint_assert!(!font_textures.is_empty(), "Missing font texture");
+    let tex_size = font_textures.first().map_or([1, 1], |(_, size)| *size);
+
     let mut tessellator = Tessellator::from_options(options);
 
     let mut clipped_meshes: Vec<ClippedMesh> = Vec::default();
@@ -904,18 +1466,14 @@ pub fn tessellate_shapes(
             continue; // skip empty clip rectangles
         }
 
-        let start_new_mesh = match clipped_meshes.last() {
-            None => true,
-            Some(cm) => cm.0 != clip_rect || cm.1.texture_id != shape.texture_id(),
-        };
-
-        if start_new_mesh {
-            clipped_meshes.push(ClippedMesh(clip_rect, Mesh::default()));
-        }
-
-        let out = &mut clipped_meshes.last_mut().unwrap().1;
         tessellator.clip_rect = clip_rect;
-        tessellator.tessellate_shape(tex_size, shape, out);
+        tessellate_clipped_shape(
+            &mut tessellator,
+            font_textures,
+            clip_rect,
+            shape,
+            &mut clipped_meshes,
+        );
     }
 
     if options.debug_paint_clip_rects {
@@ -945,3 +1503,71 @@ pub fn tessellate_shapes(
 
     clipped_meshes
 }
+
+/// Tessellate into the last of `clipped_meshes`,
+/// or a new one if the clip rectangle or texture is different.
+///
+/// Text that uses several pages of the font atlas is split into one mesh per page.
+fn tessellate_clipped_shape(
+    tessellator: &mut Tessellator,
+    font_textures: &[(TextureId, [usize; 2])],
+    clip_rect: Rect,
+    shape: Shape,
+    clipped_meshes: &mut Vec<ClippedMesh>,
+) {
+    fn mesh_for(
+        clipped_meshes: &mut Vec<ClippedMesh>,
+        clip_rect: Rect,
+        texture_id: TextureId,
+    ) -> &mut Mesh {
+        let start_new_mesh = match clipped_meshes.last() {
+            None => true,
+            Some(cm) => cm.0 != clip_rect || cm.1.texture_id != texture_id,
+        };
+
+        if start_new_mesh {
+            clipped_meshes.push(ClippedMesh(clip_rect, Mesh::with_texture(texture_id)));
+        }
+
+        &mut clipped_meshes.last_mut().unwrap().1
+    }
+
+    let font_texture = |page: usize| {
+        let font_texture = font_textures.get(page).copied();
+        crate::epaint_assert!(font_texture.is_some(), "Missing font texture {}", page);
+        font_texture.unwrap_or((TextureId::default(), [1, 1]))
+    };
+
+    match shape {
+        Shape::Vec(shapes) => {
+            for shape in shapes {
+                tessellate_clipped_shape(
+                    tessellator,
+                    font_textures,
+                    clip_rect,
+                    shape,
+                    clipped_meshes,
+                );
+            }
+        }
+        Shape::Text(text_shape) => {
+            let pages = text_shape.galley.atlas_pages();
+            if pages.len() <= 1 {
+                let (texture_id, tex_size) = font_texture(pages.first().copied().unwrap_or(0));
+                let out = mesh_for(clipped_meshes, clip_rect, texture_id);
+                tessellator.tessellate_shape(tex_size, Shape::Text(text_shape), out);
+            } else {
+                for page in pages {
+                    let (texture_id, tex_size) = font_texture(page);
+                    let out = mesh_for(clipped_meshes, clip_rect, texture_id);
+                    tessellator.tessellate_text_page(page, tex_size, &text_shape, out);
+                }
+            }
+        }
+        shape => {
+            let (_, tex_size) = font_texture(0);
+            let out = mesh_for(clipped_meshes, clip_rect, shape.texture_id());
+            tessellator.tessellate_shape(tex_size, shape, out);
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! The Unicode Bidirectional Algorithm, for mixing left-to-right and right-to-left text
+//! (e.g. English and Hebrew) in the same paragraph.
+//!
+//! Text is first laid out in logical order (the order of the text),
+//! and then each row is reordered into visual order with [`reorder_row`].
+//! [`super::Glyph::bidi_level`] remembers the direction of each glyph,
+//! so that cursors can be placed on the correct side of it.
+
+use std::ops::Range;
+
+use super::{Row, TextDirection};
+
+/// The bidi embedding level of each byte of a text.
+///
+/// Even levels are left-to-right, odd levels are right-to-left.
+pub(crate) struct BidiLevels {
+    /// One per byte, or empty if everything is left-to-right.
+    levels: Vec<u8>,
+
+    /// The byte range and level of each paragraph. Empty if everything is left-to-right.
+    paragraphs: Vec<(Range<usize>, u8)>,
+}
+
+impl BidiLevels {
+    pub fn new(text: &str, base_direction: Option<TextDirection>) -> Self {
+        let all_ltr = Self {
+            levels: vec![],
+            paragraphs: vec![],
+        };
+
+        let base_level = match base_direction {
+            None => None,
+            Some(TextDirection::LeftToRight) => Some(unicode_bidi::Level::ltr()),
+            Some(TextDirection::RightToLeft) => Some(unicode_bidi::Level::rtl()),
+        };
+
+        if text.is_ascii() && base_direction != Some(TextDirection::RightToLeft) {
+            return all_ltr; // early-out optimization
+        }
+
+        let info = unicode_bidi::BidiInfo::new(text, base_level);
+        let paragraphs: Vec<(Range<usize>, u8)> = info
+            .paragraphs
+            .iter()
+            .map(|paragraph| (paragraph.range.clone(), paragraph.level.number()))
+            .collect();
+
+        if !info.has_rtl() && paragraphs.iter().all(|(_, level)| *level == 0) {
+            return all_ltr;
+        }
+
+        Self {
+            levels: info.levels.iter().map(|level| level.number()).collect(),
+            paragraphs,
+        }
+    }
+
+    /// The levels of the given byte range, or an empty slice if they are all zero.
+    pub fn levels(&self, byte_range: Range<usize>) -> &[u8] {
+        if self.levels.is_empty() {
+            &[]
+        } else {
+            &self.levels[byte_range]
+        }
+    }
+
+    /// The level of the paragraph containing the given byte.
+    pub fn paragraph_level(&self, byte_index: usize) -> u8 {
+        self.paragraphs
+            .iter()
+            .find(|(range, _)| byte_index < range.end)
+            .or_else(|| self.paragraphs.last())
+            .map_or(0, |(_, level)| *level)
+    }
+}
+
+/// Level of the `char` starting at `byte_index`, as returned by [`BidiLevels::levels`].
+#[inline]
+pub(crate) fn level_at(levels: &[u8], byte_index: usize) -> u8 {
+    levels.get(byte_index).copied().unwrap_or(0)
+}
+
+/// Right-to-left text uses mirrored brackets, so that `(` still opens a parenthesis.
+///
+/// The text shaper does this by itself, so this is only used for unshaped text.
+pub(crate) fn mirrored(chr: char) -> char {
+    match chr {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        '‹' => '›',
+        '›' => '‹',
+        _ => chr,
+    }
+}
+
+/// Move the glyphs of a row (which are in logical order) to their visual positions.
+///
+/// The glyphs stay in logical order in [`Row::glyphs`], only their x positions change.
+/// Shaped clusters are moved as a unit.
+pub(crate) fn reorder_row(row: &mut Row, paragraph_level: u8) {
+    if paragraph_level == 0 && row.glyphs.iter().all(|glyph| glyph.bidi_level == 0) {
+        return; // early-out optimization
+    }
+
+    // Rule L1: trailing whitespace goes to the paragraph level:
+    for glyph in row.glyphs.iter_mut().rev() {
+        if glyph.chr.is_whitespace() {
+            glyph.bidi_level = paragraph_level;
+        } else {
+            break;
+        }
+    }
+
+    // Clusters, as ranges of glyph indices:
+    let mut clusters: Vec<Range<usize>> = vec![];
+    for (i, glyph) in row.glyphs.iter().enumerate() {
+        match clusters.last_mut() {
+            Some(cluster) if glyph.continues_cluster => cluster.end = i + 1,
+            _ => clusters.push(i..i + 1),
+        }
+    }
+
+    let widths: Vec<f32> = clusters
+        .iter()
+        .map(|cluster| {
+            let start_x = row.glyphs[cluster.start].pos.x;
+            match row.glyphs.get(cluster.end) {
+                Some(next) => next.pos.x - start_x,
+                None => row.glyphs[cluster.end - 1].max_x() - start_x,
+            }
+        })
+        .collect();
+
+    // Rule L2: from the highest level down to the lowest odd level,
+    // reverse any contiguous sequence of clusters at that level or higher.
+    let mut order: Vec<usize> = (0..clusters.len()).collect();
+    let level_of = |cluster_index: usize| row.glyphs[clusters[cluster_index].start].bidi_level;
+    let max_level = order.iter().map(|&c| level_of(c)).max().unwrap_or(0);
+    let min_odd_level = order
+        .iter()
+        .map(|&c| level_of(c))
+        .filter(|level| level % 2 == 1)
+        .min()
+        .unwrap_or(max_level + 1);
+    for level in (min_odd_level..=max_level).rev() {
+        let mut i = 0;
+        while i < order.len() {
+            if level_of(order[i]) >= level {
+                let start = i;
+                while i < order.len() && level_of(order[i]) >= level {
+                    i += 1;
+                }
+                order[start..i].reverse();
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    let mut x = row.glyphs[0].pos.x;
+    for &cluster_index in &order {
+        let cluster = clusters[cluster_index].clone();
+        let width = widths[cluster_index];
+        let old_start_x = row.glyphs[cluster.start].pos.x;
+        let rtl = row.glyphs[cluster.start].is_rtl();
+
+        for i in cluster.clone() {
+            let glyph = &mut row.glyphs[i];
+            if rtl {
+                // Mirror the `char`s inside of the cluster, so that the first one is rightmost:
+                glyph.pos.x = x + width - (glyph.max_x() - old_start_x);
+            } else {
+                glyph.pos.x = x + (glyph.pos.x - old_start_x);
+            }
+        }
+
+        if rtl && cluster.len() > 1 {
+            // The first `char` paints the whole cluster, so keep the painted glyphs in place:
+            let first = cluster.start;
+            let shift = row.glyphs[first].pos.x - x;
+            row.glyphs[first].uv_rect.offset.x -= shift;
+            for (index, uv_rect) in &mut row.extra_glyphs {
+                if *index == first {
+                    uv_rect.offset.x -= shift;
+                }
+            }
+        }
+
+        x += width;
+    }
+}
@@ -0,0 +1,332 @@
+//! Color glyphs, e.g. emojis.
+//!
+//! We support two kinds of color fonts:
+//! * `COLR`/`CPAL` (version 0): each glyph is a stack of outlined glyphs, each with a solid color.
+//! * `CBDT` and `sbix`: each glyph is a PNG image. Requires the `color_emoji` feature.
+//!
+//! Color glyphs are rasterized into the RGBA page of the [`crate::TextureAtlas`],
+//! and are not tinted by the text color.
+
+use ahash::AHashMap;
+use emath::{vec2, Vec2};
+
+use crate::{Color32, ColorImage, Rgba};
+
+/// The color glyphs of a font.
+pub(crate) struct ColorGlyphs {
+    /// `COLR` layers of each base glyph, from bottom to top.
+    layers: AHashMap<u16, Vec<(ab_glyph::GlyphId, Color32)>>,
+
+    /// Does the font have `CBDT` or `sbix` bitmaps?
+    #[cfg(feature = "color_emoji")]
+    has_bitmaps: bool,
+}
+
+/// A rasterized color glyph.
+pub(crate) struct ColorGlyphImage {
+    /// Position of the top left corner relative to the glyph origin on the baseline, in pixels.
+    pub min: Vec2,
+    pub image: ColorImage,
+}
+
+impl ColorGlyphs {
+    /// Returns `None` if the font has no color glyphs that we can show.
+    pub fn parse(font: &[u8], index: u32) -> Option<Self> {
+        let layers = font_table(font, index, b"COLR")
+            .zip(font_table(font, index, b"CPAL"))
+            .and_then(|(colr, cpal)| parse_colr(colr, cpal))
+            .unwrap_or_default();
+
+        #[cfg(feature = "color_emoji")]
+        let has_bitmaps = font_table(font, index, b"CBDT").is_some()
+            || font_table(font, index, b"sbix").is_some();
+        #[cfg(not(feature = "color_emoji"))]
+        let has_bitmaps = false;
+
+        if layers.is_empty() && !has_bitmaps {
+            None
+        } else {
+            Some(Self {
+                layers,
+                #[cfg(feature = "color_emoji")]
+                has_bitmaps,
+            })
+        }
+    }
+
+    /// Rasterize the glyph if it is a color glyph.
+    pub fn rasterize(
+        &self,
+        font: &ab_glyph::FontArc,
+        glyph_id: ab_glyph::GlyphId,
+        scale_in_pixels: f32,
+    ) -> Option<ColorGlyphImage> {
+        if let Some(layers) = self.layers.get(&glyph_id.0) {
+            return rasterize_layers(font, layers, scale_in_pixels);
+        }
+
+        #[cfg(feature = "color_emoji")]
+        if self.has_bitmaps {
+            return rasterize_bitmap(font, glyph_id, scale_in_pixels);
+        }
+
+        None
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Find a table in a `.ttf`/`.otf` file (or a `.ttc` collection).
+fn font_table<'a>(font: &'a [u8], index: u32, tag: &[u8; 4]) -> Option<&'a [u8]> {
+    let start = if font.get(0..4)? == b"ttcf" {
+        read_u32(font, 12 + 4 * index as usize)? as usize
+    } else {
+        0
+    };
+
+    let num_tables = read_u16(font, start + 4)? as usize;
+    (0..num_tables).find_map(|i| {
+        let record = start + 12 + 16 * i;
+        if font.get(record..record + 4)? == tag {
+            let offset = read_u32(font, record + 8)? as usize;
+            let length = read_u32(font, record + 12)? as usize;
+            font.get(offset..offset + length)
+        } else {
+            None
+        }
+    })
+}
+
+/// Parse version 0 of the `COLR` table, with colors from the first palette in `CPAL`.
+fn parse_colr(
+    colr: &[u8],
+    cpal: &[u8],
+) -> Option<AHashMap<u16, Vec<(ab_glyph::GlyphId, Color32)>>> {
+    let num_base_glyphs = read_u16(colr, 2)? as usize;
+    let base_glyphs_offset = read_u32(colr, 4)? as usize;
+    let layers_offset = read_u32(colr, 8)? as usize;
+
+    let colors_offset = read_u32(cpal, 8)? as usize;
+    let first_color = read_u16(cpal, 12)? as usize; // of the first palette
+    let palette_color = |palette_index: u16| -> Option<Color32> {
+        if palette_index == 0xFFFF {
+            // Means "the text color", but we don't tint color glyphs.
+            return Some(Color32::WHITE);
+        }
+        let offset = colors_offset + 4 * (first_color + palette_index as usize);
+        let bgra = cpal.get(offset..offset + 4)?;
+        Some(Color32::from_rgba_unmultiplied(
+            bgra[2], bgra[1], bgra[0], bgra[3],
+        ))
+    };
+
+    let mut glyphs = AHashMap::default();
+    for i in 0..num_base_glyphs {
+        let record = base_glyphs_offset + 6 * i;
+        let base_glyph = read_u16(colr, record)?;
+        let first_layer = read_u16(colr, record + 2)? as usize;
+        let num_layers = read_u16(colr, record + 4)? as usize;
+
+        let layers = (first_layer..first_layer + num_layers)
+            .map(|layer| {
+                let record = layers_offset + 4 * layer;
+                let glyph_id = read_u16(colr, record)?;
+                let color = palette_color(read_u16(colr, record + 2)?)?;
+                Some((ab_glyph::GlyphId(glyph_id), color))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        glyphs.insert(base_glyph, layers);
+    }
+    Some(glyphs)
+}
+
+fn rasterize_layers(
+    font: &ab_glyph::FontArc,
+    layers: &[(ab_glyph::GlyphId, Color32)],
+    scale_in_pixels: f32,
+) -> Option<ColorGlyphImage> {
+    use ab_glyph::Font as _;
+
+    let outlines: Vec<(ab_glyph::OutlinedGlyph, Rgba)> = layers
+        .iter()
+        .filter_map(|&(glyph_id, color)| {
+            let glyph = glyph_id
+                .with_scale_and_position(scale_in_pixels, ab_glyph::Point { x: 0.0, y: 0.0 });
+            Some((font.outline_glyph(glyph)?, Rgba::from(color)))
+        })
+        .collect();
+
+    let (min_x, min_y, max_x, max_y) = outlines.iter().fold(
+        (
+            f32::INFINITY,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::NEG_INFINITY,
+        ),
+        |(min_x, min_y, max_x, max_y), (outline, _)| {
+            let bb = outline.px_bounds();
+            (
+                min_x.min(bb.min.x),
+                min_y.min(bb.min.y),
+                max_x.max(bb.max.x),
+                max_y.max(bb.max.y),
+            )
+        },
+    );
+    if !(min_x < max_x && min_y < max_y) {
+        return None;
+    }
+
+    let size = [(max_x - min_x) as usize, (max_y - min_y) as usize];
+    let mut pixels = vec![Rgba::TRANSPARENT; size[0] * size[1]];
+    for (outline, color) in &outlines {
+        let bb = outline.px_bounds();
+        let dx = (bb.min.x - min_x) as usize;
+        let dy = (bb.min.y - min_y) as usize;
+        outline.draw(|x, y, coverage| {
+            let (x, y) = (dx + x as usize, dy + y as usize);
+            if x < size[0] && y < size[1] {
+                let src = *color * coverage;
+                let dst = &mut pixels[y * size[0] + x];
+                *dst = src + *dst * (1.0 - src.a()); // "over" blending
+            }
+        });
+    }
+
+    Some(ColorGlyphImage {
+        min: vec2(min_x, min_y),
+        image: ColorImage {
+            size,
+            pixels: pixels.into_iter().map(Color32::from).collect(),
+        },
+    })
+}
+
+/// Decode the `CBDT`/`sbix` image of the glyph and scale it to the font size.
+#[cfg(feature = "color_emoji")]
+fn rasterize_bitmap(
+    font: &ab_glyph::FontArc,
+    glyph_id: ab_glyph::GlyphId,
+    scale_in_pixels: f32,
+) -> Option<ColorGlyphImage> {
+    use ab_glyph::Font as _;
+
+    let glyph_image = font.glyph_raster_image(glyph_id, scale_in_pixels.round() as u16)?;
+    let image = match glyph_image.format {
+        ab_glyph::GlyphImageFormat::Png => decode_png(glyph_image.data)?,
+        #[allow(unreachable_patterns)]
+        _ => return None,
+    };
+
+    let scale = scale_in_pixels / glyph_image.scale;
+    let size = [
+        ((image.width() as f32 * scale).round() as usize).max(1),
+        ((image.height() as f32 * scale).round() as usize).max(1),
+    ];
+
+    // `origin` is the bottom left corner, relative to the baseline, with y up:
+    let top = glyph_image.origin.y + image.height() as f32;
+    Some(ColorGlyphImage {
+        min: vec2(glyph_image.origin.x * scale, -top * scale),
+        image: resize(&image, size),
+    })
+}
+
+#[cfg(feature = "color_emoji")]
+fn decode_png(data: &[u8]) -> Option<ColorImage> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().ok()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).ok()?;
+    let buffer = &buffer[..info.buffer_size()];
+
+    let size = [info.width as usize, info.height as usize];
+    let pixels = match info.color_type {
+        png::ColorType::Rgba => buffer
+            .chunks_exact(4)
+            .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
+            .collect(),
+        png::ColorType::Rgb => buffer
+            .chunks_exact(3)
+            .map(|p| Color32::from_rgb(p[0], p[1], p[2]))
+            .collect(),
+        png::ColorType::GrayscaleAlpha => buffer
+            .chunks_exact(2)
+            .map(|p| Color32::from_rgba_unmultiplied(p[0], p[0], p[0], p[1]))
+            .collect(),
+        png::ColorType::Grayscale => buffer.iter().map(|&l| Color32::from_gray(l)).collect(),
+        png::ColorType::Indexed => return None, // expanded by `Transformations::EXPAND`
+    };
+    Some(ColorImage { size, pixels })
+}
+
+/// Resize with a box filter, which is good for scaling down (the common case for emojis).
+#[cfg(feature = "color_emoji")]
+fn resize(image: &ColorImage, size: [usize; 2]) -> ColorImage {
+    let sx = image.width() as f32 / size[0] as f32;
+    let sy = image.height() as f32 / size[1] as f32;
+
+    let mut pixels = Vec::with_capacity(size[0] * size[1]);
+    for y in 0..size[1] {
+        let y0 = (y as f32 * sy) as usize;
+        let y1 = (((y + 1) as f32 * sy).ceil() as usize).clamp(y0 + 1, image.height());
+        for x in 0..size[0] {
+            let x0 = (x as f32 * sx) as usize;
+            let x1 = (((x + 1) as f32 * sx).ceil() as usize).clamp(x0 + 1, image.width());
+
+            let mut sum = Rgba::TRANSPARENT;
+            for iy in y0..y1 {
+                for ix in x0..x1 {
+                    sum = sum + Rgba::from(image[(ix, iy)]);
+                }
+            }
+            let count = ((x1 - x0) * (y1 - y0)) as f32;
+            pixels.push(Color32::from(sum * (1.0 / count)));
+        }
+    }
+    ColorImage { size, pixels }
+}
+
+#[test]
+fn test_parse_colr() {
+    // One base glyph (7) with two layers: glyph 3 in red, then glyph 4 in the text color.
+    let colr: &[u8] = &[
+        0, 0, // version
+        0, 1, // number of base glyph records
+        0, 0, 0, 14, // offset to base glyph records
+        0, 0, 0, 20, // offset to layer records
+        0, 2, // number of layer records
+        0, 7, 0, 0, 0, 2, // base glyph 7, first layer 0, two layers
+        0, 3, 0, 0, // glyph 3, palette index 0
+        0, 4, 0xFF, 0xFF, // glyph 4, text color
+    ];
+    let cpal: &[u8] = &[
+        0, 0, // version
+        0, 1, // entries per palette
+        0, 1, // number of palettes
+        0, 1, // number of color records
+        0, 0, 0, 14, // offset to color records
+        0, 0, // first color of palette 0
+        0, 0, 255, 255, // red (BGRA)
+    ];
+
+    let glyphs = parse_colr(colr, cpal).unwrap();
+    assert_eq!(
+        glyphs[&7],
+        vec![
+            (ab_glyph::GlyphId(3), Color32::from_rgb(255, 0, 0)),
+            (ab_glyph::GlyphId(4), Color32::WHITE),
+        ]
+    );
+}
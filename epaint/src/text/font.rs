@@ -1,3 +1,7 @@
+use super::{
+    color_glyphs::ColorGlyphs,
+    sdf_glyphs::{SdfGlyphs, SDF_SCALE_IN_PIXELS},
+};
 use crate::{
     mutex::{Arc, Mutex, RwLock},
     TextureAtlas,
@@ -22,12 +26,24 @@ pub struct UvRect {
 
     /// Bottom right corner (exclusive).
     pub max: [u16; 2],
+
+    /// Which page (texture) of the [`TextureAtlas`] the glyph is in.
+    pub page: u16,
+
+    /// The texels are the colors of the glyph (e.g. an emoji),
+    /// so it should not be tinted by the text color.
+    pub colored: bool,
 }
 
 impl UvRect {
     pub fn is_nothing(&self) -> bool {
         self.min == self.max
     }
+
+    /// Does this cover any texels in the given page of the [`TextureAtlas`]?
+    pub fn is_in_page(&self, page: usize) -> bool {
+        !self.is_nothing() && self.page as usize == page
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -64,14 +80,28 @@ pub struct FontImpl {
     y_offset: f32,
     pixels_per_point: f32,
     glyph_info_cache: RwLock<AHashMap<char, GlyphInfo>>, // TODO: standard Mutex
+    /// Glyphs already in the atlas, so that we only rasterize each glyph once.
+    glyph_id_cache: RwLock<AHashMap<u16, GlyphInfo>>,
     atlas: Arc<Mutex<TextureAtlas>>,
+    /// The parsed font file, for the text shaper. Shared by all sizes of the font.
+    #[cfg(feature = "text_shaping")]
+    shaping_face: Arc<super::shaping::ShapingFace>,
+    /// `None` if this is not a color font.
+    color_glyphs: Option<Arc<ColorGlyphs>>,
+    /// Set if the glyphs should be signed distance fields (see [`super::FontDefinitions::sdf`]).
+    /// Shared by all sizes of the font.
+    sdf_glyphs: Option<Arc<SdfGlyphs>>,
 }
 
 impl FontImpl {
-    pub fn new(
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
         atlas: Arc<Mutex<TextureAtlas>>,
         pixels_per_point: f32,
         ab_glyph_font: ab_glyph::FontArc,
+        #[cfg(feature = "text_shaping")] shaping_face: Arc<super::shaping::ShapingFace>,
+        color_glyphs: Option<Arc<ColorGlyphs>>,
+        sdf_glyphs: Option<Arc<SdfGlyphs>>,
         scale_in_pixels: u32,
         y_offset: f32,
     ) -> FontImpl {
@@ -95,10 +125,20 @@ impl FontImpl {
             y_offset,
             pixels_per_point,
             glyph_info_cache: Default::default(),
+            glyph_id_cache: Default::default(),
             atlas,
+            #[cfg(feature = "text_shaping")]
+            shaping_face,
+            color_glyphs,
+            sdf_glyphs,
         }
     }
 
+    /// Does this font have glyphs in color (e.g. emojis)?
+    pub fn has_color_glyphs(&self) -> bool {
+        self.color_glyphs.is_some()
+    }
+
     /// An un-ordered iterator over all supported characters.
     fn characters(&self) -> impl Iterator<Item = char> + '_ {
         use ab_glyph::Font as _;
@@ -150,20 +190,66 @@ impl FontImpl {
                 None
             }
         } else {
-            let glyph_info = allocate_glyph(
-                &mut self.atlas.lock(),
-                &self.ab_glyph_font,
-                glyph_id,
-                self.scale_in_pixels as f32,
-                self.y_offset,
-                self.pixels_per_point,
-            );
-
+            let glyph_info = self.glyph_info_from_id(glyph_id);
             self.glyph_info_cache.write().insert(c, glyph_info);
             Some(glyph_info)
         }
     }
 
+    /// Look up a glyph by its id in the font, rasterizing it to the atlas if needed.
+    ///
+    /// The id must not be zero (the "missing glyph").
+    pub(crate) fn glyph_info_from_id(&self, glyph_id: ab_glyph::GlyphId) -> GlyphInfo {
+        if let Some(glyph_info) = self.glyph_id_cache.read().get(&glyph_id.0) {
+            return *glyph_info;
+        }
+
+        let glyph_info = allocate_glyph(
+            &mut self.atlas.lock(),
+            &self.ab_glyph_font,
+            self.color_glyphs.as_deref(),
+            self.sdf_glyphs.as_deref(),
+            glyph_id,
+            self.scale_in_pixels as f32,
+            self.y_offset,
+            self.pixels_per_point,
+        );
+        self.glyph_id_cache.write().insert(glyph_id.0, glyph_info);
+        glyph_info
+    }
+
+    /// Forget the glyphs in a page of the [`TextureAtlas`] that has been cleared.
+    pub(crate) fn forget_atlas_page(&self, page: usize) {
+        let keep = |glyph_info: &GlyphInfo| !glyph_info.uv_rect.is_in_page(page);
+        self.glyph_info_cache
+            .write()
+            .retain(|_, glyph_info| keep(glyph_info));
+        self.glyph_id_cache
+            .write()
+            .retain(|_, glyph_info| keep(glyph_info));
+    }
+
+    /// Can this font show the given character?
+    #[cfg(feature = "text_shaping")]
+    pub(crate) fn has_glyph(&self, c: char) -> bool {
+        self.glyph_info(c).is_some()
+    }
+
+    #[cfg(feature = "text_shaping")]
+    pub(crate) fn shaping_face(&self) -> &super::shaping::ShapingFace {
+        &self.shaping_face
+    }
+
+    /// Multiply with this to go from unscaled font units to points.
+    #[cfg(feature = "text_shaping")]
+    pub(crate) fn points_per_font_unit(&self) -> f32 {
+        use ab_glyph::{Font as _, ScaleFont as _};
+        self.ab_glyph_font
+            .as_scaled(self.scale_in_pixels as f32)
+            .h_scale_factor()
+            / self.pixels_per_point
+    }
+
     #[inline]
     pub fn pair_kerning(
         &self,
@@ -183,13 +269,19 @@ impl FontImpl {
         self.height_in_points
     }
 
+    /// Distance from the top of a row of text down to the baseline. In points.
+    #[inline]
+    pub fn baseline(&self) -> f32 {
+        self.height_in_points + self.y_offset
+    }
+
     #[inline(always)]
     pub fn pixels_per_point(&self) -> f32 {
         self.pixels_per_point
     }
 }
 
-type FontIndex = usize;
+pub(crate) type FontIndex = usize;
 
 // TODO: rename?
 /// Wrapper over multiple `FontImpl` (e.g. a primary + fallbacks for emojis)
@@ -201,6 +293,8 @@ pub struct Font {
     pixels_per_point: f32,
     row_height: f32,
     glyph_info_cache: AHashMap<char, (FontIndex, GlyphInfo)>,
+    /// Characters none of the fonts have, so they use [`Self::replacement_glyph`].
+    missing_chars: ahash::AHashSet<char>,
 }
 
 impl Font {
@@ -213,6 +307,7 @@ impl Font {
                 pixels_per_point: 1.0,
                 row_height: 0.0,
                 glyph_info_cache: Default::default(),
+                missing_chars: Default::default(),
             };
         }
 
@@ -226,23 +321,26 @@ impl Font {
             pixels_per_point,
             row_height,
             glyph_info_cache: Default::default(),
+            missing_chars: Default::default(),
         };
 
+        slf.replacement_glyph = slf.find_replacement_glyph();
+
+        slf
+    }
+
+    fn find_replacement_glyph(&mut self) -> (FontIndex, GlyphInfo) {
         const PRIMARY_REPLACEMENT_CHAR: char = '◻'; // white medium square
         const FALLBACK_REPLACEMENT_CHAR: char = '?'; // fallback for the fallback
 
-        let replacement_glyph = slf
-            .glyph_info_no_cache_or_fallback(PRIMARY_REPLACEMENT_CHAR)
-            .or_else(|| slf.glyph_info_no_cache_or_fallback(FALLBACK_REPLACEMENT_CHAR))
+        self.glyph_info_no_cache_or_fallback(PRIMARY_REPLACEMENT_CHAR)
+            .or_else(|| self.glyph_info_no_cache_or_fallback(FALLBACK_REPLACEMENT_CHAR))
             .unwrap_or_else(|| {
                 panic!(
                     "Failed to find replacement characters {:?} or {:?}",
                     PRIMARY_REPLACEMENT_CHAR, FALLBACK_REPLACEMENT_CHAR
                 )
-            });
-        slf.replacement_glyph = replacement_glyph;
-
-        slf
+            })
     }
 
     pub fn preload_common_characters(&mut self) {
@@ -278,6 +376,13 @@ impl Font {
         self.row_height
     }
 
+    /// Distance from the top of a row of text down to the baseline of the primary font. In points.
+    pub fn baseline(&self) -> f32 {
+        self.fonts
+            .first()
+            .map_or(self.row_height, |font_impl| font_impl.baseline())
+    }
+
     pub fn uv_rect(&self, c: char) -> UvRect {
         self.glyph_info_cache
             .get(&c)
@@ -297,11 +402,62 @@ impl Font {
         }
 
         let font_index_glyph_info = self.glyph_info_no_cache_or_fallback(c);
-        let font_index_glyph_info = font_index_glyph_info.unwrap_or(self.replacement_glyph);
+        let font_index_glyph_info = font_index_glyph_info.unwrap_or_else(|| {
+            self.missing_chars.insert(c);
+            self.replacement_glyph
+        });
         self.glyph_info_cache.insert(c, font_index_glyph_info);
         font_index_glyph_info
     }
 
+    /// Does any of the fonts have this character?
+    #[cfg(feature = "system_fonts")]
+    pub(crate) fn has_glyph(&mut self, c: char) -> bool {
+        self.glyph_info(c);
+        !self.missing_chars.contains(&c)
+    }
+
+    /// The visible characters in `text` that none of the fonts have.
+    #[cfg(feature = "system_fonts")]
+    pub(crate) fn missing_chars(&mut self, text: &str) -> Vec<char> {
+        let mut missing = vec![];
+        for c in text.chars() {
+            if c.is_whitespace() || c.is_control() || invisible_char(c) {
+                continue;
+            }
+            if !self.has_glyph(c) && !missing.contains(&c) {
+                missing.push(c);
+            }
+        }
+        missing
+    }
+
+    /// Add a fallback font last, to use for characters none of the current fonts have.
+    #[cfg(feature = "system_fonts")]
+    pub(crate) fn add_fallback(&mut self, font_impl: Arc<FontImpl>) {
+        if self.fonts.iter().any(|font| Arc::ptr_eq(font, &font_impl)) {
+            return;
+        }
+        self.fonts.push(font_impl);
+        self.characters = None;
+
+        // Look for the missing characters again:
+        for c in self.missing_chars.drain() {
+            self.glyph_info_cache.remove(&c);
+        }
+    }
+
+    /// Forget the glyphs in a page of the [`TextureAtlas`] that has been cleared.
+    ///
+    /// Call [`FontImpl::forget_atlas_page`] on the fonts first.
+    pub(crate) fn forget_atlas_page(&mut self, page: usize) {
+        self.glyph_info_cache
+            .retain(|_, (_, glyph_info)| !glyph_info.uv_rect.is_in_page(page));
+        if self.replacement_glyph.1.uv_rect.is_in_page(page) && !self.fonts.is_empty() {
+            self.replacement_glyph = self.find_replacement_glyph();
+        }
+    }
+
     #[inline]
     pub(crate) fn glyph_info_and_font_impl(&mut self, c: char) -> (Option<&FontImpl>, GlyphInfo) {
         if self.fonts.is_empty() {
@@ -312,6 +468,31 @@ impl Font {
         (Some(font_impl), glyph_info)
     }
 
+    /// Which font to use for this character, or `None` if none of them have it.
+    ///
+    /// If `preferred` can show the character, that is used,
+    /// so that e.g. combining marks stay in the same font as the letter they combine with.
+    #[cfg(feature = "text_shaping")]
+    pub(crate) fn font_index_for_char(
+        &self,
+        c: char,
+        preferred: Option<FontIndex>,
+    ) -> Option<FontIndex> {
+        if let Some(preferred) = preferred {
+            if !c.is_ascii() && self.fonts[preferred].has_glyph(c) {
+                return Some(preferred);
+            }
+        }
+        self.fonts
+            .iter()
+            .position(|font_impl| font_impl.has_glyph(c))
+    }
+
+    #[cfg(feature = "text_shaping")]
+    pub(crate) fn font_impl(&self, font_index: FontIndex) -> &FontImpl {
+        &self.fonts[font_index]
+    }
+
     fn glyph_info_no_cache_or_fallback(&mut self, c: char) -> Option<(FontIndex, GlyphInfo)> {
         for (font_index, font_impl) in self.fonts.iter().enumerate() {
             if let Some(glyph_info) = font_impl.glyph_info(c) {
@@ -331,9 +512,12 @@ fn invisible_char(c: char) -> bool {
     ('\u{200B}'..='\u{206F}').contains(&c) // TODO: heed bidi characters
 }
 
+#[allow(clippy::too_many_arguments)]
 fn allocate_glyph(
     atlas: &mut TextureAtlas,
     font: &ab_glyph::FontArc,
+    color_glyphs: Option<&ColorGlyphs>,
+    sdf_glyphs: Option<&SdfGlyphs>,
     glyph_id: ab_glyph::GlyphId,
     scale_in_pixels: f32,
     y_offset: f32,
@@ -342,6 +526,69 @@ fn allocate_glyph(
     assert!(glyph_id.0 != 0);
     use ab_glyph::{Font as _, ScaleFont};
 
+    let advance_width_in_points =
+        font.as_scaled(scale_in_pixels).h_advance(glyph_id) / pixels_per_point;
+
+    if let Some(color_glyph) = color_glyphs
+        .and_then(|color_glyphs| color_glyphs.rasterize(font, glyph_id, scale_in_pixels))
+    {
+        let [glyph_width, glyph_height] = color_glyph.image.size;
+        let (page, glyph_pos, image) = atlas.allocate_color((glyph_width, glyph_height));
+        for y in 0..glyph_height {
+            for x in 0..glyph_width {
+                image[(glyph_pos.0 + x, glyph_pos.1 + y)] = color_glyph.image[(x, y)];
+            }
+        }
+
+        let offset_in_pixels = vec2(color_glyph.min.x, scale_in_pixels + color_glyph.min.y);
+        let offset = offset_in_pixels / pixels_per_point + y_offset * Vec2::Y;
+        return GlyphInfo {
+            id: glyph_id,
+            advance_width: advance_width_in_points,
+            uv_rect: UvRect {
+                offset,
+                size: vec2(glyph_width as f32, glyph_height as f32) / pixels_per_point,
+                min: [glyph_pos.0 as u16, glyph_pos.1 as u16],
+                max: [
+                    (glyph_pos.0 + glyph_width) as u16,
+                    (glyph_pos.1 + glyph_height) as u16,
+                ],
+                page: page as u16,
+                colored: true,
+            },
+        };
+    }
+
+    if let Some(sdf_glyphs) = sdf_glyphs {
+        // Scale the glyph rasterized at `SDF_SCALE_IN_PIXELS` to our size:
+        let scale = scale_in_pixels / SDF_SCALE_IN_PIXELS;
+        let uv_rect = sdf_glyphs
+            .glyph(atlas, font, glyph_id)
+            .map(|glyph| {
+                let [glyph_width, glyph_height] = glyph.size;
+                let offset_in_pixels =
+                    vec2(scale * glyph.min.x, scale_in_pixels + scale * glyph.min.y);
+                let offset = offset_in_pixels / pixels_per_point + y_offset * Vec2::Y;
+                UvRect {
+                    offset,
+                    size: scale * vec2(glyph_width as f32, glyph_height as f32) / pixels_per_point,
+                    min: [glyph.pos.0 as u16, glyph.pos.1 as u16],
+                    max: [
+                        (glyph.pos.0 + glyph_width) as u16,
+                        (glyph.pos.1 + glyph_height) as u16,
+                    ],
+                    page: glyph.page as u16,
+                    colored: false,
+                }
+            })
+            .unwrap_or_default();
+        return GlyphInfo {
+            id: glyph_id,
+            advance_width: advance_width_in_points,
+            uv_rect,
+        };
+    }
+
     let glyph =
         glyph_id.with_scale_and_position(scale_in_pixels, ab_glyph::Point { x: 0.0, y: 0.0 });
 
@@ -352,7 +599,7 @@ fn allocate_glyph(
         if glyph_width == 0 || glyph_height == 0 {
             UvRect::default()
         } else {
-            let (glyph_pos, image) = atlas.allocate((glyph_width, glyph_height));
+            let (page, glyph_pos, image) = atlas.allocate((glyph_width, glyph_height));
             glyph.draw(|x, y, v| {
                 if v > 0.0 {
                     let px = glyph_pos.0 + x as usize;
@@ -371,14 +618,13 @@ fn allocate_glyph(
                     (glyph_pos.0 + glyph_width) as u16,
                     (glyph_pos.1 + glyph_height) as u16,
                 ],
+                page: page as u16,
+                colored: false,
             }
         }
     });
     let uv_rect = uv_rect.unwrap_or_default();
 
-    let advance_width_in_points =
-        font.as_scaled(scale_in_pixels).h_advance(glyph_id) / pixels_per_point;
-
     GlyphInfo {
         id: glyph_id,
         advance_width: advance_width_in_points,
@@ -1,9 +1,11 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use crate::{
     mutex::{Arc, Mutex, MutexGuard},
     text::{
+        color_glyphs::ColorGlyphs,
         font::{Font, FontImpl},
+        sdf_glyphs::SdfGlyphs,
         Galley, LayoutJob,
     },
     TextureAtlas,
@@ -110,6 +112,55 @@ impl std::fmt::Display for FontFamily {
 
 // ----------------------------------------------------------------------------
 
+/// How thick the strokes of a font are, in the CSS sense: `400` is normal and `700` is bold.
+///
+/// See [`FontDefinitions::variants`].
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct FontWeight(pub u16);
+
+impl FontWeight {
+    pub const THIN: Self = Self(100);
+    pub const EXTRA_LIGHT: Self = Self(200);
+    pub const LIGHT: Self = Self(300);
+    pub const NORMAL: Self = Self(400);
+    pub const MEDIUM: Self = Self(500);
+    pub const SEMI_BOLD: Self = Self(600);
+    pub const BOLD: Self = Self(700);
+    pub const EXTRA_BOLD: Self = Self(800);
+    pub const BLACK: Self = Self(900);
+}
+
+impl Default for FontWeight {
+    #[inline]
+    fn default() -> Self {
+        Self::NORMAL
+    }
+}
+
+/// A bold and/or italic version of a [`FontFamily`].
+///
+/// See [`FontDefinitions::variants`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct FontVariant {
+    pub weight: FontWeight,
+
+    /// Is this a real italic (or oblique) font?
+    ///
+    /// If so, text with [`crate::text::TextFormat::italics`] will use it as is
+    /// instead of slanting the glyphs of the normal font.
+    pub italic: bool,
+
+    /// Keys into [`FontDefinitions::font_data`], in order of priority.
+    ///
+    /// The fonts of the family itself (in [`FontDefinitions::families`])
+    /// are used as fallbacks after these.
+    pub fonts: Vec<String>,
+}
+
+// ----------------------------------------------------------------------------
+
 /// A `.ttf` or `.otf` file and a font face index.
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -120,6 +171,10 @@ pub struct FontData {
     /// Which font face in the file to use.
     /// When in doubt, use `0`.
     pub index: u32,
+
+    /// Extra scale and vertical tweak to apply to all text of this font.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tweak: FontTweak,
 }
 
 impl FontData {
@@ -127,6 +182,7 @@ impl FontData {
         Self {
             font: std::borrow::Cow::Borrowed(font),
             index: 0,
+            tweak: Default::default(),
         }
     }
 
@@ -134,6 +190,47 @@ impl FontData {
         Self {
             font: std::borrow::Cow::Owned(font),
             index: 0,
+            tweak: Default::default(),
+        }
+    }
+
+    pub fn tweak(self, tweak: FontTweak) -> Self {
+        Self { tweak, ..self }
+    }
+}
+
+/// Extra scale and vertical tweak to apply to all text of a certain font.
+///
+/// Useful for fallback fonts (e.g. emojis) that are designed
+/// with a different size or baseline than the primary font.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct FontTweak {
+    /// Scale the font by this much.
+    ///
+    /// Default: `1.0` (no scaling).
+    pub scale: f32,
+
+    /// Shift font downwards by this fraction of the font size (in points).
+    ///
+    /// A positive value shifts the text downwards.
+    /// A negative value shifts it upwards.
+    ///
+    /// Example value: `-0.2`.
+    pub y_offset_factor: f32,
+
+    /// Shift font downwards by this amount of logical points.
+    ///
+    /// Example value: `2.0`.
+    pub y_offset: f32,
+}
+
+impl Default for FontTweak {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            y_offset_factor: 0.0,
+            y_offset: 0.0,
         }
     }
 }
@@ -193,8 +290,49 @@ pub struct FontDefinitions {
     /// When looking for a character glyph `epaint` will start with
     /// the first font and then move to the second, and so on.
     /// So the first font is the primary, and then comes a list of fallbacks in order of priority.
-    // TODO: per font size-modifier.
+    ///
+    /// To show emojis in color, put a color emoji font before the bundled `"NotoEmoji-Regular"`.
     pub families: BTreeMap<FontFamily, Vec<String>>,
+
+    /// Bold, italic and other weights of the fonts in [`Self::families`].
+    ///
+    /// The fonts in [`Self::families`] are the normal weight, non-italic variant of each family.
+    /// Text asking for another [`FontWeight`] or for italics
+    /// uses the closest variant listed here.
+    ///
+    /// ```
+    /// # use epaint::text::{FontData, FontDefinitions, FontFamily, FontVariant, FontWeight};
+    /// let mut fonts = FontDefinitions::default();
+    /// fonts.font_data.insert("my_bold_font".to_owned(),
+    ///    FontData::from_static(include_bytes!("../../fonts/Ubuntu-Light.ttf")));
+    /// fonts.variants.entry(FontFamily::Proportional).or_default().push(FontVariant {
+    ///     weight: FontWeight::BOLD,
+    ///     italic: false,
+    ///     fonts: vec!["my_bold_font".to_owned()],
+    /// });
+    /// ```
+    pub variants: BTreeMap<FontFamily, Vec<FontVariant>>,
+
+    /// If a character is missing from all the fonts of a family,
+    /// look for a font that has it among the fonts installed on the system.
+    ///
+    /// The system fonts are scanned when the [`Fonts`] are created with these definitions.
+    /// This does nothing without the `system_fonts` feature.
+    /// Default: `false`.
+    pub system_fallback: bool,
+
+    /// Rasterize the glyphs as signed distance fields (see [`crate::SdfImage`]).
+    ///
+    /// Each glyph is then only rasterized once, and shared by all sizes of a font,
+    /// so zooming and changing `pixels_per_point` is cheap, and text stays crisp at any scale.
+    /// Small text looks a bit softer than with the normal glyphs.
+    ///
+    /// Color glyphs (emojis) are not affected.
+    ///
+    /// This requires a painter that supports [`crate::ImageData::Sdf`], like `egui_glow`.
+    /// Other painters fall back to an approximation that is only crisp at normal text sizes.
+    /// Default: `false`.
+    pub sdf: bool,
 }
 
 impl Default for FontDefinitions {
@@ -223,7 +361,13 @@ impl Default for FontDefinitions {
             // Bigger emojis, and more. <http://jslegers.github.io/emoji-icon-font/>:
             font_data.insert(
                 "emoji-icon-font".to_owned(),
-                FontData::from_static(include_bytes!("../../fonts/emoji-icon-font.ttf")),
+                FontData::from_static(include_bytes!("../../fonts/emoji-icon-font.ttf")).tweak(
+                    FontTweak {
+                        scale: 0.8,               // make it smaller
+                        y_offset_factor: 0.29375, // move it down slightly
+                        y_offset: 0.0,
+                    },
+                ),
             );
 
             families.insert(
@@ -254,10 +398,82 @@ impl Default for FontDefinitions {
         Self {
             font_data,
             families,
+            variants: Default::default(),
+            system_fallback: false,
+            sdf: false,
         }
     }
 }
 
+#[cfg(feature = "system_fonts")]
+impl FontDefinitions {
+    /// Add a font family installed on the system as [`FontFamily::Name`],
+    /// including its bold and italic faces as [`Self::variants`].
+    ///
+    /// The fonts of [`FontFamily::Proportional`] are used as fallbacks.
+    /// Returns `false` if there is no such family.
+    ///
+    /// ```no_run
+    /// # use epaint::text::{FontDefinitions, FontFamily, SystemFonts};
+    /// let system_fonts = SystemFonts::load();
+    /// let mut fonts = FontDefinitions::default();
+    /// if fonts.add_system_family(&system_fonts, "DejaVu Serif") {
+    ///     let family = FontFamily::Name("DejaVu Serif".into());
+    /// }
+    /// ```
+    pub fn add_system_family(
+        &mut self,
+        system_fonts: &super::SystemFonts,
+        family_name: &str,
+    ) -> bool {
+        let regular = match system_fonts.find_face(family_name, FontWeight::NORMAL, false) {
+            Some(id) => id,
+            None => return false,
+        };
+        let font_data = match system_fonts.load_face(regular) {
+            Some(font_data) => font_data,
+            None => return false,
+        };
+        self.font_data.insert(family_name.to_owned(), font_data);
+
+        let family = FontFamily::Name(family_name.into());
+        let mut fonts = vec![family_name.to_owned()];
+        if let Some(fallbacks) = self.families.get(&FontFamily::Proportional) {
+            fonts.extend(fallbacks.iter().cloned());
+        }
+        self.families.insert(family.clone(), fonts);
+
+        let mut variants = vec![];
+        for &(weight, italic) in &[
+            (FontWeight::NORMAL, true),
+            (FontWeight::BOLD, false),
+            (FontWeight::BOLD, true),
+        ] {
+            let id = match system_fonts.find_face(family_name, weight, italic) {
+                Some(id) if id != regular && system_fonts.is_italic(id) == italic => id,
+                _ => continue, // the family doesn't have this variant
+            };
+            if let Some(font_data) = system_fonts.load_face(id) {
+                let font_name = format!(
+                    "{} {}{}",
+                    family_name,
+                    weight.0,
+                    if italic { " italic" } else { "" }
+                );
+                self.font_data.insert(font_name.clone(), font_data);
+                variants.push(FontVariant {
+                    weight,
+                    italic,
+                    fonts: vec![font_name],
+                });
+            }
+        }
+        self.variants.insert(family, variants);
+
+        true
+    }
+}
+
 // ----------------------------------------------------------------------------
 
 /// The collection of fonts used by `epaint`.
@@ -265,7 +481,7 @@ impl Default for FontDefinitions {
 /// Required in order to paint text.
 /// Create one and reuse. Cheap to clone.
 ///
-/// You need to call [`Self::begin_frame`] and [`Self::font_image_delta`] once every frame.
+/// You need to call [`Self::begin_frame`] and [`Self::font_image_deltas`] once every frame.
 ///
 /// Wrapper for `Arc<Mutex<FontsAndCache>>`.
 pub struct Fonts(Arc<Mutex<FontsAndCache>>);
@@ -295,31 +511,68 @@ impl Fonts {
     ///
     /// This function will react to changes in `pixels_per_point` and `max_texture_side`,
     /// as well as notice when the font atlas is getting full, and handle that.
+    ///
+    /// When the font atlas is out of pages, the page least recently used by a [`Galley`]
+    /// from [`Self::layout_job`] is cleared.
+    /// Any [`Galley`] you have kept around from before then may no longer paint correctly,
+    /// which you can check with [`Self::is_galley_stale`].
     pub fn begin_frame(&self, pixels_per_point: f32, max_texture_side: usize) {
         let mut fonts_and_cache = self.0.lock();
 
         let pixels_per_point_changed =
             (fonts_and_cache.fonts.pixels_per_point - pixels_per_point).abs() > 1e-3;
         let max_texture_side_changed = fonts_and_cache.fonts.max_texture_side != max_texture_side;
-        let font_atlas_almost_full = fonts_and_cache.fonts.atlas.lock().fill_ratio() > 0.8;
-        let needs_recreate =
-            pixels_per_point_changed || max_texture_side_changed || font_atlas_almost_full;
+        let font_atlas_overflowed = fonts_and_cache.fonts.atlas.lock().overflowed();
+        let needs_recreate = max_texture_side_changed || font_atlas_overflowed;
 
-        if needs_recreate {
+        if needs_recreate || pixels_per_point_changed {
             let definitions = fonts_and_cache.fonts.definitions.clone();
-
+            #[cfg(feature = "system_fonts")]
+            let system_fonts = fonts_and_cache.fonts.system_fonts.take(); // expensive to load again
+
+            let fonts = if needs_recreate {
+                let atlas = FontsImpl::new_atlas(max_texture_side);
+                FontsImpl::with_atlas(pixels_per_point, max_texture_side, definitions, atlas)
+            } else {
+                // Keep the atlas. The glyphs of the old size will be evicted when they are no longer used.
+                let atlas = fonts_and_cache.fonts.atlas.clone();
+                let mut fonts =
+                    FontsImpl::with_atlas(pixels_per_point, max_texture_side, definitions, atlas);
+                // Signed distance fields don't depend on the size, so we can keep them:
+                fonts.font_impl_cache.sdf_glyphs =
+                    std::mem::take(&mut fonts_and_cache.fonts.font_impl_cache.sdf_glyphs);
+                fonts
+            };
             *fonts_and_cache = FontsAndCache {
-                fonts: FontsImpl::new(pixels_per_point, max_texture_side, definitions),
+                fonts,
                 galley_cache: Default::default(),
             };
+
+            #[cfg(feature = "system_fonts")]
+            {
+                fonts_and_cache.fonts.system_fonts = system_fonts;
+            }
         }
 
         fonts_and_cache.galley_cache.flush_cache();
+
+        let FontsAndCache {
+            fonts,
+            galley_cache,
+        } = &mut *fonts_and_cache;
+        let evicted_page = fonts.atlas.lock().begin_frame(galley_cache.atlas_pages());
+        if let Some(page) = evicted_page {
+            fonts.forget_atlas_page(page);
+        }
     }
 
-    /// Call at the end of each frame (before painting) to get the change to the font texture since last call.
-    pub fn font_image_delta(&self) -> Option<crate::ImageDelta> {
-        self.lock().fonts.atlas.lock().take_delta()
+    /// Call at the end of each frame (before painting) to get the changes to the font textures since last call,
+    /// as `(page, change)`.
+    ///
+    /// Each page of the font atlas is its own texture.
+    /// The first page should be [`crate::TextureId::default`].
+    pub fn font_image_deltas(&self) -> Vec<(usize, crate::ImageDelta)> {
+        self.lock().fonts.atlas.lock().take_deltas()
     }
 
     /// Access the underlying [`FontsAndCache`].
@@ -339,10 +592,10 @@ impl Fonts {
         self.lock().fonts.max_texture_side
     }
 
-    /// Current size of the font image.
-    /// Pass this to [`crate::Tessellator`].
-    pub fn font_image_size(&self) -> [usize; 2] {
-        self.lock().fonts.atlas.lock().size()
+    /// Current size of each page (texture) of the font atlas.
+    /// Pass these to [`crate::tessellate_shapes`].
+    pub fn font_image_sizes(&self) -> Vec<[usize; 2]> {
+        self.lock().fonts.atlas.lock().page_sizes()
     }
 
     /// Width of this character in points.
@@ -392,6 +645,24 @@ impl Fonts {
         self.lock().fonts.atlas.lock().fill_ratio()
     }
 
+    /// Number of pages, memory use etc of the font atlas.
+    pub fn font_atlas_stats(&self) -> crate::stats::FontAtlasStats {
+        self.lock().fonts.atlas.lock().stats()
+    }
+
+    /// Has a page of the font atlas used by the galley been cleared since the galley was laid out?
+    ///
+    /// If so, the galley would paint the wrong glyphs, and needs to be laid out again
+    /// (e.g. with [`Self::layout_job`], and then folded or concatenated again if it was [`Galley::derived`]).
+    pub fn is_galley_stale(&self, galley: &Galley) -> bool {
+        let fonts_and_cache = self.lock();
+        let atlas = fonts_and_cache.fonts.atlas.lock();
+        galley
+            .page_generations
+            .iter()
+            .any(|&(page, generation)| atlas.page_generation(page) != Some(generation))
+    }
+
     /// Will wrap text at the given width and line break at `\n`.
     ///
     /// The implementation uses memoization so repeated calls are cheap.
@@ -452,6 +723,10 @@ impl FontsAndCache {
 
 // ----------------------------------------------------------------------------
 
+/// How many pages (textures) the font atlas can have
+/// before we start clearing the least recently used one.
+const MAX_FONT_ATLAS_PAGES: usize = 4;
+
 /// The collection of fonts used by `epaint`.
 ///
 /// Required in order to paint text.
@@ -461,7 +736,11 @@ pub struct FontsImpl {
     definitions: FontDefinitions,
     atlas: Arc<Mutex<TextureAtlas>>,
     font_impl_cache: FontImplCache,
-    sized_family: ahash::AHashMap<(u32, FontFamily), Font>,
+    /// Keyed on pixel size, family and index into [`FontDefinitions::variants`].
+    sized_family: ahash::AHashMap<(u32, FontFamily, Option<usize>), Font>,
+    /// Loaded up front if [`FontDefinitions::system_fallback`] is set.
+    #[cfg(feature = "system_fonts")]
+    system_fonts: Option<Arc<super::SystemFonts>>,
 }
 
 impl FontsImpl {
@@ -471,6 +750,28 @@ impl FontsImpl {
         pixels_per_point: f32,
         max_texture_side: usize,
         definitions: FontDefinitions,
+    ) -> Self {
+        let atlas = Self::new_atlas(max_texture_side);
+        #[allow(unused_mut)]
+        let mut fonts = Self::with_atlas(pixels_per_point, max_texture_side, definitions, atlas);
+        #[cfg(feature = "system_fonts")]
+        fonts.load_system_fonts();
+        fonts
+    }
+
+    fn new_atlas(max_texture_side: usize) -> Arc<Mutex<TextureAtlas>> {
+        let texture_width = max_texture_side.at_most(8 * 1024);
+        let initial_height = 64;
+        let atlas = TextureAtlas::new([texture_width, initial_height], MAX_FONT_ATLAS_PAGES);
+        Arc::new(Mutex::new(atlas))
+    }
+
+    /// Like [`Self::new`], but without loading the system fonts.
+    fn with_atlas(
+        pixels_per_point: f32,
+        max_texture_side: usize,
+        definitions: FontDefinitions,
+        atlas: Arc<Mutex<TextureAtlas>>,
     ) -> Self {
         assert!(
             0.0 < pixels_per_point && pixels_per_point < 100.0,
@@ -478,21 +779,12 @@ impl FontsImpl {
             pixels_per_point
         );
 
-        let texture_width = max_texture_side.at_most(8 * 1024);
-        let initial_height = 64;
-        let mut atlas = TextureAtlas::new([texture_width, initial_height]);
-
-        {
-            // Make the top left pixel fully white:
-            let (pos, image) = atlas.allocate((1, 1));
-            assert_eq!(pos, (0, 0));
-            image[pos] = 255;
-        }
-
-        let atlas = Arc::new(Mutex::new(atlas));
-
-        let font_impl_cache =
-            FontImplCache::new(atlas.clone(), pixels_per_point, &definitions.font_data);
+        let font_impl_cache = FontImplCache::new(
+            atlas.clone(),
+            pixels_per_point,
+            &definitions.font_data,
+            definitions.sdf,
+        );
 
         Self {
             pixels_per_point,
@@ -501,6 +793,8 @@ impl FontsImpl {
             atlas,
             font_impl_cache,
             sized_family: Default::default(),
+            #[cfg(feature = "system_fonts")]
+            system_fonts: None,
         }
     }
 
@@ -516,19 +810,148 @@ impl FontsImpl {
 
     /// Get the right font implementation from size and [`FontFamily`].
     pub fn font(&mut self, font_id: &FontId) -> &mut Font {
+        self.sized_font(font_id, None)
+    }
+
+    /// Get the font of the given [`FontFamily`] that is the closest match
+    /// to the given weight and italics. See [`FontDefinitions::variants`].
+    pub fn font_variant(
+        &mut self,
+        font_id: &FontId,
+        weight: FontWeight,
+        italic: bool,
+    ) -> &mut Font {
+        let variant = self.variant_index(&font_id.family, weight, italic);
+        self.sized_font(font_id, variant)
+    }
+
+    /// Is there a real italic font for this family and weight,
+    /// or does italic text need to be slanted by us?
+    pub fn has_italic_variant(&self, family: &FontFamily, weight: FontWeight) -> bool {
+        self.variant_index(family, weight, true)
+            .map_or(false, |index| {
+                self.definitions.variants[family][index].italic
+            })
+    }
+
+    /// Index into [`FontDefinitions::variants`], or `None` if the normal font is the best match.
+    fn variant_index(
+        &self,
+        family: &FontFamily,
+        weight: FontWeight,
+        italic: bool,
+    ) -> Option<usize> {
+        let variants = self.definitions.variants.get(family)?;
+
+        let distance = |variant_weight: FontWeight, variant_italic: bool| {
+            let italic_mismatch = if variant_italic == italic { 0 } else { 1000 };
+            (i32::from(variant_weight.0) - i32::from(weight.0)).abs() + italic_mismatch
+        };
+
+        let normal_distance = distance(FontWeight::NORMAL, false);
+        let (index, variant_distance) = variants
+            .iter()
+            .map(|variant| distance(variant.weight, variant.italic))
+            .enumerate()
+            .min_by_key(|&(_, distance)| distance)?;
+
+        if variant_distance < normal_distance {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    /// Scan the system fonts and index which characters they have, if [`FontDefinitions::system_fallback`] is set.
+    ///
+    /// This is slow, so we do it once up front, instead of while laying out text.
+    #[cfg(feature = "system_fonts")]
+    fn load_system_fonts(&mut self) {
+        if self.definitions.system_fallback && self.system_fonts.is_none() {
+            let system_fonts = super::SystemFonts::load();
+            system_fonts.index_fallbacks();
+            self.system_fonts = Some(Arc::new(system_fonts));
+        }
+    }
+
+    /// If [`FontDefinitions::system_fallback`] is set, find system fonts
+    /// for the characters in `text` that the font would otherwise show with the replacement glyph.
+    #[cfg(feature = "system_fonts")]
+    pub(crate) fn add_system_fallbacks(
+        &mut self,
+        font_id: &FontId,
+        weight: FontWeight,
+        italic: bool,
+        text: &str,
+    ) {
+        let system_fonts = match &self.system_fonts {
+            Some(system_fonts) => system_fonts.clone(),
+            None => return, // `system_fallback` is not set
+        };
+
+        let missing_chars = self
+            .font_variant(font_id, weight, italic)
+            .missing_chars(text);
+        if missing_chars.is_empty() {
+            return;
+        }
+
+        let scale_in_pixels = self.font_impl_cache.scale_as_pixels(font_id.size);
+
+        for c in missing_chars {
+            if self.font_variant(font_id, weight, italic).has_glyph(c) {
+                continue; // found in a fallback we added for an earlier char
+            }
+            if let Some((font_name, font_data)) = system_fonts.fallback_for(c) {
+                self.font_impl_cache.add_font_data(&font_name, &font_data);
+                let font_impl = self.font_impl_cache.font_impl(scale_in_pixels, &font_name);
+                self.font_variant(font_id, weight, italic)
+                    .add_fallback(font_impl);
+            }
+        }
+    }
+
+    /// The current [`TextureAtlas::page_generation`] of each of the given pages.
+    pub(crate) fn page_generations(&self, pages: &[usize]) -> Vec<(usize, u32)> {
+        let atlas = self.atlas.lock();
+        pages
+            .iter()
+            .filter_map(|&page| Some((page, atlas.page_generation(page)?)))
+            .collect()
+    }
+
+    /// Forget all glyphs in a page of the atlas that has been cleared.
+    fn forget_atlas_page(&mut self, page: usize) {
+        for font_impl in self.font_impl_cache.cache.values() {
+            font_impl.forget_atlas_page(page);
+        }
+        for sdf_glyphs in self.font_impl_cache.sdf_glyphs.values() {
+            sdf_glyphs.forget_atlas_page(page);
+        }
+        for font in self.sized_family.values_mut() {
+            font.forget_atlas_page(page);
+        }
+    }
+
+    fn sized_font(&mut self, font_id: &FontId, variant: Option<usize>) -> &mut Font {
         let FontId { size, family } = font_id;
         let scale_in_pixels = self.font_impl_cache.scale_as_pixels(*size);
 
         self.sized_family
-            .entry((scale_in_pixels, family.clone()))
+            .entry((scale_in_pixels, family.clone(), variant))
             .or_insert_with(|| {
                 let fonts = &self.definitions.families.get(family);
                 let fonts = fonts.unwrap_or_else(|| {
                     panic!("FontFamily::{:?} is not bound to any fonts", family)
                 });
 
-                let fonts: Vec<Arc<FontImpl>> = fonts
+                let variant_fonts = variant
+                    .map(|index| &self.definitions.variants[family][index].fonts[..])
+                    .unwrap_or_default();
+
+                let fonts: Vec<Arc<FontImpl>> = variant_fonts
                     .iter()
+                    .chain(fonts.iter())
                     .map(|font_name| self.font_impl_cache.font_impl(scale_in_pixels, font_name))
                     .collect();
 
@@ -553,6 +976,8 @@ struct CachedGalley {
     /// When it was last used
     last_used: u32,
     galley: Arc<Galley>,
+    /// The pages of the font atlas used by the galley.
+    atlas_pages: Vec<usize>,
 }
 
 #[derive(Default)]
@@ -577,6 +1002,7 @@ impl GalleyCache {
                 let galley = Arc::new(galley);
                 entry.insert(CachedGalley {
                     last_used: self.generation,
+                    atlas_pages: galley.atlas_pages(),
                     galley: galley.clone(),
                 });
                 galley
@@ -596,6 +1022,14 @@ impl GalleyCache {
         });
         self.generation = self.generation.wrapping_add(1);
     }
+
+    /// The pages of the font atlas used by the galleys in the cache.
+    fn atlas_pages(&self) -> BTreeSet<usize> {
+        self.cache
+            .values()
+            .flat_map(|cached| cached.atlas_pages.iter().copied())
+            .collect()
+    }
 }
 
 // ----------------------------------------------------------------------------
@@ -603,7 +1037,16 @@ impl GalleyCache {
 struct FontImplCache {
     atlas: Arc<Mutex<TextureAtlas>>,
     pixels_per_point: f32,
-    ab_glyph_fonts: BTreeMap<String, ab_glyph::FontArc>,
+    ab_glyph_fonts: BTreeMap<String, (FontTweak, ab_glyph::FontArc)>,
+    /// Each font parsed for the text shaper, shared by all sizes.
+    #[cfg(feature = "text_shaping")]
+    shaping_faces: BTreeMap<String, Arc<super::shaping::ShapingFace>>,
+    /// The fonts that have color glyphs.
+    color_glyphs: BTreeMap<String, Arc<ColorGlyphs>>,
+    /// See [`FontDefinitions::sdf`].
+    sdf: bool,
+    /// The signed distance fields of each font, shared by all sizes.
+    sdf_glyphs: BTreeMap<String, Arc<SdfGlyphs>>,
 
     /// Map font pixel sizes and names to the cached `FontImpl`.
     cache: ahash::AHashMap<(u32, String), Arc<FontImpl>>,
@@ -614,20 +1057,64 @@ impl FontImplCache {
         atlas: Arc<Mutex<TextureAtlas>>,
         pixels_per_point: f32,
         font_data: &BTreeMap<String, FontData>,
+        sdf: bool,
     ) -> Self {
         let ab_glyph_fonts = font_data
             .iter()
-            .map(|(name, font_data)| (name.clone(), ab_glyph_font_from_font_data(name, font_data)))
+            .map(|(name, font_data)| {
+                let tweak = font_data.tweak;
+                let ab_glyph = ab_glyph_font_from_font_data(name, font_data);
+                (name.clone(), (tweak, ab_glyph))
+            })
+            .collect();
+
+        let color_glyphs = font_data
+            .iter()
+            .filter_map(|(name, font_data)| {
+                let color_glyphs = ColorGlyphs::parse(&font_data.font, font_data.index)?;
+                Some((name.clone(), Arc::new(color_glyphs)))
+            })
             .collect();
 
         Self {
             atlas,
             pixels_per_point,
             ab_glyph_fonts,
+            color_glyphs,
+            sdf,
+            sdf_glyphs: Default::default(),
+            #[cfg(feature = "text_shaping")]
+            shaping_faces: font_data
+                .iter()
+                .map(|(name, font_data)| {
+                    let face = super::shaping::ShapingFace::parse(font_data.clone());
+                    (name.clone(), Arc::new(face))
+                })
+                .collect(),
             cache: Default::default(),
         }
     }
 
+    /// Make a font that is not in [`FontDefinitions::font_data`] available to [`Self::font_impl`].
+    #[cfg(feature = "system_fonts")]
+    pub fn add_font_data(&mut self, font_name: &str, font_data: &FontData) {
+        if self.ab_glyph_fonts.contains_key(font_name) {
+            return;
+        }
+        let ab_glyph = ab_glyph_font_from_font_data(font_name, font_data);
+        self.ab_glyph_fonts
+            .insert(font_name.to_owned(), (font_data.tweak, ab_glyph));
+        #[cfg(feature = "text_shaping")]
+        self.shaping_faces.insert(
+            font_name.to_owned(),
+            Arc::new(super::shaping::ShapingFace::parse(font_data.clone())),
+        );
+        if let Some(color_glyphs) = ColorGlyphs::parse(&font_data.font, font_data.index) {
+            self.color_glyphs
+                .insert(font_name.to_owned(), Arc::new(color_glyphs));
+        }
+    }
+
     #[inline]
     pub fn scale_as_pixels(&self, scale_in_points: f32) -> u32 {
         let scale_in_pixels = self.pixels_per_point * scale_in_points;
@@ -638,33 +1125,42 @@ impl FontImplCache {
     }
 
     pub fn font_impl(&mut self, scale_in_pixels: u32, font_name: &str) -> Arc<FontImpl> {
-        let scale_in_pixels = if font_name == "emoji-icon-font" {
-            (scale_in_pixels as f32 * 0.8).round() as u32 // TODO: remove font scale HACK!
-        } else {
-            scale_in_pixels
-        };
+        let (tweak, ab_glyph_font) = self
+            .ab_glyph_fonts
+            .get(font_name)
+            .unwrap_or_else(|| panic!("No font data found for {:?}", font_name))
+            .clone();
+
+        let scale_in_pixels = (scale_in_pixels as f32 * tweak.scale).round() as u32;
 
-        let y_offset = if font_name == "emoji-icon-font" {
+        let y_offset = {
             let scale_in_points = scale_in_pixels as f32 / self.pixels_per_point;
-            scale_in_points * 0.29375 // TODO: remove font alignment hack
-        } else {
-            0.0
+            scale_in_points * tweak.y_offset_factor + tweak.y_offset
         };
         let y_offset = y_offset - 3.0; // Tweaked to make text look centered in buttons and text edit fields
 
+        let sdf_glyphs = if self.sdf {
+            Some(
+                self.sdf_glyphs
+                    .entry(font_name.to_owned())
+                    .or_default()
+                    .clone(),
+            )
+        } else {
+            None
+        };
+
         self.cache
             .entry((scale_in_pixels, font_name.to_owned()))
             .or_insert_with(|| {
-                let ab_glyph_font = self
-                    .ab_glyph_fonts
-                    .get(font_name)
-                    .unwrap_or_else(|| panic!("No font data found for {:?}", font_name))
-                    .clone();
-
                 Arc::new(FontImpl::new(
                     self.atlas.clone(),
                     self.pixels_per_point,
                     ab_glyph_font,
+                    #[cfg(feature = "text_shaping")]
+                    self.shaping_faces[font_name].clone(),
+                    self.color_glyphs.get(font_name).cloned(),
+                    sdf_glyphs,
                     scale_in_pixels,
                     y_offset,
                 ))
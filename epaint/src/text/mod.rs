@@ -1,8 +1,15 @@
 //! Everything related to text, fonts, text layout, cursors etc.
 
+mod bidi;
+mod color_glyphs;
 pub mod cursor;
 mod font;
 mod fonts;
+mod sdf_glyphs;
+#[cfg(feature = "text_shaping")]
+mod shaping;
+#[cfg(feature = "system_fonts")]
+mod system_fonts;
 mod text_layout;
 mod text_layout_types;
 
@@ -10,10 +17,19 @@ mod text_layout_types;
 pub const TAB_SIZE: usize = 4;
 
 pub use {
-    fonts::{FontData, FontDefinitions, FontFamily, FontId, Fonts, FontsImpl},
+    fonts::{
+        FontData, FontDefinitions, FontFamily, FontId, FontTweak, FontVariant, FontWeight, Fonts,
+        FontsImpl,
+    },
     text_layout::layout,
     text_layout_types::*,
 };
 
+#[cfg(feature = "system_fonts")]
+pub use system_fonts::SystemFonts;
+
 /// Suggested character to use to replace those in password text fields.
 pub const PASSWORD_REPLACEMENT_CHAR: char = '•';
+
+/// Stands in for an [`InlineBox`] in the text of a [`LayoutJob`].
+pub const OBJECT_REPLACEMENT_CHAR: char = '\u{FFFC}';
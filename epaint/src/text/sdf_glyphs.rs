@@ -0,0 +1,219 @@
+//! Glyphs as signed distance fields, see [`super::FontDefinitions::sdf`].
+//!
+//! Each glyph is rasterized once, at [`SDF_SCALE_IN_PIXELS`],
+//! and then shared by all sizes of the font.
+//! The painter turns the interpolated distances into crisp edges at whatever scale the glyph is painted.
+
+use ahash::AHashMap;
+use emath::{pos2, vec2, Pos2, Rect, Vec2};
+
+use crate::{mutex::RwLock, AlphaImage, TextureAtlas};
+
+/// The size the glyphs are rasterized at.
+pub(crate) const SDF_SCALE_IN_PIXELS: f32 = 48.0;
+
+/// How far from the edges of the glyph we store distances, in texels.
+///
+/// This is also the padding around each glyph.
+pub(crate) const SDF_SPREAD: f32 = 6.0;
+
+/// A glyph in a signed distance field page of the [`TextureAtlas`].
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SdfGlyph {
+    /// Position of the top left corner relative to the glyph origin on the baseline,
+    /// in pixels at [`SDF_SCALE_IN_PIXELS`].
+    pub min: Vec2,
+    /// Size in texels, including the padding.
+    pub size: [usize; 2],
+    pub page: usize,
+    /// Top left corner in the page.
+    pub pos: (usize, usize),
+}
+
+/// The glyphs of a font that have been added to the [`TextureAtlas`] as signed distance fields.
+#[derive(Default)]
+pub(crate) struct SdfGlyphs {
+    /// `None` for glyphs without an outline, e.g. space.
+    glyphs: RwLock<AHashMap<u16, Option<SdfGlyph>>>,
+}
+
+impl SdfGlyphs {
+    /// Look up a glyph, rasterizing it to the atlas if needed.
+    pub fn glyph(
+        &self,
+        atlas: &mut TextureAtlas,
+        font: &ab_glyph::FontArc,
+        glyph_id: ab_glyph::GlyphId,
+    ) -> Option<SdfGlyph> {
+        if let Some(glyph) = self.glyphs.read().get(&glyph_id.0) {
+            return *glyph;
+        }
+
+        let glyph = rasterize(font, glyph_id).map(|(min, distances)| {
+            let [width, height] = distances.size;
+            let (page, pos, image) = atlas.allocate_sdf(SDF_SPREAD, (width, height));
+            for y in 0..height {
+                for x in 0..width {
+                    image[(pos.0 + x, pos.1 + y)] = distances[(x, y)];
+                }
+            }
+            SdfGlyph {
+                min,
+                size: [width, height],
+                page,
+                pos,
+            }
+        });
+        self.glyphs.write().insert(glyph_id.0, glyph);
+        glyph
+    }
+
+    /// Forget the glyphs in a page of the [`TextureAtlas`] that has been cleared.
+    pub fn forget_atlas_page(&self, page: usize) {
+        self.glyphs
+            .write()
+            .retain(|_, glyph| glyph.map_or(true, |glyph| glyph.page != page));
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Returns the top left corner (see [`SdfGlyph::min`]) and the encoded distances (see [`crate::SdfImage`]).
+fn rasterize(font: &ab_glyph::FontArc, glyph_id: ab_glyph::GlyphId) -> Option<(Vec2, AlphaImage)> {
+    use ab_glyph::{Font as _, ScaleFont as _};
+
+    let outline = font.outline(glyph_id)?;
+    let scaled = font.as_scaled(SDF_SCALE_IN_PIXELS);
+    let (h_scale, v_scale) = (scaled.h_scale_factor(), scaled.v_scale_factor());
+    // Font units have y pointing up:
+    let to_pixels = |p: ab_glyph::Point| pos2(p.x * h_scale, -p.y * v_scale);
+
+    let mut segments = vec![];
+    for curve in &outline.curves {
+        flatten(curve, &to_pixels, &mut segments);
+    }
+    if segments.is_empty() {
+        return None;
+    }
+
+    let bounds = Rect::from_two_pos(to_pixels(outline.bounds.min), to_pixels(outline.bounds.max));
+    let min = bounds.min.floor() - Vec2::splat(SDF_SPREAD);
+    let max = bounds.max.ceil() + Vec2::splat(SDF_SPREAD);
+    let size = [(max.x - min.x) as usize, (max.y - min.y) as usize];
+
+    let mut image = AlphaImage::new(size);
+    for y in 0..size[1] {
+        for x in 0..size[0] {
+            let p = min + vec2(x as f32 + 0.5, y as f32 + 0.5);
+            image[(x, y)] = encode_distance(signed_distance(&segments, p));
+        }
+    }
+    Some((min.to_vec2(), image))
+}
+
+/// Approximate an outline curve with line segments.
+fn flatten(
+    curve: &ab_glyph::OutlineCurve,
+    to_pixels: &impl Fn(ab_glyph::Point) -> Pos2,
+    segments: &mut Vec<[Pos2; 2]>,
+) {
+    use ab_glyph::OutlineCurve;
+
+    let points: Vec<Pos2> = match curve {
+        OutlineCurve::Line(a, b) => vec![to_pixels(*a), to_pixels(*b)],
+        OutlineCurve::Quad(a, b, c) => vec![to_pixels(*a), to_pixels(*b), to_pixels(*c)],
+        OutlineCurve::Cubic(a, b, c, d) => {
+            vec![to_pixels(*a), to_pixels(*b), to_pixels(*c), to_pixels(*d)]
+        }
+    };
+
+    if points.len() == 2 {
+        segments.push([points[0], points[1]]);
+        return;
+    }
+
+    // About one segment every other pixel is plenty at this size:
+    let length: f32 = points.windows(2).map(|w| w[0].distance(w[1])).sum();
+    let num_segments = ((length / 2.0).ceil() as usize).clamp(1, 16);
+
+    let mut previous = points[0];
+    for i in 1..=num_segments {
+        let t = i as f32 / num_segments as f32;
+        let point = bezier_point(&points, t);
+        segments.push([previous, point]);
+        previous = point;
+    }
+}
+
+/// De Casteljau's algorithm.
+fn bezier_point(points: &[Pos2], t: f32) -> Pos2 {
+    let mut points = points.to_vec();
+    while points.len() > 1 {
+        for i in 0..points.len() - 1 {
+            points[i] = points[i] + t * (points[i + 1] - points[i]);
+        }
+        points.pop();
+    }
+    points[0]
+}
+
+/// Distance from `p` to the closest edge, in pixels.
+/// Positive inside the glyph, using the non-zero winding rule like the font rasterizer.
+fn signed_distance(segments: &[[Pos2; 2]], p: Pos2) -> f32 {
+    let mut distance_sq = f32::INFINITY;
+    let mut winding = 0;
+    for &[a, b] in segments {
+        distance_sq = distance_sq.min(distance_sq_to_segment(p, a, b));
+
+        // Count crossings of a ray going right from `p`:
+        if (a.y <= p.y) != (b.y <= p.y) {
+            let x = a.x + (p.y - a.y) * (b.x - a.x) / (b.y - a.y);
+            if x > p.x {
+                winding += if b.y > a.y { 1 } else { -1 };
+            }
+        }
+    }
+
+    let distance = distance_sq.sqrt();
+    if winding == 0 {
+        -distance
+    } else {
+        distance
+    }
+}
+
+fn distance_sq_to_segment(p: Pos2, a: Pos2, b: Pos2) -> f32 {
+    let ab = b - a;
+    let length_sq = ab.length_sq();
+    let t = if length_sq > 0.0 {
+        (((p - a).x * ab.x + (p - a).y * ab.y) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    p.distance_sq(a + t * ab)
+}
+
+/// See [`crate::SdfImage`].
+fn encode_distance(distance: f32) -> u8 {
+    let value = 0.5 + distance / (2.0 * SDF_SPREAD);
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[test]
+fn test_signed_distance() {
+    // A square with the corners (0,0) and (10,10):
+    let corners = [
+        pos2(0.0, 0.0),
+        pos2(10.0, 0.0),
+        pos2(10.0, 10.0),
+        pos2(0.0, 10.0),
+    ];
+    let segments: Vec<[Pos2; 2]> = (0..4).map(|i| [corners[i], corners[(i + 1) % 4]]).collect();
+
+    assert_eq!(signed_distance(&segments, pos2(5.0, 5.0)), 5.0);
+    assert_eq!(signed_distance(&segments, pos2(2.0, 5.0)), 2.0);
+    assert_eq!(signed_distance(&segments, pos2(-3.0, 5.0)), -3.0);
+    assert_eq!(encode_distance(0.0), 128);
+    assert_eq!(encode_distance(SDF_SPREAD), 255);
+    assert_eq!(encode_distance(-2.0 * SDF_SPREAD), 0);
+}
@@ -0,0 +1,119 @@
+//! Text shaping using [`rustybuzz`].
+//!
+//! Shaping turns a run of text into positioned glyphs,
+//! taking care of ligatures, combining marks, Arabic joining, Indic conjuncts etc.
+
+use std::ops::Range;
+
+use super::font::{FontImpl, GlyphInfo};
+use emath::{vec2, Vec2};
+
+pub(crate) use face::ShapingFace;
+
+mod face {
+    #![allow(clippy::mem_forget)] // used inside of `self_cell!`
+
+    use super::super::FontData;
+
+    type OptionalFace<'a> = Option<rustybuzz::Face<'a>>;
+
+    self_cell::self_cell!(
+        /// A font file parsed for [`rustybuzz`], together with the data it borrows.
+        ///
+        /// Parsing is done once per font, and shared by all its sizes.
+        pub(crate) struct ShapingFace {
+            owner: FontData,
+
+            #[covariant]
+            dependent: OptionalFace,
+        }
+    );
+
+    impl ShapingFace {
+        pub fn parse(font_data: FontData) -> Self {
+            Self::new(font_data, |font_data| {
+                rustybuzz::Face::from_slice(&font_data.font, font_data.index)
+            })
+        }
+    }
+}
+
+/// The glyphs that together show one or more `char`s,
+/// e.g. a ligature, or a letter with its combining marks.
+///
+/// A cursor can not be placed inside of a cluster, nor can a row be broken there.
+pub(crate) struct Cluster {
+    /// The `char`s of this cluster, as a byte range into the shaped text.
+    pub byte_range: Range<usize>,
+
+    /// How far to move before the next cluster. Unit: points.
+    pub advance: f32,
+
+    /// What to paint, with offsets (in points) from the start of the cluster.
+    pub glyphs: Vec<(GlyphInfo, Vec2)>,
+}
+
+/// Shape a run of text that only uses one font.
+///
+/// The clusters are returned in the order of the text, even for right-to-left text.
+/// Returns `None` if the font could not be parsed.
+pub(crate) fn shape(font_impl: &FontImpl, text: &str, rtl: bool) -> Option<Vec<Cluster>> {
+    let face = font_impl.shaping_face().borrow_dependent().as_ref()?;
+
+    let mut buffer = rustybuzz::UnicodeBuffer::new();
+    buffer.push_str(text);
+    buffer.set_direction(if rtl {
+        rustybuzz::Direction::RightToLeft
+    } else {
+        rustybuzz::Direction::LeftToRight
+    });
+    let output = rustybuzz::shape(face, &[], buffer);
+
+    let points_per_font_unit = font_impl.points_per_font_unit();
+
+    // The output is in visual order, so right-to-left text comes out reversed,
+    // but the glyphs of each cluster are always next to each other.
+    let mut clusters: Vec<Cluster> = vec![];
+    for (info, pos) in output.glyph_infos().iter().zip(output.glyph_positions()) {
+        let start = info.cluster as usize;
+        if clusters
+            .last()
+            .map_or(true, |c| c.byte_range.start != start)
+        {
+            clusters.push(Cluster {
+                byte_range: start..start,
+                advance: 0.0,
+                glyphs: vec![],
+            });
+        }
+        let cluster = clusters.last_mut().unwrap();
+
+        if info.glyph_id != 0 {
+            let glyph_info = font_impl.glyph_info_from_id(ab_glyph::GlyphId(info.glyph_id as u16));
+            if !glyph_info.uv_rect.is_nothing() {
+                let offset = vec2(
+                    cluster.advance + pos.x_offset as f32 * points_per_font_unit,
+                    -pos.y_offset as f32 * points_per_font_unit, // y is up in font units
+                );
+                cluster.glyphs.push((glyph_info, offset));
+            }
+        }
+        cluster.advance += pos.x_advance as f32 * points_per_font_unit;
+    }
+
+    if clusters.is_empty() {
+        return None;
+    }
+
+    clusters.sort_by_key(|cluster| cluster.byte_range.start);
+
+    // Make sure every `char` belongs to exactly one cluster:
+    clusters[0].byte_range.start = 0;
+    let mut end = text.len();
+    for cluster in clusters.iter_mut().rev() {
+        cluster.byte_range.end = end;
+        end = cluster.byte_range.start;
+    }
+
+    Some(clusters)
+}
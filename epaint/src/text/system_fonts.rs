@@ -0,0 +1,190 @@
+//! Fonts installed on the system, found with [`fontdb`].
+//!
+//! Only available with the `system_fonts` feature.
+
+use crate::mutex::Mutex;
+
+use super::{FontData, FontWeight};
+
+/// The fonts installed on the system.
+///
+/// Use this to add a font family by name with [`super::FontDefinitions::add_system_family`],
+/// or set [`super::FontDefinitions::system_fallback`] to find fonts for characters
+/// that are missing from your fonts (e.g. Chinese, Japanese and Korean).
+pub struct SystemFonts {
+    db: fontdb::Database,
+
+    /// Which face of [`Self::db`] to use as fallback for each `char`, as an index into [`fontdb::Database::faces`].
+    ///
+    /// Built by [`Self::index_fallbacks`], or the first time we look for a fallback,
+    /// by reading the character map of every face.
+    /// Characters that no face has are missing from the index, so they are only ever searched for once.
+    fallback_index: Mutex<Option<ahash::AHashMap<char, usize>>>,
+}
+
+impl std::fmt::Debug for SystemFonts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SystemFonts")
+            .field("num_faces", &self.db.len())
+            .finish()
+    }
+}
+
+impl SystemFonts {
+    /// Scan the font directories of the system.
+    ///
+    /// On Linux this reads the directories listed in the fontconfig configuration (`/etc/fonts/fonts.conf`).
+    ///
+    /// This reads the header of every installed font, so it can be slow.
+    /// Do it once and keep the result around.
+    pub fn load() -> Self {
+        let mut db = fontdb::Database::new();
+        db.load_system_fonts();
+        Self::from_database(db)
+    }
+
+    /// Use the fonts of an existing [`fontdb::Database`].
+    pub fn from_database(db: fontdb::Database) -> Self {
+        Self {
+            db,
+            fallback_index: Default::default(),
+        }
+    }
+
+    /// The names of all font families installed on the system, sorted and deduplicated.
+    pub fn family_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .db
+            .faces()
+            .iter()
+            .map(|face| face.family.clone())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Load the face of the given family that best matches the weight and style.
+    ///
+    /// Returns `None` if there is no such family, or the font file could not be read.
+    pub fn font_data(
+        &self,
+        family_name: &str,
+        weight: FontWeight,
+        italic: bool,
+    ) -> Option<FontData> {
+        self.find_face(family_name, weight, italic)
+            .and_then(|id| self.load_face(id))
+    }
+
+    /// The face of the given family that best matches the weight and style.
+    pub(crate) fn find_face(
+        &self,
+        family_name: &str,
+        weight: FontWeight,
+        italic: bool,
+    ) -> Option<fontdb::ID> {
+        self.db.query(&fontdb::Query {
+            families: &[fontdb::Family::Name(family_name)],
+            weight: fontdb::Weight(weight.0),
+            stretch: fontdb::Stretch::Normal,
+            style: if italic {
+                fontdb::Style::Italic
+            } else {
+                fontdb::Style::Normal
+            },
+        })
+    }
+
+    /// Is the given face italic (or oblique)?
+    pub(crate) fn is_italic(&self, id: fontdb::ID) -> bool {
+        self.db
+            .face(id)
+            .map_or(false, |face| face.style != fontdb::Style::Normal)
+    }
+
+    pub(crate) fn load_face(&self, id: fontdb::ID) -> Option<FontData> {
+        self.db.with_face_data(id, |data, index| FontData {
+            index,
+            ..FontData::from_owned(data.to_vec())
+        })
+    }
+
+    /// Read the character maps of all faces now, so [`Self::fallback_for`] is fast.
+    pub(crate) fn index_fallbacks(&self) {
+        let mut fallback_index = self.fallback_index.lock();
+        if fallback_index.is_none() {
+            *fallback_index = Some(self.build_fallback_index());
+        }
+    }
+
+    /// Find an upright, normal weight font that has the given character.
+    ///
+    /// Returns a unique name for the font (its `PostScript` name) and its data.
+    pub(crate) fn fallback_for(&self, c: char) -> Option<(String, FontData)> {
+        let index = *self
+            .fallback_index
+            .lock()
+            .get_or_insert_with(|| self.build_fallback_index())
+            .get(&c)?;
+
+        let face = &self.db.faces()[index];
+        let font_data = self.load_face(face.id)?;
+        Some((face.post_script_name.clone(), font_data))
+    }
+
+    /// Map every `char` to the preferred face that has it.
+    fn build_fallback_index(&self) -> ahash::AHashMap<char, usize> {
+        let faces = self.db.faces();
+
+        // Prefer faces that look like they could be the regular face of a family:
+        let mut candidates: Vec<usize> = (0..faces.len()).collect();
+        candidates.sort_by_key(|&i| {
+            let face = &faces[i];
+            (
+                face.style != fontdb::Style::Normal,
+                face.stretch != fontdb::Stretch::Normal,
+                (i32::from(face.weight.0) - 400).abs(),
+            )
+        });
+
+        let mut fallback_index = ahash::AHashMap::default();
+        for i in candidates {
+            for c in self.chars_in_face(faces[i].id) {
+                fallback_index.entry(c).or_insert(i);
+            }
+        }
+        fallback_index
+    }
+
+    /// All characters the face has glyphs for.
+    fn chars_in_face(&self, id: fontdb::ID) -> Vec<char> {
+        use ab_glyph::Font as _;
+
+        self.db
+            .with_face_data(id, |data, index| {
+                let font = ab_glyph::FontRef::try_from_slice_and_index(data, index).ok()?;
+                let chars: Vec<char> = font
+                    .codepoint_ids()
+                    .filter(|(glyph_id, _)| glyph_id.0 != 0)
+                    .map(|(_, c)| c)
+                    .collect();
+                Some(chars)
+            })
+            .flatten()
+            .unwrap_or_default()
+    }
+}
+
+#[test]
+fn test_fallback_for() {
+    let mut db = fontdb::Database::new();
+    db.load_font_data(include_bytes!("../../fonts/Hack-Regular.ttf").to_vec());
+    let system_fonts = SystemFonts::from_database(db);
+
+    let (font_name, font_data) = system_fonts.fallback_for('a').unwrap();
+    assert_eq!(font_name, "Hack-Regular");
+    assert!(!font_data.font.is_empty());
+
+    assert!(system_fonts.fallback_for('中').is_none(), "Hack has no CJK");
+}
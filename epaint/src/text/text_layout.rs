@@ -1,7 +1,12 @@
-use std::ops::RangeInclusive;
-
-use super::{FontsImpl, Galley, Glyph, LayoutJob, LayoutSection, Row, RowVisuals};
-use crate::{mutex::Arc, Color32, Mesh, Stroke, Vertex};
+use std::ops::{Range, RangeInclusive};
+
+use super::{
+    bidi::{self, BidiLevels},
+    font::{Font, UvRect},
+    FontsImpl, Galley, Glyph, InlineBox, LayoutJob, LayoutSection, Row, RowVisuals, TextFormat,
+    TextOverflow, OBJECT_REPLACEMENT_CHAR,
+};
+use crate::{mutex::Arc, util::FloatOrd as _, Color32, Mesh, Stroke, Vertex};
 use emath::*;
 
 // ----------------------------------------------------------------------------
@@ -42,8 +47,23 @@ struct Paragraph {
     /// Start of the next glyph to be added.
     pub cursor_x: f32,
     pub glyphs: Vec<Glyph>,
+    /// See [`Row::extra_glyphs`].
+    pub extra_glyphs: Vec<(usize, UvRect)>,
     /// In case of an empty paragraph ("\n"), use this as height.
     pub empty_paragraph_height: f32,
+    /// The base level of the paragraph for the Unicode Bidirectional Algorithm.
+    pub bidi_level: u8,
+}
+
+impl Paragraph {
+    /// The extra glyphs for the given range of glyphs, with indices relative to the range.
+    fn extra_glyphs_in(&self, range: std::ops::Range<usize>) -> Vec<(usize, UvRect)> {
+        self.extra_glyphs
+            .iter()
+            .filter(|(index, _)| range.contains(index))
+            .map(|&(index, uv_rect)| (index - range.start, uv_rect))
+            .collect()
+    }
 }
 
 /// Layout text into a [`Galley`].
@@ -51,14 +71,27 @@ struct Paragraph {
 /// In most cases you should use [`crate::Fonts::layout_job`] instead
 /// since that memoizes the input, making subsequent layouting of the same text much faster.
 pub fn layout(fonts: &mut FontsImpl, job: Arc<LayoutJob>) -> Galley {
-    let mut paragraphs = vec![Paragraph::default()];
+    let bidi = BidiLevels::new(&job.text, job.base_direction);
+
+    let mut paragraphs = vec![Paragraph {
+        cursor_x: job.paragraph_indent,
+        bidi_level: bidi.paragraph_level(0),
+        ..Default::default()
+    }];
     for (section_index, section) in job.sections.iter().enumerate() {
-        layout_section(fonts, &job, section_index as u32, section, &mut paragraphs);
+        layout_section(
+            fonts,
+            &job,
+            &bidi,
+            section_index as u32,
+            section,
+            &mut paragraphs,
+        );
     }
 
     let point_scale = PointScale::new(fonts.pixels_per_point());
 
-    let mut rows = rows_from_paragraphs(paragraphs, job.wrap_width);
+    let (mut rows, elided) = rows_from_paragraphs(fonts, &job, paragraphs);
 
     let justify = job.justify && job.wrap_width.is_finite();
 
@@ -71,12 +104,17 @@ pub fn layout(fonts: &mut FontsImpl, job: Arc<LayoutJob>) -> Galley {
         }
     }
 
-    galley_from_rows(point_scale, job, rows)
+    let format_summary = format_summary(fonts, &job);
+
+    let mut galley = galley_from_rows(point_scale, job, &format_summary, rows, elided);
+    galley.page_generations = fonts.page_generations(&galley.atlas_pages());
+    galley
 }
 
 fn layout_section(
     fonts: &mut FontsImpl,
     job: &LayoutJob,
+    bidi: &BidiLevels,
     section_index: u32,
     section: &LayoutSection,
     out_paragraphs: &mut Vec<Paragraph>,
@@ -85,44 +123,294 @@ fn layout_section(
         leading_space,
         byte_range,
         format,
+        inline_box,
     } = section;
-    let font = fonts.font(&format.font_id);
-    let font_height = font.row_height();
+    #[cfg(feature = "system_fonts")]
+    fonts.add_system_fallbacks(
+        &format.font_id,
+        format.weight,
+        format.italics,
+        &job.text[byte_range.clone()],
+    );
+    let font = fonts.font_variant(&format.font_id, format.weight, format.italics);
+    let line_height = format.line_height.unwrap_or_else(|| font.row_height());
 
     let mut paragraph = out_paragraphs.last_mut().unwrap();
     if paragraph.glyphs.is_empty() {
-        paragraph.empty_paragraph_height = font_height; // TODO: replace this hack with actually including `\n` in the glyphs?
+        paragraph.empty_paragraph_height = line_height; // TODO: replace this hack with actually including `\n` in the glyphs?
     }
 
     paragraph.cursor_x += leading_space;
 
-    let mut last_glyph_id = None;
+    let text = &job.text[byte_range.clone()];
 
-    for chr in job.text[byte_range.clone()].chars() {
-        if job.break_on_newline && chr == '\n' {
-            out_paragraphs.push(Paragraph::default());
+    if let Some(inline_box) = inline_box {
+        let levels = bidi.levels(byte_range.clone());
+        layout_inline_boxes(font, inline_box, text, levels, section_index, paragraph);
+        return;
+    }
+
+    #[cfg(not(feature = "text_shaping"))]
+    let mut last_glyph_id = None;
+    let mut line_start = byte_range.start;
+
+    for (i, line) in text
+        .split(|chr| job.break_on_newline && chr == '\n')
+        .enumerate()
+    {
+        if i > 0 {
+            out_paragraphs.push(Paragraph {
+                cursor_x: job.paragraph_indent,
+                bidi_level: bidi.paragraph_level(line_start),
+                ..Default::default()
+            });
             paragraph = out_paragraphs.last_mut().unwrap();
-            paragraph.empty_paragraph_height = font_height; // TODO: replace this hack with actually including `\n` in the glyphs?
+            paragraph.empty_paragraph_height = line_height; // TODO: replace this hack with actually including `\n` in the glyphs?
+        }
+
+        let levels = bidi.levels(line_start..line_start + line.len());
+        let first_glyph = paragraph.glyphs.len();
+
+        #[cfg(feature = "text_shaping")]
+        layout_shaped(font, line, levels, section_index, paragraph);
+
+        #[cfg(not(feature = "text_shaping"))]
+        layout_chars(
+            font,
+            line,
+            levels,
+            section_index,
+            &mut last_glyph_id,
+            paragraph,
+        );
+
+        apply_line_height_and_letter_spacing(font, format, first_glyph, paragraph);
+
+        line_start += line.len() + 1; // skip the `\n`
+    }
+}
+
+/// Apply [`TextFormat::line_height`] and [`TextFormat::extra_letter_spacing`]
+/// to the glyphs from `first_glyph` and on.
+fn apply_line_height_and_letter_spacing(
+    font: &Font,
+    format: &TextFormat,
+    first_glyph: usize,
+    paragraph: &mut Paragraph,
+) {
+    if let Some(line_height) = format.line_height {
+        // Center the glyphs in the taller (or shorter) row:
+        let half_leading = font.round_to_pixel((line_height - font.row_height()) / 2.0);
+        for glyph in &mut paragraph.glyphs[first_glyph..] {
+            glyph.size.y = line_height;
+            glyph.uv_rect.offset.y += half_leading;
+        }
+        for (index, uv_rect) in &mut paragraph.extra_glyphs {
+            if *index >= first_glyph {
+                uv_rect.offset.y += half_leading;
+            }
+        }
+    }
+
+    let spacing = format.extra_letter_spacing;
+    if spacing != 0.0 {
+        // The space goes after each cluster, so that ligatures stay together:
+        let mut shift = 0.0;
+        for i in first_glyph..paragraph.glyphs.len() {
+            let ends_cluster = paragraph
+                .glyphs
+                .get(i + 1)
+                .map_or(true, |next| !next.continues_cluster);
+            let glyph = &mut paragraph.glyphs[i];
+            glyph.pos.x += shift;
+            if ends_cluster {
+                glyph.size.x += spacing;
+                shift += spacing;
+            }
+        }
+        paragraph.cursor_x += shift;
+    }
+}
+
+/// Place one glyph per `char`, with kerning.
+///
+/// `levels` are the bidi levels of `text`, as returned by [`BidiLevels::levels`].
+fn layout_chars(
+    font: &mut Font,
+    text: &str,
+    levels: &[u8],
+    section_index: u32,
+    last_glyph_id: &mut Option<ab_glyph::GlyphId>,
+    paragraph: &mut Paragraph,
+) {
+    let font_height = font.row_height();
+
+    for (byte_index, chr) in text.char_indices() {
+        let bidi_level = bidi::level_at(levels, byte_index);
+        let glyph_chr = if bidi_level % 2 == 1 {
+            bidi::mirrored(chr)
         } else {
-            let (font_impl, glyph_info) = font.glyph_info_and_font_impl(chr);
-            if let Some(font_impl) = font_impl {
-                if let Some(last_glyph_id) = last_glyph_id {
-                    paragraph.cursor_x += font_impl.pair_kerning(last_glyph_id, glyph_info.id);
-                }
+            chr
+        };
+        let (font_impl, glyph_info) = font.glyph_info_and_font_impl(glyph_chr);
+        if let Some(font_impl) = font_impl {
+            if let Some(last_glyph_id) = *last_glyph_id {
+                paragraph.cursor_x += font_impl.pair_kerning(last_glyph_id, glyph_info.id);
             }
+        }
+
+        paragraph.glyphs.push(Glyph {
+            chr,
+            pos: pos2(paragraph.cursor_x, f32::NAN),
+            size: vec2(glyph_info.advance_width, font_height),
+            uv_rect: glyph_info.uv_rect,
+            section_index,
+            continues_cluster: false,
+            bidi_level,
+        });
+
+        paragraph.cursor_x += glyph_info.advance_width;
+        paragraph.cursor_x = font.round_to_pixel(paragraph.cursor_x);
+        *last_glyph_id = Some(glyph_info.id);
+    }
+}
+
+/// Place one [`InlineBox`] per `char`.
+///
+/// The glyph is as tall as needed to line up the baseline of the box with that of the `font`
+/// when the row is bottom-aligned, with the box at the top of the glyph.
+fn layout_inline_boxes(
+    font: &Font,
+    inline_box: &InlineBox,
+    text: &str,
+    levels: &[u8],
+    section_index: u32,
+    paragraph: &mut Paragraph,
+) {
+    let descent = font.row_height() - font.baseline();
+    let glyph_height = (inline_box.baseline + descent).max(inline_box.size.y);
+
+    for (byte_index, chr) in text.char_indices() {
+        paragraph.glyphs.push(Glyph {
+            chr,
+            pos: pos2(paragraph.cursor_x, f32::NAN),
+            size: vec2(inline_box.size.x, glyph_height),
+            uv_rect: UvRect::default(),
+            section_index,
+            continues_cluster: false,
+            bidi_level: bidi::level_at(levels, byte_index),
+        });
+
+        paragraph.cursor_x += inline_box.size.x;
+        paragraph.cursor_x = font.round_to_pixel(paragraph.cursor_x);
+    }
+}
+
+/// Split the text into runs that each use a single font and direction, and shape each run.
+#[cfg(feature = "text_shaping")]
+fn layout_shaped(
+    font: &mut Font,
+    text: &str,
+    levels: &[u8],
+    section_index: u32,
+    paragraph: &mut Paragraph,
+) {
+    let mut run_start = 0;
+    let mut run_font = None;
+    let mut run_level = 0;
+
+    for (i, chr) in text.char_indices() {
+        let font_index = if chr == '\t' {
+            None // fonts don't know how wide a tab is, so we handle it ourselves
+        } else {
+            font.font_index_for_char(chr, run_font)
+        };
+        let level = bidi::level_at(levels, i);
+        if i != run_start && (font_index != run_font || level != run_level) {
+            let run = run_start..i;
+            layout_shaped_run(font, text, run, levels, run_font, section_index, paragraph);
+            run_start = i;
+        }
+        run_font = font_index;
+        run_level = level;
+    }
+
+    if run_start < text.len() {
+        let run = run_start..text.len();
+        layout_shaped_run(font, text, run, levels, run_font, section_index, paragraph);
+    }
+}
+
+/// Shape a run of text in a single font.
+///
+/// Each `char` still gets its own [`Glyph`], so that cursors work as usual.
+/// The first `char` of a cluster gets the width of the whole cluster and paints all its glyphs,
+/// while the rest are zero-width at the end of the cluster,
+/// so that cursors are only placed at cluster boundaries.
+#[cfg(feature = "text_shaping")]
+fn layout_shaped_run(
+    font: &mut Font,
+    text: &str,
+    run: std::ops::Range<usize>,
+    levels: &[u8],
+    font_index: Option<super::font::FontIndex>,
+    section_index: u32,
+    paragraph: &mut Paragraph,
+) {
+    let levels = if levels.is_empty() {
+        levels
+    } else {
+        &levels[run.clone()]
+    };
+    let text = &text[run];
+    let rtl = bidi::level_at(levels, 0) % 2 == 1;
+
+    let clusters = font_index
+        .and_then(|font_index| super::shaping::shape(font.font_impl(font_index), text, rtl));
+    let clusters = if let Some(clusters) = clusters {
+        clusters
+    } else {
+        // Missing characters, tabs etc.
+        layout_chars(font, text, levels, section_index, &mut None, paragraph);
+        return;
+    };
+
+    let font_height = font.row_height();
+
+    for cluster in clusters {
+        let cluster_start = cluster.byte_range.start;
+        let cluster_text = &text[cluster.byte_range];
+        let first_index = paragraph.glyphs.len();
+        let end_x = font.round_to_pixel(paragraph.cursor_x + cluster.advance);
 
+        for (i, (byte_index, chr)) in cluster_text.char_indices().enumerate() {
+            let (x, width) = if i == 0 {
+                (paragraph.cursor_x, cluster.advance)
+            } else {
+                (end_x, 0.0)
+            };
             paragraph.glyphs.push(Glyph {
                 chr,
-                pos: pos2(paragraph.cursor_x, f32::NAN),
-                size: vec2(glyph_info.advance_width, font_height),
-                uv_rect: glyph_info.uv_rect,
+                pos: pos2(x, f32::NAN),
+                size: vec2(width, font_height),
+                uv_rect: UvRect::default(),
                 section_index,
+                continues_cluster: i > 0,
+                bidi_level: bidi::level_at(levels, cluster_start + byte_index),
             });
+        }
 
-            paragraph.cursor_x += glyph_info.advance_width;
-            paragraph.cursor_x = font.round_to_pixel(paragraph.cursor_x);
-            last_glyph_id = Some(glyph_info.id);
+        for (i, (glyph_info, offset)) in cluster.glyphs.iter().enumerate() {
+            let mut uv_rect = glyph_info.uv_rect;
+            uv_rect.offset += *offset;
+            if i == 0 {
+                paragraph.glyphs[first_index].uv_rect = uv_rect;
+            } else {
+                paragraph.extra_glyphs.push((first_index, uv_rect));
+            }
         }
+
+        paragraph.cursor_x = end_x;
     }
 }
 
@@ -131,17 +419,32 @@ fn rect_from_x_range(x_range: RangeInclusive<f32>) -> Rect {
     Rect::from_x_y_ranges(x_range, 0.0..=0.0)
 }
 
-fn rows_from_paragraphs(paragraphs: Vec<Paragraph>, wrap_width: f32) -> Vec<Row> {
+/// Also returns `true` if the text was elided because of [`LayoutJob::max_rows`].
+fn rows_from_paragraphs(
+    fonts: &mut FontsImpl,
+    job: &LayoutJob,
+    paragraphs: Vec<Paragraph>,
+) -> (Vec<Row>, bool) {
+    let wrap_width = job.wrap_width;
+    let max_rows = job.max_rows.max(1);
     let num_paragraphs = paragraphs.len();
 
+    // The end of the text, in case we need to show it after a `…`:
+    let text_end = (job.overflow == TextOverflow::EllipsisMiddle && max_rows < usize::MAX)
+        .then(|| paragraphs.last().cloned())
+        .flatten();
+
     let mut rows = vec![];
 
     for (i, paragraph) in paragraphs.into_iter().enumerate() {
         let is_last_paragraph = (i + 1) == num_paragraphs;
+        let first_row = rows.len();
+        let bidi_level = paragraph.bidi_level;
 
         if paragraph.glyphs.is_empty() {
             rows.push(Row {
                 glyphs: vec![],
+                extra_glyphs: vec![],
                 visuals: Default::default(),
                 rect: Rect::from_min_size(
                     pos2(paragraph.cursor_x, 0.0),
@@ -156,6 +459,7 @@ fn rows_from_paragraphs(paragraphs: Vec<Paragraph>, wrap_width: f32) -> Vec<Row>
                 let paragraph_min_x = paragraph.glyphs[0].pos.x;
                 rows.push(Row {
                     glyphs: paragraph.glyphs,
+                    extra_glyphs: paragraph.extra_glyphs,
                     visuals: Default::default(),
                     rect: rect_from_x_range(paragraph_min_x..=paragraph_max_x),
                     ends_with_newline: !is_last_paragraph,
@@ -165,9 +469,132 @@ fn rows_from_paragraphs(paragraphs: Vec<Paragraph>, wrap_width: f32) -> Vec<Row>
                 rows.last_mut().unwrap().ends_with_newline = !is_last_paragraph;
             }
         }
+
+        let elide = rows.len() >= max_rows
+            && (rows.len() > max_rows
+                || !is_last_paragraph
+                || rows[max_rows - 1].rect.max.x > wrap_width);
+        if elide {
+            rows.truncate(max_rows);
+            let row = rows.last_mut().unwrap();
+            elide_row(fonts, job, row, bidi_level, text_end.as_ref());
+        }
+
+        for row in &mut rows[first_row..] {
+            bidi::reorder_row(row, bidi_level);
+        }
+
+        if elide {
+            return (rows, true);
+        }
     }
 
-    rows
+    (rows, false)
+}
+
+/// Make the last row to show fit in [`LayoutJob::wrap_width`], ending it as decided by [`LayoutJob::overflow`].
+///
+/// Called before the row is reordered for bidi text, so the glyphs are still left-to-right.
+fn elide_row(
+    fonts: &mut FontsImpl,
+    job: &LayoutJob,
+    row: &mut Row,
+    bidi_level: u8,
+    text_end: Option<&Paragraph>,
+) {
+    row.ends_with_newline = false;
+
+    let section_index = row.glyphs.last().map_or(0, |glyph| glyph.section_index);
+    let ellipsis = if job.overflow == TextOverflow::Clip {
+        None
+    } else if let Some(section) = job.sections.get(section_index as usize) {
+        let format = &section.format;
+        let font = fonts.font_variant(&format.font_id, format.weight, format.italics);
+        let (_, glyph_info) = font.glyph_info_and_font_impl('…');
+        let height = row
+            .glyphs
+            .last()
+            .map_or_else(|| font.row_height(), |glyph| glyph.size.y);
+        Some(Glyph {
+            chr: '…',
+            pos: pos2(0.0, f32::NAN),
+            size: vec2(glyph_info.advance_width, height),
+            uv_rect: glyph_info.uv_rect,
+            section_index,
+            continues_cluster: false,
+            bidi_level,
+        })
+    } else {
+        None
+    };
+    let ellipsis_width = ellipsis.map_or(0.0, |glyph| glyph.size.x);
+
+    let row_min_x = row
+        .glyphs
+        .first()
+        .map_or(row.rect.min.x, |glyph| glyph.pos.x);
+    let available_width = (job.wrap_width - row_min_x - ellipsis_width).at_least(0.0);
+
+    let (prefix_width, text_end) = match text_end {
+        Some(text_end) if ellipsis.is_some() => (available_width / 2.0, Some(text_end)),
+        _ => (available_width, None),
+    };
+
+    // Keep whole clusters that fit:
+    let mut num_kept = row.glyphs.len();
+    while num_kept > 0
+        && (row.glyphs[num_kept - 1].max_x() - row_min_x > prefix_width
+            || row.glyphs[num_kept - 1].chr.is_whitespace()
+            || row
+                .glyphs
+                .get(num_kept)
+                .map_or(false, |next| next.continues_cluster))
+    {
+        num_kept -= 1;
+    }
+    row.glyphs.truncate(num_kept);
+    row.extra_glyphs.retain(|&(index, _)| index < num_kept);
+
+    let mut cursor_x = row.glyphs.last().map_or(row_min_x, |glyph| glyph.max_x());
+    if let Some(mut ellipsis) = ellipsis {
+        ellipsis.pos.x = cursor_x;
+        cursor_x += ellipsis.size.x;
+        row.glyphs.push(ellipsis);
+    }
+
+    if let Some(text_end) = text_end {
+        let suffix_width = available_width - (cursor_x - ellipsis_width - row_min_x);
+        let glyphs = &text_end.glyphs;
+        let end_x = glyphs.last().map_or(0.0, |glyph| glyph.max_x());
+        let mut suffix_start = glyphs.len();
+        while suffix_start > 0 && end_x - glyphs[suffix_start - 1].pos.x <= suffix_width {
+            suffix_start -= 1;
+        }
+        while suffix_start < glyphs.len()
+            && (glyphs[suffix_start].continues_cluster || glyphs[suffix_start].chr.is_whitespace())
+        {
+            suffix_start += 1;
+        }
+
+        if let Some(first) = glyphs.get(suffix_start) {
+            let offset = cursor_x - first.pos.x;
+            let index_offset = row.glyphs.len();
+            row.glyphs
+                .extend(glyphs[suffix_start..].iter().map(|&glyph| Glyph {
+                    pos: pos2(glyph.pos.x + offset, glyph.pos.y),
+                    ..glyph
+                }));
+            row.extra_glyphs.extend(
+                text_end
+                    .extra_glyphs_in(suffix_start..glyphs.len())
+                    .into_iter()
+                    .map(|(index, uv_rect)| (index + index_offset, uv_rect)),
+            );
+        }
+    }
+
+    let max_x = row.glyphs.last().map_or(row_min_x, |glyph| glyph.max_x());
+    row.rect = rect_from_x_range(row_min_x..=max_x);
 }
 
 fn line_break(paragraph: &Paragraph, wrap_width: f32, out_rows: &mut Vec<Row>) {
@@ -187,6 +614,7 @@ fn line_break(paragraph: &Paragraph, wrap_width: f32, out_rows: &mut Vec<Row>) {
                 // TODO: this records the height of this first row as zero, though that is probably fine since first_row_indentation usually comes with a first_row_min_height.
                 out_rows.push(Row {
                     glyphs: vec![],
+                    extra_glyphs: vec![],
                     visuals: Default::default(),
                     rect: rect_from_x_range(first_row_indentation..=first_row_indentation),
                     ends_with_newline: false,
@@ -208,6 +636,7 @@ fn line_break(paragraph: &Paragraph, wrap_width: f32, out_rows: &mut Vec<Row>) {
 
                 out_rows.push(Row {
                     glyphs,
+                    extra_glyphs: paragraph.extra_glyphs_in(row_start_idx..last_kept_index + 1),
                     visuals: Default::default(),
                     rect: rect_from_x_range(paragraph_min_x..=paragraph_max_x),
                     ends_with_newline: false,
@@ -221,7 +650,13 @@ fn line_break(paragraph: &Paragraph, wrap_width: f32, out_rows: &mut Vec<Row>) {
             }
         }
 
-        row_break_candidates.add(i, glyph.chr);
+        let next_continues_cluster = paragraph
+            .glyphs
+            .get(i + 1)
+            .map_or(false, |next| next.continues_cluster);
+        if !next_continues_cluster {
+            row_break_candidates.add(i, glyph.chr);
+        }
     }
 
     if row_start_idx < paragraph.glyphs.len() {
@@ -239,6 +674,7 @@ fn line_break(paragraph: &Paragraph, wrap_width: f32, out_rows: &mut Vec<Row>) {
 
         out_rows.push(Row {
             glyphs,
+            extra_glyphs: paragraph.extra_glyphs_in(row_start_idx..paragraph.glyphs.len()),
             visuals: Default::default(),
             rect: rect_from_x_range(paragraph_min_x..=paragraph_max_x),
             ends_with_newline: false,
@@ -257,33 +693,50 @@ fn halign_and_jusitfy_row(
         return;
     }
 
-    let num_leading_spaces = row
-        .glyphs
-        .iter()
-        .take_while(|glyph| glyph.chr.is_whitespace())
+    // Indices of the glyphs from left to right (only different from the logical order for bidi text):
+    let mut visual_order: Vec<usize> = (0..row.glyphs.len()).collect();
+    if row.has_rtl() {
+        visual_order.sort_by_key(|&i| row.glyphs[i].pos.x.ord());
+    }
+    let glyph_at = |k: usize| &row.glyphs[visual_order[k]];
+
+    // Are the glyphs at these two adjacent visual positions part of the same cluster?
+    let same_cluster = |k: usize| {
+        let (a, b) = (visual_order[k], visual_order[k + 1]);
+        (b == a + 1 && row.glyphs[b].continues_cluster)
+            || (a == b + 1 && row.glyphs[a].continues_cluster)
+    };
+
+    let num_glyphs = row.glyphs.len();
+    let num_leading_spaces = (0..num_glyphs)
+        .take_while(|&k| glyph_at(k).chr.is_whitespace())
         .count();
 
-    let glyph_range = if num_leading_spaces == row.glyphs.len() {
+    let glyph_range = if num_leading_spaces == num_glyphs {
         // There is only whitespace
-        (0, row.glyphs.len())
+        (0, num_glyphs)
     } else {
-        let num_trailing_spaces = row
-            .glyphs
-            .iter()
+        let num_trailing_spaces = (0..num_glyphs)
             .rev()
-            .take_while(|glyph| glyph.chr.is_whitespace())
+            .take_while(|&k| glyph_at(k).chr.is_whitespace())
             .count();
 
-        (num_leading_spaces, row.glyphs.len() - num_trailing_spaces)
+        (num_leading_spaces, num_glyphs - num_trailing_spaces)
     };
     let num_glyphs_in_range = glyph_range.1 - glyph_range.0;
     assert!(num_glyphs_in_range > 0);
 
-    let original_min_x = row.glyphs[glyph_range.0].logical_rect().min.x;
-    let original_max_x = row.glyphs[glyph_range.1 - 1].logical_rect().max.x;
+    // We only add space between clusters (which are usually single glyphs):
+    let num_clusters_in_range = 1
+        + (glyph_range.0..glyph_range.1 - 1)
+            .filter(|&k| !same_cluster(k))
+            .count();
+
+    let original_min_x = glyph_at(glyph_range.0).logical_rect().min.x;
+    let original_max_x = glyph_at(glyph_range.1 - 1).logical_rect().max.x;
     let original_width = original_max_x - original_min_x;
 
-    let target_width = if justify && num_glyphs_in_range > 1 {
+    let target_width = if justify && num_clusters_in_range > 1 {
         wrap_width
     } else {
         original_width
@@ -295,20 +748,19 @@ fn halign_and_jusitfy_row(
         Align::RIGHT => (-target_width, 0.0),
     };
 
-    let num_spaces_in_range = row.glyphs[glyph_range.0..glyph_range.1]
-        .iter()
-        .filter(|glyph| glyph.chr.is_whitespace())
+    let num_spaces_in_range = (glyph_range.0..glyph_range.1)
+        .filter(|&k| glyph_at(k).chr.is_whitespace())
         .count();
 
-    let mut extra_x_per_glyph = if num_glyphs_in_range == 1 {
+    let mut extra_x_per_glyph = if num_clusters_in_range == 1 {
         0.0
     } else {
-        (target_width - original_width) / (num_glyphs_in_range as f32 - 1.0)
+        (target_width - original_width) / (num_clusters_in_range as f32 - 1.0)
     };
     extra_x_per_glyph = extra_x_per_glyph.at_least(0.0); // Don't contract
 
     let mut extra_x_per_space = 0.0;
-    if 0 < num_spaces_in_range && num_spaces_in_range < num_glyphs_in_range {
+    if 0 < num_spaces_in_range && num_spaces_in_range < num_clusters_in_range {
         // Add an integral number of pixels between each glyph,
         // and add the balance to the spaces:
 
@@ -316,19 +768,25 @@ fn halign_and_jusitfy_row(
 
         extra_x_per_space = (target_width
             - original_width
-            - extra_x_per_glyph * (num_glyphs_in_range as f32 - 1.0))
+            - extra_x_per_glyph * (num_clusters_in_range as f32 - 1.0))
             / (num_spaces_in_range as f32);
     }
 
     let mut translate_x = target_min_x - original_min_x - extra_x_per_glyph * glyph_range.0 as f32;
 
-    for glyph in &mut row.glyphs {
-        glyph.pos.x += translate_x;
-        glyph.pos.x = point_scale.round_to_pixel(glyph.pos.x);
-        translate_x += extra_x_per_glyph;
+    let mut new_x = Vec::with_capacity(num_glyphs);
+    for k in 0..num_glyphs {
+        let glyph = glyph_at(k);
+        new_x.push(point_scale.round_to_pixel(glyph.pos.x + translate_x));
         if glyph.chr.is_whitespace() {
             translate_x += extra_x_per_space;
         }
+        if k + 1 == num_glyphs || !same_cluster(k) {
+            translate_x += extra_x_per_glyph;
+        }
+    }
+    for (k, x) in new_x.into_iter().enumerate() {
+        row.glyphs[visual_order[k]].pos.x = x;
     }
 
     // Note we ignore the leading/trailing whitespace here!
@@ -337,11 +795,18 @@ fn halign_and_jusitfy_row(
 }
 
 /// Calculate the Y positions and tessellate the text.
-fn galley_from_rows(point_scale: PointScale, job: Arc<LayoutJob>, mut rows: Vec<Row>) -> Galley {
+fn galley_from_rows(
+    point_scale: PointScale,
+    job: Arc<LayoutJob>,
+    format_summary: &FormatSummary,
+    mut rows: Vec<Row>,
+    elided: bool,
+) -> Galley {
     let mut first_row_min_height = job.first_row_min_height;
     let mut cursor_y = 0.0;
     let mut min_x: f32 = 0.0;
     let mut max_x: f32 = 0.0;
+    let mut inline_boxes = vec![];
     for row in &mut rows {
         let mut row_height = first_row_min_height.max(row.rect.height());
         first_row_min_height = 0.0;
@@ -352,9 +817,15 @@ fn galley_from_rows(point_scale: PointScale, job: Arc<LayoutJob>, mut rows: Vec<
 
         // Now positions each glyph:
         for glyph in &mut row.glyphs {
-            let format = &job.sections[glyph.section_index as usize].format;
-            glyph.pos.y = cursor_y + format.valign.to_factor() * (row_height - glyph.size.y);
+            let section = &job.sections[glyph.section_index as usize];
+            glyph.pos.y =
+                cursor_y + section.format.valign.to_factor() * (row_height - glyph.size.y);
             glyph.pos.y = point_scale.round_to_pixel(glyph.pos.y);
+
+            if let Some(inline_box) = &section.inline_box {
+                let rect = Rect::from_min_size(glyph.pos, inline_box.size);
+                inline_boxes.push((glyph.section_index as usize, rect));
+            }
         }
 
         row.rect.min.y = cursor_y;
@@ -363,17 +834,18 @@ fn galley_from_rows(point_scale: PointScale, job: Arc<LayoutJob>, mut rows: Vec<
         min_x = min_x.min(row.rect.min.x);
         max_x = max_x.max(row.rect.max.x);
         cursor_y += row_height;
+        if row.ends_with_newline {
+            cursor_y += job.paragraph_spacing;
+        }
         cursor_y = point_scale.round_to_pixel(cursor_y);
     }
 
-    let format_summary = format_summary(&job);
-
     let mut mesh_bounds = Rect::NOTHING;
     let mut num_vertices = 0;
     let mut num_indices = 0;
 
     for row in &mut rows {
-        row.visuals = tessellate_row(point_scale, &job, &format_summary, row);
+        row.visuals = tessellate_row(point_scale, &job, format_summary, row);
         mesh_bounds = mesh_bounds.union(row.visuals.mesh_bounds);
         num_vertices += row.visuals.mesh.vertices.len();
         num_indices += row.visuals.mesh.indices.len();
@@ -388,6 +860,10 @@ fn galley_from_rows(point_scale: PointScale, job: Arc<LayoutJob>, mut rows: Vec<
         mesh_bounds,
         num_vertices,
         num_indices,
+        inline_boxes,
+        elided,
+        page_generations: vec![],
+        derived: false,
     }
 }
 
@@ -396,14 +872,20 @@ struct FormatSummary {
     any_background: bool,
     any_underline: bool,
     any_strikethrough: bool,
+    /// For each section: should we slant the glyphs because there is no real italic font?
+    fake_italics: Vec<bool>,
 }
 
-fn format_summary(job: &LayoutJob) -> FormatSummary {
+fn format_summary(fonts: &FontsImpl, job: &LayoutJob) -> FormatSummary {
     let mut format_summary = FormatSummary::default();
     for section in &job.sections {
-        format_summary.any_background |= section.format.background != Color32::TRANSPARENT;
-        format_summary.any_underline |= section.format.underline != Stroke::none();
-        format_summary.any_strikethrough |= section.format.strikethrough != Stroke::none();
+        let format = &section.format;
+        format_summary.any_background |= format.background != Color32::TRANSPARENT;
+        format_summary.any_underline |= format.underline != Stroke::none();
+        format_summary.any_strikethrough |= format.strikethrough != Stroke::none();
+        format_summary.fake_italics.push(
+            format.italics && !fonts.has_italic_variant(&format.font_id.family, format.weight),
+        );
     }
     format_summary
 }
@@ -423,14 +905,37 @@ fn tessellate_row(
     mesh.reserve_triangles(row.glyphs.len() * 2);
     mesh.reserve_vertices(row.glyphs.len() * 4);
 
+    // Backgrounds and lines use the white texel, which is in every page of the atlas:
+    let atlas_pages = row_atlas_pages(row);
+    let first_page = atlas_pages.first().copied().unwrap_or(0);
+    let mut page_index_ranges = vec![];
+
     if format_summary.any_background {
         add_row_backgrounds(job, row, &mut mesh);
     }
+    extend_page_index_ranges(&mut page_index_ranges, first_page, &mesh);
 
     let glyph_vertex_start = mesh.vertices.len();
-    tessellate_glyphs(point_scale, job, row, &mut mesh);
+    for &page in &atlas_pages {
+        tessellate_glyphs(
+            point_scale,
+            job,
+            format_summary,
+            row,
+            page,
+            false,
+            &mut mesh,
+        );
+        extend_page_index_ranges(&mut page_index_ranges, page, &mesh);
+    }
     let glyph_vertex_end = mesh.vertices.len();
 
+    // Outside of `glyph_vertex_range`, so they don't get tinted by `override_text_color`:
+    for &page in &atlas_pages {
+        tessellate_glyphs(point_scale, job, format_summary, row, page, true, &mut mesh);
+        extend_page_index_ranges(&mut page_index_ranges, page, &mesh);
+    }
+
     if format_summary.any_underline {
         add_row_hline(point_scale, row, &mut mesh, |glyph| {
             let format = &job.sections[glyph.section_index as usize].format;
@@ -449,12 +954,47 @@ fn tessellate_row(
         });
     }
 
+    extend_page_index_ranges(&mut page_index_ranges, first_page, &mesh);
+
     let mesh_bounds = mesh.calc_bounds();
 
     RowVisuals {
         mesh,
         mesh_bounds,
         glyph_vertex_range: glyph_vertex_start..glyph_vertex_end,
+        page_index_ranges,
+    }
+}
+
+/// The pages of the font atlas used by the glyphs of the row, sorted.
+fn row_atlas_pages(row: &Row) -> Vec<usize> {
+    let mut pages = vec![];
+    let uv_rects = row.glyphs.iter().map(|glyph| &glyph.uv_rect);
+    let extra_uv_rects = row.extra_glyphs.iter().map(|(_, uv_rect)| uv_rect);
+    for uv_rect in uv_rects.chain(extra_uv_rects) {
+        let page = uv_rect.page as usize;
+        if !uv_rect.is_nothing() && !pages.contains(&page) {
+            pages.push(page);
+        }
+    }
+    pages.sort_unstable();
+    pages
+}
+
+/// Say that the indices added to `mesh` since the last call uses the given page of the font atlas.
+fn extend_page_index_ranges(
+    page_index_ranges: &mut Vec<(usize, Range<usize>)>,
+    page: usize,
+    mesh: &Mesh,
+) {
+    let start = page_index_ranges.last().map_or(0, |(_, range)| range.end);
+    let end = mesh.indices.len();
+    if start == end {
+        return;
+    }
+    match page_index_ranges.last_mut() {
+        Some((last_page, range)) if *last_page == page => range.end = end,
+        _ => page_index_ranges.push((page, start..end)),
     }
 }
 
@@ -503,55 +1043,92 @@ fn add_row_backgrounds(job: &LayoutJob, row: &Row, mesh: &mut Mesh) {
     end_run(run_start.take(), last_rect.right());
 }
 
-fn tessellate_glyphs(point_scale: PointScale, job: &LayoutJob, row: &Row, mesh: &mut Mesh) {
+fn tessellate_glyphs(
+    point_scale: PointScale,
+    job: &LayoutJob,
+    format_summary: &FormatSummary,
+    row: &Row,
+    page: usize,
+    colored: bool,
+    mesh: &mut Mesh,
+) {
+    let wanted = |uv_rect: &UvRect| uv_rect.page as usize == page && uv_rect.colored == colored;
     for glyph in &row.glyphs {
-        let uv_rect = glyph.uv_rect;
-        if !uv_rect.is_nothing() {
-            let mut left_top = glyph.pos + uv_rect.offset;
-            left_top.x = point_scale.round_to_pixel(left_top.x);
-            left_top.y = point_scale.round_to_pixel(left_top.y);
-
-            let rect = Rect::from_min_max(left_top, left_top + uv_rect.size);
-            let uv = Rect::from_min_max(
-                pos2(uv_rect.min[0] as f32, uv_rect.min[1] as f32),
-                pos2(uv_rect.max[0] as f32, uv_rect.max[1] as f32),
-            );
+        if wanted(&glyph.uv_rect) {
+            tessellate_glyph(point_scale, job, format_summary, glyph, glyph.uv_rect, mesh);
+        }
+    }
+    for &(index, uv_rect) in &row.extra_glyphs {
+        if wanted(&uv_rect) {
+            let glyph = &row.glyphs[index];
+            tessellate_glyph(point_scale, job, format_summary, glyph, uv_rect, mesh);
+        }
+    }
+}
 
-            let format = &job.sections[glyph.section_index as usize].format;
+/// Paint `uv_rect` relative to the position of `glyph`, in the format of `glyph`.
+fn tessellate_glyph(
+    point_scale: PointScale,
+    job: &LayoutJob,
+    format_summary: &FormatSummary,
+    glyph: &Glyph,
+    uv_rect: UvRect,
+    mesh: &mut Mesh,
+) {
+    if uv_rect.is_nothing() {
+        return;
+    }
 
-            let color = format.color;
+    let mut left_top = glyph.pos + uv_rect.offset;
+    left_top.x = point_scale.round_to_pixel(left_top.x);
+    left_top.y = point_scale.round_to_pixel(left_top.y);
 
-            if format.italics {
-                let idx = mesh.vertices.len() as u32;
-                mesh.add_triangle(idx, idx + 1, idx + 2);
-                mesh.add_triangle(idx + 2, idx + 1, idx + 3);
+    let rect = Rect::from_min_max(left_top, left_top + uv_rect.size);
+    let uv = Rect::from_min_max(
+        pos2(uv_rect.min[0] as f32, uv_rect.min[1] as f32),
+        pos2(uv_rect.max[0] as f32, uv_rect.max[1] as f32),
+    );
 
-                let top_offset = rect.height() * 0.25 * Vec2::X;
+    let format = &job.sections[glyph.section_index as usize].format;
 
-                mesh.vertices.push(Vertex {
-                    pos: rect.left_top() + top_offset,
-                    uv: uv.left_top(),
-                    color,
-                });
-                mesh.vertices.push(Vertex {
-                    pos: rect.right_top() + top_offset,
-                    uv: uv.right_top(),
-                    color,
-                });
-                mesh.vertices.push(Vertex {
-                    pos: rect.left_bottom(),
-                    uv: uv.left_bottom(),
-                    color,
-                });
-                mesh.vertices.push(Vertex {
-                    pos: rect.right_bottom(),
-                    uv: uv.right_bottom(),
-                    color,
-                });
-            } else {
-                mesh.add_rect_with_uv(rect, uv, color);
-            }
-        }
+    if uv_rect.colored {
+        // Keep the colors of the glyph, but fade it with the text:
+        let color = Color32::from_white_alpha(format.color.a());
+        mesh.add_rect_with_uv(rect, uv, color);
+        return;
+    }
+
+    let color = format.color;
+
+    if format_summary.fake_italics[glyph.section_index as usize] {
+        let idx = mesh.vertices.len() as u32;
+        mesh.add_triangle(idx, idx + 1, idx + 2);
+        mesh.add_triangle(idx + 2, idx + 1, idx + 3);
+
+        let top_offset = rect.height() * 0.25 * Vec2::X;
+
+        mesh.vertices.push(Vertex {
+            pos: rect.left_top() + top_offset,
+            uv: uv.left_top(),
+            color,
+        });
+        mesh.vertices.push(Vertex {
+            pos: rect.right_top() + top_offset,
+            uv: uv.right_top(),
+            color,
+        });
+        mesh.vertices.push(Vertex {
+            pos: rect.left_bottom(),
+            uv: uv.left_bottom(),
+            color,
+        });
+        mesh.vertices.push(Vertex {
+            pos: rect.right_bottom(),
+            uv: uv.right_bottom(),
+            color,
+        });
+    } else {
+        mesh.add_rect_with_uv(rect, uv, color);
     }
 }
 
@@ -647,7 +1224,7 @@ impl RowBreakCandidates {
         const NON_BREAKING_SPACE: char = '\u{A0}';
         if chr.is_whitespace() && chr != NON_BREAKING_SPACE {
             self.space = Some(index);
-        } else if is_chinese(chr) {
+        } else if is_chinese(chr) || chr == OBJECT_REPLACEMENT_CHAR {
             self.logogram = Some(index);
         } else if chr == '-' {
             self.dash = Some(index);
@@ -677,3 +1254,390 @@ fn is_chinese(c: char) -> bool {
         || ('\u{3400}' <= c && c <= '\u{4DBF}')
         || ('\u{2B740}' <= c && c <= '\u{2B81F}')
 }
+
+#[cfg(feature = "text_shaping")]
+#[test]
+fn test_shaped_clusters() {
+    use super::{FontDefinitions, FontFamily, FontId};
+
+    let mut fonts = FontsImpl::new(1.0, 1024, FontDefinitions::default());
+    let mut layout_text = |text: &str, family: FontFamily| {
+        let font_id = FontId::new(14.0, family);
+        let job = LayoutJob::simple(text.to_owned(), font_id, Color32::WHITE, f32::INFINITY);
+        layout(&mut fonts, Arc::new(job))
+    };
+
+    // An `fi` ligature:
+    let galley = layout_text("fine", FontFamily::Proportional);
+    let glyphs = &galley.rows[0].glyphs;
+    assert_eq!(
+        glyphs.len(),
+        4,
+        "Still one glyph per char, so cursors keep working"
+    );
+    assert!(!glyphs[0].continues_cluster);
+    assert!(glyphs[1].continues_cluster);
+    assert!(!glyphs[0].uv_rect.is_nothing());
+    assert!(
+        glyphs[1].uv_rect.is_nothing(),
+        "The ligature is painted by the `f`"
+    );
+    assert_eq!(
+        glyphs[1].size.x, 0.0,
+        "The cursor goes before or after the ligature, never inside it"
+    );
+    assert_eq!(glyphs[1].pos.x, glyphs[2].pos.x);
+    assert_eq!(galley.rows[0].char_at(glyphs[0].max_x() - 1.0), 2);
+    let cursor = galley.cursor_right_one_character(&super::cursor::Cursor::default());
+    assert_eq!(cursor.ccursor.index, 2);
+    assert_eq!(galley.cursor_left_one_character(&cursor).ccursor.index, 0);
+
+    // An `e` followed by a combining acute accent:
+    let galley = layout_text("e\u{301}x", FontFamily::Monospace);
+    let glyphs = &galley.rows[0].glyphs;
+    assert_eq!(glyphs.len(), 3);
+    assert!(glyphs[1].continues_cluster);
+    assert!(!glyphs[2].continues_cluster);
+}
+
+#[test]
+fn test_bidi_layout() {
+    use super::{FontDefinitions, FontId, TextDirection};
+
+    let mut fonts = FontsImpl::new(1.0, 1024, FontDefinitions::default());
+    let mut layout_text = |text: &str, base_direction: Option<TextDirection>| {
+        let mut job =
+            LayoutJob::simple_singleline(text.to_owned(), FontId::default(), Color32::WHITE);
+        job.base_direction = base_direction;
+        layout(&mut fonts, Arc::new(job))
+    };
+
+    let galley = layout_text("abc \u{5D0}\u{5D1}\u{5D2}", None);
+    let row = &galley.rows[0];
+    let glyphs = &row.glyphs;
+    assert_eq!(glyphs.len(), 7);
+    assert!(!glyphs[2].is_rtl());
+    assert!(glyphs[4].is_rtl());
+    assert!(glyphs[2].pos.x < glyphs[6].pos.x, "Hebrew after English");
+    assert!(
+        glyphs[6].pos.x < glyphs[5].pos.x && glyphs[5].pos.x < glyphs[4].pos.x,
+        "Hebrew is right-to-left"
+    );
+
+    for column in 0..=row.char_count_excluding_newline() {
+        let x = row.x_offset(column);
+        assert_eq!(row.x_offset(row.char_at(x)), x, "column {}", column);
+    }
+
+    // Moving right through the whole row visits every cursor position once:
+    let mut cursor = super::cursor::Cursor::default();
+    let mut visited = vec![cursor.ccursor.index];
+    for _ in 0..7 {
+        cursor = galley.cursor_right_one_character(&cursor);
+        visited.push(cursor.ccursor.index);
+    }
+    visited.sort_unstable();
+    visited.dedup();
+    assert_eq!(visited.len(), 8);
+
+    let galley = layout_text(
+        "abc \u{5D0}\u{5D1}\u{5D2}",
+        Some(TextDirection::RightToLeft),
+    );
+    let glyphs = &galley.rows[0].glyphs;
+    assert!(
+        glyphs[4].pos.x < glyphs[0].pos.x,
+        "English first, on the right"
+    );
+    assert!(
+        glyphs[0].pos.x < glyphs[1].pos.x,
+        "English is still left-to-right"
+    );
+}
+
+#[test]
+fn test_font_variants() {
+    use super::{FontDefinitions, FontFamily, FontId, FontVariant, FontWeight, TextFormat};
+
+    let mut definitions = FontDefinitions::default();
+    let variants = definitions
+        .variants
+        .entry(FontFamily::Proportional)
+        .or_default();
+    variants.push(FontVariant {
+        weight: FontWeight::BOLD,
+        italic: false,
+        fonts: vec!["Hack".to_owned()],
+    });
+    variants.push(FontVariant {
+        weight: FontWeight::NORMAL,
+        italic: true,
+        fonts: vec!["Hack".to_owned()],
+    });
+
+    let mut fonts = FontsImpl::new(1.0, 1024, definitions);
+    let mut layout_text = |family: FontFamily, weight: FontWeight, italics: bool| {
+        let format = TextFormat {
+            font_id: FontId::new(14.0, family),
+            weight,
+            italics,
+            ..Default::default()
+        };
+        layout(
+            &mut fonts,
+            Arc::new(LayoutJob::single_section("iii".to_owned(), format)),
+        )
+    };
+
+    let normal = layout_text(FontFamily::Proportional, FontWeight::NORMAL, false);
+    let bold = layout_text(FontFamily::Proportional, FontWeight::BOLD, false);
+    let semi_bold = layout_text(FontFamily::Proportional, FontWeight::SEMI_BOLD, false);
+    assert!(normal.size().x < bold.size().x, "The bold variant is used");
+    assert_eq!(bold.size(), semi_bold.size(), "The closest variant is used");
+
+    // Italics with a real italic font are not slanted:
+    let italic = layout_text(FontFamily::Proportional, FontWeight::NORMAL, true);
+    let vertices = &italic.rows[0].visuals.mesh.vertices;
+    assert_eq!(vertices[0].pos.x, vertices[2].pos.x);
+
+    // …but they are if there is no italic font:
+    let italic = layout_text(FontFamily::Monospace, FontWeight::NORMAL, true);
+    let vertices = &italic.rows[0].visuals.mesh.vertices;
+    assert!(vertices[0].pos.x > vertices[2].pos.x);
+}
+
+#[test]
+fn test_color_glyphs_are_not_tinted() {
+    use super::{FontDefinitions, FontId};
+
+    let mut fonts = FontsImpl::new(1.0, 1024, FontDefinitions::default());
+    let job = Arc::new(LayoutJob::simple_singleline(
+        "ab".into(),
+        FontId::default(),
+        Color32::RED,
+    ));
+    let mut galley = layout(&mut fonts, job.clone());
+    let row = &mut galley.rows[0];
+    row.glyphs[0].uv_rect.colored = true; // as if 'a' was an emoji from a color font
+
+    let format_summary = format_summary(&fonts, &job);
+    let visuals = tessellate_row(PointScale::new(1.0), &job, &format_summary, row);
+    let vertices = &visuals.mesh.vertices;
+    let glyph_vertex_range = visuals.glyph_vertex_range;
+    assert_eq!(glyph_vertex_range.len(), 4, "Only 'b' is tinted");
+    assert!(vertices[glyph_vertex_range.clone()]
+        .iter()
+        .all(|vertex| vertex.color == Color32::RED));
+    let color_glyph = &vertices[glyph_vertex_range.end..glyph_vertex_range.end + 4];
+    assert!(color_glyph
+        .iter()
+        .all(|vertex| vertex.color == Color32::WHITE));
+}
+
+#[test]
+fn test_inline_box() {
+    use super::{FontDefinitions, FontId, TextFormat};
+
+    let mut fonts = FontsImpl::new(1.0, 1024, FontDefinitions::default());
+    let format = TextFormat::simple(FontId::default(), Color32::WHITE);
+    let font_height = fonts.font(&FontId::default()).row_height();
+
+    let mut job = LayoutJob::default();
+    job.append("Hello ", 0.0, format.clone());
+    job.append_inline_box(InlineBox::new(vec2(40.0, 30.0)), format.clone());
+    job.append(" world", 0.0, format.clone());
+    job.append_inline_box(InlineBox::new(vec2(40.0, 10.0)), format);
+    job.wrap_width = 80.0;
+    let galley = layout(&mut fonts, Arc::new(job));
+
+    assert_eq!(galley.inline_boxes.len(), 2);
+    let (section_index, rect) = galley.inline_boxes[0];
+    assert_eq!(section_index, 1);
+    assert_eq!(rect.size(), vec2(40.0, 30.0));
+    assert!(rect.min.x > 0.0, "After 'Hello '");
+    assert!(
+        galley.rows[0].height() >= 30.0 + font_height - fonts.font(&FontId::default()).baseline(),
+        "The row makes room for the box, sitting on the baseline"
+    );
+
+    let (section_index, rect) = galley.inline_boxes[1];
+    assert_eq!(section_index, 3);
+    assert!(
+        rect.min.y >= galley.rows[0].rect.max.y,
+        "The second box is wrapped to a later row"
+    );
+}
+
+#[test]
+fn test_line_height_and_spacing() {
+    use super::{FontDefinitions, FontId};
+
+    let mut fonts = FontsImpl::new(1.0, 1024, FontDefinitions::default());
+    let mut layout_job = |job: LayoutJob| layout(&mut fonts, Arc::new(job));
+
+    let format = TextFormat::simple(FontId::proportional(14.0), Color32::WHITE);
+    let plain = layout_job(LayoutJob::single_section(
+        "ab\ncd".to_owned(),
+        format.clone(),
+    ));
+
+    let spaced = layout_job(LayoutJob::single_section(
+        "ab\ncd".to_owned(),
+        TextFormat {
+            line_height: Some(30.0),
+            extra_letter_spacing: 2.0,
+            ..format.clone()
+        },
+    ));
+    assert_eq!(spaced.rows[0].height(), 30.0);
+    assert_eq!(
+        spaced.rows[0].glyphs[1].pos.x,
+        plain.rows[0].glyphs[1].pos.x + 2.0
+    );
+    assert_eq!(
+        spaced.rows[0].rect.width(),
+        plain.rows[0].rect.width() + 4.0
+    );
+
+    let mut job = LayoutJob::single_section("ab\ncd".to_owned(), format);
+    job.paragraph_spacing = 10.0;
+    job.paragraph_indent = 20.0;
+    let indented = layout_job(job);
+    assert_eq!(indented.rows[1].min_y(), plain.rows[1].min_y() + 10.0);
+    assert_eq!(indented.rows[1].glyphs[0].pos.x, 20.0);
+    assert_eq!(indented.size().y, plain.size().y + 10.0);
+}
+
+#[test]
+fn test_elision() {
+    use super::{FontDefinitions, FontId};
+
+    let mut fonts = FontsImpl::new(1.0, 1024, FontDefinitions::default());
+    let mut layout_elided = |text: &str, max_rows: usize, overflow: TextOverflow| {
+        let mut job = LayoutJob::simple(
+            text.to_owned(),
+            FontId::monospace(10.0),
+            Color32::WHITE,
+            100.0,
+        );
+        job.max_rows = max_rows;
+        job.overflow = overflow;
+        layout(&mut fonts, Arc::new(job))
+    };
+    let row_text = |galley: &Galley, row: usize| -> String {
+        galley.rows[row]
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.chr)
+            .collect()
+    };
+
+    let text = "/home/someone/projects/egui/epaint/src/text/text_layout.rs";
+
+    let galley = layout_elided(text, usize::MAX, TextOverflow::Ellipsis);
+    assert!(!galley.elided);
+    assert!(galley.rows.len() > 1);
+
+    let galley = layout_elided(text, 1, TextOverflow::Ellipsis);
+    assert!(galley.elided);
+    assert_eq!(galley.rows.len(), 1);
+    assert!(galley.size().x <= 100.0);
+    let elided = row_text(&galley, 0);
+    assert!(
+        elided.starts_with("/home/") && elided.ends_with('…'),
+        "{}",
+        elided
+    );
+
+    let galley = layout_elided(text, 1, TextOverflow::EllipsisMiddle);
+    assert!(galley.size().x <= 100.0);
+    let elided = row_text(&galley, 0);
+    assert!(elided.starts_with("/home/"), "{}", elided);
+    assert!(
+        elided.contains('…') && elided.ends_with("layout.rs"),
+        "{}",
+        elided
+    );
+
+    let galley = layout_elided(text, 1, TextOverflow::Clip);
+    assert!(galley.size().x <= 100.0);
+    assert!(!row_text(&galley, 0).contains('…'));
+
+    let galley = layout_elided("one\ntwo\nthree", 2, TextOverflow::Ellipsis);
+    assert!(galley.elided);
+    assert_eq!(row_text(&galley, 1), "two…");
+    assert!(!galley.rows[1].ends_with_newline);
+
+    let galley = layout_elided("one\ntwo", 2, TextOverflow::Ellipsis);
+    assert!(!galley.elided);
+}
+
+#[test]
+fn test_galley_concat() {
+    use super::{FontDefinitions, FontId};
+
+    let mut fonts = FontsImpl::new(1.0, 1024, FontDefinitions::default());
+    let mut layout_text = |text: &str| {
+        let job = LayoutJob::simple(
+            text.to_owned(),
+            FontId::monospace(10.0),
+            Color32::WHITE,
+            50.0,
+        );
+        layout(&mut fonts, Arc::new(job))
+    };
+
+    let text = "first paragraph, which wraps\n\nthird";
+    let whole = layout_text(text);
+    let parts: Vec<Arc<Galley>> = text.split('\n').map(|p| Arc::new(layout_text(p))).collect();
+    let concat = Galley::concat(&parts);
+
+    assert_eq!(concat.job.text, whole.job.text);
+    assert_eq!(concat.rect, whole.rect);
+    assert_eq!(concat.rows.len(), whole.rows.len());
+    for (a, b) in concat.rows.iter().zip(&whole.rows) {
+        assert_eq!(a.rect, b.rect);
+        assert_eq!(a.ends_with_newline, b.ends_with_newline);
+        assert_eq!(a.glyphs.len(), b.glyphs.len());
+    }
+    assert_eq!(concat.num_vertices, whole.num_vertices);
+    assert!(concat.derived && !whole.derived);
+    assert_eq!(
+        concat.pos_from_cursor(&concat.end()),
+        whole.pos_from_cursor(&whole.end())
+    );
+}
+
+#[test]
+fn test_galley_fold() {
+    use super::{FontDefinitions, FontId};
+
+    let mut fonts = FontsImpl::new(1.0, 1024, FontDefinitions::default());
+    let job = LayoutJob::simple(
+        "fn main() {\n    one();\n    two();\n}".to_owned(),
+        FontId::monospace(10.0),
+        Color32::WHITE,
+        f32::INFINITY,
+    );
+    let galley = layout(&mut fonts, Arc::new(job));
+    let row_height = galley.rows[0].height();
+
+    let hidden = 1..3;
+    let folded = galley.fold(&[hidden]);
+    assert_eq!(folded.rows.len(), galley.rows.len());
+    assert_eq!(
+        folded.rect.height(),
+        galley.rect.height() - 2.0 * row_height
+    );
+    assert_eq!(folded.rows[1].height(), 0.0);
+    assert_eq!(folded.rows[3].rect.min.y, galley.rows[1].rect.min.y);
+    assert!(folded.num_vertices < galley.num_vertices);
+    assert!(folded.derived && !galley.derived);
+
+    // The hidden characters still count:
+    assert_eq!(folded.end().ccursor, galley.end().ccursor);
+    assert_eq!(
+        folded.pos_from_cursor(&folded.end()).min.y,
+        galley.rows[1].rect.min.y
+    );
+}
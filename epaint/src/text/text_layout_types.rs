@@ -1,9 +1,9 @@
 #![allow(clippy::derive_hash_xor_eq)] // We need to impl Hash for f32, but we don't implement Eq, which is fine
 
-use std::ops::Range;
+use std::ops::{Range, RangeInclusive};
 
-use super::{cursor::*, font::UvRect};
-use crate::{mutex::Arc, Color32, FontId, Mesh, Stroke};
+use super::{cursor::*, font::UvRect, FontWeight, OBJECT_REPLACEMENT_CHAR};
+use crate::{mutex::Arc, util::FloatOrd as _, Color32, FontId, Mesh, Stroke};
 use emath::*;
 
 /// Describes the task of laying out text.
@@ -70,6 +70,27 @@ pub struct LayoutJob {
 
     /// Justify text so that word-wrapped rows fill the whole [`Self::wrap_width`]
     pub justify: bool,
+
+    /// The base direction of each paragraph, used when mixing left-to-right and right-to-left text.
+    ///
+    /// If `None`, the direction of each paragraph is decided by its first strong character,
+    /// e.g. a paragraph starting with a Hebrew letter is right-to-left.
+    pub base_direction: Option<TextDirection>,
+
+    /// Extra vertical space after each paragraph, i.e. after each `\n`. In points.
+    pub paragraph_spacing: f32,
+
+    /// Indentation of the first row of each paragraph. In points.
+    pub paragraph_indent: f32,
+
+    /// Show at most this many rows, eliding the rest of the text as decided by [`Self::overflow`].
+    ///
+    /// The last row is also elided if it is wider than [`Self::wrap_width`].
+    /// Default: [`usize::MAX`].
+    pub max_rows: usize,
+
+    /// What to do with the text that does not fit in [`Self::max_rows`].
+    pub overflow: TextOverflow,
 }
 
 impl Default for LayoutJob {
@@ -83,6 +104,11 @@ impl Default for LayoutJob {
             break_on_newline: true,
             halign: Align::LEFT,
             justify: false,
+            base_direction: None,
+            paragraph_spacing: 0.0,
+            paragraph_indent: 0.0,
+            max_rows: usize::MAX,
+            overflow: TextOverflow::default(),
         }
     }
 }
@@ -96,6 +122,7 @@ impl LayoutJob {
                 leading_space: 0.0,
                 byte_range: 0..text.len(),
                 format: TextFormat::simple(font_id, color),
+                inline_box: None,
             }],
             text,
             wrap_width,
@@ -112,6 +139,7 @@ impl LayoutJob {
                 leading_space: 0.0,
                 byte_range: 0..text.len(),
                 format: TextFormat::simple(font_id, color),
+                inline_box: None,
             }],
             text,
             wrap_width: f32::INFINITY,
@@ -127,6 +155,7 @@ impl LayoutJob {
                 leading_space: 0.0,
                 byte_range: 0..text.len(),
                 format,
+                inline_box: None,
             }],
             text,
             wrap_width: f32::INFINITY,
@@ -149,6 +178,26 @@ impl LayoutJob {
             leading_space,
             byte_range,
             format,
+            inline_box: None,
+        });
+    }
+
+    /// Reserve space for something that is not text, e.g. an image or a widget.
+    ///
+    /// This adds a single [`OBJECT_REPLACEMENT_CHAR`] to the text,
+    /// which is laid out (and wrapped) like a glyph of the size of the box.
+    /// The `format` decides which font baseline the box is aligned with.
+    ///
+    /// Where the box ended up is in [`Galley::inline_boxes`].
+    pub fn append_inline_box(&mut self, inline_box: InlineBox, format: TextFormat) {
+        let start = self.text.len();
+        self.text.push(OBJECT_REPLACEMENT_CHAR);
+        let byte_range = start..self.text.len();
+        self.sections.push(LayoutSection {
+            leading_space: 0.0,
+            byte_range,
+            format,
+            inline_box: Some(inline_box),
         });
     }
 
@@ -173,6 +222,11 @@ impl std::hash::Hash for LayoutJob {
             break_on_newline,
             halign,
             justify,
+            base_direction,
+            paragraph_spacing,
+            paragraph_indent,
+            max_rows,
+            overflow,
         } = self;
 
         text.hash(state);
@@ -182,9 +236,47 @@ impl std::hash::Hash for LayoutJob {
         break_on_newline.hash(state);
         halign.hash(state);
         justify.hash(state);
+        base_direction.hash(state);
+        crate::f32_hash(state, *paragraph_spacing);
+        crate::f32_hash(state, *paragraph_indent);
+        max_rows.hash(state);
+        overflow.hash(state);
+    }
+}
+
+/// How to elide text that does not fit in [`LayoutJob::max_rows`].
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum TextOverflow {
+    /// Just cut the text off.
+    Clip,
+
+    /// End the last row with `…`.
+    Ellipsis,
+
+    /// Put `…` in the middle of the last row, followed by the end of the text.
+    ///
+    /// Good for file paths, where the end is the most interesting part.
+    EllipsisMiddle,
+}
+
+impl Default for TextOverflow {
+    fn default() -> Self {
+        Self::Ellipsis
     }
 }
 
+/// The direction text is written in.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum TextDirection {
+    /// E.g. English.
+    LeftToRight,
+
+    /// E.g. Arabic and Hebrew.
+    RightToLeft,
+}
+
 // ----------------------------------------------------------------------------
 
 #[derive(Clone, Debug, PartialEq)]
@@ -195,6 +287,9 @@ pub struct LayoutSection {
     /// Range into the galley text
     pub byte_range: Range<usize>,
     pub format: TextFormat,
+    /// If set, each `char` of the section is a box of this size instead of a glyph.
+    /// See [`LayoutJob::append_inline_box`].
+    pub inline_box: Option<InlineBox>,
 }
 
 impl std::hash::Hash for LayoutSection {
@@ -204,22 +299,66 @@ impl std::hash::Hash for LayoutSection {
             leading_space,
             byte_range,
             format,
+            inline_box,
         } = self;
         crate::f32_hash(state, *leading_space);
         byte_range.hash(state);
         format.hash(state);
+        inline_box.hash(state);
+    }
+}
+
+/// Space reserved in a [`LayoutJob`] for something that is not text, e.g. an image or a widget.
+///
+/// See [`LayoutJob::append_inline_box`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct InlineBox {
+    /// Width and height, in points.
+    pub size: Vec2,
+
+    /// Distance from the top of the box down to its baseline,
+    /// which is lined up with the baseline of the text.
+    ///
+    /// Use `size.y` (see [`Self::new`]) to put the bottom of the box on the baseline, like an image.
+    /// If the box goes further below the baseline than the font does,
+    /// it is instead aligned with the bottom of the row.
+    pub baseline: f32,
+}
+
+impl InlineBox {
+    /// A box sitting on the baseline.
+    pub fn new(size: Vec2) -> Self {
+        Self {
+            size,
+            baseline: size.y,
+        }
+    }
+}
+
+impl std::hash::Hash for InlineBox {
+    #[inline]
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        let Self { size, baseline } = self;
+        crate::f32_hash(state, size.x);
+        crate::f32_hash(state, size.y);
+        crate::f32_hash(state, *baseline);
     }
 }
 
 // ----------------------------------------------------------------------------
 
-#[derive(Clone, Debug, Hash, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct TextFormat {
     pub font_id: FontId,
     /// Text color
     pub color: Color32,
     pub background: Color32,
+    /// Use a bolder or lighter variant of the font, if there is one.
+    /// See [`crate::text::FontDefinitions::variants`].
+    pub weight: FontWeight,
+    /// Use the italic variant of the font if there is one, otherwise slant the glyphs.
     pub italics: bool,
     pub underline: Stroke,
     pub strikethrough: Stroke,
@@ -227,6 +366,42 @@ pub struct TextFormat {
     /// can get the effect of raised text.
     pub valign: Align,
     // TODO: lowered
+    /// Height of each row of text, with the glyphs centered vertically.
+    /// `None` means the height of the font.
+    pub line_height: Option<f32>,
+    /// Extra horizontal space after each character. In points.
+    pub extra_letter_spacing: f32,
+}
+
+impl std::hash::Hash for TextFormat {
+    #[inline]
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        let Self {
+            font_id,
+            color,
+            background,
+            weight,
+            italics,
+            underline,
+            strikethrough,
+            valign,
+            line_height,
+            extra_letter_spacing,
+        } = self;
+        font_id.hash(state);
+        color.hash(state);
+        background.hash(state);
+        weight.hash(state);
+        italics.hash(state);
+        underline.hash(state);
+        strikethrough.hash(state);
+        valign.hash(state);
+        line_height.is_some().hash(state);
+        if let Some(line_height) = line_height {
+            crate::f32_hash(state, *line_height);
+        }
+        crate::f32_hash(state, *extra_letter_spacing);
+    }
 }
 
 impl Default for TextFormat {
@@ -236,10 +411,13 @@ impl Default for TextFormat {
             font_id: FontId::default(),
             color: Color32::GRAY,
             background: Color32::TRANSPARENT,
+            weight: FontWeight::NORMAL,
             italics: false,
             underline: Stroke::none(),
             strikethrough: Stroke::none(),
             valign: Align::BOTTOM,
+            line_height: None,
+            extra_letter_spacing: 0.0,
         }
     }
 }
@@ -291,6 +469,27 @@ pub struct Galley {
 
     /// Total number of indices in all the row meshes.
     pub num_indices: usize,
+
+    /// Where each [`InlineBox`] ended up, as `(index into LayoutJob::sections, rect)`,
+    /// relative to the galley position.
+    pub inline_boxes: Vec<(usize, Rect)>,
+
+    /// Was some of the text left out because of [`LayoutJob::max_rows`]?
+    ///
+    /// If so, the rows no longer match the text, so cursors into the galley are not meaningful.
+    pub elided: bool,
+
+    /// The pages of the font atlas used by the galley,
+    /// with their [`crate::TextureAtlas::page_generation`] when the galley was laid out.
+    ///
+    /// See [`crate::Fonts::is_galley_stale`].
+    pub page_generations: Vec<(usize, u32)>,
+
+    /// Was this galley made from other galleys, with [`Self::scale`], [`Self::concat`] or [`Self::fold`]?
+    ///
+    /// If so, laying out [`Self::job`] again does not give the same galley,
+    /// so if it goes stale it must be rebuilt the way it was made.
+    pub derived: bool,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -298,6 +497,12 @@ pub struct Row {
     /// One for each `char`.
     pub glyphs: Vec<Glyph>,
 
+    /// Additional glyphs to paint, as an index into [`Self::glyphs`] and a glyph
+    /// positioned relative to that.
+    ///
+    /// Only used with text shaping, when a cluster needs more glyphs than it has `char`s.
+    pub extra_glyphs: Vec<(usize, UvRect)>,
+
     /// Logical bounding rectangle based on font heights etc.
     /// Use this when drawing a selection or similar!
     /// Includes leading and trailing whitespace.
@@ -326,8 +531,13 @@ pub struct RowVisuals {
     pub mesh_bounds: Rect,
 
     /// The range of vertices in the mesh the contain glyphs.
-    /// Before comes backgrounds (if any), and after any underlines and strikethrough.
+    /// Before comes backgrounds (if any), and after any color glyphs (e.g. emojis),
+    /// underlines and strikethrough.
     pub glyph_vertex_range: Range<usize>,
+
+    /// Which page (texture) of the font atlas each range of [`Mesh::indices`] in [`Self::mesh`] uses,
+    /// in order, covering all the indices.
+    pub page_index_ranges: Vec<(usize, Range<usize>)>,
 }
 
 impl Default for RowVisuals {
@@ -336,6 +546,7 @@ impl Default for RowVisuals {
             mesh: Default::default(),
             mesh_bounds: Rect::NOTHING,
             glyph_vertex_range: 0..0,
+            page_index_ranges: vec![],
         }
     }
 }
@@ -352,9 +563,23 @@ pub struct Glyph {
     pub uv_rect: UvRect,
     /// Index into [`LayoutJob::sections`]. Decides color etc.
     pub section_index: u32,
+    /// This `char` is shaped together with the one before it, e.g. as a ligature or combining mark.
+    /// Rows are never broken before such a glyph.
+    ///
+    /// Such a glyph has zero width, and is placed at the end of the cluster.
+    pub continues_cluster: bool,
+    /// The embedding level from the Unicode Bidirectional Algorithm.
+    /// Odd levels are right-to-left, even are left-to-right.
+    pub bidi_level: u8,
 }
 
 impl Glyph {
+    /// Is this part of right-to-left text?
+    #[inline]
+    pub fn is_rtl(&self) -> bool {
+        self.bidi_level % 2 == 1
+    }
+
     pub fn max_x(&self) -> f32 {
         self.pos.x + self.size.x
     }
@@ -396,23 +621,124 @@ impl Row {
         self.rect.height()
     }
 
+    /// Does this row contain any right-to-left text?
+    ///
+    /// If not, the glyphs are in visual order.
+    pub fn has_rtl(&self) -> bool {
+        self.glyphs.iter().any(Glyph::is_rtl)
+    }
+
     /// Closest char at the desired x coordinate.
     /// Returns something in the range `[0, char_count_excluding_newline()]`.
     pub fn char_at(&self, desired_x: f32) -> usize {
+        if self.has_rtl() {
+            return self.char_at_bidi(desired_x);
+        }
+
         for (i, glyph) in self.glyphs.iter().enumerate() {
-            if desired_x < glyph.logical_rect().center().x {
+            if !glyph.continues_cluster && desired_x < glyph.logical_rect().center().x {
                 return i;
             }
         }
         self.char_count_excluding_newline()
     }
 
+    /// Is the given cursor position inside of a shaped cluster (e.g. a ligature)?
+    fn is_inside_cluster(&self, column: usize) -> bool {
+        self.glyphs
+            .get(column)
+            .map_or(false, |glyph| glyph.continues_cluster)
+    }
+
+    /// [`Self::char_at`] for rows where the glyphs may not be in visual order.
+    fn char_at_bidi(&self, desired_x: f32) -> usize {
+        let distance = |glyph: &Glyph| {
+            if desired_x < glyph.pos.x {
+                glyph.pos.x - desired_x
+            } else if glyph.max_x() < desired_x {
+                desired_x - glyph.max_x()
+            } else {
+                0.0
+            }
+        };
+
+        let closest = self
+            .glyphs
+            .iter()
+            .enumerate()
+            .filter(|(_, glyph)| !glyph.continues_cluster)
+            .min_by_key(|(_, glyph)| distance(glyph).ord());
+
+        if let Some((i, glyph)) = closest {
+            let left_half = desired_x < glyph.logical_rect().center().x;
+            if left_half == glyph.is_rtl() {
+                // After the glyph, and the rest of its cluster:
+                let mut after = i + 1;
+                while self.is_inside_cluster(after) {
+                    after += 1;
+                }
+                after
+            } else {
+                i
+            }
+        } else {
+            0
+        }
+    }
+
+    /// The x coordinate of a cursor placed before the given char.
+    ///
+    /// In right-to-left text this is to the right of the char.
     pub fn x_offset(&self, column: usize) -> f32 {
         if let Some(glyph) = self.glyphs.get(column) {
-            glyph.pos.x
+            if glyph.is_rtl() {
+                glyph.max_x()
+            } else {
+                glyph.pos.x
+            }
         } else {
-            self.rect.right()
+            match self.glyphs.last() {
+                Some(last) if last.is_rtl() => last.pos.x,
+                _ => self.rect.right(),
+            }
+        }
+    }
+
+    /// Every cursor position in the row, with its x coordinate.
+    ///
+    /// Positions inside of shaped clusters are skipped.
+    fn column_x_offsets(&self) -> impl Iterator<Item = (usize, f32)> + '_ {
+        (0..=self.char_count_excluding_newline())
+            .filter(move |&column| !self.is_inside_cluster(column))
+            .map(move |column| (column, self.x_offset(column)))
+    }
+
+    /// The horizontal spans covered by the chars in the given range,
+    /// e.g. for painting a selection.
+    ///
+    /// With mixed left-to-right and right-to-left text a range of chars can be split into several spans.
+    pub fn x_ranges(&self, columns: Range<usize>) -> Vec<RangeInclusive<f32>> {
+        if !self.has_rtl() {
+            return vec![self.x_offset(columns.start)..=self.x_offset(columns.end)];
+        }
+
+        let mut spans: Vec<(f32, f32)> = self.glyphs
+            [columns.start..columns.end.min(self.glyphs.len())]
+            .iter()
+            .map(|glyph| (glyph.pos.x, glyph.max_x()))
+            .collect();
+        spans.sort_by_key(|(min, _)| min.ord());
+
+        let mut ranges: Vec<RangeInclusive<f32>> = vec![];
+        for (min, max) in spans {
+            match ranges.last_mut() {
+                Some(last) if min <= *last.end() + 0.5 => {
+                    *last = *last.start()..=last.end().max(max);
+                }
+                _ => ranges.push(min..=max),
+            }
         }
+        ranges
     }
 }
 
@@ -430,6 +756,210 @@ impl Galley {
     pub fn size(&self) -> Vec2 {
         self.rect.size()
     }
+
+    /// The pages (textures) of the font atlas used by this galley, sorted.
+    pub fn atlas_pages(&self) -> Vec<usize> {
+        let mut pages: Vec<usize> = self
+            .rows
+            .iter()
+            .flat_map(|row| row.visuals.page_index_ranges.iter().map(|(page, _)| *page))
+            .collect();
+        pages.sort_unstable();
+        pages.dedup();
+        pages
+    }
+
+    /// Scale everything around the galley position, in-place.
+    ///
+    /// The glyphs are not re-rasterized, so text scaled up a lot will look blurry.
+    pub fn scale(&mut self, factor: f32) {
+        self.derived = true;
+        let transform = TSTransform::from_scaling(factor);
+        for row in &mut self.rows {
+            for glyph in &mut row.glyphs {
+                glyph.pos = transform * glyph.pos;
+                glyph.size *= factor;
+            }
+            row.rect = transform.mul_rect(row.rect);
+            row.visuals.mesh.transform(transform);
+            row.visuals.mesh_bounds = transform.mul_rect(row.visuals.mesh_bounds);
+        }
+        self.rect = transform.mul_rect(self.rect);
+        self.mesh_bounds = transform.mul_rect(self.mesh_bounds);
+        for (_, rect) in &mut self.inline_boxes {
+            *rect = transform.mul_rect(*rect);
+        }
+    }
+
+    /// Stack galleys on top of each other, as if their texts were joined with `\n`.
+    ///
+    /// This lets you lay out a long text one paragraph at a time, and only re-layout
+    /// the paragraphs that changed. Each part should be a single paragraph,
+    /// and the wrapping and alignment settings are taken from the first part.
+    /// The [`LayoutJob::paragraph_spacing`] of each part is added below it.
+    pub fn concat(parts: &[Arc<Galley>]) -> Galley {
+        let mut job = parts
+            .first()
+            .map_or_else(LayoutJob::default, |first| LayoutJob {
+                text: Default::default(),
+                sections: Default::default(),
+                ..(*first.job).clone()
+            });
+        let mut rows: Vec<Row> = Vec::new();
+        let mut rect = Rect::NOTHING;
+        let mut mesh_bounds = Rect::NOTHING;
+        let mut num_vertices = 0;
+        let mut num_indices = 0;
+        let mut inline_boxes = Vec::new();
+        let mut elided = false;
+        let mut page_generations: Vec<(usize, u32)> = Vec::new();
+        let mut y = 0.0;
+
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                // The newline belongs to the last section of the previous part:
+                if let Some(section) = job.sections.last_mut() {
+                    section.byte_range.end += 1;
+                }
+                job.text.push('\n');
+                if let Some(row) = rows.last_mut() {
+                    row.ends_with_newline = true;
+                }
+            }
+
+            let byte_offset = job.text.len();
+            let section_offset = job.sections.len();
+            job.text += &part.job.text;
+            job.sections
+                .extend(part.job.sections.iter().map(|section| LayoutSection {
+                    byte_range: section.byte_range.start + byte_offset
+                        ..section.byte_range.end + byte_offset,
+                    ..section.clone()
+                }));
+
+            let delta = vec2(0.0, y);
+            for row in &part.rows {
+                let mut row = row.clone();
+                for glyph in &mut row.glyphs {
+                    glyph.pos += delta;
+                    glyph.section_index += section_offset as u32;
+                }
+                row.rect = row.rect.translate(delta);
+                row.visuals.mesh.translate(delta);
+                row.visuals.mesh_bounds = row.visuals.mesh_bounds.translate(delta);
+                rows.push(row);
+            }
+            rect = rect.union(part.rect.translate(delta));
+            mesh_bounds = mesh_bounds.union(part.mesh_bounds.translate(delta));
+            num_vertices += part.num_vertices;
+            num_indices += part.num_indices;
+            inline_boxes.extend(
+                part.inline_boxes
+                    .iter()
+                    .map(|(section, rect)| (section + section_offset, rect.translate(delta))),
+            );
+            elided |= part.elided;
+            page_generations.extend_from_slice(&part.page_generations);
+            y += part.rect.height() + part.job.paragraph_spacing;
+        }
+        // If the parts disagree on a page, keep the oldest generation so that the result is stale too:
+        page_generations.sort_unstable();
+        page_generations.dedup_by_key(|(page, _)| *page);
+
+        Galley {
+            job: Arc::new(job),
+            rows,
+            rect: if parts.is_empty() {
+                Rect::from_min_max(Pos2::ZERO, Pos2::ZERO)
+            } else {
+                rect
+            },
+            mesh_bounds,
+            num_vertices,
+            num_indices,
+            inline_boxes,
+            elided,
+            page_generations,
+            derived: true,
+        }
+    }
+
+    /// Hide the given paragraphs, e.g. for code folding.
+    ///
+    /// `hidden` are sorted ranges of paragraph indices.
+    /// The rows of the hidden paragraphs get zero height and nothing to paint,
+    /// and the rows below them move up. The hidden rows keep their glyphs,
+    /// so cursors into the galley still count all of the text.
+    pub fn fold(&self, hidden: &[Range<usize>]) -> Galley {
+        let mut galley = self.clone();
+        if hidden.is_empty() {
+            return galley;
+        }
+        galley.derived = true;
+
+        // How far each original y coordinate moves up:
+        let mut moves: Vec<(RangeInclusive<f32>, f32)> = Vec::new();
+        let mut paragraph = 0;
+        let mut dy = 0.0;
+        let mut hidden_top = None;
+        let mut collapsed_y = 0.0;
+        for row in &mut galley.rows {
+            let is_hidden = hidden.iter().any(|range| range.contains(&paragraph));
+            if row.ends_with_newline {
+                paragraph += 1;
+            }
+            let original = row.rect.y_range();
+            if is_hidden {
+                let top = *hidden_top.get_or_insert(row.min_y());
+                galley.num_vertices -= row.visuals.mesh.vertices.len();
+                galley.num_indices -= row.visuals.mesh.indices.len();
+                row.visuals = RowVisuals::default();
+                row.rect.min.y = collapsed_y;
+                row.rect.max.y = collapsed_y;
+                for glyph in &mut row.glyphs {
+                    glyph.pos.y = collapsed_y;
+                }
+                moves.push((top..=*original.end(), f32::INFINITY));
+                continue;
+            }
+            if let Some(top) = hidden_top.take() {
+                dy += row.min_y() - top;
+            }
+            let delta = vec2(0.0, -dy);
+            for glyph in &mut row.glyphs {
+                glyph.pos += delta;
+            }
+            row.rect = row.rect.translate(delta);
+            row.visuals.mesh.translate(delta);
+            row.visuals.mesh_bounds = row.visuals.mesh_bounds.translate(delta);
+            collapsed_y = row.max_y();
+            moves.push((original, dy));
+        }
+        if let Some(top) = hidden_top {
+            dy += galley.rect.max.y - top;
+        }
+
+        for (_, rect) in &mut galley.inline_boxes {
+            match moves
+                .iter()
+                .find(|(range, _)| range.contains(&rect.center().y))
+            {
+                Some((_, dy)) if dy.is_finite() => *rect = rect.translate(vec2(0.0, -dy)),
+                Some(_) => *rect = Rect::from_min_max(rect.min, rect.min), // hidden
+                None => {}
+            }
+        }
+
+        galley.rect.max.y -= dy;
+        galley.mesh_bounds = galley
+            .rows
+            .iter()
+            .filter(|row| !row.visuals.mesh.is_empty())
+            .fold(Rect::NOTHING, |bounds, row| {
+                bounds.union(row.visuals.mesh_bounds)
+            });
+        galley
+    }
 }
 
 // ----------------------------------------------------------------------------
@@ -742,24 +1272,109 @@ impl Galley {
 
 /// ## Cursor positions
 impl Galley {
+    /// Move one character to the left on screen.
+    ///
+    /// In right-to-left text this moves forwards in the text.
     pub fn cursor_left_one_character(&self, cursor: &Cursor) -> Cursor {
-        if cursor.ccursor.index == 0 {
+        if self.is_bidi_row(cursor.rcursor.row) {
+            self.cursor_one_character_visually(cursor, false)
+        } else if cursor.ccursor.index == 0 {
             Default::default()
         } else {
             let ccursor = CCursor {
                 index: cursor.ccursor.index,
                 prefer_next_row: true, // default to this when navigating. It is more often useful to put cursor at the begging of a row than at the end.
             };
-            self.from_ccursor(ccursor - 1)
+            let mut new_cursor = self.from_ccursor(ccursor - 1);
+            while self.is_inside_cluster(&new_cursor) {
+                new_cursor = self.from_ccursor(new_cursor.ccursor - 1);
+            }
+            new_cursor
         }
     }
 
+    /// Move one character to the right on screen.
+    ///
+    /// In right-to-left text this moves backwards in the text.
     pub fn cursor_right_one_character(&self, cursor: &Cursor) -> Cursor {
+        if self.is_bidi_row(cursor.rcursor.row) {
+            return self.cursor_one_character_visually(cursor, true);
+        }
+
         let ccursor = CCursor {
             index: cursor.ccursor.index,
             prefer_next_row: true, // default to this when navigating. It is more often useful to put cursor at the begging of a row than at the end.
         };
-        self.from_ccursor(ccursor + 1)
+        let mut new_cursor = self.from_ccursor(ccursor + 1);
+        while self.is_inside_cluster(&new_cursor) {
+            new_cursor = self.from_ccursor(new_cursor.ccursor + 1);
+        }
+        new_cursor
+    }
+
+    fn is_bidi_row(&self, row: usize) -> bool {
+        self.rows.get(row).map_or(false, Row::has_rtl)
+    }
+
+    /// Is the cursor inside of a shaped cluster (e.g. a ligature)?
+    fn is_inside_cluster(&self, cursor: &Cursor) -> bool {
+        self.rows
+            .get(cursor.rcursor.row)
+            .map_or(false, |row| row.is_inside_cluster(cursor.rcursor.column))
+    }
+
+    /// Move to the closest cursor position to the left or right on screen,
+    /// continuing on the row below (when moving right) or above (when moving left).
+    ///
+    /// Used for rows with right-to-left text, where logical and visual order differ.
+    fn cursor_one_character_visually(&self, cursor: &Cursor, rightwards: bool) -> Cursor {
+        let row_nr = cursor.rcursor.row;
+        let row = &self.rows[row_nr];
+        let x = row.x_offset(cursor.rcursor.column);
+        // Cursor positions at (almost) the same x are treated as one.
+        const EPSILON: f32 = 0.01;
+        let next = if rightwards {
+            row.column_x_offsets()
+                .filter(|(_, column_x)| *column_x > x + EPSILON)
+                .min_by_key(|(_, column_x)| column_x.ord())
+        } else {
+            row.column_x_offsets()
+                .filter(|(_, column_x)| *column_x < x - EPSILON)
+                .max_by_key(|(_, column_x)| column_x.ord())
+        };
+
+        let rcursor = if let Some((column, _)) = next {
+            RCursor {
+                row: row_nr,
+                column,
+            }
+        } else if rightwards && row_nr + 1 < self.rows.len() {
+            // Leftmost position of the next row:
+            let next_row = &self.rows[row_nr + 1];
+            let column = next_row
+                .column_x_offsets()
+                .min_by_key(|(_, column_x)| column_x.ord())
+                .map_or(0, |(column, _)| column);
+            RCursor {
+                row: row_nr + 1,
+                column,
+            }
+        } else if !rightwards && row_nr > 0 {
+            // Rightmost position of the previous row:
+            let prev_row = &self.rows[row_nr - 1];
+            let column = prev_row
+                .column_x_offsets()
+                .max_by_key(|(_, column_x)| column_x.ord())
+                .map_or(0, |(column, _)| column);
+            RCursor {
+                row: row_nr - 1,
+                column,
+            }
+        } else {
+            return *cursor;
+        };
+
+        self.from_rcursor(rcursor)
     }
 
     pub fn cursor_up_one_row(&self, cursor: &Cursor) -> Cursor {
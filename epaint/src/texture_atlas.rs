@@ -1,4 +1,6 @@
-use crate::{AlphaImage, ImageDelta};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::{stats::FontAtlasStats, AlphaImage, Color32, ColorImage, ImageDelta, SdfImage};
 
 #[derive(Clone, Copy, Eq, PartialEq)]
 struct Rectu {
@@ -27,12 +29,16 @@ impl Rectu {
     };
 }
 
-/// Contains font data in an atlas, where each character occupied a small rectangle.
-///
-/// More characters can be added, possibly expanding the texture.
+/// One texture of the [`TextureAtlas`].
 #[derive(Clone)]
-pub struct TextureAtlas {
+struct AtlasPage {
     image: AlphaImage,
+    /// Set for pages of signed distance fields (see [`TextureAtlas::allocate_sdf`]),
+    /// where [`Self::image`] holds distances instead of coverage.
+    sdf_spread: Option<f32>,
+    /// Only created when the first color glyph is added.
+    /// The parts covered by the glyphs in [`Self::image`] are transparent.
+    color_image: Option<ColorImage>,
     /// What part of the image that is dirty
     dirty: Rectu,
 
@@ -40,24 +46,40 @@ pub struct TextureAtlas {
     cursor: (usize, usize),
     row_height: usize,
 
-    /// Set when someone requested more space than was available.
-    overflowed: bool,
+    /// The last [`TextureAtlas::generation`] the page was used.
+    last_used: u32,
+
+    /// See [`TextureAtlas::page_generation`].
+    page_generation: u32,
 }
 
-impl TextureAtlas {
-    pub fn new(size: [usize; 2]) -> Self {
-        assert!(size[0] >= 1024, "Tiny texture atlas");
-        Self {
+/// A new value for each page that is created, in any atlas,
+/// so that a page that is cleared and reused never gets the same generation as before.
+fn next_page_generation() -> u32 {
+    static NEXT_PAGE_GENERATION: AtomicU32 = AtomicU32::new(0);
+    NEXT_PAGE_GENERATION.fetch_add(1, Ordering::Relaxed)
+}
+
+impl AtlasPage {
+    fn new(size: [usize; 2], generation: u32, sdf_spread: Option<f32>) -> Self {
+        let mut page = Self {
             image: AlphaImage::new(size),
+            sdf_spread,
+            color_image: None,
             dirty: Rectu::EVERYTHING,
             cursor: (0, 0),
             row_height: 0,
-            overflowed: false,
-        }
-    }
+            last_used: generation,
+            page_generation: next_page_generation(),
+        };
+
+        // Make the top left pixel fully white, for backgrounds and underlines (see `crate::WHITE_UV`).
+        // In a signed distance field that is as far inside as we can get, which is also white.
+        let pos = page.allocate((1, 1)).unwrap();
+        assert_eq!(pos, (0, 0));
+        page.image[pos] = 255;
 
-    pub fn size(&self) -> [usize; 2] {
-        self.image.size
+        page
     }
 
     fn max_height(&self) -> usize {
@@ -65,33 +87,78 @@ impl TextureAtlas {
         self.image.width()
     }
 
-    /// When this get high, it might be time to clear and start over!
-    pub fn fill_ratio(&self) -> f32 {
-        if self.overflowed {
-            1.0
-        } else {
-            (self.cursor.1 + self.row_height) as f32 / self.max_height() as f32
-        }
+    fn fill_ratio(&self) -> f32 {
+        (self.cursor.1 + self.row_height) as f32 / self.max_height() as f32
     }
 
     /// Call to get the change to the image since last call.
-    pub fn take_delta(&mut self) -> Option<ImageDelta> {
+    fn take_delta(&mut self) -> Option<ImageDelta> {
         let dirty = std::mem::replace(&mut self.dirty, Rectu::NOTHING);
         if dirty == Rectu::NOTHING {
             None
         } else if dirty == Rectu::EVERYTHING {
-            Some(ImageDelta::full(self.image.clone()))
+            Some(ImageDelta::full(self.region([0, 0], self.image.size)))
         } else {
             let pos = [dirty.min_x, dirty.min_y];
             let size = [dirty.max_x - dirty.min_x, dirty.max_y - dirty.min_y];
-            let region = self.image.region(pos, size);
-            Some(ImageDelta::partial(pos, region))
+            Some(ImageDelta::partial(pos, self.region(pos, size)))
         }
     }
 
+    fn region(&self, pos: [usize; 2], size: [usize; 2]) -> crate::ImageData {
+        if let Some(spread) = self.sdf_spread {
+            SdfImage {
+                size,
+                pixels: self.image.region(pos, size).pixels,
+                spread,
+            }
+            .into()
+        } else if self.color_image.is_some() {
+            self.color_region(pos, size).into()
+        } else {
+            self.image.region(pos, size).into()
+        }
+    }
+
+    /// The coverage masks as white (like the painters do when uploading an [`AlphaImage`] with gamma `1.0`),
+    /// with the color glyphs on top.
+    fn color_region(&self, pos: [usize; 2], size: [usize; 2]) -> ColorImage {
+        let color_image = self.color_image.as_ref().unwrap();
+        let alpha = self.image.region(pos, size);
+        let mut pixels: Vec<Color32> = alpha.srgba_pixels(1.0).collect();
+        for y in 0..size[1] {
+            for x in 0..size[0] {
+                let color = color_image[(pos[0] + x, pos[1] + y)];
+                if color != Color32::TRANSPARENT {
+                    pixels[y * size[0] + x] = color;
+                }
+            }
+        }
+        ColorImage { size, pixels }
+    }
+
+    /// Make sure [`Self::color_image`] exists and has the same size as [`Self::image`].
+    fn color_image(&mut self) -> &mut ColorImage {
+        if self.color_image.is_none() {
+            self.dirty = Rectu::EVERYTHING; // the texture changes format
+        }
+        let color_image = self
+            .color_image
+            .get_or_insert_with(|| ColorImage::new([0, 0], Color32::TRANSPARENT));
+        if color_image.size != self.image.size {
+            color_image.size = self.image.size;
+            color_image
+                .pixels
+                .resize(self.image.pixels.len(), Color32::TRANSPARENT);
+        }
+        color_image
+    }
+
     /// Returns the coordinates of where the rect ended up,
     /// and invalidates the region.
-    pub fn allocate(&mut self, (w, h): (usize, usize)) -> ((usize, usize), &mut AlphaImage) {
+    ///
+    /// Returns `None` if the page is full.
+    fn allocate(&mut self, (w, h): (usize, usize)) -> Option<(usize, usize)> {
         /// On some low-precision GPUs (my old iPad) characters get muddled up
         /// if we don't add some empty pixels between the characters.
         /// On modern high-precision GPUs this is not needed.
@@ -103,35 +170,272 @@ impl TextureAtlas {
             w,
             self.image.width()
         );
-        if self.cursor.0 + w > self.image.width() {
+
+        let mut cursor = self.cursor;
+        let mut row_height = self.row_height;
+        if cursor.0 + w > self.image.width() {
             // New row:
-            self.cursor.0 = 0;
-            self.cursor.1 += self.row_height + PADDING;
-            self.row_height = 0;
+            cursor.0 = 0;
+            cursor.1 += row_height + PADDING;
+            row_height = 0;
         }
+        row_height = row_height.max(h);
 
-        self.row_height = self.row_height.max(h);
-
-        let required_height = self.cursor.1 + self.row_height;
-
+        let required_height = cursor.1 + row_height;
         if required_height > self.max_height() {
-            // This is a bad place to be - we need to start reusing space :/
-            eprintln!("epaint texture atlas overflowed!");
-            self.cursor = (0, self.image.height() / 3); // Restart a bit down - the top of the atlas has too many important things in it
-            self.overflowed = true; // this will signal the user that we need to recreate the texture atlas next frame.
-        } else if resize_to_min_height(&mut self.image, required_height) {
+            return None;
+        }
+        if resize_to_min_height(&mut self.image, required_height) {
             self.dirty = Rectu::EVERYTHING;
         }
 
-        let pos = self.cursor;
-        self.cursor.0 += w + PADDING;
+        let pos = cursor;
+        self.cursor = (cursor.0 + w + PADDING, cursor.1);
+        self.row_height = row_height;
 
         self.dirty.min_x = self.dirty.min_x.min(pos.0);
         self.dirty.min_y = self.dirty.min_y.min(pos.1);
         self.dirty.max_x = self.dirty.max_x.max(pos.0 + w);
         self.dirty.max_y = self.dirty.max_y.max(pos.1 + h);
 
-        (pos, &mut self.image)
+        Some(pos)
+    }
+}
+
+/// Contains font data in an atlas, where each character occupied a small rectangle.
+///
+/// The atlas consists of one or more pages, each of which is its own texture.
+/// More characters can be added, possibly expanding the current page or starting a new one.
+/// When we are out of pages, [`Self::begin_frame`] clears the least recently used page
+/// so that its space can be reused.
+///
+/// Every page has a white texel at (0,0) (see [`crate::WHITE_UV`]).
+///
+/// Most glyphs are coverage masks in an [`AlphaImage`], which are tinted by the text color.
+/// Color glyphs (e.g. emojis) are added with [`Self::allocate_color`] into an RGBA copy of the page.
+/// Once a page has color glyphs, the whole page is sent as a [`ColorImage`] by [`Self::take_deltas`].
+/// Signed distance fields are added with [`Self::allocate_sdf`] into pages of their own,
+/// which are sent as [`SdfImage`]:s.
+#[derive(Clone)]
+pub struct TextureAtlas {
+    pages: Vec<AtlasPage>,
+    /// The page we are allocating new glyphs in.
+    current_page: Option<usize>,
+    /// The page we are allocating new signed distance fields in.
+    current_sdf_page: Option<usize>,
+
+    /// The size of a new page.
+    page_size: [usize; 2],
+    max_pages: usize,
+
+    /// Increased by one in each call to [`Self::begin_frame`].
+    generation: u32,
+    /// Number of pages cleared by [`Self::begin_frame`].
+    num_evictions: usize,
+
+    /// Set when someone requested more space than was available.
+    overflowed: bool,
+}
+
+impl TextureAtlas {
+    /// `size` is the initial size of each page.
+    /// The pages grow in height until they are square.
+    pub fn new(size: [usize; 2], max_pages: usize) -> Self {
+        assert!(size[0] >= 1024, "Tiny texture atlas");
+        assert!(max_pages >= 1);
+        Self {
+            pages: vec![AtlasPage::new(size, 0, None)],
+            current_page: Some(0),
+            current_sdf_page: None,
+            page_size: size,
+            max_pages,
+            generation: 0,
+            num_evictions: 0,
+            overflowed: false,
+        }
+    }
+
+    /// The number of pages (textures) in the atlas.
+    pub fn num_pages(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// The current size of each page.
+    pub fn page_sizes(&self) -> Vec<[usize; 2]> {
+        self.pages.iter().map(|page| page.image.size).collect()
+    }
+
+    /// Changes when the page is cleared by [`Self::begin_frame`],
+    /// so that anything referring to the glyphs in it can tell that they are gone.
+    ///
+    /// `None` if there is no such page.
+    pub fn page_generation(&self, page: usize) -> Option<u32> {
+        self.pages.get(page).map(|page| page.page_generation)
+    }
+
+    /// When this get high, it might be time to clear and start over!
+    ///
+    /// This is how much of the space of all possible pages that has been used.
+    pub fn fill_ratio(&self) -> f32 {
+        if self.overflowed {
+            1.0
+        } else {
+            let filled: f32 = self.pages.iter().map(AtlasPage::fill_ratio).sum();
+            filled / self.max_pages as f32
+        }
+    }
+
+    /// Has someone requested more space than was available?
+    ///
+    /// If so, some glyphs have been overwritten, and the atlas should be recreated.
+    pub fn overflowed(&self) -> bool {
+        self.overflowed
+    }
+
+    pub fn stats(&self) -> FontAtlasStats {
+        FontAtlasStats {
+            num_pages: self.pages.len(),
+            max_pages: self.max_pages,
+            num_bytes: self
+                .pages
+                .iter()
+                .map(|page| {
+                    page.image.pixels.len() * if page.color_image.is_some() { 4 } else { 1 }
+                })
+                .sum(),
+            fill_ratio: self.fill_ratio(),
+            num_evictions: self.num_evictions,
+        }
+    }
+
+    /// Call to get the changes to the pages since last call,
+    /// as `(page index, change)`.
+    ///
+    /// New pages are always sent as a whole.
+    pub fn take_deltas(&mut self) -> Vec<(usize, ImageDelta)> {
+        self.pages
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, page)| Some((index, page.take_delta()?)))
+            .collect()
+    }
+
+    /// Call once per frame, with the pages used by the text painted in the last frame.
+    ///
+    /// If we are running out of space, the least recently used page is cleared,
+    /// and its index is returned. All glyphs in it must then be forgotten.
+    pub fn begin_frame(&mut self, used_pages: impl IntoIterator<Item = usize>) -> Option<usize> {
+        for page in used_pages {
+            if let Some(page) = self.pages.get_mut(page) {
+                page.last_used = self.generation;
+            }
+        }
+
+        let generation = self.generation;
+        self.generation = self.generation.wrapping_add(1);
+
+        if self.pages.len() < self.max_pages {
+            return None;
+        }
+        let nearly_full =
+            |page: Option<usize>| page.map_or(false, |page| self.pages[page].fill_ratio() > 0.8);
+        let sdf_spread = if nearly_full(self.current_page) {
+            None
+        } else if nearly_full(self.current_sdf_page) {
+            self.pages[self.current_sdf_page?].sdf_spread
+        } else {
+            return None;
+        };
+
+        let index = (0..self.pages.len())
+            .filter(|&index| self.pages[index].last_used != generation)
+            .max_by_key(|&index| generation.wrapping_sub(self.pages[index].last_used))?;
+
+        self.pages[index] = AtlasPage::new(self.page_size, self.generation, sdf_spread);
+        for current in [&mut self.current_page, &mut self.current_sdf_page] {
+            if *current == Some(index) {
+                *current = None;
+            }
+        }
+        *self.current_page_mut(sdf_spread.is_some()) = Some(index);
+        self.num_evictions += 1;
+        Some(index)
+    }
+
+    /// Returns the page and coordinates of where the rect ended up,
+    /// and invalidates the region.
+    pub fn allocate(&mut self, (w, h): (usize, usize)) -> (usize, (usize, usize), &mut AlphaImage) {
+        let (index, pos) = self.allocate_in_some_page(None, (w, h));
+        (index, pos, &mut self.pages[index].image)
+    }
+
+    /// Like [`Self::allocate`], but for a signed distance field with the given spread (see [`SdfImage`]).
+    ///
+    /// The distances are encoded like in [`SdfImage`], and are put in separate pages from the other glyphs.
+    pub fn allocate_sdf(
+        &mut self,
+        spread: f32,
+        (w, h): (usize, usize),
+    ) -> (usize, (usize, usize), &mut AlphaImage) {
+        let (index, pos) = self.allocate_in_some_page(Some(spread), (w, h));
+        (index, pos, &mut self.pages[index].image)
+    }
+
+    /// Like [`Self::allocate`], but for a color glyph.
+    ///
+    /// The first call for a page switches the whole page to RGBA.
+    pub fn allocate_color(
+        &mut self,
+        (w, h): (usize, usize),
+    ) -> (usize, (usize, usize), &mut ColorImage) {
+        let (index, pos) = self.allocate_in_some_page(None, (w, h));
+        (index, pos, self.pages[index].color_image())
+    }
+
+    fn current_page_mut(&mut self, sdf: bool) -> &mut Option<usize> {
+        if sdf {
+            &mut self.current_sdf_page
+        } else {
+            &mut self.current_page
+        }
+    }
+
+    fn allocate_in_some_page(
+        &mut self,
+        sdf_spread: Option<f32>,
+        (w, h): (usize, usize),
+    ) -> (usize, (usize, usize)) {
+        let generation = self.generation;
+        let current_page = *self.current_page_mut(sdf_spread.is_some());
+
+        if let Some(index) = current_page {
+            if let Some(pos) = self.pages[index].allocate((w, h)) {
+                self.pages[index].last_used = generation;
+                return (index, pos);
+            }
+        }
+
+        // If all pages are of the other kind we go past `max_pages`,
+        // and let `begin_frame` evict the extra page when it is no longer used.
+        if self.pages.len() < self.max_pages || current_page.is_none() {
+            self.pages
+                .push(AtlasPage::new(self.page_size, generation, sdf_spread));
+            let index = self.pages.len() - 1;
+            *self.current_page_mut(sdf_spread.is_some()) = Some(index);
+            if let Some(pos) = self.pages[index].allocate((w, h)) {
+                return (index, pos);
+            }
+        }
+
+        // This is a bad place to be - we need to start reusing space :/
+        eprintln!("epaint texture atlas overflowed!");
+        self.overflowed = true; // this will signal the user that we need to recreate the texture atlas next frame.
+        let index = self.current_page_mut(sdf_spread.is_some()).unwrap_or(0);
+        let page = &mut self.pages[index];
+        page.cursor = (0, page.image.height() / 3); // Restart a bit down - the top of the atlas has too many important things in it
+        page.row_height = 0;
+        let pos = page.allocate((w, h)).unwrap_or((0, 0));
+        (index, pos)
     }
 }
 
@@ -147,3 +451,59 @@ fn resize_to_min_height(image: &mut AlphaImage, required_height: usize) -> bool
         false
     }
 }
+
+#[test]
+fn test_evict_least_recently_used_page() {
+    let mut atlas = TextureAtlas::new([1024, 64], 2);
+    let glyph = (1024, 500);
+
+    assert_eq!(atlas.allocate(glyph).0, 0);
+    assert_eq!(atlas.allocate(glyph).0, 0);
+    assert_eq!(atlas.allocate(glyph).0, 1, "first page is full");
+    assert_eq!(atlas.num_pages(), 2);
+    assert_eq!(atlas.begin_frame([1]), None, "there is still room");
+
+    assert_eq!(atlas.allocate(glyph).0, 1);
+    let page_generations = [atlas.page_generation(0), atlas.page_generation(1)];
+    assert_eq!(
+        atlas.begin_frame([1]),
+        Some(0),
+        "page 0 was not used last frame"
+    );
+    assert_ne!(atlas.page_generation(0), page_generations[0]);
+    assert_eq!(atlas.page_generation(1), page_generations[1]);
+    assert_eq!(atlas.allocate(glyph).0, 0);
+    assert_eq!(atlas.stats().num_evictions, 1);
+    assert!(!atlas.overflowed());
+
+    // Every page has the white texel:
+    let deltas = atlas.take_deltas();
+    assert_eq!(deltas.len(), 2);
+    for (_, delta) in deltas {
+        assert!(delta.is_whole());
+        match &delta.image {
+            crate::ImageData::Alpha(image) => assert_eq!(image[(0, 0)], 255),
+            _ => panic!("expected alpha"),
+        }
+    }
+}
+
+#[test]
+fn test_sdf_pages() {
+    let mut atlas = TextureAtlas::new([1024, 64], 2);
+
+    assert_eq!(atlas.allocate((10, 10)).0, 0);
+    assert_eq!(atlas.allocate_sdf(4.0, (10, 10)).0, 1);
+    assert_eq!(atlas.allocate_color((10, 10)).0, 0);
+    assert_eq!(atlas.allocate_sdf(4.0, (10, 10)).0, 1);
+
+    let deltas = atlas.take_deltas();
+    assert_eq!(deltas.len(), 2);
+    match &deltas[1].1.image {
+        crate::ImageData::Sdf(image) => {
+            assert_eq!(image.spread, 4.0);
+            assert_eq!(image.pixels[0], 255, "the white texel is far inside");
+        }
+        _ => panic!("expected a signed distance field"),
+    }
+}